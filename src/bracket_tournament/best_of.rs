@@ -0,0 +1,144 @@
+//! Best-of-N matches.
+//!
+//! The config holds a default `best_of` for every round plus `best_of_rounds`, which overrides it for
+//! single rounds. A round can be named by its collection (`Round 2`, `Losers Round 1`,
+//! `Grand Final`, ...) or, for the last rounds of the bracket, as `Final` and `Semifinal`.
+use crate::bracket_tournament::double_elimination::{
+    parse_winners_round, GRAND_FINAL, GRAND_FINAL_RESET,
+};
+use dbc_bot::{DrawRule, Format};
+use mongodb::bson::Document;
+
+pub const BEST_OF_CHOICES: [i32; 4] = [1, 3, 5, 7];
+const DEFAULT_BEST_OF: i32 = 3;
+
+pub fn is_valid_best_of(best_of: i32) -> bool {
+    BEST_OF_CHOICES.contains(&best_of)
+}
+
+/// Games a player must win to take a best-of-`best_of` match.
+pub fn wins_needed(best_of: i32) -> i32 {
+    best_of / 2 + 1
+}
+
+/// Other names a host can use for a round, e.g. `Final` for the last round of a single elimination.
+fn aliases(config: &Document, round: &str) -> Vec<&'static str> {
+    let total = config.get_i32("total").unwrap_or(0);
+    match Format::from_config(config) {
+        Format::DoubleElimination if round == GRAND_FINAL || round == GRAND_FINAL_RESET => {
+            vec!["Final"]
+        }
+        Format::SingleElimination | Format::GroupStage => match parse_winners_round(round) {
+            Some(r) if r == total => vec!["Final"],
+            Some(r) if r == total - 1 => vec!["Semifinal"],
+            _ => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+/// Number of games of a match in the given round.
+pub fn best_of(config: &Document, round: &str) -> i32 {
+    let overrides = config.get_document("best_of_rounds").ok();
+    let mut names = vec![round];
+    names.extend(aliases(config, round));
+    names
+        .iter()
+        .find_map(|name| {
+            overrides?
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .and_then(|(_, value)| value.as_i32())
+        })
+        .or_else(|| config.get_i32("best_of").ok())
+        .filter(|best_of| is_valid_best_of(*best_of))
+        .unwrap_or(DEFAULT_BEST_OF)
+}
+
+/// Parses per-round overrides written one per line, e.g. `Final: 7`.
+pub fn parse_overrides(text: &str) -> Result<Document, String> {
+    let mut overrides = Document::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let Some((round, games)) = line.split_once([':', '=']) else {
+            return Err(format!("`{line}` is not written as `Round: games`"));
+        };
+        match games.trim().parse::<i32>() {
+            Ok(games) if is_valid_best_of(games) => {
+                overrides.insert(round.trim(), games);
+            }
+            _ => return Err(format!("`{}` is not 1, 3, 5 or 7", games.trim())),
+        }
+    }
+    Ok(overrides)
+}
+
+/// Decides a match from the caller's results against their opponent, oldest first.
+///
+/// Returns the games won and lost by the caller, or `None` when the match is not finished yet.
+pub fn decide(results: &[String], best_of: i32, draw_rule: &DrawRule) -> Option<(i32, i32)> {
+    let needed = wins_needed(best_of);
+    let (mut wins, mut defeats, mut played) = (0, 0, 0);
+    for result in results {
+        match (result.as_str(), draw_rule) {
+            ("victory", _) => wins += 1,
+            ("defeat", _) => defeats += 1,
+            ("draw", DrawRule::Ignore) => {}
+            _ => continue,
+        }
+        played += 1;
+        if wins >= needed || defeats >= needed || (played >= best_of && wins != defeats) {
+            return Some((wins, defeats));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{best_of, decide, parse_overrides};
+    use crate::database::fixtures::config;
+    use dbc_bot::DrawRule;
+    use mongodb::bson::doc;
+
+    fn games(results: &[&str]) -> Vec<String> {
+        results.iter().map(|result| result.to_string()).collect()
+    }
+
+    #[test]
+    fn rounds_can_override_the_default_best_of() {
+        let mut settings = config("Single Elimination", 1, 3);
+        settings.insert("best_of", 1);
+        settings.insert(
+            "best_of_rounds",
+            parse_overrides("Final: 7\nround 2 = 5").unwrap(),
+        );
+        assert_eq!(best_of(&settings, "Round 1"), 1);
+        assert_eq!(best_of(&settings, "Round 2"), 5);
+        assert_eq!(best_of(&settings, "Round 3"), 7);
+
+        settings.insert("best_of_rounds", doc! {"semifinal": 3});
+        assert_eq!(best_of(&settings, "Round 2"), 3);
+        assert!(parse_overrides("Final: 4").is_err());
+        assert!(parse_overrides("Final 3").is_err());
+    }
+
+    #[test]
+    fn matches_end_once_a_player_has_enough_wins() {
+        let results = games(&["victory", "defeat", "victory"]);
+        assert_eq!(decide(&results[..2], 3, &DrawRule::Replay), None);
+        assert_eq!(decide(&results, 3, &DrawRule::Replay), Some((2, 1)));
+        // Results after the match was decided are not counted
+        let results = games(&["defeat", "defeat", "victory"]);
+        assert_eq!(decide(&results, 3, &DrawRule::Replay), Some((0, 2)));
+    }
+
+    #[test]
+    fn draws_are_replayed_or_use_up_a_game() {
+        let results = games(&["victory", "draw", "draw"]);
+        assert_eq!(decide(&results, 3, &DrawRule::Replay), None);
+        assert_eq!(decide(&results, 3, &DrawRule::Ignore), Some((1, 0)));
+        // Nobody leads once every game is played, so the match goes on
+        let results = games(&["draw", "draw", "draw"]);
+        assert_eq!(decide(&results, 3, &DrawRule::Ignore), None);
+    }
+}
@@ -1,24 +1,18 @@
+use crate::bracket_tournament::double_elimination::{self, GRAND_FINAL, GRAND_FINAL_RESET};
 use crate::database::config::set_config;
 use crate::database::find::{find_enemy_by_match_id_and_self_tag, find_self_by_discord_id};
+use crate::database::models::{Player, RegionConfig};
+use crate::database::repository::Repository;
+use crate::database::update::result_key;
+use crate::visual::bracket::{self, Bracket, BracketMatch};
 use crate::{Context, Error};
-use base64::{engine::general_purpose, Engine as _};
-use dbc_bot::{CustomError, QuoteStripper, Region};
+use chrono::{TimeZone, Utc};
+use dbc_bot::{CustomError, Format, QuoteStripper, Region};
 use futures::TryStreamExt;
-use mongodb::bson::doc;
-use std::env;
-use std::process::Command;
-use std::process::Stdio;
+use mongodb::bson::{doc, Document};
 use tracing::{error, info};
 
 pub async fn update_bracket(ctx: &Context<'_>, region: Option<&Region>) -> Result<(), Error> {
-    let current_dir = match env::current_dir() {
-        Ok(dir) => dir,
-        Err(e) => {
-            error!("Failed to get current directory: {e}");
-            return Err(Box::new(CustomError(format!("{e}"))));
-        }
-    };
-
     let current_region = match region {
         Some(region) => region.clone(),
         None => {
@@ -35,7 +29,7 @@ pub async fn update_bracket(ctx: &Context<'_>, region: Option<&Region>) -> Resul
                     );
                 }
             };
-            Region::find_key(caller.get_str("region").unwrap()).unwrap()
+            Player::from_document(&caller)?.region()?
         }
     };
 
@@ -46,115 +40,37 @@ pub async fn update_bracket(ctx: &Context<'_>, region: Option<&Region>) -> Resul
         .get(&current_region)
         .unwrap();
     let collection: mongodb::Collection<mongodb::bson::Document> = database.collection("Config");
-    let config = collection.find_one(None, None).await?.unwrap();
+    let config = collection
+        .find_one(None, None)
+        .await?
+        .ok_or("The config of this region has not been created yet")?;
 
-    let mut player_data: Vec<(i32, i32, String, String, bool, bool)> = Vec::new();
-    let mut match_ids = Vec::new();
-
-    for round_number in 1..=config.get("total").unwrap().as_i32().unwrap() {
-        let round_name = format!("Round {}", round_number);
-        let mut database: mongodb::Cursor<mongodb::bson::Document> = ctx
-            .data()
-            .database
-            .regional_databases
-            .get(&current_region)
-            .unwrap()
-            .collection(&round_name)
-            .find(None, None)
-            .await?;
-
-        while let Some(current_document) = database.try_next().await? {
-            let match_id = current_document
-                .get("match_id")
-                .and_then(|n| n.as_i32())
-                .unwrap_or(0);
-            if match_ids.contains(&match_id) {
-                continue;
-            }
-            let tag = if let Some(tag) = current_document.get("tag").and_then(|n| n.as_str()) {
-                tag
-            } else {
-                continue;
-            };
-            match_ids.push(match_id);
-            player_data.push((
-                round_number,
-                match_id,
-                current_document
-                    .get("discord_name")
-                    .map_or(" ".to_string(), |name| name.to_string().strip_quote()),
-                (find_enemy_by_match_id_and_self_tag(
-                    ctx,
-                    &current_region,
-                    &round_name,
-                    &match_id,
-                    tag,
-                )
-                .await)
-                    .map_or(" ".to_string(), |document| {
-                        document
-                            .get("discord_name")
-                            .unwrap()
-                            .to_string()
-                            .strip_quote()
-                    }),
-                current_document
-                    .get("winner")
-                    .map_or_else(|| false, |is_winner| is_winner.as_bool().unwrap()),
-                (find_enemy_by_match_id_and_self_tag(
-                    ctx,
-                    &current_region,
-                    &round_name,
-                    &match_id,
-                    tag,
-                )
-                .await)
-                    .map_or(false, |document| {
-                        document
-                            .get("winner")
-                            .map_or(false, |is_winner| is_winner.as_bool().unwrap())
-                    }),
-            ));
-        }
-        match_ids.clear();
-    }
-    let sep = "/se/pa/ra/tor/";
-    let data = match player_data.is_empty() {
-        true => format!("1{sep}1{sep} {sep} {sep} {sep} "),
-        false => player_data.iter().map(|(round, match_id, player1_tag, player2_tag, is_winner1, is_winner2)| {
-                let a = format!("{round}{sep}{match_id}{sep}{player1_tag}{sep}{player2_tag}{sep}{is_winner1}{sep}{is_winner2}");
-                a
-        }).collect::<Vec<String>>().join(",")
-    };
+    let bracket = collect_bracket(ctx, &current_region, &config).await?;
     info!("Generating bracket.");
-    let output = Command::new("python3")
-        .arg("scripts/bracket_generation.py")
-        .arg(current_region.to_string())
-        .arg(config.get("total").unwrap().to_string())
-        .arg(data)
-        .stdout(Stdio::piped())
-        .current_dir(current_dir)
-        .spawn()?;
-
-    let stdout = output.wait_with_output()?.stdout;
-    let buffer = std::str::from_utf8(&stdout)?;
-    if buffer.len() < 100 {
-        return Err("Failed to capture Python script output".into());
-    }
-
-    let image_bytes = match general_purpose::STANDARD.decode(buffer) {
-        Ok(bytes) => bytes,
-        Err(e) => {
-            error!("{e}");
-            info!("Debug: {buffer}");
-            return Err(e.into());
-        }
-    };
+    let image_bytes =
+        bracket::winners_bracket(&current_region, bracket.total, &bracket.winners).await?;
     info!("Bracket generated.");
-    let attachment = poise::serenity_prelude::AttachmentType::Bytes {
+    let mut attachments = vec![];
+    attachments.push(poise::serenity_prelude::AttachmentType::Bytes {
         data: image_bytes.into(),
         filename: format!("Tournament_bracket_{}.png", current_region.short()),
-    };
+    });
+
+    if bracket.finals > 0 {
+        info!("Generating losers bracket.");
+        let losers_bytes = bracket::losers_bracket(
+            &current_region,
+            bracket.total,
+            &bracket.losers,
+            bracket.finals,
+        )
+        .await?;
+        info!("Losers bracket generated.");
+        attachments.push(poise::serenity_prelude::AttachmentType::Bytes {
+            data: losers_bytes.into(),
+            filename: format!("Tournament_losers_bracket_{}.png", current_region.short()),
+        });
+    }
 
     match config
         .get("bracket_channel")
@@ -172,7 +88,10 @@ pub async fn update_bracket(ctx: &Context<'_>, region: Option<&Region>) -> Resul
                     );
                     match poise::serenity_prelude::ChannelId(channel_id.unwrap())
                         .edit_message(&ctx, bracket_message_id.unwrap(), |m| {
-                            m.attachment(attachment)
+                            for attachment in attachments {
+                                m.attachment(attachment);
+                            }
+                            m
                         })
                         .await
                     {
@@ -188,7 +107,7 @@ pub async fn update_bracket(ctx: &Context<'_>, region: Option<&Region>) -> Resul
                 None => {
                     info!("Sending bracket messages at {}.", channel_id.unwrap());
                     match poise::serenity_prelude::ChannelId(channel_id.unwrap())
-                        .send_message(&ctx, |m| m.add_file(attachment))
+                        .send_message(&ctx, |m| m.add_files(attachments))
                         .await
                     {
                         Ok(message) => {
@@ -241,3 +160,128 @@ pub async fn update_bracket(ctx: &Context<'_>, region: Option<&Region>) -> Resul
 
     Ok(())
 }
+
+/// Collects the matches of every round of the bracket, in the columns they are drawn in.
+pub async fn collect_bracket(
+    ctx: &Context<'_>,
+    region: &Region,
+    config: &Document,
+) -> Result<Bracket, Error> {
+    let total = RegionConfig::from_document(config)?.total;
+    let mut winners: Vec<BracketMatch> = Vec::new();
+    for round_number in 1..=total {
+        let round_name = format!("Round {}", round_number);
+        winners.extend(collect_matches(ctx, region, &round_name, round_number).await?);
+    }
+    let mut losers: Vec<BracketMatch> = Vec::new();
+    let mut finals = vec![];
+    if Format::from_config(config) == Format::DoubleElimination {
+        finals.push(GRAND_FINAL);
+        if config.get_bool("bracket_reset").unwrap_or(false) {
+            finals.push(GRAND_FINAL_RESET);
+        }
+        let losers_rounds = double_elimination::losers_rounds(total);
+        for round_number in 1..=losers_rounds {
+            let round_name = double_elimination::losers_round_name(round_number);
+            losers.extend(collect_matches(ctx, region, &round_name, round_number).await?);
+        }
+        for (index, round_name) in finals.iter().enumerate() {
+            let column = losers_rounds + 1 + index as i32;
+            losers.extend(collect_matches(ctx, region, round_name, column).await?);
+        }
+    }
+    Ok(Bracket {
+        total,
+        winners,
+        losers,
+        finals: finals.len() as i32,
+    })
+}
+
+/// Collects the matches of a round for the bracket, placed in the given column.
+async fn collect_matches(
+    ctx: &Context<'_>,
+    region: &Region,
+    round_name: &str,
+    column: i32,
+) -> Result<Vec<BracketMatch>, Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let mut player_data = Vec::new();
+    let mut match_ids = Vec::new();
+    let mut cursor: mongodb::Cursor<Document> =
+        database.collection(round_name).find(None, None).await?;
+
+    while let Some(current_document) = cursor.try_next().await? {
+        let match_id = current_document
+            .get("match_id")
+            .and_then(|n| n.as_i32())
+            .unwrap_or(0);
+        if match_ids.contains(&match_id) {
+            continue;
+        }
+        let tag = if let Some(tag) = current_document.get("tag").and_then(|n| n.as_str()) {
+            tag
+        } else {
+            continue;
+        };
+        match_ids.push(match_id);
+        let enemy =
+            find_enemy_by_match_id_and_self_tag(ctx, region, round_name, &match_id, tag).await;
+        let result = database
+            .find_result(&result_key(region, round_name, match_id))
+            .await?;
+        // The score is stored winner first
+        let score = result.as_ref().and_then(|result| {
+            let score = result.get_array("score").ok()?;
+            let (winner, loser) = (score.first()?.as_i32()?, score.get(1)?.as_i32()?);
+            match result.get("winner") == current_document.get("_id") {
+                true => Some((winner, loser)),
+                false => Some((loser, winner)),
+            }
+        });
+        let won = match &result {
+            Some(result) if result.contains_key("double_forfeit") => None,
+            Some(result) => Some(result.get("winner") == current_document.get("_id")),
+            None => current_document
+                .get_bool("defeated")
+                .ok()
+                .map(|defeated| !defeated),
+        };
+        let decided_at = result
+            .as_ref()
+            .and_then(|result| result.get_datetime("recorded_at").ok())
+            .or_else(|| current_document.get_datetime("decided_at").ok())
+            .and_then(|time| Utc.timestamp_millis_opt(time.timestamp_millis()).single());
+        player_data.push(BracketMatch {
+            column,
+            match_id,
+            player1: current_document
+                .get("discord_name")
+                .map_or(" ".to_string(), |name| name.to_string().strip_quote()),
+            player2: enemy.as_ref().map_or(" ".to_string(), |document| {
+                document
+                    .get("discord_name")
+                    .unwrap()
+                    .to_string()
+                    .strip_quote()
+            }),
+            winner1: current_document
+                .get("winner")
+                .map_or_else(|| false, |is_winner| is_winner.as_bool().unwrap()),
+            winner2: enemy.as_ref().map_or(false, |document| {
+                document
+                    .get("winner")
+                    .map_or(false, |is_winner| is_winner.as_bool().unwrap())
+            }),
+            won,
+            tag1: Some(tag.to_string()),
+            tag2: enemy
+                .as_ref()
+                .and_then(|document| document.get_str("tag").ok())
+                .map(str::to_string),
+            score,
+            decided_at,
+        });
+    }
+    Ok(player_data)
+}
@@ -0,0 +1,576 @@
+//! Files in the shape Challonge uses for participants and matches, to mirror a bracket there and to
+//! register players from a bracket made elsewhere.
+//!
+//! The export follows Challonge's `tournament` JSON with `participants` and `matches`. Rounds keep
+//! Challonge's numbering: losers rounds are negative, the grand finals come after the last winners
+//! round and the third-place match is round 0. Group stage matchdays are not part of the export.
+//!
+//! The import reads the same JSON, a bare list of participants or a CSV file with a header row.
+//! Challonge has no field for a Brawl Stars tag or a Discord id, so both may also be written in
+//! `misc`, e.g. `#2PP 123456789012345678`. Participants go through the same checks as players who
+//! register themselves: banned players and accounts failing the eligibility rules are skipped. The
+//! file is enough to register them, so accounts the Brawl Stars API cannot be asked about are
+//! imported as written: of the eligibility rules, only the age of their Discord account is checked.
+use crate::bracket_tournament::double_elimination::{
+    parse_losers_round, parse_winners_round, GRAND_FINAL, GRAND_FINAL_RESET,
+};
+use crate::bracket_tournament::placements::{has_third_place_match, THIRD_PLACE_MATCH};
+use crate::bracket_tournament::seeding::normalise_tag;
+use crate::database::ban::find_ban;
+use crate::database::config::{find_config, make_player_doc};
+use crate::database::eligibility::{account_age_problem, failed_rules};
+use crate::database::find::is_mannequin;
+use crate::database::repository::{Repository, Write, CONFIG, PLAYERS};
+use crate::database::update::result_key;
+use crate::Error;
+use dbc_bot::{Format, Region};
+use mongodb::bson::{doc, DateTime, Document};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// A player read from an imported file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Participant {
+    pub name: String,
+    pub tag: String,
+    pub discord_id: String,
+    pub discord_name: String,
+    pub seed: Option<i32>,
+}
+
+/// Challonge's number for a round collection, or `None` for rounds that are not exported.
+pub fn challonge_round(round: &str, total: i32) -> Option<i32> {
+    match round {
+        GRAND_FINAL => Some(total + 1),
+        GRAND_FINAL_RESET => Some(total + 2),
+        THIRD_PLACE_MATCH => Some(0),
+        round => parse_winners_round(round).or_else(|| parse_losers_round(round).map(|n| -n)),
+    }
+}
+
+/// The participants and matches of a region's tournament, as Challonge returns them.
+pub async fn export_tournament(database: &dyn Repository, region: &Region) -> Result<Value, Error> {
+    let config = find_config(database).await;
+    let total = config.get_i32("total").unwrap_or(0);
+    let format = Format::from_config(&config);
+
+    let mut participants = vec![];
+    let mut ids: BTreeMap<String, i64> = BTreeMap::new();
+    let mut registered = database.find_in_round(PLAYERS, doc! {}).await?;
+    registered.retain(|player| !is_mannequin(player));
+    let seeds = database.find_in_round("Round 1", doc! {}).await?;
+    let seed_of = |player: &Document| {
+        seeds
+            .iter()
+            .find(|seeded| seeded.get("_id") == player.get("_id"))
+            .and_then(|seeded| seeded.get_i32("seed").ok())
+    };
+    registered.sort_by_key(|player| seed_of(player).unwrap_or(i32::MAX));
+    for (index, player) in registered.iter().enumerate() {
+        let id = index as i64 + 1;
+        ids.insert(
+            player.get("_id").map_or(String::new(), |id| id.to_string()),
+            id,
+        );
+        participants.push(json!({
+            "participant": {
+                "id": id,
+                "name": player.get_str("discord_name").or(player.get_str("name")).unwrap_or(""),
+                "seed": seed_of(player).unwrap_or(id as i32),
+                "misc": format!(
+                    "{} {}",
+                    player.get_str("tag").unwrap_or(""),
+                    player.get_str("discord_id").unwrap_or("")
+                ),
+                "active": true,
+            }
+        }));
+    }
+    let participant = |player: Option<&Document>| -> Option<i64> {
+        let player = player.filter(|player| !is_mannequin(player))?;
+        ids.get(&player.get("_id")?.to_string()).copied()
+    };
+
+    let mut rounds: Vec<(i32, String)> = database
+        .round_names()
+        .await?
+        .into_iter()
+        .filter_map(|name| Some((challonge_round(&name, total)?, name)))
+        .collect();
+    // Winners rounds first, then the losers rounds, like Challonge lists them
+    rounds.sort_by_key(|(round, _)| (*round < 0, round.abs()));
+    let mut matches = vec![];
+    for (round, name) in rounds {
+        let mut games: BTreeMap<i32, Vec<Document>> = BTreeMap::new();
+        for player in database.find_in_round(&name, doc! {}).await? {
+            if let Ok(match_id) = player.get_i32("match_id") {
+                games.entry(match_id).or_default().push(player);
+            }
+        }
+        for (match_id, players) in games {
+            let (player1, player2) = (players.first(), players.get(1));
+            let winner = players
+                .iter()
+                .find(|player| player.get_bool("defeated") == Ok(false));
+            let loser = players
+                .iter()
+                .find(|player| player.get_bool("defeated") == Ok(true));
+            let state = match (winner.or(loser), participant(player1), participant(player2)) {
+                (Some(_), _, _) => "complete",
+                (None, Some(_), Some(_)) => "open",
+                _ => "pending",
+            };
+            let result = database
+                .find_result(&result_key(region, &name, match_id))
+                .await?;
+            // The ledger stores the score winner first
+            let scores_csv = result
+                .as_ref()
+                .and_then(|result| {
+                    let score = result.get_array("score").ok()?;
+                    let (won, lost) = (score.first()?.as_i32()?, score.get(1)?.as_i32()?);
+                    match result.get("winner") == player1.and_then(|player| player.get("_id")) {
+                        true => Some(format!("{won}-{lost}")),
+                        false => Some(format!("{lost}-{won}")),
+                    }
+                })
+                .unwrap_or_default();
+            let completed_at = winner
+                .or(loser)
+                .and_then(|player| player.get_datetime("decided_at").ok())
+                .and_then(|time| time.try_to_rfc3339_string().ok());
+            matches.push(json!({
+                "match": {
+                    "id": matches.len() + 1,
+                    "state": state,
+                    "round": round,
+                    "suggested_play_order": matches.len() + 1,
+                    "player1_id": participant(player1),
+                    "player2_id": participant(player2),
+                    "winner_id": participant(winner),
+                    "loser_id": participant(loser),
+                    "scores_csv": scores_csv,
+                    "completed_at": completed_at,
+                }
+            }));
+        }
+    }
+
+    let tournament_type = match format {
+        Format::SingleElimination | Format::GroupStage => "single elimination",
+        Format::DoubleElimination => "double elimination",
+        Format::Swiss => "swiss",
+    };
+    Ok(json!({
+        "tournament": {
+            "name": format!("{} tournament", region.full()),
+            "tournament_type": tournament_type,
+            "hold_third_place_match": has_third_place_match(&config),
+            "grand_finals_modifier": match config.get_bool("bracket_reset").unwrap_or(false) {
+                true => Value::Null,
+                false => json!("single match"),
+            },
+            "participants_count": participants.len(),
+            "participants": participants,
+            "matches": matches,
+        }
+    }))
+}
+
+/// Reads the participants of a Challonge JSON export or of a CSV file with a header row.
+///
+/// Returns every problem found in the file if a participant cannot be registered.
+pub fn parse_participants(filename: &str, content: &str) -> Result<Vec<Participant>, String> {
+    let rows: Vec<BTreeMap<String, String>> = if filename.to_lowercase().ends_with(".csv") {
+        parse_csv(content)?
+    } else {
+        parse_json(content)?
+    };
+    if rows.is_empty() {
+        return Err("The file has no participants".to_string());
+    }
+    let mut participants: Vec<Participant> = vec![];
+    let mut problems = vec![];
+    for (index, row) in rows.iter().enumerate() {
+        let field = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| row.get(*key).filter(|value| !value.trim().is_empty()))
+                .map(|value| value.trim().to_string())
+        };
+        let misc = field(&["misc"]).unwrap_or_default();
+        let words: Vec<&str> = misc
+            .split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '|'))
+            .filter(|word| !word.is_empty())
+            .collect();
+        let tag = field(&["tag", "player_tag", "brawl_stars_tag"]).or_else(|| {
+            words
+                .iter()
+                .find(|word| word.starts_with('#'))
+                .map(|tag| tag.to_string())
+        });
+        let discord_id = field(&["discord_id", "discord"]).or_else(|| {
+            words
+                .iter()
+                .find(|word| word.len() >= 15 && word.chars().all(|c| c.is_ascii_digit()))
+                .map(|id| id.to_string())
+        });
+        let name = field(&["name", "display_name", "username"]).unwrap_or_default();
+        let row = index + 1;
+        let (Some(tag), Some(discord_id)) = (tag, discord_id) else {
+            problems.push(format!(
+                "Participant {row} ({name}) needs a Brawl Stars tag and a Discord id"
+            ));
+            continue;
+        };
+        let seed = match field(&["seed"]).map(|seed| seed.parse::<i32>()) {
+            Some(Ok(seed)) => Some(seed),
+            Some(Err(_)) => {
+                problems.push(format!("Participant {row} ({name}) has an invalid seed"));
+                continue;
+            }
+            None => None,
+        };
+        let tag = normalise_tag(&tag);
+        if participants
+            .iter()
+            .any(|participant| participant.tag == tag)
+        {
+            problems.push(format!("{tag} is listed twice"));
+            continue;
+        }
+        if participants
+            .iter()
+            .any(|participant| participant.discord_id == discord_id)
+        {
+            problems.push(format!("Discord id {discord_id} is listed twice"));
+            continue;
+        }
+        participants.push(Participant {
+            discord_name: field(&["discord_name"]).unwrap_or_else(|| name.clone()),
+            name,
+            tag,
+            discord_id,
+            seed,
+        });
+    }
+    match problems.is_empty() {
+        true => Ok(participants),
+        false => Err(problems.join("\n")),
+    }
+}
+
+/// Why a participant is left out of the import, if they are: a ban in the region or eligibility
+/// rules the account fails. Without a `profile` from the `player` endpoint, only bans and the age
+/// of the Discord account are checked.
+pub async fn skip_reason(
+    general: &dyn Repository,
+    config: &Document,
+    region: &Region,
+    participant: &Participant,
+    profile: Option<&Value>,
+) -> Result<Option<String>, Error> {
+    if let Some(ban) = find_ban(
+        general,
+        Some(&participant.discord_id),
+        Some(&participant.tag),
+        region,
+    )
+    .await?
+    {
+        return Ok(Some(format!(
+            "Banned: {}",
+            ban.get_str("reason").unwrap_or("No reason given")
+        )));
+    }
+    let failed = profile
+        .map(|profile| failed_rules(config, profile))
+        .unwrap_or_default()
+        .into_iter()
+        .chain(account_age_problem(
+            config,
+            &participant.discord_id,
+            DateTime::now(),
+        ))
+        .collect::<Vec<String>>();
+    Ok((!failed.is_empty()).then(|| failed.join(" ")))
+}
+
+/// Every write that replaces the registered players with the participants, and makes their order in
+/// the file, or their seeds, the seed list.
+///
+/// A participant's in-game name and icon come from their `profile` when the account was looked up,
+/// and from the file otherwise.
+pub fn plan_import(region: &Region, participants: &[(Participant, Option<Value>)]) -> Vec<Write> {
+    let mut writes = vec![Write::Delete {
+        round: PLAYERS.to_string(),
+        filter: doc! {},
+    }];
+    for (participant, profile) in participants {
+        let profile = profile.clone().unwrap_or_else(|| {
+            json!({
+                "name": participant.name,
+                "tag": participant.tag,
+                "icon": {"id": null},
+                "nameColor": null,
+            })
+        });
+        let mut player = make_player_doc(
+            &profile,
+            &participant.discord_id,
+            &participant.discord_name,
+            region,
+        );
+        player.insert("tag", &participant.tag);
+        writes.push(Write::Insert {
+            round: PLAYERS.to_string(),
+            document: player,
+        });
+    }
+    let mut seeded: Vec<&Participant> = participants
+        .iter()
+        .map(|(participant, _)| participant)
+        .collect();
+    seeded.sort_by_key(|participant| participant.seed.unwrap_or(i32::MAX));
+    let seed_list: Vec<&str> = seeded
+        .iter()
+        .map(|participant| participant.tag.as_str())
+        .collect();
+    writes.push(Write::Update {
+        round: CONFIG.to_string(),
+        filter: doc! {},
+        update: doc! {"$set": {"seeding": "Manual", "seed_list": seed_list}},
+    });
+    writes
+}
+
+/// Rows of a Challonge export, of a list of `{"participant": {...}}` or of plain objects.
+fn parse_json(content: &str) -> Result<Vec<BTreeMap<String, String>>, String> {
+    let value: Value =
+        serde_json::from_str(content).map_err(|e| format!("The file is not valid JSON: {e}"))?;
+    let list = value
+        .pointer("/tournament/participants")
+        .or_else(|| value.get("participants"))
+        .unwrap_or(&value)
+        .as_array()
+        .ok_or("The file has no list of participants")?;
+    Ok(list
+        .iter()
+        .map(|entry| {
+            entry
+                .get("participant")
+                .unwrap_or(entry)
+                .as_object()
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .filter_map(|(key, value)| {
+                            let value = match value {
+                                Value::String(value) => value.clone(),
+                                Value::Number(value) => value.to_string(),
+                                _ => return None,
+                            };
+                            Some((key.to_lowercase(), value))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+        .collect())
+}
+
+/// Rows of a CSV file, keyed by the lowercased names of its header row.
+fn parse_csv(content: &str) -> Result<Vec<BTreeMap<String, String>>, String> {
+    let mut lines = content
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty());
+    let header: Vec<String> = split_csv_line(lines.next().ok_or("The file is empty")?)
+        .into_iter()
+        .map(|column| column.trim().to_lowercase().replace(' ', "_"))
+        .collect();
+    Ok(lines
+        .map(|line| header.iter().cloned().zip(split_csv_line(line)).collect())
+        .collect())
+}
+
+/// Splits a line of CSV, with fields optionally quoted and `""` for a quote inside quotes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            (c, _) => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_tournament, parse_participants, plan_import, skip_reason, Participant};
+    use crate::database::ban::{make_ban_doc, BANS};
+    use crate::database::config::make_config;
+    use crate::database::fixtures::{config, in_round, player, start};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::Repository;
+    use crate::database::update::record_result;
+    use dbc_bot::Region;
+    use mongodb::bson::doc;
+
+    #[test]
+    fn challonge_participants_are_read_from_json_and_csv() {
+        let json = r##"{"tournament": {"participants": [
+            {"participant": {"id": 7, "name": "Alice", "seed": 2, "misc": "#2pp 123456789012345678"}},
+            {"participant": {"id": 8, "name": "Bob", "seed": 1, "misc": "#8qq, 223456789012345678"}}
+        ]}}"##;
+        let participants = parse_participants("bracket.json", json).unwrap();
+        assert_eq!(
+            participants[0],
+            Participant {
+                name: "Alice".into(),
+                tag: "#2PP".into(),
+                discord_id: "123456789012345678".into(),
+                discord_name: "Alice".into(),
+                seed: Some(2),
+            }
+        );
+        let csv = "Name,Tag,Discord ID,Seed\n\"Carol, the third\",#9RR,323456789012345678,\n";
+        let participants = parse_participants("players.CSV", csv).unwrap();
+        assert_eq!(participants[0].name, "Carol, the third");
+        assert_eq!(participants[0].seed, None);
+        assert!(parse_participants("players.csv", "name,tag\nDave,#1AB\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn challonge_import_registers_and_seeds_players() {
+        let repository = MemoryRepository::new(config("Single Elimination", 0, 0));
+        repository.insert("Players", player(9));
+        let participants: Vec<(Participant, Option<serde_json::Value>)> =
+            [(1, Some(2)), (2, Some(1)), (3, None)]
+                .into_iter()
+                .map(|(n, seed)| {
+                    let participant = Participant {
+                        name: format!("Player {n}"),
+                        tag: format!("#P{n}"),
+                        discord_id: n.to_string(),
+                        discord_name: format!("player{n}"),
+                        seed,
+                    };
+                    // Only the first account could be looked up
+                    let profile = (n == 1).then(|| {
+                        serde_json::json!({
+                            "name": "In-game name",
+                            "tag": "#P1",
+                            "icon": {"id": 28000042},
+                            "nameColor": null,
+                        })
+                    });
+                    (participant, profile)
+                })
+                .collect();
+        repository
+            .apply(plan_import(&Region::EU, &participants))
+            .await
+            .unwrap();
+
+        let players = repository.documents("Players");
+        let tags: Vec<&str> = players
+            .iter()
+            .map(|player| player.get_str("tag").unwrap())
+            .collect();
+        assert_eq!(tags, vec!["#P1", "#P2", "#P3"]);
+        assert_eq!(players[0].get_str("name"), Ok("In-game name"));
+        assert_eq!(players[0].get_i64("icon"), Ok(28000042));
+        assert_eq!(players[1].get_str("name"), Ok("Player 2"));
+        let config = repository.config().await.unwrap().unwrap();
+        assert_eq!(config.get_str("seeding"), Ok("Manual"));
+        assert_eq!(
+            config.get_array("seed_list").unwrap(),
+            &vec!["#P2".into(), "#P1".into(), "#P3".into()]
+        );
+    }
+
+    #[tokio::test]
+    async fn challonge_import_skips_banned_and_ineligible_participants() {
+        let general = MemoryRepository::default();
+        general.insert(
+            BANS,
+            make_ban_doc(Some("1"), None, "Cheating", "9", None, None),
+        );
+        let mut settings = make_config();
+        settings.insert("eligibility", doc! {"min_trophies": 10000});
+        let participant = |n: i32| Participant {
+            name: format!("Player {n}"),
+            tag: format!("#P{n}"),
+            discord_id: n.to_string(),
+            discord_name: format!("player{n}"),
+            seed: None,
+        };
+        let account = serde_json::json!({"trophies": 20000});
+        let reason = |n: i32, profile: Option<serde_json::Value>| {
+            let (general, settings) = (&general, &settings);
+            async move {
+                skip_reason(
+                    general,
+                    settings,
+                    &Region::EU,
+                    &participant(n),
+                    profile.as_ref(),
+                )
+                .await
+                .unwrap()
+            }
+        };
+        assert_eq!(
+            reason(1, Some(account.clone())).await.as_deref(),
+            Some("Banned: Cheating")
+        );
+        assert!(reason(2, Some(serde_json::json!({"trophies": 500})))
+            .await
+            .is_some());
+        // Accounts that could not be looked up are only checked for bans
+        assert_eq!(reason(2, None).await, None);
+        assert!(reason(1, None).await.is_some());
+        assert_eq!(reason(2, Some(account)).await, None);
+    }
+
+    #[tokio::test]
+    async fn challonge_export_lists_participants_and_matches() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 2));
+        for player in start(&repository, 4, 4).await {
+            repository.insert("Players", player);
+        }
+        let winner = in_round(&repository, "Round 1", 1).await;
+        let loser = in_round(&repository, "Round 1", 4).await;
+        record_result(&repository, &Region::EU, "Round 1", &winner, &loser, None)
+            .await
+            .unwrap();
+
+        let export = export_tournament(&repository, &Region::EU).await.unwrap();
+        let tournament = &export["tournament"];
+        assert_eq!(tournament["participants"].as_array().unwrap().len(), 4);
+        let matches = tournament["matches"].as_array().unwrap();
+        let states: Vec<(i64, &str)> = matches
+            .iter()
+            .map(|game| {
+                (
+                    game["match"]["round"].as_i64().unwrap(),
+                    game["match"]["state"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(states, vec![(1, "complete"), (1, "open"), (2, "pending")]);
+        let first = &matches[0]["match"];
+        assert_eq!(first["winner_id"], 1);
+        assert_eq!(first["loser_id"], 4);
+    }
+}
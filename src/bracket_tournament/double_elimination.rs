@@ -0,0 +1,201 @@
+//! Layout of a double-elimination bracket.
+//!
+//! The winners bracket is stored in the usual `Round N` collections. Players who lose there drop into
+//! `Losers Round N` collections, and the champions of both brackets meet in the `Grand Final`.
+//!
+//! The tournament moves forward in stages (the `round` field of the config). At stage `s` the
+//! winners bracket plays `Round s` while the losers bracket plays `Losers Round s-1`, so the losers
+//! bracket always has the dropped players of the previous stage available. With `total` winners
+//! rounds, the grand final is played at stage `2 * total` and the optional reset at `2 * total + 1`.
+use mongodb::bson::Document;
+
+pub const LOSERS_ROUND: &str = "Losers Round";
+pub const GRAND_FINAL: &str = "Grand Final";
+pub const GRAND_FINAL_RESET: &str = "Grand Final Reset";
+
+pub fn losers_round_name(round: i32) -> String {
+    format!("{LOSERS_ROUND} {round}")
+}
+
+/// Returns the losers round number of a collection name, e.g. `Losers Round 3` -> `3`.
+pub fn parse_losers_round(name: &str) -> Option<i32> {
+    name.strip_prefix(LOSERS_ROUND)?.trim().parse::<i32>().ok()
+}
+
+/// Returns the winners round number of a collection name, e.g. `Round 3` -> `3`.
+pub fn parse_winners_round(name: &str) -> Option<i32> {
+    name.strip_prefix("Round ")?.trim().parse::<i32>().ok()
+}
+
+/// Number of rounds in the losers bracket for a winners bracket of `total` rounds.
+pub fn losers_rounds(total: i32) -> i32 {
+    (2 * (total - 1)).max(0)
+}
+
+pub fn grand_final_stage(total: i32) -> i32 {
+    2 * total
+}
+
+/// Number of stages needed to finish the tournament, counting the bracket reset if it is enabled.
+pub fn total_stages(total: i32, bracket_reset: bool) -> i32 {
+    grand_final_stage(total) + bracket_reset as i32
+}
+
+/// Collections that are played at the given stage. Stages past the grand final stay on the reset.
+pub fn stage_rounds(stage: i32, total: i32) -> Vec<String> {
+    if stage == 0 {
+        return vec!["Players".to_string()];
+    }
+    let mut rounds = vec![];
+    if stage <= total {
+        rounds.push(format!("Round {stage}"));
+    }
+    if (1..=losers_rounds(total)).contains(&(stage - 1)) {
+        rounds.push(losers_round_name(stage - 1));
+    }
+    if stage == grand_final_stage(total) {
+        rounds.push(GRAND_FINAL.to_string());
+    }
+    if stage > grand_final_stage(total) {
+        rounds.push(GRAND_FINAL_RESET.to_string());
+    }
+    rounds
+}
+
+pub fn matches_in_winners_round(round: i32, total: i32) -> i32 {
+    1 << (total - round).max(0)
+}
+
+/// Losers rounds come in pairs of equal size: the odd round is played among the losers bracket
+/// itself and the even round adds the players dropping from the winners bracket.
+pub fn matches_in_losers_round(round: i32, total: i32) -> i32 {
+    1 << (total - 1 - (round + 1) / 2).max(0)
+}
+
+/// Matches scheduled in a collection of a double-elimination bracket.
+pub fn matches_in_round(name: &str, total: i32) -> i32 {
+    if let Some(round) = parse_winners_round(name) {
+        matches_in_winners_round(round, total)
+    } else if let Some(round) = parse_losers_round(name) {
+        matches_in_losers_round(round, total)
+    } else {
+        1
+    }
+}
+
+/// Where the loser of a winners bracket match drops to, as `(losers round, match id)`.
+///
+/// Losers of later rounds are placed in reverse order so that they do not immediately meet the
+/// player they have already lost to.
+pub fn losers_drop(round: i32, match_id: i32, total: i32) -> (i32, i32) {
+    if round == 1 {
+        (1, (match_id + 1) / 2)
+    } else {
+        let losers_round = 2 * (round - 1);
+        let matches = matches_in_losers_round(losers_round, total);
+        (losers_round, matches + 1 - match_id)
+    }
+}
+
+/// Where the winner of a losers bracket match advances to, as `(losers round, match id)`.
+///
+/// Returns `None` for the losers final, whose winner goes to the grand final.
+pub fn losers_advance(round: i32, match_id: i32, total: i32) -> Option<(i32, i32)> {
+    if round >= losers_rounds(total) {
+        None
+    } else if round % 2 == 1 {
+        Some((round + 1, match_id))
+    } else {
+        Some((round + 1, (match_id + 1) / 2))
+    }
+}
+
+/// Whether the player entered the grand final as the champion of the winners bracket.
+pub fn is_from_winners(player: &Document) -> bool {
+    player.get_bool("from_winners").unwrap_or(false)
+}
+
+/// Whether losing a match in this collection knocks the player out of the tournament.
+pub fn loser_is_eliminated(round: &str, winner: &Document, bracket_reset: bool) -> bool {
+    if parse_winners_round(round).is_some() {
+        false
+    } else if round == GRAND_FINAL {
+        is_from_winners(winner) || !bracket_reset
+    } else {
+        true
+    }
+}
+
+/// Whether winning a match in this collection makes the player the tournament champion.
+pub fn decides_champion(round: &str, winner: &Document, bracket_reset: bool) -> bool {
+    match round {
+        GRAND_FINAL => is_from_winners(winner) || !bracket_reset,
+        GRAND_FINAL_RESET => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::fixtures::{config, in_round, match_ids, start};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::Repository;
+    use crate::database::update::record_result;
+    use dbc_bot::Region;
+    use mongodb::bson::doc;
+
+    #[tokio::test]
+    async fn double_elimination_loser_drops_to_losers_bracket() {
+        let repository = MemoryRepository::new(config("Double Elimination", 1, 2));
+        start(&repository, 4, 4).await;
+        for (winner, loser) in [(1, 4), (2, 3)] {
+            let winner = in_round(&repository, "Round 1", winner).await;
+            let loser = in_round(&repository, "Round 1", loser).await;
+            record_result(&repository, &Region::EU, "Round 1", &winner, &loser, None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            match_ids(&repository, "Round 2"),
+            vec![(Some("#P1".into()), 1), (Some("#P2".into()), 1)]
+        );
+        assert_eq!(
+            match_ids(&repository, "Losers Round 1"),
+            vec![(Some("#P3".into()), 1), (Some("#P4".into()), 1)]
+        );
+        let dropped = in_round(&repository, "Losers Round 1", 4).await;
+        assert!(dropped.get("defeated").is_none());
+        assert_eq!(dropped.get_bool("battle"), Ok(false));
+    }
+
+    #[tokio::test]
+    async fn double_elimination_byes_resolve_themselves() {
+        let repository = MemoryRepository::new(config("Double Elimination", 1, 2));
+        start(&repository, 2, 4).await;
+        for n in [1, 2] {
+            let winner = in_round(&repository, "Round 1", n).await;
+            let bye = repository
+                .find_in_round(
+                    "Round 1",
+                    doc! {"tag": null, "match_id": winner.get_i32("match_id").unwrap()},
+                )
+                .await
+                .unwrap()
+                .remove(0);
+            record_result(&repository, &Region::EU, "Round 1", &winner, &bye, None)
+                .await
+                .unwrap();
+        }
+        // Both byes dropped into the same losers match, which nobody has to play
+        let byes = repository.documents("Losers Round 1");
+        assert_eq!(byes.len(), 2);
+        assert!(byes.iter().all(|bye| bye.get_bool("battle") == Ok(true)));
+        assert_eq!(
+            repository
+                .count_in_round("Losers Round 2", doc! {"tag": null})
+                .await
+                .unwrap(),
+            1
+        );
+    }
+}
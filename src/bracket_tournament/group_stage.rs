@@ -0,0 +1,491 @@
+//! Round-robin group stage played before the single-elimination bracket.
+//!
+//! Every matchday of the group stage is stored in a `Group Round N` collection, using the same
+//! player documents as the elimination rounds plus a `group` field. Match ids are unique within a
+//! matchday across all groups, so the usual submission flow works unchanged. Once the last matchday
+//! is finished, the top players of each group are seeded into `Round 1`.
+use std::collections::HashMap;
+
+use crate::bracket_tournament::seeding::{bracket_positions, seed_players};
+use crate::database::config::{find_config, get_config};
+use crate::database::find::is_disqualified;
+use crate::database::mannequin::add_mannequin;
+use crate::database::repository::{Repository, Write, CONFIG, PLAYERS};
+use crate::database::team::members;
+use crate::database::update::move_to_match;
+use crate::discord::role::remove_role_by_id;
+use crate::{Context, Error};
+use dbc_bot::Region;
+use mongodb::bson::{doc, Document};
+use tracing::error;
+
+pub const GROUP_ROUND: &str = "Group Round";
+
+pub fn group_round_name(round: i32) -> String {
+    format!("{GROUP_ROUND} {round}")
+}
+
+/// Returns the matchday of a collection name, e.g. `Group Round 2` -> `2`.
+pub fn parse_group_round(name: &str) -> Option<i32> {
+    name.strip_prefix(GROUP_ROUND)?.trim().parse::<i32>().ok()
+}
+
+/// Letter used to name a group, e.g. `0` -> `A`.
+pub fn group_name(index: usize) -> String {
+    ((b'A' + (index % 26) as u8) as char).to_string()
+}
+
+/// Number of matchdays needed for a round robin between `players` players.
+pub fn matchdays(players: usize) -> usize {
+    match players {
+        0 | 1 => 0,
+        n if n % 2 == 0 => n - 1,
+        n => n,
+    }
+}
+
+/// Generates every pairing of a round robin with the circle method, one `Vec` per matchday.
+///
+/// With an odd number of players, one player rests on each matchday.
+pub fn round_robin(players: usize) -> Vec<Vec<(usize, usize)>> {
+    let slots = players + players % 2;
+    let mut circle: Vec<usize> = (0..slots).collect();
+    let mut schedule = vec![];
+    for _ in 0..matchdays(players) {
+        let mut pairs = vec![];
+        for i in 0..slots / 2 {
+            let (home, away) = (circle[i], circle[slots - 1 - i]);
+            if home < players && away < players {
+                pairs.push((home, away));
+            }
+        }
+        schedule.push(pairs);
+        circle[1..].rotate_right(1);
+    }
+    schedule
+}
+
+/// Splits the players into `groups` groups of at most `group_size` players, given in seeding order.
+///
+/// Seeds are dealt in a snake (A, B, C, C, B, A, ...) so every group gets a similar spread of seeds.
+pub fn split_into_groups(
+    players: Vec<Document>,
+    groups: usize,
+    group_size: usize,
+) -> Result<Vec<Vec<Document>>, Error> {
+    if groups == 0 || group_size < 2 {
+        return Err("The group stage is not configured!".into());
+    }
+    if players.len() > groups * group_size {
+        return Err(format!(
+            "{} players do not fit into {} groups of {}!",
+            players.len(),
+            groups,
+            group_size
+        )
+        .into());
+    }
+    if players.len() < groups * 2 {
+        return Err(format!(
+            "{} players are not enough to fill {} groups!",
+            players.len(),
+            groups
+        )
+        .into());
+    }
+    let mut split = vec![vec![]; groups];
+    for (index, player) in players.into_iter().enumerate() {
+        let (row, column) = (index / groups, index % groups);
+        let group = if row % 2 == 0 {
+            column
+        } else {
+            groups - 1 - column
+        };
+        split[group].push(player);
+    }
+    Ok(split)
+}
+
+/// A player's record in their group.
+#[derive(Debug, Clone)]
+pub struct Standing {
+    pub player: Document,
+    pub wins: i32,
+    pub losses: i32,
+    /// Wins against the other players with the same number of wins.
+    pub head_to_head: i32,
+    /// Total wins of the opponents this player has beaten.
+    pub strength: i32,
+    pub disqualified: bool,
+}
+
+/// Ranks players from their finished matches, given as `(winner tag, loser tag)`.
+///
+/// Ties on wins are broken by the results between the tied players, then by the wins of the beaten
+/// opponents, and finally by tag so the order is stable. Disqualified players are always ranked last.
+pub fn rank(players: Vec<Document>, results: &[(String, String)]) -> Vec<Standing> {
+    let tag = |player: &Document| player.get_str("tag").unwrap_or("").to_string();
+    let mut wins: HashMap<String, i32> = HashMap::new();
+    let mut losses: HashMap<String, i32> = HashMap::new();
+    for (winner, loser) in results {
+        *wins.entry(winner.clone()).or_default() += 1;
+        *losses.entry(loser.clone()).or_default() += 1;
+    }
+    let mut standings: Vec<Standing> = players
+        .into_iter()
+        .map(|player| {
+            let own = tag(&player);
+            let strength = results
+                .iter()
+                .filter(|(winner, _)| *winner == own)
+                .map(|(_, loser)| wins.get(loser).copied().unwrap_or(0))
+                .sum();
+            Standing {
+                wins: wins.get(&own).copied().unwrap_or(0),
+                losses: losses.get(&own).copied().unwrap_or(0),
+                head_to_head: 0,
+                strength,
+                disqualified: is_disqualified(&player),
+                player,
+            }
+        })
+        .collect();
+    for index in 0..standings.len() {
+        let own = tag(&standings[index].player);
+        let tied: Vec<String> = standings
+            .iter()
+            .filter(|other| other.wins == standings[index].wins)
+            .map(|other| tag(&other.player))
+            .collect();
+        standings[index].head_to_head = results
+            .iter()
+            .filter(|(winner, loser)| *winner == own && tied.contains(loser))
+            .count() as i32;
+    }
+    standings.sort_by(|a, b| {
+        a.disqualified
+            .cmp(&b.disqualified)
+            .then(b.wins.cmp(&a.wins))
+            .then(b.head_to_head.cmp(&a.head_to_head))
+            .then(b.strength.cmp(&a.strength))
+            .then(tag(&a.player).cmp(&tag(&b.player)))
+    });
+    standings
+}
+
+/// Creates every matchday of the group stage from the registered players.
+///
+/// Returns the number of matchdays.
+pub async fn setup_group_stage(database: &dyn Repository) -> Result<i32, Error> {
+    let config = find_config(database).await;
+    let groups = split_into_groups(
+        seed_players(database).await?,
+        config.get_i32("groups").unwrap_or(0).max(0) as usize,
+        config.get_i32("group_size").unwrap_or(0).max(0) as usize,
+    )?;
+    let mut rounds: Vec<Vec<Document>> = vec![];
+    for (index, group) in groups.iter().enumerate() {
+        for player in group {
+            database
+                .update_in_round(
+                    PLAYERS,
+                    doc! {"_id": player.get_object_id("_id")?},
+                    doc! {"$set": {"group": group_name(index)}},
+                )
+                .await?;
+        }
+        for (day, pairs) in round_robin(group.len()).into_iter().enumerate() {
+            if rounds.len() <= day {
+                rounds.push(vec![]);
+            }
+            for (home, away) in pairs {
+                let match_id = rounds[day].len() as i32 / 2 + 1;
+                for player in [&group[home], &group[away]] {
+                    let mut player = move_to_match(player.clone(), match_id);
+                    player.insert("group", group_name(index));
+                    rounds[day].push(player);
+                }
+            }
+        }
+    }
+    for (day, players) in rounds.iter().enumerate() {
+        for player in players {
+            database
+                .insert_into_round(&group_round_name(day as i32 + 1), player)
+                .await?;
+        }
+    }
+    Ok(rounds.len() as i32)
+}
+
+/// Writes that forfeit every remaining group match of a disqualified player, so their opponents get
+/// free wins.
+pub async fn forfeit_group_matches(
+    database: &dyn Repository,
+    round: &str,
+    player: &Document,
+    reason: &str,
+) -> Result<Vec<Write>, Error> {
+    let current = parse_group_round(round).unwrap_or(0);
+    let mut writes = vec![];
+    for name in database.round_names().await? {
+        if parse_group_round(&name).is_some_and(|later| later > current) {
+            writes.push(Write::Update {
+                round: name,
+                filter: doc! {"tag": player.get_str("tag")?},
+                update: doc! {"$set": {"reason": reason}},
+            });
+        }
+    }
+    Ok(writes)
+}
+
+/// Computes the standings of every group, ordered by group name.
+pub async fn group_standings(
+    database: &dyn Repository,
+) -> Result<Vec<(String, Vec<Standing>)>, Error> {
+    let mut players: HashMap<String, HashMap<String, Document>> = HashMap::new();
+    let mut results: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    for name in database.round_names().await? {
+        if parse_group_round(&name).is_none() {
+            continue;
+        }
+        let documents = database.find_in_round(&name, doc! {}).await?;
+        for player in &documents {
+            let (Ok(group), Ok(tag)) = (player.get_str("group"), player.get_str("tag")) else {
+                continue;
+            };
+            let known = players.entry(group.to_string()).or_default();
+            if !known.get(tag).is_some_and(is_disqualified) {
+                known.insert(tag.to_string(), player.clone());
+            }
+            if player.get_bool("defeated") != Ok(false) {
+                continue;
+            }
+            let match_id = player.get_i32("match_id")?;
+            if let Some(loser) = documents.iter().find(|other| {
+                other.get_i32("match_id") == Ok(match_id) && other.get_str("tag") != Ok(tag)
+            }) {
+                results.entry(group.to_string()).or_default().push((
+                    tag.to_string(),
+                    loser.get_str("tag").unwrap_or("").to_string(),
+                ));
+            }
+        }
+    }
+    let mut standings: Vec<(String, Vec<Standing>)> = players
+        .into_iter()
+        .map(|(group, members)| {
+            let results = results.remove(&group).unwrap_or_default();
+            (group, rank(members.into_values().collect(), &results))
+        })
+        .collect();
+    standings.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(standings)
+}
+
+/// Seeds the top players of every group into `Round 1` of the elimination bracket, and removes the
+/// regional role from everyone knocked out in the group stage. Returns the number of rounds.
+pub async fn seed_knockout(ctx: &Context<'_>, region: &Region) -> Result<i32, Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let (writes, rounds, eliminated) = plan_knockout(database, region).await?;
+    database.apply(writes).await?;
+    let config = get_config(ctx, region).await;
+    for player in eliminated {
+        let mut team = vec![player.clone()];
+        team.extend(members(&player));
+        for member in team {
+            let Ok(id) = member.get_str("discord_id").unwrap_or("").parse::<u64>() else {
+                continue;
+            };
+            if let Err(e) = remove_role_by_id(ctx.serenity_context(), &config, id).await {
+                error!("{e}");
+            }
+        }
+    }
+    Ok(rounds)
+}
+
+/// Every write that seeds the top players of every group into `Round 1` of the elimination bracket.
+///
+/// Group winners are seeded first, then the runners-up and so on, and the seeds are placed in the
+/// standard bracket positions so that players from the same group meet as late as possible. Returns
+/// the writes, the number of rounds and the players knocked out in the group stage.
+pub async fn plan_knockout(
+    database: &dyn Repository,
+    region: &Region,
+) -> Result<(Vec<Write>, i32, Vec<Document>), Error> {
+    let config = find_config(database).await;
+    let advance = config.get_i32("group_advance").unwrap_or(0).max(1) as usize;
+    let standings = group_standings(database).await?;
+    let mut qualified = vec![];
+    for place in 0..advance {
+        for (_, group) in &standings {
+            if let Some(standing) = group.get(place).filter(|s| !s.disqualified) {
+                qualified.push(standing.player.clone());
+            }
+        }
+    }
+    if qualified.len() < 2 {
+        return Err("Not enough players qualified from the group stage!".into());
+    }
+    let eliminated = standings
+        .iter()
+        .flat_map(|(_, group)| group.iter().skip(advance))
+        .map(|standing| standing.player.clone())
+        .collect();
+    let rounds = (qualified.len() as f64).log2().ceil() as i32;
+    let size = 1_usize << rounds;
+    let mut writes = vec![];
+    for (slot, seed) in bracket_positions(size).into_iter().enumerate() {
+        let match_id = slot as i32 / 2 + 1;
+        let player = match qualified.get(seed - 1) {
+            Some(player) => {
+                let mut player = move_to_match(player.clone(), match_id);
+                player.insert("seed", seed as i32);
+                player
+            }
+            None => add_mannequin(region, Some(match_id)),
+        };
+        writes.push(Write::Insert {
+            round: "Round 1".to_string(),
+            document: player,
+        });
+    }
+    writes.push(Write::Update {
+        round: CONFIG.to_string(),
+        filter: doc! {},
+        update: doc! {"$set": {"total": rounds}},
+    });
+    Ok((writes, rounds, eliminated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{group_round_name, plan_knockout, rank, round_robin, setup_group_stage};
+    use crate::database::fixtures::{config, match_ids, player};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::Repository;
+    use dbc_bot::Region;
+    use mongodb::bson::{doc, Document};
+
+    fn group_of(repository: &MemoryRepository, n: i32) -> String {
+        let tag = format!("#P{n}");
+        repository
+            .documents("Players")
+            .iter()
+            .find(|player| player.get_str("tag") == Ok(&tag))
+            .and_then(|player| player.get_str("group").ok())
+            .unwrap_or("")
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn group_stage_deals_seeds_in_a_snake() {
+        let mut settings = config("Group Stage", 0, 0);
+        settings.insert("seeding", "Manual");
+        settings.insert(
+            "seed_list",
+            (1..=6).map(|n| format!("#P{n}")).collect::<Vec<_>>(),
+        );
+        settings.insert("groups", 2);
+        settings.insert("group_size", 3);
+        let repository = MemoryRepository::new(settings);
+        for n in 1..=6 {
+            repository.insert("Players", player(n));
+        }
+
+        assert_eq!(setup_group_stage(&repository).await.unwrap(), 3);
+        let groups: Vec<String> = (1..=6).map(|n| group_of(&repository, n)).collect();
+        assert_eq!(groups, vec!["A", "B", "B", "A", "A", "B"]);
+        for day in 1..=3 {
+            let ids = match_ids(&repository, &group_round_name(day));
+            assert_eq!(ids.len(), 4);
+            assert_eq!(ids.iter().filter(|(_, id)| *id == 1).count(), 2);
+            assert_eq!(ids.iter().filter(|(_, id)| *id == 2).count(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn group_winners_are_seeded_into_the_bracket() {
+        let mut settings = config("Group Stage", 1, 0);
+        settings.insert("seeding", "Manual");
+        settings.insert(
+            "seed_list",
+            (1..=4).map(|n| format!("#P{n}")).collect::<Vec<_>>(),
+        );
+        settings.insert("groups", 2);
+        settings.insert("group_size", 2);
+        settings.insert("group_advance", 1);
+        let repository = MemoryRepository::new(settings);
+        for n in 1..=4 {
+            repository.insert("Players", player(n));
+        }
+        setup_group_stage(&repository).await.unwrap();
+        for (n, defeated) in [(1, false), (4, true), (2, true), (3, false)] {
+            repository
+                .update_in_round(
+                    &group_round_name(1),
+                    doc! {"tag": format!("#P{n}")},
+                    doc! {"$set": {"battle": true, "defeated": defeated}},
+                )
+                .await
+                .unwrap();
+        }
+
+        let (writes, rounds, eliminated) = plan_knockout(&repository, &Region::EU).await.unwrap();
+        assert_eq!(rounds, 1);
+        let eliminated: Vec<&str> = eliminated
+            .iter()
+            .map(|player| player.get_str("tag").unwrap())
+            .collect();
+        assert_eq!(eliminated, vec!["#P4", "#P2"]);
+        repository.apply(writes).await.unwrap();
+        assert_eq!(
+            match_ids(&repository, "Round 1"),
+            vec![(Some("#P1".into()), 1), (Some("#P3".into()), 1)]
+        );
+        let config = repository.config().await.unwrap().unwrap();
+        assert_eq!(config.get_i32("total"), Ok(1));
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_once() {
+        let schedule = round_robin(5);
+        assert_eq!(schedule.len(), 5);
+        let mut pairs: Vec<(usize, usize)> = schedule
+            .into_iter()
+            .flatten()
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        assert_eq!(pairs.len(), 10);
+    }
+
+    #[test]
+    fn ties_are_broken_by_head_to_head() {
+        let players: Vec<Document> = (1..=4).map(player).collect();
+        let results: Vec<(String, String)> = [(2, 1), (1, 3), (1, 4), (2, 3), (4, 2), (3, 4)]
+            .into_iter()
+            .map(|(winner, loser)| (format!("#P{winner}"), format!("#P{loser}")))
+            .collect();
+        let tags = |standings: Vec<super::Standing>| -> Vec<String> {
+            standings
+                .iter()
+                .map(|standing| standing.player.get_str("tag").unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(
+            tags(rank(players.clone(), &results)),
+            vec!["#P2", "#P1", "#P3", "#P4"]
+        );
+
+        let mut players = players;
+        players[1].insert("reason", "Disqualified");
+        assert_eq!(
+            tags(rank(players, &results)),
+            vec!["#P1", "#P3", "#P4", "#P2"]
+        );
+    }
+}
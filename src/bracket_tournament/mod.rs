@@ -1 +1,8 @@
+pub mod best_of;
 pub mod bracket_update;
+pub mod challonge;
+pub mod double_elimination;
+pub mod group_stage;
+pub mod placements;
+pub mod seeding;
+pub mod swiss;
@@ -0,0 +1,476 @@
+//! Third-place match and final placements.
+//!
+//! When enabled, the semifinal losers of a single-elimination bracket meet in the `Third Place Match`
+//! collection, which is played alongside the final. Once the tournament is decided, every player is
+//! given a placement: players knocked out at the same point share a range of places, e.g. `5–8`.
+//! Placements are stored in the `Placements` collection, one document per tournament.
+use std::collections::HashSet;
+
+use crate::bracket_tournament::double_elimination::{
+    is_from_winners, losers_round_name, losers_rounds, GRAND_FINAL, GRAND_FINAL_RESET,
+};
+use crate::bracket_tournament::group_stage::group_standings;
+use crate::bracket_tournament::swiss::swiss_standings;
+use crate::database::config::{find_config, get_config};
+use crate::database::find::{is_disqualified, is_mannequin};
+use crate::database::mannequin::add_mannequin;
+use crate::database::repository::Repository;
+use crate::database::update::move_to_match;
+use crate::{Context, Error};
+use dbc_bot::{Format, Region};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::options::FindOneOptions;
+use mongodb::{Collection, Database};
+use poise::serenity_prelude::{ChannelId, Http, Timestamp};
+
+pub const THIRD_PLACE_MATCH: &str = "Third Place Match";
+
+/// Whether the third-place match is played in this tournament.
+pub fn has_third_place_match(config: &Document) -> bool {
+    matches!(
+        Format::from_config(config),
+        Format::SingleElimination | Format::GroupStage
+    ) && config.get_bool("third_place_match").unwrap_or(false)
+        && config.get_i32("total").unwrap_or(0) >= 2
+}
+
+/// Whether the final of the bracket is played at the current stage of the tournament.
+pub fn is_final_stage(config: &Document) -> bool {
+    config.get_i32("round").unwrap_or(0)
+        == config.get_i32("group_rounds").unwrap_or(0) + config.get_i32("total").unwrap_or(0)
+}
+
+/// Puts both semifinal losers into the third-place match. A disqualified loser is replaced by a
+/// mannequin, so their opponent gets a free win.
+pub async fn setup_third_place_match(ctx: &Context<'_>, region: &Region) -> Result<(), Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let config = get_config(ctx, region).await;
+    let semifinal = format!("Round {}", config.get_i32("total")? - 1);
+    let losers: Vec<Document> = database
+        .collection::<Document>(&semifinal)
+        .find(doc! {"defeated": true}, None)
+        .await?
+        .try_collect()
+        .await?;
+    let collection: Collection<Document> = database.collection(THIRD_PLACE_MATCH);
+    for index in 0..2 {
+        let player = match losers.get(index) {
+            Some(loser) if !is_mannequin(loser) && !is_disqualified(loser) => {
+                move_to_match(loser.clone(), 1)
+            }
+            _ => add_mannequin(region, Some(1)),
+        };
+        collection.insert_one(player, None).await?;
+    }
+    Ok(())
+}
+
+/// Places shared by everyone in a bucket of players, e.g. `5` and `8` for `5–8`.
+#[derive(Debug, Clone)]
+pub struct Placement {
+    pub first: usize,
+    pub last: usize,
+    pub player: Document,
+}
+
+impl Placement {
+    pub fn label(&self) -> String {
+        if self.first == self.last {
+            ordinal(self.first)
+        } else {
+            format!("{}–{}", self.first, self.last)
+        }
+    }
+}
+
+pub fn ordinal(place: usize) -> String {
+    let suffix = match (place % 10, place % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{place}{suffix}")
+}
+
+/// Turns buckets of players, from the best to the worst, into placements.
+pub fn share_places(buckets: Vec<Vec<Document>>) -> Vec<Placement> {
+    let mut placements = vec![];
+    let mut first = 1;
+    for bucket in buckets.into_iter().filter(|bucket| !bucket.is_empty()) {
+        let last = first + bucket.len() - 1;
+        for player in bucket {
+            placements.push(Placement {
+                first,
+                last,
+                player,
+            });
+        }
+        first = last + 1;
+    }
+    placements
+}
+
+async fn find_all(database: &dyn Repository, collection: &str) -> Result<Vec<Document>, Error> {
+    database
+        .find_in_round(collection, doc! {"tag": {"$ne": null}})
+        .await
+}
+
+/// Splits the players of a finished match into `(winners, losers)`.
+async fn match_result(
+    database: &dyn Repository,
+    collection: &str,
+) -> Result<(Vec<Document>, Vec<Document>), Error> {
+    Ok(find_all(database, collection)
+        .await?
+        .into_iter()
+        .filter(|player| player.get_bool("defeated").is_ok())
+        .partition(|player| player.get_bool("defeated") == Ok(false)))
+}
+
+async fn single_elimination_buckets(
+    database: &dyn Repository,
+    config: &Document,
+) -> Result<Vec<Vec<Document>>, Error> {
+    let total = config.get_i32("total")?;
+    let (champion, runner_up) = match_result(database, &format!("Round {total}")).await?;
+    let mut buckets = vec![champion, runner_up];
+    let (third, fourth) = if has_third_place_match(config) {
+        match_result(database, THIRD_PLACE_MATCH).await?
+    } else {
+        (vec![], vec![])
+    };
+    let podium: HashSet<String> = third
+        .iter()
+        .chain(fourth.iter())
+        .filter_map(|player| player.get_str("tag").ok().map(str::to_string))
+        .collect();
+    buckets.push(third);
+    buckets.push(fourth);
+    for round in (1..total).rev() {
+        let (_, losers) = match_result(database, &format!("Round {round}")).await?;
+        buckets.push(
+            losers
+                .into_iter()
+                .filter(|player| !podium.contains(player.get_str("tag").unwrap_or("")))
+                .collect(),
+        );
+    }
+    Ok(buckets)
+}
+
+async fn double_elimination_buckets(
+    database: &dyn Repository,
+    config: &Document,
+) -> Result<Vec<Vec<Document>>, Error> {
+    let total = config.get_i32("total")?;
+    let (reset_winner, reset_loser) = match_result(database, GRAND_FINAL_RESET).await?;
+    let mut buckets = if reset_winner.is_empty() {
+        let (winner, loser) = match_result(database, GRAND_FINAL).await?;
+        vec![winner, loser]
+    } else {
+        vec![reset_winner, reset_loser]
+    };
+    for round in (1..=losers_rounds(total)).rev() {
+        let (_, losers) = match_result(database, &losers_round_name(round)).await?;
+        buckets.push(losers);
+    }
+    Ok(buckets)
+}
+
+/// Players who did not make it out of the group stage, bucketed by their place in their group.
+async fn group_buckets(
+    database: &dyn Repository,
+    knockout: &HashSet<String>,
+) -> Result<Vec<Vec<Document>>, Error> {
+    let mut buckets: Vec<Vec<Document>> = vec![];
+    for (_, group) in group_standings(database).await? {
+        for (place, standing) in group.into_iter().enumerate() {
+            if knockout.contains(standing.player.get_str("tag").unwrap_or("")) {
+                continue;
+            }
+            if buckets.len() <= place {
+                buckets.resize(place + 1, vec![]);
+            }
+            buckets[place].push(standing.player);
+        }
+    }
+    Ok(buckets)
+}
+
+/// Computes the placement of every player from the rounds played.
+pub async fn compute_placements(
+    database: &dyn Repository,
+    config: &Document,
+) -> Result<Vec<Placement>, Error> {
+    let mut buckets = match Format::from_config(config) {
+        Format::Swiss => swiss_standings(database)
+            .await?
+            .into_iter()
+            .map(|record| vec![record.player])
+            .collect(),
+        Format::DoubleElimination => double_elimination_buckets(database, config).await?,
+        Format::SingleElimination | Format::GroupStage => {
+            single_elimination_buckets(database, config).await?
+        }
+    };
+    let mut placed: HashSet<String> = buckets
+        .iter()
+        .flatten()
+        .filter_map(|player| player.get_str("tag").ok().map(str::to_string))
+        .collect();
+    if Format::from_config(config) == Format::GroupStage {
+        let groups = group_buckets(database, &placed).await?;
+        placed.extend(
+            groups
+                .iter()
+                .flatten()
+                .filter_map(|player| player.get_str("tag").ok().map(str::to_string)),
+        );
+        buckets.extend(groups);
+    }
+    // Anyone left was knocked out without losing a match, e.g. disqualified before it was played
+    let unplaced = find_all(database, "Players")
+        .await?
+        .into_iter()
+        .filter(|player| !placed.contains(player.get_str("tag").unwrap_or("")))
+        .collect();
+    buckets.push(unplaced);
+    Ok(share_places(buckets))
+}
+
+/// Whether every match deciding a placement has been played.
+pub async fn is_finished(database: &dyn Repository, config: &Document) -> Result<bool, Error> {
+    let total = config.get_i32("total").unwrap_or(0);
+    let decided = |(winners, _): (Vec<Document>, Vec<Document>)| !winners.is_empty();
+    match Format::from_config(config) {
+        Format::Swiss => Ok(false),
+        Format::DoubleElimination => {
+            let bracket_reset = config.get_bool("bracket_reset").unwrap_or(false);
+            let (winners, _) = match_result(database, GRAND_FINAL).await?;
+            match winners.first() {
+                Some(winner) if is_from_winners(winner) || !bracket_reset => Ok(true),
+                Some(_) => Ok(decided(match_result(database, GRAND_FINAL_RESET).await?)),
+                None => Ok(false),
+            }
+        }
+        Format::SingleElimination | Format::GroupStage => Ok(decided(
+            match_result(database, &format!("Round {total}")).await?,
+        ) && (!has_third_place_match(config)
+            || decided(match_result(database, THIRD_PLACE_MATCH).await?))),
+    }
+}
+
+/// Stores the placements once the tournament is decided. Returns them the first time only.
+///
+/// Swiss tournaments have no deciding match, so they are recorded with `force` once the last round
+/// is over.
+pub async fn record_placements(
+    database: &Database,
+    force: bool,
+) -> Result<Option<Vec<Placement>>, Error> {
+    let config = find_config(database).await;
+    if config.get_bool("placements_recorded").unwrap_or(false)
+        || !(force || is_finished(database, &config).await?)
+    {
+        return Ok(None);
+    }
+    let placements = compute_placements(database, &config).await?;
+    let stored: Vec<Document> = placements
+        .iter()
+        .map(|placement| {
+            doc! {
+                "first": placement.first as i32,
+                "last": placement.last as i32,
+                "place": placement.label(),
+                "name": display_name(&placement.player),
+                "tag": placement.player.get_str("tag").unwrap_or(""),
+                "discord_id": placement.player.get_str("discord_id").unwrap_or(""),
+            }
+        })
+        .collect();
+    database
+        .collection::<Document>("Placements")
+        .insert_one(
+            doc! {
+                "finished_at": DateTime::now(),
+                "format": format!("{:?}", Format::from_config(&config)),
+                "placements": stored,
+            },
+            None,
+        )
+        .await?;
+    database
+        .collection::<Document>("Config")
+        .update_one(doc! {}, doc! {"$set": {"placements_recorded": true}}, None)
+        .await?;
+    Ok(Some(placements))
+}
+
+/// The latest placements stored for a region, as `(place, name, discord id)`.
+pub async fn latest_placements(
+    database: &Database,
+) -> Result<Option<Vec<(String, String, String)>>, Error> {
+    let options = FindOneOptions::builder()
+        .sort(doc! {"finished_at": -1})
+        .build();
+    let Some(latest) = database
+        .collection::<Document>("Placements")
+        .find_one(None, options)
+        .await?
+    else {
+        return Ok(None);
+    };
+    Ok(Some(
+        latest
+            .get_array("placements")?
+            .iter()
+            .filter_map(|placement| placement.as_document())
+            .map(|placement| {
+                (
+                    placement.get_str("place").unwrap_or("").to_string(),
+                    placement.get_str("name").unwrap_or("").to_string(),
+                    placement.get_str("discord_id").unwrap_or("").to_string(),
+                )
+            })
+            .collect(),
+    ))
+}
+
+/// Announces the final placements in the channel where results are published.
+pub async fn post_placements(
+    http: impl AsRef<Http>,
+    database: &Database,
+    region: &Region,
+    placements: &[Placement],
+) -> Result<(), Error> {
+    let config = find_config(database).await;
+    let Ok(channel) = config.get_str("channel").unwrap_or("").parse::<u64>() else {
+        return Ok(());
+    };
+    let rows: Vec<(String, String, String)> = placements
+        .iter()
+        .map(|placement| {
+            (
+                placement.label(),
+                display_name(&placement.player),
+                placement
+                    .player
+                    .get_str("discord_id")
+                    .unwrap_or("")
+                    .to_string(),
+            )
+        })
+        .collect();
+    ChannelId(channel)
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.title(format!("Final placements - {}", region.full()))
+                    .description(format_placements(&rows))
+                    .color(0xFFFF00)
+                    .timestamp(Timestamp::now())
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+/// Team name for team tournaments, otherwise the in-game name.
+pub fn display_name(player: &Document) -> String {
+    player
+        .get_str("team_name")
+        .or(player.get_str("name"))
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Formats placements for an embed, cut to fit in its description.
+pub fn format_placements(placements: &[(String, String, String)]) -> String {
+    const ROWS: usize = 50;
+    let mut lines: Vec<String> = placements
+        .iter()
+        .take(ROWS)
+        .map(|(place, name, discord_id)| {
+            let medal = match place.as_str() {
+                "1st" => "🥇",
+                "2nd" => "🥈",
+                "3rd" => "🥉",
+                _ => "▫️",
+            };
+            format!("{medal} **{place}** {name} (<@{discord_id}>)")
+        })
+        .collect();
+    if placements.len() > ROWS {
+        lines.push(format!("...and {} more players", placements.len() - ROWS));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compute_placements, is_finished, ordinal};
+    use crate::database::fixtures::{config, in_round, player, start};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::update::record_result;
+    use dbc_bot::Region;
+
+    async fn win(repository: &MemoryRepository, round: &str, winner: i32, loser: i32) {
+        let winner = in_round(repository, round, winner).await;
+        let loser = in_round(repository, round, loser).await;
+        record_result(repository, &Region::EU, round, &winner, &loser, None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn knocked_out_players_share_places() {
+        let settings = config("Single Elimination", 2, 2);
+        let repository = MemoryRepository::new(settings.clone());
+        for player in start(&repository, 4, 4).await {
+            repository.insert("Players", player);
+        }
+        // Registered, but disqualified before playing a match
+        repository.insert("Players", player(5));
+        win(&repository, "Round 1", 1, 4).await;
+        win(&repository, "Round 1", 2, 3).await;
+        assert!(!is_finished(&repository, &settings).await.unwrap());
+        win(&repository, "Round 2", 2, 1).await;
+        assert!(is_finished(&repository, &settings).await.unwrap());
+
+        let mut placements: Vec<(String, String)> = compute_placements(&repository, &settings)
+            .await
+            .unwrap()
+            .iter()
+            .map(|placement| {
+                (
+                    placement.label(),
+                    placement.player.get_str("tag").unwrap().to_string(),
+                )
+            })
+            .collect();
+        let expected = [
+            ("1st", "#P2"),
+            ("2nd", "#P1"),
+            ("3–4", "#P3"),
+            ("3–4", "#P4"),
+            ("5th", "#P5"),
+        ];
+        placements[2..4].sort();
+        assert_eq!(
+            placements,
+            expected.map(|(place, tag)| (place.to_string(), tag.to_string()))
+        );
+    }
+
+    #[test]
+    fn ordinals_use_the_right_suffix() {
+        let ordinals: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 111].map(ordinal).to_vec();
+        assert_eq!(
+            ordinals,
+            ["1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "111th"]
+        );
+    }
+}
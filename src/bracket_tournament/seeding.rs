@@ -0,0 +1,127 @@
+//! Seeding of the elimination bracket.
+//!
+//! Players are first ordered by the seeding chosen in the config, then placed with the standard
+//! bracket positions (1 vs N, 2 vs N-1, ...), so the top seeds can only meet in the last rounds and
+//! the byes of an incomplete bracket go to the top seeds.
+use crate::brawlstars::api::{request, APIResult};
+use crate::database::config::find_config;
+use crate::database::repository::{Repository, PLAYERS};
+use crate::Error;
+use dbc_bot::Seeding;
+use mongodb::bson::{doc, Document};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use tracing::error;
+
+/// Seed placed in each slot of a bracket of `size` players, e.g. `8` -> `[1, 8, 4, 5, 2, 7, 3, 6]`.
+///
+/// Two consecutive slots play the same first-round match. `size` must be a power of two.
+pub fn bracket_positions(size: usize) -> Vec<usize> {
+    if size == 0 {
+        return vec![];
+    }
+    let mut positions = vec![1];
+    while positions.len() < size {
+        let sum = positions.len() * 2 + 1;
+        positions = positions
+            .iter()
+            .flat_map(|&seed| [seed, sum - seed])
+            .collect();
+    }
+    positions
+}
+
+/// The characters a Brawl Stars player tag is made of.
+const TAG_CHARACTERS: &str = "0289PYLQGRJCUV";
+
+/// Normalises a player tag so that tags typed by hosts match the ones stored, e.g. `abc` -> `#ABC`.
+pub fn normalise_tag(tag: &str) -> String {
+    format!("#{}", tag.trim().trim_start_matches('#').to_uppercase())
+}
+
+/// Whether a tag, once normalised, can be a Brawl Stars player tag.
+pub fn is_valid_tag(tag: &str) -> bool {
+    let tag = normalise_tag(tag);
+    (4..=13).contains(&tag.len()) && tag[1..].chars().all(|c| TAG_CHARACTERS.contains(c))
+}
+
+/// Reads the manual seed list of a region's config, from the first seed to the last.
+pub fn seed_list(config: &Document) -> Vec<String> {
+    config
+        .get_array("seed_list")
+        .map(|list| {
+            list.iter()
+                .filter_map(|tag| tag.as_str())
+                .map(normalise_tag)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Orders the players by their position in the manual seed list.
+///
+/// Players missing from the list are seeded after the listed ones, in registration order.
+pub fn order_by_list(mut players: Vec<Document>, list: &[String]) -> Vec<Document> {
+    players.sort_by_key(|player| {
+        let tag = normalise_tag(player.get_str("tag").unwrap_or(""));
+        list.iter().position(|t| *t == tag).unwrap_or(usize::MAX)
+    });
+    players
+}
+
+/// Shuffles the players with a seeded generator, so the same seed always gives the same draw.
+pub fn order_randomly(mut players: Vec<Document>, seed: u64) -> Vec<Document> {
+    players.shuffle(&mut StdRng::seed_from_u64(seed));
+    players
+}
+
+/// Orders the players by a trophy count of their Brawl Stars profile, highest first.
+///
+/// Players whose profile cannot be fetched are counted with 0 trophies.
+async fn order_by_trophies(players: Vec<Document>, key: &str) -> Vec<Document> {
+    let mut counted = vec![];
+    for player in players {
+        let tag = player.get_str("tag").unwrap_or("").to_string();
+        let trophies = match request("player", &tag).await {
+            Ok(APIResult::Successful(profile)) => profile[key].as_i64().unwrap_or(0),
+            Ok(_) | Err(_) => {
+                error!("Failed to get the trophies of {tag} for seeding");
+                0
+            }
+        };
+        counted.push((trophies, player));
+    }
+    counted.sort_by_key(|(trophies, _)| std::cmp::Reverse(*trophies));
+    counted.into_iter().map(|(_, player)| player).collect()
+}
+
+/// Returns the registered players of a region in seeding order, from the first seed to the last.
+///
+/// A random draw uses the seed recorded in the config, or records a new one, so the draw can be
+/// reproduced later.
+pub async fn seed_players(database: &dyn Repository) -> Result<Vec<Document>, Error> {
+    let config = find_config(database).await;
+    let players = database
+        .find_in_round(PLAYERS, doc! {"discord_id": {"$ne": null}})
+        .await?;
+    let ordered = match Seeding::from_config(&config) {
+        Seeding::Trophies => order_by_trophies(players, "trophies").await,
+        Seeding::HighestTrophies => order_by_trophies(players, "highestTrophies").await,
+        Seeding::Manual => order_by_list(players, &seed_list(&config)),
+        Seeding::Random => {
+            let seed = match config.get_i64("rng_seed") {
+                Ok(seed) => seed,
+                Err(_) => {
+                    let seed = rand::random::<u32>() as i64;
+                    database
+                        .update_config(doc! {"$set": {"rng_seed": seed}})
+                        .await?;
+                    seed
+                }
+            };
+            order_randomly(players, seed as u64)
+        }
+    };
+    Ok(ordered)
+}
@@ -0,0 +1,311 @@
+//! Swiss-system rounds.
+//!
+//! Swiss rounds reuse the `Round N` collections, but nobody is eliminated: every round pairs
+//! players with the same number of wins who have not met before, and the next round is only
+//! generated once the current one is finished. A bye is given by pairing a player with a mannequin,
+//! which the usual submission flow already turns into a free win.
+use std::collections::HashMap;
+
+use crate::bracket_tournament::double_elimination::parse_winners_round;
+use crate::database::find::{is_disqualified, is_mannequin};
+use crate::database::mannequin::add_mannequin;
+use crate::database::repository::{Repository, PLAYERS};
+use crate::database::update::move_to_match;
+use crate::Error;
+use dbc_bot::Region;
+use mongodb::bson::{doc, Document};
+
+/// Upper bound on the pairing search before rematches are allowed, so a round is always generated.
+const PAIRING_BUDGET: usize = 100_000;
+
+/// A player's results over the Swiss rounds played so far.
+#[derive(Debug, Clone)]
+pub struct SwissRecord {
+    pub player: Document,
+    pub tag: String,
+    pub wins: i32,
+    pub losses: i32,
+    /// Tags of every real opponent, in the order they were played.
+    pub opponents: Vec<String>,
+    /// Tags of the opponents this player has beaten.
+    pub beaten: Vec<String>,
+    pub had_bye: bool,
+    pub disqualified: bool,
+    /// Sum of the wins of every opponent.
+    pub buchholz: i32,
+    /// Sum of the wins of every beaten opponent.
+    pub sonneborn: i32,
+}
+
+impl SwissRecord {
+    fn new(player: Document) -> Self {
+        SwissRecord {
+            tag: player.get_str("tag").unwrap_or("").to_string(),
+            disqualified: is_disqualified(&player),
+            player,
+            wins: 0,
+            losses: 0,
+            opponents: vec![],
+            beaten: vec![],
+            had_bye: false,
+            buchholz: 0,
+            sonneborn: 0,
+        }
+    }
+}
+
+/// Reads the results of every Swiss round and returns the standings.
+///
+/// Players are ranked by wins, then Buchholz (the wins of all their opponents), then the wins of the
+/// opponents they have beaten, and finally by tag so the order is stable. Disqualified players are
+/// always ranked last.
+pub async fn swiss_standings(database: &dyn Repository) -> Result<Vec<SwissRecord>, Error> {
+    let players = database
+        .find_in_round(PLAYERS, doc! {"discord_id": {"$ne": null}})
+        .await?;
+    let mut records: HashMap<String, SwissRecord> = players
+        .into_iter()
+        .map(|player| {
+            let record = SwissRecord::new(player);
+            (record.tag.clone(), record)
+        })
+        .collect();
+    for name in database.round_names().await? {
+        if parse_winners_round(&name).is_none() {
+            continue;
+        }
+        let documents = database.find_in_round(&name, doc! {}).await?;
+        for player in &documents {
+            let Some(record) = player.get_str("tag").ok().and_then(|t| records.get_mut(t)) else {
+                continue;
+            };
+            record.disqualified |= is_disqualified(player);
+            let match_id = player.get_i32("match_id")?;
+            let opponent = documents.iter().find(|other| {
+                other.get_i32("match_id") == Ok(match_id)
+                    && other.get_object_id("_id") != player.get_object_id("_id")
+            });
+            match opponent {
+                Some(opponent) if is_mannequin(opponent) => record.had_bye = true,
+                Some(opponent) => record
+                    .opponents
+                    .push(opponent.get_str("tag").unwrap_or("").to_string()),
+                None => {}
+            }
+            match player.get_bool("defeated") {
+                Ok(false) => {
+                    record.wins += 1;
+                    if let Some(opponent) = opponent.filter(|o| !is_mannequin(o)) {
+                        record
+                            .beaten
+                            .push(opponent.get_str("tag").unwrap_or("").to_string());
+                    }
+                }
+                Ok(true) => record.losses += 1,
+                Err(_) => {}
+            }
+        }
+    }
+    let wins: HashMap<String, i32> = records
+        .iter()
+        .map(|(tag, record)| (tag.clone(), record.wins))
+        .collect();
+    let mut standings: Vec<SwissRecord> = records
+        .into_values()
+        .map(|mut record| {
+            let sum = |tags: &[String]| tags.iter().filter_map(|t| wins.get(t)).sum();
+            record.buchholz = sum(&record.opponents);
+            record.sonneborn = sum(&record.beaten);
+            record
+        })
+        .collect();
+    standings.sort_by(|a, b| {
+        a.disqualified
+            .cmp(&b.disqualified)
+            .then(b.wins.cmp(&a.wins))
+            .then(b.buchholz.cmp(&a.buchholz))
+            .then(b.sonneborn.cmp(&a.sonneborn))
+            .then(a.tag.cmp(&b.tag))
+    });
+    Ok(standings)
+}
+
+/// Pairs the players of a round, given in standings order.
+///
+/// Returns the pairs as indices into `records`, plus the player receiving a bye when the number of
+/// players is odd. The bye goes to the lowest ranked player who has not had one yet. Rematches are
+/// only allowed when no pairing without them can be found.
+pub fn pair(records: &[SwissRecord]) -> (Vec<(usize, usize)>, Option<usize>) {
+    let mut order: Vec<usize> = (0..records.len()).collect();
+    let bye = if order.len() % 2 == 1 {
+        let index = order
+            .iter()
+            .rposition(|&i| !records[i].had_bye)
+            .unwrap_or(order.len() - 1);
+        Some(order.remove(index))
+    } else {
+        None
+    };
+    let (mut budget, mut unlimited) = (PAIRING_BUDGET, usize::MAX);
+    let pairs = pair_players(&order, records, false, &mut budget)
+        .or_else(|| pair_players(&order, records, true, &mut unlimited))
+        .unwrap_or_default();
+    (pairs, bye)
+}
+
+fn pair_players(
+    order: &[usize],
+    records: &[SwissRecord],
+    allow_rematch: bool,
+    budget: &mut usize,
+) -> Option<Vec<(usize, usize)>> {
+    let Some((&first, rest)) = order.split_first() else {
+        return Some(vec![]);
+    };
+    let mut candidates = rest.to_vec();
+    // A stable sort keeps the standings order among players with the same difference in wins
+    candidates.sort_by_key(|&c| (records[first].wins - records[c].wins).abs());
+    for candidate in candidates {
+        if *budget == 0 {
+            return None;
+        }
+        *budget -= 1;
+        if !allow_rematch && records[first].opponents.contains(&records[candidate].tag) {
+            continue;
+        }
+        let remaining: Vec<usize> = rest.iter().copied().filter(|&i| i != candidate).collect();
+        if let Some(mut pairs) = pair_players(&remaining, records, allow_rematch, budget) {
+            pairs.insert(0, (first, candidate));
+            return Some(pairs);
+        }
+    }
+    None
+}
+
+/// Generates the pairings of a Swiss round from the results of the previous ones.
+///
+/// Returns the number of matches in the new round.
+pub async fn generate_round(
+    database: &dyn Repository,
+    region: &Region,
+    round: i32,
+) -> Result<i32, Error> {
+    let standings: Vec<SwissRecord> = swiss_standings(database)
+        .await?
+        .into_iter()
+        .filter(|record| !record.disqualified)
+        .collect();
+    if standings.len() < 2 {
+        return Err("Not enough players left to pair another round!".into());
+    }
+    let (pairs, bye) = pair(&standings);
+    let round = format!("Round {round}");
+    let mut match_id = 0;
+    for (home, away) in pairs {
+        match_id += 1;
+        for index in [home, away] {
+            database
+                .insert_into_round(
+                    &round,
+                    &move_to_match(standings[index].player.clone(), match_id),
+                )
+                .await?;
+        }
+    }
+    if let Some(index) = bye {
+        match_id += 1;
+        database
+            .insert_into_round(
+                &round,
+                &move_to_match(standings[index].player.clone(), match_id),
+            )
+            .await?;
+        database
+            .insert_into_round(&round, &add_mannequin(region, Some(match_id)))
+            .await?;
+    }
+    Ok(match_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate_round;
+    use crate::database::fixtures::{match_ids, player};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::Repository;
+    use dbc_bot::Region;
+    use mongodb::bson::doc;
+
+    async fn finish(repository: &MemoryRepository, round: &str, winners: &[i32]) {
+        for document in repository.documents(round) {
+            let Ok(tag) = document.get_str("tag") else {
+                continue;
+            };
+            let won = winners.iter().any(|n| format!("#P{n}") == tag);
+            repository
+                .update_in_round(round, doc! {"tag": tag}, doc! {"$set": {"defeated": !won}})
+                .await
+                .unwrap();
+        }
+    }
+
+    fn tagged(ids: &[(i32, i32)]) -> Vec<(Option<String>, i32)> {
+        ids.iter()
+            .map(|(n, id)| (Some(format!("#P{n}")), *id))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn swiss_rounds_avoid_rematches() {
+        let repository = MemoryRepository::default();
+        for n in 1..=4 {
+            repository.insert("Players", player(n));
+        }
+
+        assert_eq!(
+            generate_round(&repository, &Region::EU, 1).await.unwrap(),
+            2
+        );
+        assert_eq!(
+            match_ids(&repository, "Round 1"),
+            tagged(&[(1, 1), (2, 1), (3, 2), (4, 2)])
+        );
+        finish(&repository, "Round 1", &[1, 3]).await;
+
+        generate_round(&repository, &Region::EU, 2).await.unwrap();
+        assert_eq!(
+            match_ids(&repository, "Round 2"),
+            tagged(&[(1, 1), (2, 2), (3, 1), (4, 2)])
+        );
+        finish(&repository, "Round 2", &[1, 2]).await;
+
+        // #P1 has already met #P2 and #P3, so they play the last placed #P4 instead
+        generate_round(&repository, &Region::EU, 3).await.unwrap();
+        assert_eq!(
+            match_ids(&repository, "Round 3"),
+            tagged(&[(1, 1), (2, 2), (3, 2), (4, 1)])
+        );
+    }
+
+    #[tokio::test]
+    async fn swiss_bye_goes_to_the_lowest_player_without_one() {
+        let repository = MemoryRepository::default();
+        for n in 1..=3 {
+            repository.insert("Players", player(n));
+        }
+
+        assert_eq!(
+            generate_round(&repository, &Region::EU, 1).await.unwrap(),
+            2
+        );
+        let mut expected = vec![(None, 2)];
+        expected.extend(tagged(&[(1, 1), (2, 1), (3, 2)]));
+        assert_eq!(match_ids(&repository, "Round 1"), expected);
+        finish(&repository, "Round 1", &[1, 3]).await;
+
+        generate_round(&repository, &Region::EU, 2).await.unwrap();
+        let mut expected = vec![(None, 2)];
+        expected.extend(tagged(&[(1, 1), (2, 2), (3, 1)]));
+        assert_eq!(match_ids(&repository, "Round 2"), expected);
+    }
+}
@@ -0,0 +1,65 @@
+//! Proof that a registrant owns the account of the tag they entered.
+//!
+//! The registrant is asked to change their profile icon, which only the owner of the account can
+//! do, and the change is then checked through the `player` endpoint. The icon asked for is the
+//! default one every account has unlocked, or any other icon if the account already uses it.
+use serde_json::Value;
+
+/// The profile icon every account starts with.
+pub const DEFAULT_ICON: i64 = 28000000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IconChallenge {
+    /// The icon of the account when the challenge was given.
+    pub original: i64,
+    /// The icon to set, `None` for any icon other than the original one.
+    pub required: Option<i64>,
+}
+
+impl IconChallenge {
+    /// A challenge for an account, from its `player` data.
+    pub fn new(player: &Value) -> Self {
+        let original = player["icon"]["id"].as_i64().unwrap_or(DEFAULT_ICON);
+        IconChallenge {
+            original,
+            required: (original != DEFAULT_ICON).then_some(DEFAULT_ICON),
+        }
+    }
+
+    /// Whether the account, from its refreshed `player` data, now uses the icon asked for.
+    pub fn is_met(&self, player: &Value) -> bool {
+        match (player["icon"]["id"].as_i64(), self.required) {
+            (Some(icon), Some(required)) => icon == required,
+            (Some(icon), None) => icon != self.original,
+            (None, _) => false,
+        }
+    }
+
+    /// What the registrant has to do, for the challenge prompt.
+    pub fn instruction(&self) -> &'static str {
+        match self.required {
+            Some(_) => "Change your in-game profile icon to the one shown below",
+            None => "Change your in-game profile icon to any other icon",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IconChallenge, DEFAULT_ICON};
+
+    #[test]
+    fn icon_challenge_needs_the_icon_to_change() {
+        let icon = |id: i64| serde_json::json!({"icon": {"id": id}});
+        let challenge = IconChallenge::new(&icon(28000123));
+        assert_eq!(challenge.required, Some(DEFAULT_ICON));
+        assert!(!challenge.is_met(&icon(28000123)));
+        assert!(challenge.is_met(&icon(DEFAULT_ICON)));
+
+        let challenge = IconChallenge::new(&icon(DEFAULT_ICON));
+        assert_eq!(challenge.required, None);
+        assert!(!challenge.is_met(&icon(DEFAULT_ICON)));
+        assert!(challenge.is_met(&icon(28000123)));
+        assert!(!challenge.is_met(&serde_json::json!({})));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::database::audit::{audit_entries, audit_filter, describe_entry, AUDIT_LOG};
+use crate::database::repository::Repository;
+use crate::discord::checks::is_host;
+use crate::{Context, Error};
+use dbc_bot::Region;
+use mongodb::bson::Document;
+
+const ENTRIES_PER_PAGE: u64 = 15;
+const TIMEOUT: u64 = 600;
+
+/// Browse the record of host actions, the newest first.
+#[poise::command(slash_command, guild_only, check = "is_host", rename = "audit")]
+pub async fn audit_log(
+    ctx: Context<'_>,
+    #[description = "Only show actions in this region"] region: Option<Region>,
+    #[description = "Only show actions of this user id"] user_id: Option<String>,
+    #[description = "Only show actions starting with this, e.g. config or player.disqualify"]
+    action: Option<String>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let general = &ctx.data().database.general;
+    let filter = audit_filter(region.as_ref(), user_id.as_deref(), action.as_deref());
+    let total = general.count_documents(AUDIT_LOG, filter.clone()).await?;
+    if total == 0 {
+        ctx.send(|s| {
+            s.ephemeral(true).embed(|e| {
+                e.title("Audit log")
+                    .description("No host action matches these filters.")
+            })
+        })
+        .await?;
+        return Ok(());
+    }
+    let pages = total.div_ceil(ENTRIES_PER_PAGE);
+
+    let ctx_id = ctx.id();
+    let prev_button_id = format!("{}prev", ctx_id);
+    let next_button_id = format!("{}next", ctx_id);
+    let mut current_page = 0;
+    let description = page(general, &filter, current_page).await?;
+    ctx.send(|s| {
+        s.ephemeral(true)
+            .embed(|e| {
+                e.title(format!("Audit log ({total})"))
+                    .description(description)
+                    .footer(|f| f.text(format!("Page {}/{pages}", current_page + 1)))
+            })
+            .components(|c| {
+                c.create_action_row(|a| {
+                    a.create_button(|b| b.custom_id(&prev_button_id).emoji('◀'))
+                        .create_button(|b| b.custom_id(&next_button_id).emoji('▶'))
+                })
+            })
+    })
+    .await?;
+
+    while let Some(press) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(std::time::Duration::from_secs(TIMEOUT))
+        .await
+    {
+        if press.data.custom_id == next_button_id {
+            current_page = (current_page + 1) % pages;
+        } else if press.data.custom_id == prev_button_id {
+            current_page = current_page.checked_sub(1).unwrap_or(pages - 1);
+        } else {
+            continue;
+        }
+        let description = page(general, &filter, current_page).await?;
+        press
+            .create_interaction_response(ctx, |r| {
+                r.kind(poise::serenity_prelude::InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.embed(|e| {
+                            e.title(format!("Audit log ({total})"))
+                                .description(description)
+                                .footer(|f| f.text(format!("Page {}/{pages}", current_page + 1)))
+                        })
+                    })
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+/// The entries of a page of the viewer, one per line.
+async fn page(general: &dyn Repository, filter: &Document, page: u64) -> Result<String, Error> {
+    let entries = audit_entries(
+        general,
+        filter.clone(),
+        page * ENTRIES_PER_PAGE,
+        ENTRIES_PER_PAGE as i64,
+    )
+    .await?;
+    Ok(entries
+        .iter()
+        .map(describe_entry)
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
@@ -0,0 +1,164 @@
+use crate::bracket_tournament::seeding::{is_valid_tag, normalise_tag};
+use crate::database::audit::audit;
+use crate::database::ban::{active_bans, describe_ban, lift_bans, make_ban_doc, BANS};
+use crate::database::repository::Repository;
+use crate::discord::{checks::is_host, prompt::prompt};
+use crate::{Context, Error};
+use dbc_bot::{chunk, Region};
+use mongodb::bson::{Bson, DateTime};
+use tracing::info;
+
+const BANS_PER_PAGE: usize = 20;
+
+/// Ban players from future tournaments, lift bans and list them.
+#[poise::command(
+    slash_command,
+    guild_only,
+    check = "is_host",
+    subcommands("ban_add", "ban_remove", "ban_list")
+)]
+pub async fn ban(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Ban a Discord user, a player tag or both from registering.
+#[poise::command(slash_command, guild_only, check = "is_host", rename = "add")]
+pub async fn ban_add(
+    ctx: Context<'_>,
+    #[description = "Reason of the ban"] reason: String,
+    #[description = "User id"] user_id: Option<String>,
+    #[description = "Player tag"] player_tag: Option<String>,
+    #[description = "Days until the ban expires, leave blank for a permanent ban"] days: Option<
+        u32,
+    >,
+    #[description = "Region the ban applies to, leave blank for every region"] region: Option<
+        Region,
+    >,
+) -> Result<(), Error> {
+    let msg = ctx
+        .send(|s| {
+            s.reply(true)
+                .ephemeral(true)
+                .embed(|e| e.title("Banning").description("Hold a second..."))
+        })
+        .await?;
+    let user_id = user_id.map(|id| id.trim().to_string());
+    let player_tag = player_tag.as_deref().map(normalise_tag);
+    if user_id
+        .as_ref()
+        .is_some_and(|id| id.parse::<u64>().is_err())
+        || player_tag.as_ref().is_some_and(|tag| !is_valid_tag(tag))
+        || (user_id.is_none() && player_tag.is_none())
+    {
+        return prompt(
+            &ctx,
+            &msg,
+            "Nobody to ban!",
+            "Please provide a valid user id, a player tag or both.",
+            None,
+            Some(0xFF0000),
+        )
+        .await;
+    }
+    let expires_at = days.map(|days| {
+        DateTime::from_millis(DateTime::now().timestamp_millis() + days as i64 * 86_400_000)
+    });
+    let ban = make_ban_doc(
+        user_id.as_deref(),
+        player_tag.as_deref(),
+        reason.trim(),
+        &ctx.author().id.to_string(),
+        expires_at,
+        region.as_ref(),
+    );
+    ctx.data()
+        .database
+        .general
+        .insert_document(BANS, &ban)
+        .await?;
+    info!("{} added a ban: {ban}", ctx.author().id);
+    audit(&ctx, region.as_ref(), "ban.add", Bson::Null, ban.clone()).await?;
+    prompt(
+        &ctx,
+        &msg,
+        "Ban added!",
+        format!(
+            "{}\nPlayers already registered are not removed. Disqualify them from the host menu if needed.",
+            describe_ban(&ban)
+        ),
+        None,
+        Some(0xFFFF00),
+    )
+    .await
+}
+
+/// Lift every ban on a Discord user or a player tag.
+#[poise::command(slash_command, guild_only, check = "is_host", rename = "remove")]
+pub async fn ban_remove(
+    ctx: Context<'_>,
+    #[description = "User id or player tag"] target: String,
+) -> Result<(), Error> {
+    let msg = ctx
+        .send(|s| {
+            s.reply(true)
+                .ephemeral(true)
+                .embed(|e| e.title("Lifting bans").description("Hold a second..."))
+        })
+        .await?;
+    let lifted = lift_bans(&ctx.data().database.general, &target).await?;
+    info!("{} lifted {lifted} bans on {target}", ctx.author().id);
+    if lifted > 0 {
+        audit(&ctx, None, "ban.remove", target.trim(), Bson::Null).await?;
+    }
+    match lifted {
+        0 => {
+            prompt(
+                &ctx,
+                &msg,
+                "No ban found!",
+                format!("There is no ban on {target}."),
+                None,
+                Some(0xFF0000),
+            )
+            .await
+        }
+        lifted => {
+            prompt(
+                &ctx,
+                &msg,
+                "Ban lifted!",
+                format!("{lifted} ban(s) on {target} have been lifted."),
+                None,
+                Some(0xFFFF00),
+            )
+            .await
+        }
+    }
+}
+
+/// List the bans still in force.
+#[poise::command(slash_command, guild_only, check = "is_host", rename = "list")]
+pub async fn ban_list(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let bans = active_bans(&ctx.data().database.general).await?;
+    if bans.is_empty() {
+        ctx.send(|s| {
+            s.ephemeral(true)
+                .embed(|e| e.title("Bans").description("Nobody is banned."))
+        })
+        .await?;
+        return Ok(());
+    }
+    let lines = bans.iter().map(describe_ban).collect::<Vec<String>>();
+    for (page, lines) in chunk(&lines, BANS_PER_PAGE).iter().enumerate() {
+        ctx.send(|s| {
+            s.ephemeral(true).embed(|e| {
+                e.title(format!("Bans ({}), page {}", bans.len(), page + 1))
+                    .description(lines.join("\n"))
+                    .color(0xFF0000)
+            })
+        })
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,298 @@
+use crate::bracket_tournament::challonge::{
+    export_tournament, parse_participants, plan_import, skip_reason, Participant,
+};
+use crate::brawlstars::api::{request, APIResult};
+use crate::database::audit::audit;
+use crate::database::config::get_config;
+use crate::database::repository::{Repository, PLAYERS};
+use crate::discord::role::set_roles;
+use crate::discord::{checks::is_host, prompt::prompt};
+use crate::{Context, Error};
+use dbc_bot::Region;
+use futures::StreamExt;
+use mongodb::bson::doc;
+use poise::serenity_prelude::{Attachment, AttachmentType, ButtonStyle};
+use poise::ReplyHandle;
+
+const TIMEOUT: u64 = 120;
+/// Rows listed to the host, the rest only counted.
+const ROWS_SHOWN: usize = 10;
+
+/// Export the participants and matches of a region in Challonge's JSON format.
+#[poise::command(
+    slash_command,
+    guild_only,
+    check = "is_host",
+    rename = "challonge-export"
+)]
+pub async fn challonge_export(
+    ctx: Context<'_>,
+    #[description = "Region of the tournament"] region: Region,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let database = ctx.data().database.regional_databases.get(&region).unwrap();
+    let export = export_tournament(database, &region).await?;
+    let attachment = AttachmentType::Bytes {
+        data: serde_json::to_vec_pretty(&export)?.into(),
+        filename: format!("Tournament_{}_challonge.json", region.short()),
+    };
+    ctx.send(|s| {
+        s.ephemeral(true)
+            .embed(|e| {
+                e.title("Bracket exported!").description(
+                    "<:tick:1187839626338111600> The participants and matches are ready to download.",
+                )
+            })
+            .attachment(attachment)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Register the players of a Challonge JSON or CSV file, seeded as in the file.
+///
+/// Players already registered in the region are replaced, once the host confirms. Banned players and
+/// accounts failing the eligibility rules are skipped and listed. The tournament is started
+/// separately from the host menu.
+#[poise::command(
+    slash_command,
+    guild_only,
+    check = "is_host",
+    rename = "challonge-import"
+)]
+pub async fn challonge_import(
+    ctx: Context<'_>,
+    #[description = "Region of the tournament"] region: Region,
+    #[description = "Challonge participants JSON, or a CSV with name, tag, discord_id and seed columns"]
+    file: Attachment,
+) -> Result<(), Error> {
+    let msg = ctx
+        .send(|s| {
+            s.reply(true).ephemeral(true).embed(|e| {
+                e.title("Importing participants")
+                    .description("<a:loading:1187839622680690689> Reading the file...")
+            })
+        })
+        .await?;
+    if get_config(&ctx, &region).await.get_bool("tournament") == Ok(true) {
+        return prompt(
+            &ctx,
+            &msg,
+            "The tournament has already started!",
+            "Players can only be imported before the tournament starts.",
+            None,
+            Some(0xFF0000),
+        )
+        .await;
+    }
+    let content = String::from_utf8(file.download().await?)?;
+    let participants = match parse_participants(&file.filename, &content) {
+        Ok(participants) => participants,
+        Err(problems) => {
+            return prompt(
+                &ctx,
+                &msg,
+                "Failed to import the file!",
+                format!("Nothing was imported:\n{problems}"),
+                None,
+                Some(0xFF0000),
+            )
+            .await;
+        }
+    };
+    let database = ctx.data().database.regional_databases.get(&region).unwrap();
+    let registered = database.count_in_round(PLAYERS, doc! {}).await?;
+    if registered > 0 && !confirm_replace(&ctx, &msg, registered).await? {
+        return prompt(
+            &ctx,
+            &msg,
+            "Import cancelled",
+            "Nothing was imported and the registered players are kept.",
+            None,
+            None,
+        )
+        .await;
+    }
+    prompt(
+        &ctx,
+        &msg,
+        "Importing participants",
+        format!(
+            "<a:loading:1187839622680690689> Checking the {} accounts of the file...",
+            participants.len()
+        ),
+        None,
+        None,
+    )
+    .await?;
+    let config = get_config(&ctx, &region).await;
+    let mut accepted = vec![];
+    let mut skipped = vec![];
+    let mut unchecked = vec![];
+    for participant in participants {
+        // The file is enough to register a player, the API only adds the eligibility rules
+        let profile = match request("player", &participant.tag).await {
+            Ok(APIResult::Successful(profile)) => Some(profile),
+            Ok(APIResult::NotFound(_)) => {
+                skipped.push(row(&participant, "The account could not be found."));
+                continue;
+            }
+            Ok(APIResult::APIError(code)) => {
+                unchecked.push(row(
+                    &participant,
+                    &format!("The Brawl Stars API answered with {code}."),
+                ));
+                None
+            }
+            Err(e) => {
+                unchecked.push(row(&participant, &e.to_string()));
+                None
+            }
+        };
+        match skip_reason(
+            &ctx.data().database.general,
+            &config,
+            &region,
+            &participant,
+            profile.as_ref(),
+        )
+        .await?
+        {
+            Some(reason) => skipped.push(row(&participant, &reason)),
+            None => accepted.push((participant, profile)),
+        }
+    }
+    if accepted.is_empty() {
+        return prompt(
+            &ctx,
+            &msg,
+            "Failed to import the file!",
+            format!(
+                "Nothing was imported, every row was skipped:\n{}",
+                list(&skipped)
+            ),
+            None,
+            Some(0xFF0000),
+        )
+        .await;
+    }
+    let replaced = database.find_in_round(PLAYERS, doc! {}).await?;
+    database.apply(plan_import(&region, &accepted)).await?;
+    for player in replaced.iter().filter(|player| {
+        !accepted.iter().any(|(participant, _)| {
+            player.get_str("discord_id") == Ok(participant.discord_id.as_str())
+        })
+    }) {
+        set_roles(&ctx, &region, player, false).await;
+    }
+    for player in database.find_in_round(PLAYERS, doc! {}).await? {
+        set_roles(&ctx, &region, &player, true).await;
+    }
+    audit(
+        &ctx,
+        Some(&region),
+        "challonge.import",
+        doc! {"registered": registered as i64},
+        doc! {
+            "file": &file.filename,
+            "imported": accepted.len() as i64,
+            "skipped": skipped.len() as i64,
+            "unchecked": unchecked.len() as i64,
+        },
+    )
+    .await?;
+    let mut description = format!(
+        "<:tick:1187839626338111600> {} players are registered and seeded as in the file. Start the tournament from the host menu when you are ready.",
+        accepted.len()
+    );
+    if !skipped.is_empty() {
+        description.push_str(&format!(
+            "\n\n{} rows were skipped:\n{}",
+            skipped.len(),
+            list(&skipped)
+        ));
+    }
+    if !unchecked.is_empty() {
+        description.push_str(&format!(
+            "\n\n{} accounts could not be checked against the eligibility rules and were imported as written in the file:\n{}",
+            unchecked.len(),
+            list(&unchecked)
+        ));
+    }
+    prompt(
+        &ctx,
+        &msg,
+        "Participants imported!",
+        description,
+        None,
+        Some(0xFFFF00),
+    )
+    .await
+}
+
+/// A participant's line in the lists shown to the host.
+fn row(participant: &Participant, note: &str) -> String {
+    format!(
+        "{} ({}, <@{}>): {note}",
+        participant.name, participant.tag, participant.discord_id
+    )
+}
+
+/// Asks the host whether the players already registered may be replaced by the file.
+async fn confirm_replace(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    registered: u64,
+) -> Result<bool, Error> {
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title("Players are already registered!")
+                .description(format!(
+                    "{registered} players are registered in this region. Importing the file removes their registrations and registers the players of the file instead."
+                ))
+                .color(0xFFFF00)
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| {
+                    b.label("Replace")
+                        .style(ButtonStyle::Danger)
+                        .custom_id("replace")
+                })
+                .create_button(|b| {
+                    b.label("Cancel")
+                        .style(ButtonStyle::Primary)
+                        .custom_id("cancel")
+                })
+            })
+        })
+    })
+    .await?;
+    let resp = msg.clone().into_message().await?;
+    let mut cic = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(TIMEOUT))
+        .build();
+    while let Some(mci) = &cic.next().await {
+        mci.defer(&ctx.http()).await?;
+        match mci.data.custom_id.as_str() {
+            "replace" => return Ok(true),
+            "cancel" => return Ok(false),
+            _ => continue,
+        }
+    }
+    Ok(false)
+}
+
+/// The first rows, one per line, short enough for an embed.
+fn list(rows: &[String]) -> String {
+    let mut lines = rows
+        .iter()
+        .take(ROWS_SHOWN)
+        .map(|line| format!("- {line}"))
+        .collect::<Vec<String>>();
+    if rows.len() > ROWS_SHOWN {
+        lines.push(format!("...and {} more", rows.len() - ROWS_SHOWN));
+    }
+    lines.join("\n")
+}
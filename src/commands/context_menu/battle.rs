@@ -1,5 +1,5 @@
 use crate::database::config::get_config;
-use crate::database::find::{find_enemy_by_match_id_and_self_tag, find_player_by_discord_id, find_round_from_config};
+use crate::database::find::{find_enemy_by_match_id_and_self_tag, find_player_in_current_rounds};
 use crate::database::open::tournament;
 use crate::discord::prompt::prompt;
 use crate::discord::role::{get_region_from_role, get_roles_from_user};
@@ -76,8 +76,7 @@ pub async fn view_battle(ctx: Context<'_>, user: serenity::User) -> Result<(), E
         .await;
     }
 
-    let round = find_round_from_config(&get_config(&ctx, &region).await);
-    let player = match find_player_by_discord_id(&ctx, &region, user.id.into(), &round).await {
+    let (round, player) = match find_player_in_current_rounds(&ctx, &region, user.id.into()).await {
         Ok(user) => match user {
             Some(u) => u,
             None => {
@@ -111,7 +110,8 @@ pub async fn view_battle(ctx: Context<'_>, user: serenity::User) -> Result<(), E
         &player.get_i32("match_id")?,
         &player.get_str("tag")?,
     )
-    .await{
+    .await
+    {
         Some(e) => e,
         None => {
             return prompt(
@@ -126,6 +126,6 @@ pub async fn view_battle(ctx: Context<'_>, user: serenity::User) -> Result<(), E
         }
     };
     let config = get_config(&ctx, &region).await;
-    view_opponent(&ctx, &msg, &region, player, enemy, config).await?;
+    view_opponent(&ctx, &msg, &region, &round, player, enemy, config).await?;
     Ok(())
 }
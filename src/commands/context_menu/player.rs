@@ -1,6 +1,9 @@
 use crate::brawlstars::{api::request, api::APIResult, player::stat};
 use crate::database::config::get_config;
-use crate::database::find::{find_player_by_discord_id, find_round_from_config};
+use crate::database::find::{
+    find_player_by_discord_id, find_player_in_current_rounds, find_round_from_config,
+};
+use crate::database::models::Player;
 use crate::discord::prompt::prompt;
 use crate::discord::role::{get_region_from_role, get_roles_from_user};
 use crate::{Context, Error};
@@ -46,21 +49,15 @@ pub async fn get_individual_player_data(
             region = Some(r);
         }
     }
-    if round.is_none() {
-        round = Some(find_round_from_config(
-            &get_config(&ctx, region.as_ref().unwrap()).await,
-        ));
-    }
     let id: u64 = user.id.into();
 
-    let player_from_db = match find_player_by_discord_id(
-        &ctx,
-        region.as_ref().unwrap(),
-        id,
-        &round.unwrap(),
-    )
-    .await
-    {
+    let found = match round {
+        Some(round) => find_player_by_discord_id(&ctx, region.as_ref().unwrap(), id, &round).await,
+        None => find_player_in_current_rounds(&ctx, region.as_ref().unwrap(), id)
+            .await
+            .map(|found| found.map(|(_, player)| player)),
+    };
+    let player_from_db = match found {
         Ok(player) => match player {
             Some(p) => p,
             None => {
@@ -87,7 +84,7 @@ pub async fn get_individual_player_data(
             .await;
         }
     };
-    let player = request("player", player_from_db.get_str("tag").unwrap()).await?;
+    let player = request("player", &Player::from_document(&player_from_db)?.tag).await?;
     match player {
         APIResult::Successful(p) => {
             stat(&ctx, &msg, &p, &region.unwrap(), Some(&player_from_db)).await
@@ -1,11 +1,16 @@
 use crate::database::battle::is_battle;
+use crate::database::check_in::{check_in_open, is_checked_in};
 use crate::database::config::get_config;
-use crate::database::find::{find_round_from_config, find_self_by_discord_id};
+use crate::database::dispute::find_disputable_loss;
+use crate::database::find::{find_player_in_current_rounds, is_waiting_for_next_round};
 use crate::database::open::{registration_open, registration_region_open};
+use crate::database::team::find_team_of_member;
 use crate::discord::menu::registration_menu;
 use crate::discord::menu::tournament_menu;
 use crate::discord::prompt::prompt;
 use crate::discord::role::{get_region_from_role, get_roles_from_user};
+use crate::players::registration::check_in::check_in_menu;
+use crate::players::tournament::dispute::dispute_menu;
 use crate::{Context, Error};
 use poise::ReplyHandle;
 const DELAY: u64 = 1;
@@ -41,10 +46,43 @@ pub async fn home(ctx: Context<'_>, msg: Option<ReplyHandle<'_>>) -> Result<(),
     let region = get_region_from_role(&ctx, roles).await;
     match region {
         Some(region) => {
-            let round = find_round_from_config(&get_config(&ctx, &region).await);
-            let player = match find_self_by_discord_id(&ctx, round).await? {
+            let (round, player) = match find_player_in_current_rounds(
+                &ctx,
+                &region,
+                ctx.author().id.0,
+            )
+            .await?
+            {
                 Some(player) => player,
+                None if is_waiting_for_next_round(&ctx, &region, ctx.author().id.0).await? => {
+                    return prompt(
+                            &ctx,
+                            &msg,
+                            "Your next match has not started yet!",
+                            "You have already been placed in your next round. Please stay tuned for the announcement to know when it starts!",
+                            None,
+                            Some(0xFFFF00),
+                        )
+                        .await;
+                }
                 None => {
+                    if let Some(team) =
+                        find_team_of_member(&ctx, &region, ctx.author().id.0).await?
+                    {
+                        return prompt(
+                            &ctx,
+                            &msg,
+                            "Your captain manages your team!",
+                            format!(
+                                "You play for team {} captained by <@{}>. Your captain finds your opponents and submits the results of your team.",
+                                team.get_str("team_name").unwrap_or("Unknown"),
+                                team.get_str("discord_id").unwrap_or("0")
+                            ),
+                            None,
+                            Some(0xFFFF00),
+                        )
+                        .await;
+                    }
                     prompt(
                         &ctx,
                         &msg,
@@ -57,23 +95,36 @@ pub async fn home(ctx: Context<'_>, msg: Option<ReplyHandle<'_>>) -> Result<(),
                     return Ok(());
                 }
             };
+            if round == "Players"
+                && check_in_open(&get_config(&ctx, &region).await)
+                && !is_checked_in(&player)
+            {
+                return check_in_menu(&ctx, &msg, &region).await;
+            }
             if registration_region_open(&ctx, &region).await {
                 registration_menu(&ctx, &msg, false, true, true, true, Some(player)).await
             } else {
                 if !is_battle(
                     &ctx,
-                    player.get("tag").unwrap().as_str(), // Don't unwrap this as the `is_battle()` handles it internally
-                    find_round_from_config(&get_config(&ctx, &region).await),
+                    player.get_str("tag").ok(), // Don't unwrap this as the `is_battle()` handles it internally
+                    round.clone(),
                 )
                 .await?
                 {
-                    tournament_menu(&ctx, &msg, true, true, true, true, true, player).await
+                    tournament_menu(&ctx, &msg, true, true, true, true, true, player, &round).await
                 } else {
-                    tournament_menu(&ctx, &msg, false, false, true, false, false, player).await
+                    tournament_menu(&ctx, &msg, false, false, true, false, false, player, &round)
+                        .await
                 }
             }
         }
         None => {
+            // Players knocked out of the tournament can still dispute the match they lost
+            if let Some((region, round, player)) =
+                find_disputable_loss(&ctx, ctx.author().id.0).await?
+            {
+                return dispute_menu(&ctx, &msg, &region, &round, player).await;
+            }
             if registration_open(&ctx).await {
                 registration_menu(&ctx, &msg, true, false, false, true, None).await
             } else {
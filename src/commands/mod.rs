@@ -1,3 +1,6 @@
+pub mod audit;
+pub mod ban;
+pub mod challonge;
 pub mod context_menu;
 pub mod host;
 pub mod index;
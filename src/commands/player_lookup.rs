@@ -4,7 +4,7 @@ use crate::{
     players::view::view_info,
 };
 use crate::{
-    database::find::find_tag,
+    database::find::{find_tag, TagHolder},
     discord::role::{get_region_from_role, get_roles_from_user},
     Context, Error,
 };
@@ -30,7 +30,7 @@ pub async fn lookup_player(
     // We probably don't need this. I'll give it another look later. - Doof
     match (player_tag, user_id) {
         (Some(tag), None) => {
-            if let Some(player) = find_tag(&ctx, &tag).await {
+            if let Some(TagHolder::Player(player)) = find_tag(&ctx, &tag, None).await? {
                 view_info(&ctx, &msg, player).await
             } else {
                 prompt(
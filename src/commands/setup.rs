@@ -1,3 +1,4 @@
+use crate::database::audit::audit;
 use crate::database::config::make_server_doc;
 use crate::discord::checks::is_mod;
 use crate::discord::prompt::prompt;
@@ -66,6 +67,7 @@ pub async fn setup(ctx: Context<'_>) -> Result<(), Error> {
         .timeout(std::time::Duration::from_secs(120))
         .build();
     while let Some(mci) = &cic.next().await {
+        let before = hosts.clone();
         match mci.data.custom_id.as_str() {
             "open" => {
                 role_option(&ctx, &msg, mci.clone(), &collection).await?;
@@ -86,6 +88,9 @@ pub async fn setup(ctx: Context<'_>) -> Result<(), Error> {
             .get_array("role_id")
             .unwrap()
             .to_vec();
+        if hosts != before {
+            audit(&ctx, None, "managers.role_id", before, hosts.clone()).await?;
+        }
         display_select_menu(&ctx, &msg, &hosts).await?;
     }
 
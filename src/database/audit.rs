@@ -0,0 +1,222 @@
+//! A record of every change hosts make, kept in the database.
+//!
+//! Entries are kept in the `AuditLog` collection of the general database, so they outlive the log
+//! channel and resets. Each entry has the host who acted (`actor`), the region (`null` for actions
+//! such as bans that are not tied to one), the action, the values before and after, and the time.
+use super::repository::Repository;
+use crate::{Context, Error};
+use dbc_bot::Region;
+use mongodb::bson::{doc, Bson, DateTime, Document};
+
+pub const AUDIT_LOG: &str = "AuditLog";
+
+/// Longest value shown in the audit viewer.
+const VALUE_LENGTH: usize = 100;
+
+pub fn make_audit_doc(
+    actor: &str,
+    region: Option<&Region>,
+    action: &str,
+    before: impl Into<Bson>,
+    after: impl Into<Bson>,
+) -> Document {
+    doc! {
+        "actor": actor,
+        "region": region.map(Region::short),
+        "action": action,
+        "before": before.into(),
+        "after": after.into(),
+        "at": DateTime::now(),
+    }
+}
+
+/// Records an action of the host running a command.
+pub async fn audit(
+    ctx: &Context<'_>,
+    region: Option<&Region>,
+    action: &str,
+    before: impl Into<Bson>,
+    after: impl Into<Bson>,
+) -> Result<(), Error> {
+    let entry = make_audit_doc(&ctx.author().id.to_string(), region, action, before, after);
+    ctx.data()
+        .database
+        .general
+        .insert_document(AUDIT_LOG, &entry)
+        .await
+}
+
+/// The keys of the config that changed, with their values before and after.
+pub fn config_changes(before: &Document, after: &Document) -> Vec<(String, Bson, Bson)> {
+    let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter(|key| *key != "_id")
+        .filter_map(|key| {
+            let old = before.get(key).cloned().unwrap_or(Bson::Null);
+            let new = after.get(key).cloned().unwrap_or(Bson::Null);
+            (old != new).then(|| (key.clone(), old, new))
+        })
+        .collect()
+}
+
+/// Records every change between two versions of the config of a region, as `config.<key>`.
+pub async fn audit_config(
+    ctx: &Context<'_>,
+    region: &Region,
+    before: &Document,
+    after: &Document,
+) -> Result<(), Error> {
+    for (key, old, new) in config_changes(before, after) {
+        audit(ctx, Some(region), &format!("config.{key}"), old, new).await?;
+    }
+    Ok(())
+}
+
+/// The filter on entries of a region, of a host and of actions starting with `action`, so that
+/// `config` finds every config change.
+pub fn audit_filter(
+    region: Option<&Region>,
+    actor: Option<&str>,
+    action: Option<&str>,
+) -> Document {
+    let mut filter = doc! {};
+    if let Some(region) = region {
+        filter.insert("region", region.short());
+    }
+    if let Some(actor) = actor {
+        filter.insert("actor", actor.trim());
+    }
+    if let Some(action) = action {
+        filter.insert(
+            "action",
+            doc! {"$regex": format!("^{}", regex::escape(action.trim()))},
+        );
+    }
+    filter
+}
+
+/// A page of the entries matching `filter`, the newest first.
+pub async fn audit_entries(
+    general: &dyn Repository,
+    filter: Document,
+    skip: u64,
+    limit: i64,
+) -> Result<Vec<Document>, Error> {
+    general
+        .find_documents(AUDIT_LOG, filter, doc! {"at": -1}, skip, limit)
+        .await
+}
+
+/// One line describing an entry, for the audit viewer.
+pub fn describe_entry(entry: &Document) -> String {
+    let at = entry
+        .get_datetime("at")
+        .map(|at| at.timestamp_millis() / 1000)
+        .unwrap_or(0);
+    let region = entry
+        .get_str("region")
+        .map(|region| format!(" [{region}]"))
+        .unwrap_or_default();
+    format!(
+        "<t:{at}:f> <@{}> `{}`{region}: {} → {}",
+        entry.get_str("actor").unwrap_or("0"),
+        entry.get_str("action").unwrap_or(""),
+        describe_value(entry.get("before")),
+        describe_value(entry.get("after"))
+    )
+}
+
+fn describe_value(value: Option<&Bson>) -> String {
+    let value = match value {
+        None | Some(Bson::Null) => return "none".to_string(),
+        Some(Bson::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+    };
+    match value.char_indices().nth(VALUE_LENGTH) {
+        Some((end, _)) => format!("{}…", &value[..end]),
+        None => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{audit_entries, audit_filter, config_changes, make_audit_doc, AUDIT_LOG};
+    use crate::database::config::make_config;
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::Repository;
+    use dbc_bot::Region;
+    use mongodb::bson::{DateTime, Document};
+
+    #[tokio::test]
+    async fn audit_entries_are_filtered_and_newest_first() {
+        let before = make_config();
+        let mut after = before.clone();
+        after.insert("mode", "brawlBall");
+        after.insert("map", "Hard Rock Mine");
+        let changes = config_changes(&before, &after);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|(key, ..)| key.as_str())
+                .collect::<Vec<_>>(),
+            ["map", "mode"]
+        );
+
+        let repository = MemoryRepository::new(make_config());
+        for (at, actor, region, action) in [
+            (1, "1", Some(&Region::EU), "config.mode"),
+            (2, "2", Some(&Region::EU), "player.disqualify"),
+            (3, "1", None, "ban.add"),
+            (4, "1", Some(&Region::NASA), "config.map"),
+        ] {
+            let mut entry = make_audit_doc(actor, region, action, "old", "new");
+            entry.insert("at", DateTime::from_millis(at));
+            repository.insert(AUDIT_LOG, entry);
+        }
+        let actions = |entries: Vec<Document>| {
+            entries
+                .iter()
+                .map(|entry| entry.get_str("action").unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        let all = audit_entries(&repository, audit_filter(None, None, None), 0, 0)
+            .await
+            .unwrap();
+        assert_eq!(
+            actions(all),
+            ["config.map", "ban.add", "player.disqualify", "config.mode"]
+        );
+        let filtered = audit_entries(
+            &repository,
+            audit_filter(None, Some("1"), Some("config")),
+            0,
+            0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(actions(filtered), ["config.map", "config.mode"]);
+        let eu = audit_entries(
+            &repository,
+            audit_filter(Some(&Region::EU), None, None),
+            0,
+            0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(actions(eu), ["player.disqualify", "config.mode"]);
+        let second_page = audit_entries(&repository, audit_filter(None, None, None), 1, 2)
+            .await
+            .unwrap();
+        assert_eq!(actions(second_page), ["ban.add", "player.disqualify"]);
+        let anchored = audit_filter(None, None, Some("disqualify"));
+        assert_eq!(
+            repository
+                .count_documents(AUDIT_LOG, anchored)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Bans that outlive a tournament.
+//!
+//! Bans are kept in the `Bans` collection of the general database, so a reset does not clear them.
+//! A ban is on a Discord ID, a player tag or both, may expire (`expires_at`, `null` for never) and
+//! may be limited to one region (`region`, `null` for every region). Banned players cannot
+//! register, nor be invited to a team.
+use super::repository::Repository;
+use crate::bracket_tournament::seeding::normalise_tag;
+use crate::Error;
+use dbc_bot::Region;
+use mongodb::bson::{doc, Bson, DateTime, Document};
+
+pub const BANS: &str = "Bans";
+
+pub fn make_ban_doc(
+    discord_id: Option<&str>,
+    tag: Option<&str>,
+    reason: &str,
+    issued_by: &str,
+    expires_at: Option<DateTime>,
+    region: Option<&Region>,
+) -> Document {
+    doc! {
+        "discord_id": discord_id,
+        "tag": tag.map(normalise_tag),
+        "reason": reason,
+        "issued_by": issued_by,
+        "issued_at": DateTime::now(),
+        "expires_at": expires_at,
+        "region": region.map(Region::short),
+    }
+}
+
+/// Whether a ban is still in force at `now`.
+pub fn is_active(ban: &Document, now: DateTime) -> bool {
+    match ban.get_datetime("expires_at") {
+        Ok(expires_at) => *expires_at > now,
+        Err(_) => true,
+    }
+}
+
+/// Whether a ban keeps a player out of the tournament of a region.
+pub fn applies_to(ban: &Document, region: &Region) -> bool {
+    match ban.get("region") {
+        Some(Bson::String(short)) => *short == region.short(),
+        _ => true,
+    }
+}
+
+/// The ban in force on a Discord ID or a tag in a region, if there is one.
+pub async fn find_ban(
+    general: &dyn Repository,
+    discord_id: Option<&str>,
+    tag: Option<&str>,
+    region: &Region,
+) -> Result<Option<Document>, Error> {
+    let now = DateTime::now();
+    let mut bans = vec![];
+    if let Some(discord_id) = discord_id {
+        bans.extend(
+            general
+                .find_documents(BANS, doc! {"discord_id": discord_id}, doc! {}, 0, 0)
+                .await?,
+        );
+    }
+    if let Some(tag) = tag {
+        bans.extend(
+            general
+                .find_documents(BANS, doc! {"tag": normalise_tag(tag)}, doc! {}, 0, 0)
+                .await?,
+        );
+    }
+    Ok(bans
+        .into_iter()
+        .find(|ban| is_active(ban, now) && applies_to(ban, region)))
+}
+
+/// Every ban still in force, the oldest first.
+pub async fn active_bans(general: &dyn Repository) -> Result<Vec<Document>, Error> {
+    let now = DateTime::now();
+    let mut bans = general
+        .find_documents(BANS, doc! {}, doc! {"issued_at": 1}, 0, 0)
+        .await?;
+    bans.retain(|ban| is_active(ban, now));
+    Ok(bans)
+}
+
+/// Lifts every ban on a Discord ID or a tag. Returns how many were lifted.
+pub async fn lift_bans(general: &dyn Repository, target: &str) -> Result<u64, Error> {
+    let mut lifted = 0;
+    for filter in [
+        doc! {"discord_id": target.trim()},
+        doc! {"tag": normalise_tag(target)},
+    ] {
+        lifted += general.delete_documents(BANS, filter).await?;
+    }
+    Ok(lifted)
+}
+
+/// One line describing a ban, for hosts and for the banned player.
+pub fn describe_ban(ban: &Document) -> String {
+    let mut target = vec![];
+    if let Ok(discord_id) = ban.get_str("discord_id") {
+        target.push(format!("<@{discord_id}>"));
+    }
+    if let Ok(tag) = ban.get_str("tag") {
+        target.push(tag.to_string());
+    }
+    let expiry = match ban.get_datetime("expires_at") {
+        Ok(expires_at) => format!("until <t:{}:f>", expires_at.timestamp_millis() / 1000),
+        Err(_) => "permanently".to_string(),
+    };
+    let region = match ban.get_str("region") {
+        Ok(region) => format!("from {region}"),
+        Err(_) => "from every region".to_string(),
+    };
+    format!(
+        "{} banned {region} {expiry} by <@{}>: {}",
+        target.join(" "),
+        ban.get_str("issued_by").unwrap_or("0"),
+        ban.get_str("reason").unwrap_or("No reason given")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{active_bans, find_ban, lift_bans, make_ban_doc, BANS};
+    use crate::bracket_tournament::seeding::is_valid_tag;
+    use crate::database::config::make_config;
+    use crate::database::memory::MemoryRepository;
+    use dbc_bot::Region;
+    use mongodb::bson::DateTime;
+
+    async fn is_banned(
+        repository: &MemoryRepository,
+        discord_id: Option<&str>,
+        tag: Option<&str>,
+        region: Region,
+    ) -> bool {
+        find_ban(repository, discord_id, tag, &region)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    #[tokio::test]
+    async fn bans_apply_by_id_or_tag_until_they_expire() {
+        let repository = MemoryRepository::new(make_config());
+        let past = DateTime::from_millis(DateTime::now().timestamp_millis() - 1);
+        repository.insert(
+            BANS,
+            make_ban_doc(Some("1"), Some("#p1"), "Cheating", "9", None, None),
+        );
+        repository.insert(
+            BANS,
+            make_ban_doc(Some("2"), None, "Smurfing", "9", None, Some(&Region::EU)),
+        );
+        repository.insert(
+            BANS,
+            make_ban_doc(Some("3"), None, "Toxicity", "9", Some(past), None),
+        );
+        assert!(is_banned(&repository, None, Some("P1"), Region::APAC).await);
+        assert!(is_banned(&repository, Some("1"), None, Region::NASA).await);
+        assert!(is_banned(&repository, Some("2"), None, Region::EU).await);
+        assert!(!is_banned(&repository, Some("2"), None, Region::APAC).await);
+        assert!(!is_banned(&repository, Some("3"), None, Region::EU).await);
+
+        assert_eq!(lift_bans(&repository, "#P1").await.unwrap(), 1);
+        assert!(!is_banned(&repository, Some("1"), None, Region::NASA).await);
+        repository.insert(
+            BANS,
+            make_ban_doc(Some("2"), None, "Smurfing", "9", None, Some(&Region::APAC)),
+        );
+        assert_eq!(active_bans(&repository).await.unwrap().len(), 2);
+        assert_eq!(lift_bans(&repository, " 2 ").await.unwrap(), 2);
+        assert!(active_bans(&repository).await.unwrap().is_empty());
+
+        assert!(is_valid_tag(" #2pylq "));
+        assert!(!is_valid_tag("#ABC123"));
+        assert!(!is_valid_tag("#"));
+    }
+}
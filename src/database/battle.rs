@@ -7,8 +7,7 @@ use mongodb::{
 use poise::ReplyHandle;
 
 use super::{
-    config::get_config,
-    find::{find_enemy_by_match_id_and_self_tag, find_round_from_config},
+    find::{find_enemy_by_match_id_and_self_tag, find_player_in_current_rounds},
     update::update_result,
 };
 
@@ -71,7 +70,18 @@ pub async fn force_lose(
     player: &Document,
     reason: &str,
 ) -> Result<(), Error> {
-    let round = find_round_from_config(&get_config(ctx, region).await);
+    let round = match find_player_in_current_rounds(
+        ctx,
+        region,
+        player.get_str("discord_id")?.parse::<u64>()?,
+    )
+    .await?
+    {
+        Some((round, _)) => round,
+        None => {
+            return Err("Player is not in any ongoing round!".into());
+        }
+    };
     let match_id = player.get_i32("match_id")?;
     let player_tag = player.get_str("tag")?;
     let opponent =
@@ -82,5 +92,6 @@ pub async fn force_lose(
                 return Err("No opponent found!".into());
             }
         };
-    update_result(ctx, region, &round, &opponent, player, reason).await
+    update_result(ctx, region, &round, &opponent, player, reason).await?;
+    Ok(())
 }
@@ -0,0 +1,109 @@
+//! Check-in of the registered players before the tournament starts.
+//!
+//! Check-in is optional. Once a host opens it (`check_in` in the config), registered players confirm
+//! they will play from the menu or from the button sent to them by direct message, which marks them
+//! `checked_in`. When the tournament starts, everyone who has not checked in is deregistered before
+//! the byes and rounds are calculated.
+use super::config::find_config;
+use super::repository::{Repository, PLAYERS};
+use super::team::members;
+use crate::discord::role::remove_role_by_id;
+use crate::Error;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::{Collection, Database};
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+pub fn check_in_open(config: &Document) -> bool {
+    config.get_bool("check_in").unwrap_or(false)
+}
+
+pub fn is_checked_in(player: &Document) -> bool {
+    player.get_bool("checked_in").unwrap_or(false)
+}
+
+/// Checks a registered player in. Returns `false` if they are not registered.
+pub async fn check_in(database: &dyn Repository, discord_id: &str) -> Result<bool, Error> {
+    let filter = doc! {"discord_id": discord_id};
+    if database.count_in_round(PLAYERS, filter.clone()).await? == 0 {
+        return Ok(false);
+    }
+    database
+        .update_in_round(PLAYERS, filter, doc! {"$set": {"checked_in": true}})
+        .await?;
+    Ok(true)
+}
+
+/// Opens the check-in, clearing any check-in from before.
+pub async fn open_check_in(database: &Database) -> Result<(), Error> {
+    database
+        .collection::<Document>(PLAYERS)
+        .update_many(doc! {}, doc! {"$set": {"checked_in": false}}, None)
+        .await?;
+    database
+        .update_config(doc! {"$set": {"check_in": true}})
+        .await
+}
+
+/// Deregisters every player who has not checked in and closes the check-in. Returns how many were
+/// removed, none when no check-in was held.
+pub async fn remove_absent_players(
+    ctx: &serenity::Context,
+    database: &Database,
+) -> Result<i32, Error> {
+    let config = find_config(database).await;
+    if !check_in_open(&config) {
+        return Ok(0);
+    }
+    let players: Collection<Document> = database.collection(PLAYERS);
+    let absent: Vec<Document> = players
+        .find(
+            doc! {"discord_id": {"$ne": null}, "checked_in": {"$ne": true}},
+            None,
+        )
+        .await?
+        .try_collect()
+        .await?;
+    for player in &absent {
+        players
+            .delete_one(doc! {"_id": player.get_object_id("_id")?}, None)
+            .await?;
+        let ids = std::iter::once(player.clone())
+            .chain(members(player))
+            .filter_map(|member| member.get_str("discord_id").ok()?.parse::<u64>().ok())
+            .collect::<Vec<u64>>();
+        for id in ids {
+            if let Err(e) = remove_role_by_id(ctx, &config, id).await {
+                error!("{e}");
+            }
+        }
+    }
+    database
+        .update_config(doc! {"$set": {"check_in": false}})
+        .await?;
+    Ok(absent.len() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_in, is_checked_in};
+    use crate::database::config::make_config;
+    use crate::database::fixtures::player;
+    use crate::database::memory::MemoryRepository;
+
+    #[tokio::test]
+    async fn check_in_marks_only_registered_players() {
+        let repository = MemoryRepository::new(make_config());
+        repository.insert("Players", player(1));
+        repository.insert("Players", player(2));
+        assert!(check_in(&repository, "1").await.unwrap());
+        assert!(!check_in(&repository, "3").await.unwrap());
+        let checked_in: Vec<bool> = repository
+            .documents("Players")
+            .iter()
+            .map(is_checked_in)
+            .collect();
+        assert_eq!(checked_in, vec![true, false]);
+    }
+}
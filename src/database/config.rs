@@ -1,9 +1,10 @@
 use crate::Context;
 use dbc_bot::Region;
 use mongodb::bson::{doc, Bson::Null, Document};
-use mongodb::Collection;
 use poise::serenity_prelude::json::Value;
 
+use super::repository::Repository;
+
 pub fn make_server_doc(server: &str, server_id: &str) -> Document {
     doc! {
         "server": server,
@@ -25,6 +26,36 @@ pub fn make_config() -> Document {
       "bracket_channel": Null,
       "bracket_message_id": Null,
       "log_channel": Null,
+      "format": Null,
+      "bracket_reset": false,
+      "third_place_match": false,
+      "placements_recorded": false,
+      "groups": 0,
+      "group_size": 0,
+      "group_advance": 0,
+      "group_rounds": 0,
+      "swiss_rounds": 0,
+      "seeding": Null,
+      "seed_list": [],
+      "rng_seed": Null,
+      "best_of": 3,
+      "best_of_rounds": {},
+      "draw_rule": Null,
+      "team_size": 1,
+      "watch_interval": 120,
+      "dispute_window": 30,
+      "round_length": 0,
+      "reminders": [1440, 60],
+      "deadline": Null,
+      "deadline_round": Null,
+      "reminded": [],
+      "registration_opens": Null,
+      "registration_closes": Null,
+      "tournament_starts": Null,
+      "check_in": false,
+      "check_in_opens": Null,
+      "max_players": 0,
+      "eligibility": {},
     };
     config
 }
@@ -65,8 +96,12 @@ pub fn set_config(key: &str, value: Option<&str>) -> Document {
 
 pub async fn get_config(ctx: &Context<'_>, region: &Region) -> Document {
     let database = ctx.data().database.regional_databases.get(region).unwrap();
-    let collection: Collection<Document> = database.collection("Config");
-    collection.find_one(None, None).await.unwrap().unwrap()
+    find_config(database).await
+}
+
+/// Reads the config of a regional database, for tasks running outside of a command.
+pub async fn find_config(database: &dyn Repository) -> Document {
+    database.config().await.unwrap().unwrap()
 }
 
 pub fn toggle_reg_config(status: bool) -> Document {
@@ -101,6 +136,36 @@ pub fn reset_config() -> Document {
             "channel": Null,
             "bracket_channel": Null,
             "bracket_message_id": Null,
+            "format": Null,
+            "bracket_reset": false,
+            "third_place_match": false,
+            "placements_recorded": false,
+            "groups": 0,
+            "group_size": 0,
+            "group_advance": 0,
+            "group_rounds": 0,
+            "swiss_rounds": 0,
+            "seeding": Null,
+            "seed_list": [],
+            "rng_seed": Null,
+            "best_of": 3,
+            "best_of_rounds": {},
+            "draw_rule": Null,
+            "team_size": 1,
+            "watch_interval": 120,
+            "dispute_window": 30,
+            "round_length": 0,
+            "reminders": [1440, 60],
+            "deadline": Null,
+            "deadline_round": Null,
+            "reminded": [],
+            "registration_opens": Null,
+            "registration_closes": Null,
+            "tournament_starts": Null,
+            "check_in": false,
+            "check_in_opens": Null,
+            "max_players": 0,
+            "eligibility": {},
         }
     };
     config
@@ -0,0 +1,530 @@
+//! The writes that correct a recorded result: overriding it, replaying the match or undoing a
+//! disqualification.
+//!
+//! Each correction is planned from the current state of the rounds and returned as [`Write`]s, so
+//! that it is applied in one transaction through [`Repository::apply`].
+use super::repository::{Repository, Write, RESULTS};
+use super::update::{move_to_match, result_key};
+use crate::bracket_tournament::double_elimination::{
+    is_from_winners, losers_advance, losers_drop, losers_round_name, parse_losers_round,
+    parse_winners_round, GRAND_FINAL, GRAND_FINAL_RESET,
+};
+use crate::bracket_tournament::group_stage::parse_group_round;
+use crate::bracket_tournament::placements::{has_third_place_match, THIRD_PLACE_MATCH};
+use crate::Error;
+use dbc_bot::{Format, Region};
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+
+/// A player to swap in a match the overridden result led to.
+struct Swap {
+    round: String,
+    match_id: i32,
+    placed: Document,
+    replacement: Document,
+    /// Players and later rounds to clean up when the match has to be played again.
+    reopen: Option<(Vec<ObjectId>, Vec<String>)>,
+}
+
+/// Every write that records the win of a match for the player who was recorded as its loser, and
+/// swaps the two players in the later rounds.
+///
+/// Returns the writes with the matches that have to be played again, or why the result cannot be
+/// reversed.
+pub async fn plan_reversal(
+    database: &dyn Repository,
+    config: &Document,
+    region: &Region,
+    round: &str,
+    match_id: i32,
+    winner: &Document,
+    loser: &Document,
+) -> Result<Result<(Vec<Write>, Vec<String>), String>, Error> {
+    let planned = if round == GRAND_FINAL {
+        plan_grand_final_reset(database, config, winner, loser).await?
+    } else {
+        plan_later_rounds(database, config, round, match_id, winner, loser).await?
+    };
+    let (mut writes, reopened) = match planned {
+        Ok(planned) => planned,
+        Err(reason) => return Ok(Err(reason)),
+    };
+    writes.extend([
+        Write::Update {
+            round: round.to_string(),
+            filter: doc! {"_id": winner.get_object_id("_id")?},
+            update: doc! {"$set": {"battle": true, "defeated": false}, "$unset": {"reason": ""}},
+        },
+        Write::Update {
+            round: round.to_string(),
+            filter: doc! {"_id": loser.get_object_id("_id")?},
+            update: doc! {"$set": {"battle": true, "defeated": true}},
+        },
+        Write::Update {
+            round: RESULTS.to_string(),
+            filter: doc! {"_id": result_key(region, round, match_id)},
+            update: doc! {
+                "$set": {"winner": winner.get("_id"), "loser": loser.get("_id"), "overridden": true}
+            },
+        },
+    ]);
+    Ok(Ok((writes, reopened)))
+}
+
+/// Every write that reopens a played match so that both players play it again, removing them from
+/// the matches the result led to. Games played before the replay no longer count towards the result.
+///
+/// Returns why the match cannot be reopened, if a later match has already been played.
+pub async fn plan_replay(
+    database: &dyn Repository,
+    config: &Document,
+    region: &Region,
+    round: &str,
+    match_id: i32,
+) -> Result<Result<Vec<Write>, String>, Error> {
+    let ids: Vec<ObjectId> = database
+        .find_in_round(round, doc! {"match_id": match_id})
+        .await?
+        .iter()
+        .filter_map(|player| player.get_object_id("_id").ok())
+        .collect();
+    let mut later = next_matches(config, round, match_id);
+    if round == GRAND_FINAL {
+        later.push((GRAND_FINAL_RESET.to_string(), 1));
+    }
+    if let Some((later_round, later_match)) = played_by(database, &later, &ids).await? {
+        return Ok(Err(format!(
+            "{later_round} match {later_match} has already been played after this result. Please correct it first!"
+        )));
+    }
+    let mut writes = vec![];
+    for (later_round, _) in &later {
+        for id in &ids {
+            writes.push(Write::Delete {
+                round: later_round.clone(),
+                filter: doc! {"_id": id},
+            });
+        }
+    }
+    writes.push(Write::UpdateMany {
+        round: round.to_string(),
+        filter: doc! {"match_id": match_id},
+        update: doc! {
+            "$set": {"battle": false, "ready": false, "replay_after": DateTime::now()},
+            "$unset": {"defeated": "", "decided_at": ""}
+        },
+    });
+    // The replayed match is recorded again once it has been played
+    writes.push(Write::Delete {
+        round: RESULTS.to_string(),
+        filter: doc! {"_id": result_key(region, round, match_id)},
+    });
+    Ok(Ok(writes))
+}
+
+/// Every write that undoes the disqualification of a player. Their match is reopened, the opponent
+/// is taken back out of the matches the result led to, and the bye that took the player's place in
+/// the losers bracket is removed.
+///
+/// Returns why the disqualification cannot be undone, if a later match has already been played.
+pub async fn plan_reinstatement(
+    database: &dyn Repository,
+    config: &Document,
+    region: &Region,
+    round: &str,
+    player: &Document,
+) -> Result<Result<Vec<Write>, String>, Error> {
+    let match_id = player.get_i32("match_id")?;
+    if database
+        .find_result(&result_key(region, round, match_id))
+        .await?
+        .is_some_and(|result| result.contains_key("double_forfeit"))
+    {
+        return Ok(Err(format!(
+            "Both players of {round} match {match_id} were removed. Please override the result instead!"
+        )));
+    }
+    // A disqualified player drops out of the winners bracket as a bye
+    let byes = match (Format::from_config(config), parse_winners_round(round)) {
+        (Format::DoubleElimination, Some(_)) => next_matches(config, round, match_id).split_off(1),
+        _ => vec![],
+    };
+    for (later_round, later_match) in &byes {
+        if database
+            .count_in_round(
+                later_round,
+                doc! {"match_id": later_match, "tag": null, "battle": true},
+            )
+            .await?
+            > 0
+        {
+            return Ok(Err(format!(
+                "The bye that replaced this player has already advanced from {later_round} match {later_match}. Please correct it first!"
+            )));
+        }
+    }
+    let mut writes = match plan_replay(database, config, region, round, match_id).await? {
+        Ok(writes) => writes,
+        Err(reason) => return Ok(Err(reason)),
+    };
+    for (later_round, later_match) in byes {
+        writes.push(Write::Delete {
+            round: later_round,
+            filter: doc! {"match_id": later_match, "tag": null, "battle": false},
+        });
+    }
+    writes.push(Write::Update {
+        round: round.to_string(),
+        filter: doc! {"_id": player.get_object_id("_id")?},
+        update: doc! {"$unset": {"reason": "", "disqualify_log": ""}},
+    });
+    // The player also forfeited the rest of their group
+    if let Some(current) = parse_group_round(round) {
+        for name in database.round_names().await? {
+            if parse_group_round(&name).is_some_and(|later| later > current) {
+                writes.push(Write::UpdateMany {
+                    round: name,
+                    filter: doc! {"tag": player.get_str("tag")?},
+                    update: doc! {"$unset": {"reason": ""}},
+                });
+            }
+        }
+    }
+    Ok(Ok(writes))
+}
+
+/// Matches the winner and loser of a match are sent to, as `(round, match id)`.
+pub fn next_matches(config: &Document, round: &str, match_id: i32) -> Vec<(String, i32)> {
+    let total = config.get_i32("total").unwrap_or(0);
+    match Format::from_config(config) {
+        Format::DoubleElimination => {
+            if let Some(r) = parse_winners_round(round) {
+                let (losers_round, losers_match) = losers_drop(r, match_id, total);
+                vec![
+                    if r < total {
+                        (format!("Round {}", r + 1), (match_id + 1) / 2)
+                    } else {
+                        (GRAND_FINAL.to_string(), 1)
+                    },
+                    (losers_round_name(losers_round), losers_match),
+                ]
+            } else if let Some(r) = parse_losers_round(round) {
+                match losers_advance(r, match_id, total) {
+                    Some((next_round, next_match)) => {
+                        vec![(losers_round_name(next_round), next_match)]
+                    }
+                    None => vec![(GRAND_FINAL.to_string(), 1)],
+                }
+            } else {
+                vec![]
+            }
+        }
+        Format::Swiss => vec![],
+        Format::GroupStage if parse_group_round(round).is_some() => vec![],
+        Format::SingleElimination | Format::GroupStage => match parse_winners_round(round) {
+            Some(r) => {
+                let mut next = vec![(format!("Round {}", r + 1), (match_id + 1) / 2)];
+                if r == total - 1 && has_third_place_match(config) {
+                    next.push((THIRD_PLACE_MATCH.to_string(), 1));
+                }
+                next
+            }
+            None => vec![],
+        },
+    }
+}
+
+/// The first of `matches` that one of `ids` has already played.
+async fn played_by(
+    database: &dyn Repository,
+    matches: &[(String, i32)],
+    ids: &[ObjectId],
+) -> Result<Option<(String, i32)>, Error> {
+    for (round, match_id) in matches {
+        let played = database
+            .find_in_round(round, doc! {"match_id": match_id, "battle": true})
+            .await?;
+        if played.iter().any(|player| {
+            player
+                .get_object_id("_id")
+                .is_ok_and(|id| ids.contains(&id))
+        }) {
+            return Ok(Some((round.clone(), *match_id)));
+        }
+    }
+    Ok(None)
+}
+
+/// Every write that swaps the two players in the matches the wrong result led to.
+async fn plan_later_rounds(
+    database: &dyn Repository,
+    config: &Document,
+    round: &str,
+    match_id: i32,
+    winner: &Document,
+    loser: &Document,
+) -> Result<Result<(Vec<Write>, Vec<String>), String>, Error> {
+    let (winner_id, loser_id) = (winner.get_object_id("_id")?, loser.get_object_id("_id")?);
+    let mut swaps = vec![];
+    for (next_round, next_match) in next_matches(config, round, match_id) {
+        let players = database
+            .find_in_round(&next_round, doc! {"match_id": next_match})
+            .await?;
+        let ids: Vec<ObjectId> = players
+            .iter()
+            .filter_map(|player| player.get_object_id("_id").ok())
+            .collect();
+        for placed in players {
+            let placed_id = placed.get_object_id("_id")?;
+            // The wrong winner is replaced by the real one, and the other way around
+            let replacement = if placed_id == loser_id {
+                winner.clone()
+            } else if placed_id == winner_id {
+                loser.clone()
+            } else {
+                continue;
+            };
+            let reopen = if placed.get_bool("battle").unwrap_or(false) {
+                let later = next_matches(config, &next_round, next_match);
+                if let Some((later_round, later_match)) = played_by(database, &later, &ids).await? {
+                    return Ok(Err(format!(
+                        "{next_round} match {next_match} has been played by the wrong player, and so has {later_round} match {later_match}. Please correct those matches first!"
+                    )));
+                }
+                Some((
+                    ids.clone(),
+                    later
+                        .into_iter()
+                        .map(|(later_round, _)| later_round)
+                        .collect(),
+                ))
+            } else {
+                None
+            };
+            swaps.push(Swap {
+                round: next_round.clone(),
+                match_id: next_match,
+                placed,
+                replacement,
+                reopen,
+            });
+        }
+    }
+    // Remove every wrongly placed player first, as both players can be placed in the same round
+    let mut writes = vec![];
+    for swap in &swaps {
+        writes.push(Write::Delete {
+            round: swap.round.clone(),
+            filter: doc! {"_id": swap.placed.get_object_id("_id")?},
+        });
+    }
+    let mut reopened = vec![];
+    for swap in swaps {
+        let mut moved = move_to_match(swap.replacement, swap.match_id);
+        moved.remove("reason");
+        if let Ok(from_winners) = swap.placed.get_bool("from_winners") {
+            moved.insert("from_winners", from_winners);
+        }
+        writes.push(Write::Insert {
+            round: swap.round.clone(),
+            document: moved,
+        });
+        if let Some((ids, later_rounds)) = swap.reopen {
+            writes.push(Write::UpdateMany {
+                round: swap.round.clone(),
+                filter: doc! {"match_id": swap.match_id},
+                update: doc! {"$set": {"battle": false, "ready": false}, "$unset": {"defeated": ""}},
+            });
+            for later_round in later_rounds {
+                for id in &ids {
+                    writes.push(Write::Delete {
+                        round: later_round.clone(),
+                        filter: doc! {"_id": id},
+                    });
+                }
+            }
+            reopened.push(format!("{} match {}", swap.round, swap.match_id));
+        }
+    }
+    Ok(Ok((writes, reopened)))
+}
+
+/// After a grand final override, the reset is only played when the losers bracket champion wins.
+async fn plan_grand_final_reset(
+    database: &dyn Repository,
+    config: &Document,
+    winner: &Document,
+    loser: &Document,
+) -> Result<Result<(Vec<Write>, Vec<String>), String>, Error> {
+    if database
+        .count_in_round(GRAND_FINAL_RESET, doc! {"battle": true})
+        .await?
+        > 0
+    {
+        return Ok(Err(
+            "The grand final reset has already been played. Please correct it instead!".to_string(),
+        ));
+    }
+    let mut writes = vec![Write::Delete {
+        round: GRAND_FINAL_RESET.to_string(),
+        filter: doc! {},
+    }];
+    if config.get_bool("bracket_reset").unwrap_or(false) && !is_from_winners(winner) {
+        for player in [winner, loser] {
+            writes.push(Write::Insert {
+                round: GRAND_FINAL_RESET.to_string(),
+                document: move_to_match(player.clone(), 1),
+            });
+        }
+        return Ok(Ok((writes, vec![GRAND_FINAL_RESET.to_string()])));
+    }
+    Ok(Ok((writes, vec![])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan_reinstatement, plan_replay, plan_reversal};
+    use crate::database::fixtures::{config, in_round, match_ids, start};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::{Repository, RESULTS};
+    use crate::database::update::{record_result, result_key};
+    use dbc_bot::Region;
+
+    /// A bracket of four where `#P1` beat `#P4` and `#P2` beat `#P3`, the first with `reason`.
+    async fn first_round(reason: Option<&str>) -> MemoryRepository {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 2));
+        start(&repository, 4, 4).await;
+        for (winner, loser, reason) in [(1, 4, reason), (2, 3, None)] {
+            let winner = in_round(&repository, "Round 1", winner).await;
+            let loser = in_round(&repository, "Round 1", loser).await;
+            record_result(&repository, &Region::EU, "Round 1", &winner, &loser, reason)
+                .await
+                .unwrap();
+        }
+        repository
+    }
+
+    #[tokio::test]
+    async fn overriding_a_result_swaps_the_players_in_later_rounds() {
+        let repository = first_round(None).await;
+        let settings = repository.config().await.unwrap().unwrap();
+        let winner = in_round(&repository, "Round 1", 4).await;
+        let loser = in_round(&repository, "Round 1", 1).await;
+        let (writes, reopened) = plan_reversal(
+            &repository,
+            &settings,
+            &Region::EU,
+            "Round 1",
+            1,
+            &winner,
+            &loser,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(reopened.is_empty());
+        repository.apply(writes).await.unwrap();
+
+        assert_eq!(
+            match_ids(&repository, "Round 2"),
+            vec![(Some("#P2".into()), 1), (Some("#P4".into()), 1)]
+        );
+        let winner = in_round(&repository, "Round 1", 4).await;
+        assert_eq!(winner.get_bool("defeated"), Ok(false));
+        let result = repository
+            .find_result(&result_key(&Region::EU, "Round 1", 1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.get("winner"), winner.get("_id"));
+        assert_eq!(result.get_bool("overridden"), Ok(true));
+    }
+
+    #[tokio::test]
+    async fn played_later_matches_are_reopened_by_an_override() {
+        let repository = first_round(None).await;
+        let settings = repository.config().await.unwrap().unwrap();
+        let winner = in_round(&repository, "Round 2", 1).await;
+        let loser = in_round(&repository, "Round 2", 2).await;
+        record_result(&repository, &Region::EU, "Round 2", &winner, &loser, None)
+            .await
+            .unwrap();
+
+        let winner = in_round(&repository, "Round 1", 4).await;
+        let loser = in_round(&repository, "Round 1", 1).await;
+        let replay = plan_replay(&repository, &settings, &Region::EU, "Round 1", 1)
+            .await
+            .unwrap();
+        assert!(replay.is_err());
+        let (writes, reopened) = plan_reversal(
+            &repository,
+            &settings,
+            &Region::EU,
+            "Round 1",
+            1,
+            &winner,
+            &loser,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(reopened, vec!["Round 2 match 1"]);
+        repository.apply(writes).await.unwrap();
+
+        assert_eq!(
+            match_ids(&repository, "Round 2"),
+            vec![(Some("#P2".into()), 1), (Some("#P4".into()), 1)]
+        );
+        for n in [2, 4] {
+            let player = in_round(&repository, "Round 2", n).await;
+            assert!(player.get("defeated").is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn replayed_matches_are_reopened() {
+        let repository = first_round(None).await;
+        let settings = repository.config().await.unwrap().unwrap();
+        let writes = plan_replay(&repository, &settings, &Region::EU, "Round 1", 1)
+            .await
+            .unwrap()
+            .unwrap();
+        repository.apply(writes).await.unwrap();
+
+        assert_eq!(
+            match_ids(&repository, "Round 2"),
+            vec![(Some("#P2".into()), 1)]
+        );
+        for n in [1, 4] {
+            let player = in_round(&repository, "Round 1", n).await;
+            assert!(player.get("defeated").is_none());
+            assert!(player.get("replay_after").is_some());
+        }
+        assert!(repository
+            .find_result(&result_key(&Region::EU, "Round 1", 1))
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(repository.documents(RESULTS).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reinstated_players_get_their_match_back() {
+        let repository = first_round(Some("Inactive")).await;
+        let settings = repository.config().await.unwrap().unwrap();
+        let player = in_round(&repository, "Round 1", 4).await;
+        assert_eq!(player.get_str("reason"), Ok("Inactive"));
+        let writes = plan_reinstatement(&repository, &settings, &Region::EU, "Round 1", &player)
+            .await
+            .unwrap()
+            .unwrap();
+        repository.apply(writes).await.unwrap();
+
+        let player = in_round(&repository, "Round 1", 4).await;
+        assert!(player.get("reason").is_none());
+        assert!(player.get("defeated").is_none());
+        assert_eq!(
+            match_ids(&repository, "Round 2"),
+            vec![(Some("#P2".into()), 1)]
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Round deadlines.
+//!
+//! Once the tournament moves on to a round, the round is given a deadline `round_length` hours
+//! later (`0` turns deadlines off). The players of unfinished matches are reminded `reminders`
+//! minutes before the deadline, and when it passes the matches still unfinished are resolved: a
+//! player who is ready advances over an opponent who is not, and a match where neither player is
+//! ready is a double forfeit. Matches where both players are ready, or whose result is disputed,
+//! are left to the hosts.
+use super::config::find_config;
+use super::dispute::OPEN;
+use super::find::{find_rounds_from_config, is_disqualified, is_mannequin};
+use super::repository::Repository;
+use super::update::{record_double_forfeit, record_result};
+use crate::Error;
+use dbc_bot::Region;
+use mongodb::bson::{doc, DateTime, Document};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_ROUND_LENGTH: i32 = 0;
+pub const DEFAULT_REMINDERS: [i32; 2] = [1440, 60];
+pub const INACTIVE: &str = "Inactive";
+pub const DOUBLE_FORFEIT: &str = "Double forfeit";
+
+/// How a match left unfinished at the deadline was resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The winner was ready, or faced a bye or a disqualified player.
+    Advanced {
+        round: String,
+        match_id: i32,
+        winner: Document,
+        loser: Document,
+    },
+    /// Neither player was ready, so both are out.
+    DoubleForfeit {
+        round: String,
+        match_id: i32,
+        players: [Document; 2],
+    },
+    /// Both players were ready or the result is disputed, so a host has to decide.
+    Left {
+        round: String,
+        match_id: i32,
+        players: Vec<Document>,
+    },
+}
+
+impl Outcome {
+    /// One line of the deadline log.
+    pub fn describe(&self) -> String {
+        match self {
+            Outcome::Advanced {
+                round,
+                match_id,
+                winner,
+                loser,
+            } => match is_mannequin(loser) || is_disqualified(loser) {
+                true => format!(
+                    "{round} match {match_id}: {} advances, their opponent is out",
+                    mention(winner)
+                ),
+                false => format!(
+                    "{round} match {match_id}: {} advances over {}, who was inactive",
+                    mention(winner),
+                    mention(loser)
+                ),
+            },
+            Outcome::DoubleForfeit {
+                round,
+                match_id,
+                players: [first, second],
+            } => format!(
+                "{round} match {match_id}: double forfeit, neither {} nor {} was ready",
+                mention(first),
+                mention(second)
+            ),
+            Outcome::Left {
+                round,
+                match_id,
+                players,
+            } => format!(
+                "{round} match {match_id}: left to the hosts ({})",
+                players
+                    .iter()
+                    .map(mention)
+                    .collect::<Vec<String>>()
+                    .join(" - ")
+            ),
+        }
+    }
+}
+
+fn mention(player: &Document) -> String {
+    match player.get_str("discord_id") {
+        Ok(id) => format!("<@{id}>"),
+        Err(_) => "a bye".to_string(),
+    }
+}
+
+/// Hours each round lasts, `None` when rounds have no deadline.
+pub fn round_length(config: &Document) -> Option<i64> {
+    match config
+        .get_i32("round_length")
+        .unwrap_or(DEFAULT_ROUND_LENGTH)
+    {
+        hours if hours <= 0 => None,
+        hours => Some(hours as i64),
+    }
+}
+
+/// Minutes before the deadline at which players are reminded, the earliest first.
+pub fn reminders(config: &Document) -> Vec<i64> {
+    let mut reminders: Vec<i64> = match config.get_array("reminders") {
+        Ok(reminders) => reminders
+            .iter()
+            .filter_map(|minutes| minutes.as_i32())
+            .map(i64::from)
+            .collect(),
+        Err(_) => DEFAULT_REMINDERS
+            .iter()
+            .map(|&minutes| minutes as i64)
+            .collect(),
+    };
+    reminders.retain(|&minutes| minutes > 0);
+    reminders.sort_unstable_by(|a, b| b.cmp(a));
+    reminders.dedup();
+    reminders
+}
+
+/// The deadline of the current round, if it has one and it has not been resolved yet.
+pub fn deadline(config: &Document) -> Option<DateTime> {
+    config.get_datetime("deadline").ok().copied()
+}
+
+/// Gives the current round its deadline, the first time it is seen. Returns the deadline.
+pub async fn schedule_deadline(
+    database: &dyn Repository,
+    config: &Document,
+    now: DateTime,
+) -> Result<Option<DateTime>, Error> {
+    let round = config.get_i32("round").unwrap_or(0);
+    if config.get_i32("deadline_round") == Ok(round) {
+        return Ok(deadline(config));
+    }
+    let Some(hours) = round_length(config) else {
+        return Ok(None);
+    };
+    let deadline = DateTime::from_millis(now.timestamp_millis() + hours * 3_600_000);
+    database
+        .update_config(doc! {
+            "$set": {"deadline": deadline, "deadline_round": round, "reminded": []}
+        })
+        .await?;
+    Ok(Some(deadline))
+}
+
+/// The reminders that have come due and have not been sent yet.
+pub fn due_reminders(config: &Document, deadline: DateTime, now: DateTime) -> Vec<i64> {
+    let reminded: Vec<i64> = config
+        .get_array("reminded")
+        .map(|reminded| reminded.iter().filter_map(|m| m.as_i64()).collect())
+        .unwrap_or_default();
+    let left = (deadline.timestamp_millis() - now.timestamp_millis()) / 60_000;
+    reminders(config)
+        .into_iter()
+        .filter(|minutes| left < *minutes && !reminded.contains(minutes))
+        .collect()
+}
+
+/// Marks reminders as sent for the current deadline.
+pub async fn mark_reminded(
+    database: &dyn Repository,
+    config: &Document,
+    sent: &[i64],
+) -> Result<(), Error> {
+    let mut reminded: Vec<i64> = config
+        .get_array("reminded")
+        .map(|reminded| reminded.iter().filter_map(|m| m.as_i64()).collect())
+        .unwrap_or_default();
+    reminded.extend(sent);
+    database
+        .update_config(doc! {"$set": {"reminded": reminded}})
+        .await
+}
+
+/// The matches of the current rounds that have not been played, by round and match id.
+pub async fn unfinished_matches(
+    database: &dyn Repository,
+    config: &Document,
+) -> Result<Vec<(String, i32, Vec<Document>)>, Error> {
+    let mut unfinished = vec![];
+    for round in find_rounds_from_config(config) {
+        let mut matches: BTreeMap<i32, Vec<Document>> = BTreeMap::new();
+        for player in database
+            .find_in_round(&round, doc! {"battle": false})
+            .await?
+        {
+            if let Ok(match_id) = player.get_i32("match_id") {
+                matches.entry(match_id).or_default().push(player);
+            }
+        }
+        unfinished.extend(
+            matches
+                .into_iter()
+                .map(|(match_id, players)| (round.clone(), match_id, players)),
+        );
+    }
+    Ok(unfinished)
+}
+
+/// Resolves every unfinished match of the current rounds and clears the deadline.
+pub async fn resolve_deadline(
+    database: &dyn Repository,
+    region: &Region,
+) -> Result<Vec<Outcome>, Error> {
+    let config = find_config(database).await;
+    let mut outcomes = vec![];
+    for (round, match_id, players) in unfinished_matches(database, &config).await? {
+        let Some(outcome) = resolve_match(round, match_id, players) else {
+            continue;
+        };
+        let recorded = match &outcome {
+            Outcome::Advanced {
+                round,
+                winner,
+                loser,
+                ..
+            } => {
+                let reason = match is_mannequin(loser) || is_disqualified(loser) {
+                    true => None,
+                    false => Some(INACTIVE),
+                };
+                record_result(database, region, round, winner, loser, reason).await?
+            }
+            Outcome::DoubleForfeit {
+                round,
+                players: [first, second],
+                ..
+            } => {
+                record_double_forfeit(database, region, round, first, second, DOUBLE_FORFEIT)
+                    .await?
+            }
+            Outcome::Left { .. } => true,
+        };
+        if recorded {
+            outcomes.push(outcome);
+        }
+    }
+    database
+        .update_config(doc! {"$set": {"deadline": null}})
+        .await?;
+    Ok(outcomes)
+}
+
+/// How a match unfinished at the deadline is resolved, `None` for a match between two byes, which
+/// resolves itself.
+fn resolve_match(round: String, match_id: i32, mut players: Vec<Document>) -> Option<Outcome> {
+    let left = |players| Outcome::Left {
+        round: round.clone(),
+        match_id,
+        players,
+    };
+    if players.len() != 2
+        || players
+            .iter()
+            .any(|player| player.get_str("dispute") == Ok(OPEN))
+    {
+        return Some(left(players));
+    }
+    let (second, first) = (players.remove(1), players.remove(0));
+    let out = |player: &Document| is_mannequin(player) || is_disqualified(player);
+    let ready = |player: &Document| player.get_bool("ready").unwrap_or(false);
+    let (winner, loser) = match (out(&first), out(&second)) {
+        (true, true) => return None,
+        (false, true) => (first, second),
+        (true, false) => (second, first),
+        (false, false) => match (ready(&first), ready(&second)) {
+            (true, false) => (first, second),
+            (false, true) => (second, first),
+            (true, true) => return Some(left(vec![first, second])),
+            (false, false) => {
+                return Some(Outcome::DoubleForfeit {
+                    round,
+                    match_id,
+                    players: [first, second],
+                })
+            }
+        },
+    };
+    Some(Outcome::Advanced {
+        round,
+        match_id,
+        winner,
+        loser,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{due_reminders, resolve_deadline, schedule_deadline, INACTIVE};
+    use crate::database::fixtures::{config, in_round, match_ids, start};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::Repository;
+    use crate::database::update::result_key;
+    use dbc_bot::Region;
+    use mongodb::bson::{doc, DateTime};
+
+    #[tokio::test]
+    async fn deadline_advances_ready_players_and_forfeits_the_rest() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 2));
+        start(&repository, 4, 4).await;
+        repository
+            .update_in_round(
+                "Round 1",
+                doc! {"tag": "#P1"},
+                doc! {"$set": {"ready": true}},
+            )
+            .await
+            .unwrap();
+        let outcomes = resolve_deadline(&repository, &Region::EU).await.unwrap();
+        assert_eq!(outcomes.len(), 2);
+
+        let inactive = in_round(&repository, "Round 1", 4).await;
+        assert_eq!(inactive.get_str("reason"), Ok(INACTIVE));
+        for n in [2, 3] {
+            let forfeited = in_round(&repository, "Round 1", n).await;
+            assert_eq!(forfeited.get_bool("defeated"), Ok(true));
+            assert!(forfeited.get("reason").is_some());
+        }
+        assert_eq!(
+            match_ids(&repository, "Round 2"),
+            vec![(None, 1), (Some("#P1".into()), 1)]
+        );
+        let forfeit = repository
+            .find_result(&result_key(&Region::EU, "Round 1", 2))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(forfeit.contains_key("double_forfeit"));
+    }
+
+    #[tokio::test]
+    async fn deadline_is_scheduled_once_per_round() {
+        let mut settings = config("Single Elimination", 1, 2);
+        settings.insert("round_length", 24);
+        let repository = MemoryRepository::new(settings);
+        let now = DateTime::now();
+        let deadline = schedule_deadline(
+            &repository,
+            &repository.config().await.unwrap().unwrap(),
+            now,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            deadline.timestamp_millis() - now.timestamp_millis(),
+            24 * 3_600_000
+        );
+
+        let later = DateTime::from_millis(now.timestamp_millis() + 22 * 3_600_000);
+        let config = repository.config().await.unwrap().unwrap();
+        assert_eq!(
+            schedule_deadline(&repository, &config, later)
+                .await
+                .unwrap(),
+            Some(deadline)
+        );
+        assert_eq!(due_reminders(&config, deadline, now), Vec::<i64>::new());
+        assert_eq!(due_reminders(&config, deadline, later), vec![1440]);
+    }
+}
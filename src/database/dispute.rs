@@ -0,0 +1,213 @@
+//! Disputes of match results.
+//!
+//! The loser of a match can dispute the result within the window set in the config
+//! (`dispute_window`, in minutes). A dispute is stored in the `Disputes` collection, and both players
+//! of the match are marked with its status under `dispute`. While a dispute is `open`, the match
+//! counts as unfinished, so the tournament cannot move on until a host resolves it.
+use crate::database::config::get_config;
+use crate::database::find::{find_player_by_discord_id, find_rounds_from_config};
+use crate::{Context, Error};
+use dbc_bot::Region;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use mongodb::{Collection, Database};
+use strum::IntoEnumIterator;
+
+pub const DEFAULT_DISPUTE_WINDOW: i32 = 30;
+pub const OPEN: &str = "open";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// The result stands.
+    Uphold,
+    /// The disputing player is given the win.
+    Overturn,
+    /// The match is played again.
+    Replay,
+}
+
+impl Resolution {
+    pub fn status(&self) -> &'static str {
+        match self {
+            Resolution::Uphold => "upheld",
+            Resolution::Overturn => "overturned",
+            Resolution::Replay => "replayed",
+        }
+    }
+}
+
+/// Minutes a loser has to dispute a result, `None` when disputes are turned off.
+pub fn dispute_window(config: &Document) -> Option<i64> {
+    match config
+        .get_i32("dispute_window")
+        .unwrap_or(DEFAULT_DISPUTE_WINDOW)
+    {
+        window if window <= 0 => None,
+        window => Some(window as i64),
+    }
+}
+
+/// When the window to dispute a player's last result closes, if they can still dispute it.
+///
+/// A result can only be disputed once, unless the match was replayed after a dispute.
+pub fn dispute_deadline(config: &Document, player: &Document) -> Option<DateTime> {
+    let window = dispute_window(config)?;
+    if player.get_bool("defeated") != Ok(true)
+        || player
+            .get_str("dispute")
+            .is_ok_and(|status| status != Resolution::Replay.status())
+        || player.contains_key("reason")
+    {
+        return None;
+    }
+    let decided_at = player.get_datetime("decided_at").ok()?;
+    let deadline = DateTime::from_millis(decided_at.timestamp_millis() + window * 60 * 1000);
+    (deadline > DateTime::now()).then_some(deadline)
+}
+
+/// Finds a loss the user can still dispute in the rounds currently played, in any region.
+///
+/// Players knocked out of the tournament lose their regional role, so their region is not known.
+pub async fn find_disputable_loss(
+    ctx: &Context<'_>,
+    user_id: u64,
+) -> Result<Option<(Region, String, Document)>, Error> {
+    for region in Region::iter() {
+        let config = get_config(ctx, &region).await;
+        if !config.get_bool("tournament").unwrap_or(false) {
+            continue;
+        }
+        for round in find_rounds_from_config(&config) {
+            if let Some(player) = find_player_by_discord_id(ctx, &region, user_id, &round).await? {
+                if dispute_deadline(&config, &player).is_some() {
+                    return Ok(Some((region, round, player)));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Stores a new dispute and pauses the match.
+pub async fn open_dispute(
+    database: &Database,
+    round: &str,
+    player: &Document,
+    reason: &str,
+    evidence: Vec<String>,
+) -> Result<ObjectId, Error> {
+    let match_id = player.get_i32("match_id")?;
+    let dispute = doc! {
+        "round": round,
+        "match_id": match_id,
+        "opened_by": player.get_str("discord_id").unwrap_or(""),
+        "tag": player.get_str("tag").unwrap_or(""),
+        "reason": reason,
+        "evidence": evidence,
+        "status": OPEN,
+        "opened_at": DateTime::now(),
+    };
+    let id = database
+        .collection::<Document>("Disputes")
+        .insert_one(dispute, None)
+        .await?
+        .inserted_id
+        .as_object_id()
+        .ok_or("Failed to store the dispute")?;
+    database
+        .collection::<Document>(round)
+        .update_many(
+            doc! {"match_id": match_id},
+            doc! {"$set": {"dispute": OPEN, "dispute_id": id}},
+            None,
+        )
+        .await?;
+    Ok(id)
+}
+
+pub async fn find_open_disputes(database: &Database) -> Result<Vec<Document>, Error> {
+    Ok(database
+        .collection::<Document>("Disputes")
+        .find(doc! {"status": OPEN}, None)
+        .await?
+        .try_collect()
+        .await?)
+}
+
+/// Writes the resolution to the dispute and to both players of the match.
+pub async fn close_dispute(
+    database: &Database,
+    dispute: &Document,
+    resolution: Resolution,
+    note: &str,
+    host_id: u64,
+) -> Result<(), Error> {
+    let disputes: Collection<Document> = database.collection("Disputes");
+    disputes
+        .update_one(
+            doc! {"_id": dispute.get_object_id("_id")?},
+            doc! {"$set": {
+                "status": resolution.status(),
+                "note": note,
+                "resolved_by": host_id.to_string(),
+                "resolved_at": DateTime::now(),
+            }},
+            None,
+        )
+        .await?;
+    database
+        .collection::<Document>(dispute.get_str("round")?)
+        .update_many(
+            doc! {"dispute_id": dispute.get_object_id("_id")?},
+            doc! {"$set": {"dispute": resolution.status(), "dispute_note": note}},
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dispute_deadline, dispute_window, Resolution};
+    use crate::database::config::make_config;
+    use crate::database::fixtures::player;
+    use mongodb::bson::DateTime;
+
+    fn loss(minutes_ago: i64) -> mongodb::bson::Document {
+        let mut loser = player(2);
+        loser.insert("defeated", true);
+        loser.insert(
+            "decided_at",
+            DateTime::from_millis(DateTime::now().timestamp_millis() - minutes_ago * 60 * 1000),
+        );
+        loser
+    }
+
+    #[test]
+    fn disputes_can_be_turned_off() {
+        let mut config = make_config();
+        config.insert("dispute_window", 10);
+        assert_eq!(dispute_window(&config), Some(10));
+        config.insert("dispute_window", 0);
+        assert_eq!(dispute_window(&config), None);
+        assert_eq!(dispute_deadline(&config, &loss(1)), None);
+    }
+
+    #[test]
+    fn losses_can_be_disputed_once_within_the_window() {
+        let mut config = make_config();
+        config.insert("dispute_window", 10);
+        assert!(dispute_deadline(&config, &loss(5)).is_some());
+        assert_eq!(dispute_deadline(&config, &loss(15)), None);
+
+        let mut winner = loss(5);
+        winner.insert("defeated", false);
+        assert_eq!(dispute_deadline(&config, &winner), None);
+
+        let mut disputed = loss(5);
+        disputed.insert("dispute", Resolution::Uphold.status());
+        assert_eq!(dispute_deadline(&config, &disputed), None);
+        disputed.insert("dispute", Resolution::Replay.status());
+        assert!(dispute_deadline(&config, &disputed).is_some());
+    }
+}
@@ -0,0 +1,227 @@
+//! Entry requirements of a region, checked against the `player` endpoint when someone registers or
+//! is invited into a team.
+//!
+//! The rules are stored in the `eligibility` document of the config, by the key of each rule. A
+//! rule that is missing or `null` is not enforced. Members of the clubs listed in `excluded_clubs`
+//! cannot take part either.
+//!
+//! The `player` endpoint does not tell when a Brawl Stars account was created, so the age rule,
+//! `min_account_age` in days, applies to the Discord account instead: its creation time is the
+//! timestamp at the start of its id.
+use crate::bracket_tournament::seeding::normalise_tag;
+use mongodb::bson::{DateTime, Document};
+use serde_json::Value;
+
+/// Key of the fewest days a Discord account must have existed for in the `eligibility` document.
+pub const MIN_ACCOUNT_AGE: &str = "min_account_age";
+/// Discord ids count milliseconds from the start of 2015.
+const DISCORD_EPOCH: i64 = 1_420_070_400_000;
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    MinTrophies,
+    MaxTrophies,
+    MinLevel,
+    MinBrawlers,
+    MinTrioVictories,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::MinTrophies,
+        Rule::MaxTrophies,
+        Rule::MinLevel,
+        Rule::MinBrawlers,
+        Rule::MinTrioVictories,
+    ];
+
+    /// Key of the rule in the `eligibility` document of the config.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Rule::MinTrophies => "min_trophies",
+            Rule::MaxTrophies => "max_trophies",
+            Rule::MinLevel => "min_level",
+            Rule::MinBrawlers => "min_brawlers",
+            Rule::MinTrioVictories => "min_3v3_victories",
+        }
+    }
+
+    /// What the rule measures, e.g. `trophies`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Rule::MinTrophies | Rule::MaxTrophies => "trophies",
+            Rule::MinLevel => "experience level",
+            Rule::MinBrawlers => "brawlers",
+            Rule::MinTrioVictories => "3v3 victories",
+        }
+    }
+
+    fn is_maximum(&self) -> bool {
+        matches!(self, Rule::MaxTrophies)
+    }
+
+    /// What the account has, from its `player` data.
+    fn measure(&self, player: &Value) -> i64 {
+        match self {
+            Rule::MinTrophies | Rule::MaxTrophies => player["trophies"].as_i64(),
+            Rule::MinLevel => player["expLevel"].as_i64(),
+            Rule::MinBrawlers => player["brawlers"]
+                .as_array()
+                .map(|brawlers| brawlers.len() as i64),
+            Rule::MinTrioVictories => player["3vs3Victories"].as_i64(),
+        }
+        .unwrap_or(0)
+    }
+
+    /// The rule with its limit, e.g. `at least 500 trophies`.
+    pub fn describe(&self, limit: i64) -> String {
+        match self.is_maximum() {
+            true => format!("at most {limit} {}", self.label()),
+            false => format!("at least {limit} {}", self.label()),
+        }
+    }
+}
+
+/// The rules enforced in a region, with their limits.
+pub fn rules(config: &Document) -> Vec<(Rule, i64)> {
+    let Ok(eligibility) = config.get_document("eligibility") else {
+        return vec![];
+    };
+    Rule::ALL
+        .into_iter()
+        .filter_map(|rule| {
+            let limit = eligibility.get(rule.key())?;
+            Some((rule, limit.as_i64().or(limit.as_i32().map(i64::from))?))
+        })
+        .collect()
+}
+
+/// Tags of the clubs whose members cannot take part.
+pub fn excluded_clubs(config: &Document) -> Vec<String> {
+    config
+        .get_document("eligibility")
+        .and_then(|eligibility| eligibility.get_array("excluded_clubs"))
+        .map(|clubs| {
+            clubs
+                .iter()
+                .filter_map(|club| club.as_str())
+                .map(normalise_tag)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The fewest days a Discord account must have existed for, if the region asks for any.
+pub fn min_account_age(config: &Document) -> Option<i64> {
+    let age = config
+        .get_document("eligibility")
+        .ok()?
+        .get(MIN_ACCOUNT_AGE)?;
+    age.as_i64()
+        .or(age.as_i32().map(i64::from))
+        .filter(|days| *days > 0)
+}
+
+/// When a Discord account was created, read from its id.
+pub fn account_created(discord_id: u64) -> DateTime {
+    DateTime::from_millis((discord_id >> 22) as i64 + DISCORD_EPOCH)
+}
+
+/// Why a Discord account is too new to take part, if it is.
+pub fn account_age_problem(config: &Document, discord_id: &str, now: DateTime) -> Option<String> {
+    let days = min_account_age(config)?;
+    let created = account_created(discord_id.parse().ok()?);
+    let age = (now.timestamp_millis() - created.timestamp_millis()) / DAY;
+    (age < days).then(|| {
+        format!("You need a Discord account at least {days} days old, but yours is {age} days old.")
+    })
+}
+
+/// The rules an account fails, each explained to the registrant.
+pub fn failed_rules(config: &Document, player: &Value) -> Vec<String> {
+    let club = player["club"]["tag"].as_str().map(normalise_tag);
+    let excluded = club
+        .filter(|club| excluded_clubs(config).contains(club))
+        .map(|club| {
+            format!(
+                "Members of the club {} ({club}) cannot take part, but your account is in it.",
+                player["club"]["name"].as_str().unwrap_or("Unknown")
+            )
+        });
+    rules(config)
+        .into_iter()
+        .filter_map(|(rule, limit)| {
+            let value = rule.measure(player);
+            let passed = match rule.is_maximum() {
+                true => value <= limit,
+                false => value >= limit,
+            };
+            (!passed).then(|| {
+                format!(
+                    "You need {}, but your account has {value}.",
+                    rule.describe(limit)
+                )
+            })
+        })
+        .chain(excluded)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{account_age_problem, account_created, failed_rules};
+    use crate::database::config::make_config;
+    use mongodb::bson::{doc, DateTime};
+
+    #[test]
+    fn registrants_are_told_every_rule_they_fail() {
+        let mut settings = make_config();
+        settings.insert(
+            "eligibility",
+            doc! {"min_trophies": 10000, "max_trophies": 30000, "min_brawlers": 3},
+        );
+        let account = |trophies: i64, brawlers: usize| serde_json::json!({"trophies": trophies, "brawlers": vec![serde_json::json!({}); brawlers]});
+        assert!(failed_rules(&settings, &account(20000, 3)).is_empty());
+        assert_eq!(
+            failed_rules(&settings, &account(5000, 2)),
+            vec![
+                "You need at least 10000 trophies, but your account has 5000.",
+                "You need at least 3 brawlers, but your account has 2.",
+            ]
+        );
+        assert_eq!(failed_rules(&settings, &account(35000, 3)).len(), 1);
+        assert!(failed_rules(&make_config(), &account(0, 0)).is_empty());
+
+        settings.insert("eligibility", doc! {"excluded_clubs": ["#2pp"]});
+        let member = |club: &str| serde_json::json!({"club": {"tag": club, "name": "Rivals"}});
+        assert_eq!(
+            failed_rules(&settings, &member("#2PP")),
+            vec!["Members of the club Rivals (#2PP) cannot take part, but your account is in it."]
+        );
+        assert!(failed_rules(&settings, &member("#8QQ")).is_empty());
+        assert!(failed_rules(&settings, &serde_json::json!({"club": {}})).is_empty());
+    }
+
+    #[test]
+    fn discord_accounts_younger_than_the_minimum_are_refused() {
+        // The id Discord documents as created on 2016-04-30 at 11:18:25.796 UTC
+        let id = "175928847299117063";
+        let created = account_created(id.parse().unwrap());
+        assert_eq!(created.timestamp_millis(), 1_462_015_105_796);
+
+        let mut settings = make_config();
+        assert_eq!(account_age_problem(&settings, id, created), None);
+        settings.insert("eligibility", doc! {"min_account_age": 30});
+        let days = |n: i64| DateTime::from_millis(created.timestamp_millis() + n * 86_400_000);
+        assert_eq!(
+            account_age_problem(&settings, id, days(29)),
+            Some(
+                "You need a Discord account at least 30 days old, but yours is 29 days old."
+                    .to_string()
+            )
+        );
+        assert_eq!(account_age_problem(&settings, id, days(30)), None);
+        assert_eq!(account_age_problem(&settings, "not an id", days(0)), None);
+    }
+}
@@ -1,5 +1,11 @@
+use crate::bracket_tournament::double_elimination::{self, GRAND_FINAL, LOSERS_ROUND};
+use crate::bracket_tournament::group_stage::{group_round_name, GROUP_ROUND};
+use crate::bracket_tournament::placements::{
+    has_third_place_match, is_final_stage, THIRD_PLACE_MATCH,
+};
+use crate::bracket_tournament::seeding::normalise_tag;
 use crate::{Context, Error};
-use dbc_bot::Region;
+use dbc_bot::{Format, Region};
 use mongodb::{
     bson::{doc, Bson, Document},
     Collection, Cursor,
@@ -7,7 +13,11 @@ use mongodb::{
 use strum::IntoEnumIterator;
 use tracing::error;
 
+use super::ban::find_ban;
 use super::config::get_config;
+use super::dispute::OPEN;
+use super::repository::PLAYERS;
+use super::waitlist::WAITLIST;
 
 pub async fn find_self_by_discord_id(
     ctx: &Context<'_>,
@@ -56,10 +66,11 @@ pub async fn find_player_by_discord_id(
 pub fn find_round_from_config(config: &Document) -> String {
     let round = match config.get("round") {
         Some(round) => {
-            if let Bson::Int32(0) = round {
-                "Players".to_string()
-            } else {
-                format!("Round {}", round.as_i32().unwrap())
+            let group_rounds = config.get_i32("group_rounds").unwrap_or(0);
+            match round.as_i32().unwrap() {
+                0 => "Players".to_string(),
+                round if round <= group_rounds => group_round_name(round),
+                round => format!("Round {}", round - group_rounds),
             }
         }
         _ => unreachable!("Round not found in config!"),
@@ -67,13 +78,91 @@ pub fn find_round_from_config(config: &Document) -> String {
 
     round
 }
+
+/// Returns every round collection that is played at the current stage of the tournament.
+///
+/// This is a single round for single elimination, while double elimination plays a winners and a
+/// losers round (or the grand final) at the same time.
+pub fn find_rounds_from_config(config: &Document) -> Vec<String> {
+    match Format::from_config(config) {
+        Format::DoubleElimination => double_elimination::stage_rounds(
+            config.get_i32("round").unwrap_or(0),
+            config.get_i32("total").unwrap_or(0),
+        ),
+        Format::SingleElimination | Format::GroupStage
+            if has_third_place_match(config) && is_final_stage(config) =>
+        {
+            vec![
+                find_round_from_config(config),
+                THIRD_PLACE_MATCH.to_string(),
+            ]
+        }
+        Format::SingleElimination | Format::GroupStage | Format::Swiss => {
+            vec![find_round_from_config(config)]
+        }
+    }
+}
+
+/// Whether the collection stores the players of a round, as opposed to the registrations or the config.
+pub fn is_round_collection(name: &str) -> bool {
+    name.starts_with("Round")
+        || name.starts_with(LOSERS_ROUND)
+        || name.starts_with(GRAND_FINAL)
+        || name.starts_with(GROUP_ROUND)
+        || name == THIRD_PLACE_MATCH
+}
+
+/// Asynchronously searches the rounds that are currently played for the player.
+///
+/// # Returns
+///
+/// The name of the round the player is in, alongside their document in that round.
+pub async fn find_player_in_current_rounds(
+    ctx: &Context<'_>,
+    region: &Region,
+    user_id: u64,
+) -> Result<Option<(String, Document)>, Error> {
+    let config = get_config(ctx, region).await;
+    for round in find_rounds_from_config(&config) {
+        if let Some(player) = find_player_by_discord_id(ctx, region, user_id, &round).await? {
+            return Ok(Some((round, player)));
+        }
+    }
+    Ok(None)
+}
+/// Whether the player is already placed in a round that has not started yet.
+///
+/// In double elimination, players who drop into the losers bracket or reach the grand final early
+/// wait there until the rest of the bracket catches up.
+pub async fn is_waiting_for_next_round(
+    ctx: &Context<'_>,
+    region: &Region,
+    user_id: u64,
+) -> Result<bool, Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let current = find_rounds_from_config(&get_config(ctx, region).await);
+    for name in database.list_collection_names(None).await? {
+        if !is_round_collection(&name) || current.contains(&name) {
+            continue;
+        }
+        if find_player_by_discord_id(ctx, region, user_id, &name)
+            .await?
+            .is_some_and(|player| !player.get_bool("battle").unwrap_or(true))
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 /// Asynchronously searches for enemy in the regional databases.
 ///
 /// # Arguments
 ///
 /// - `ctx` - The context of the application.
 /// - `region` - The region of the player.
-/// - `round` - The round of the match.
+/// - `round` - The round of the match. Match ids are only unique within a round, so for double
+///   elimination this has to be the winners round, losers round or grand final the player is in.
 /// - `match_id` - The match id of the player.
 /// - `other_tag` - The tag of the player.
 ///
@@ -118,57 +207,89 @@ pub async fn find_enemy_of_mannequin(
         Err(_err) => None,
     }
 }
+/// Who holds a player tag.
+pub enum TagHolder {
+    /// A registered or waitlisted player, or the team the tag is a member of.
+    Player(Document),
+    /// A ban on the tag in force in the region looked up.
+    Ban(Document),
+}
+
 /// Asynchronously searches for a player's tag in the regional databases.
 ///
 /// # Arguments
 ///
 /// * `ctx` - The context of the application.
 /// * `tag` - The tag to search for.
+/// * `region` - The region the tag is about to be registered in, if any. A ban on the tag in this
+///   region is found before any player.
 ///
 /// # Returns
 ///
-/// An `Option<Document>` representing the player's data if found, or `None` if not found or an error occurred.
-pub async fn find_tag(ctx: &Context<'_>, tag: &str) -> Option<Document> {
-    let mut result: Option<Document> = None;
-    let proper_tag = match tag.starts_with('#') {
-        true => &tag[1..],
-        false => tag,
-    };
-    for region in Region::iter() {
+/// The ban on the tag or the player holding it, or `None` if the tag is free.
+pub async fn find_tag(
+    ctx: &Context<'_>,
+    tag: &str,
+    region: Option<&Region>,
+) -> Result<Option<TagHolder>, Error> {
+    let tag = normalise_tag(tag);
+    if let Some(region) = region {
+        if let Some(ban) = find_ban(&ctx.data().database.general, None, Some(&tag), region).await? {
+            return Ok(Some(TagHolder::Ban(ban)));
+        }
+    }
+    // Waitlisted players have claimed their tag as well
+    let collections: Vec<(Region, &str)> = Region::iter()
+        .flat_map(|region| [(region.clone(), PLAYERS), (region, WAITLIST)])
+        .collect();
+    for (region, collection) in collections {
         let database = ctx.data().database.regional_databases.get(&region).unwrap();
-        let player_data: Collection<Document> = database.collection("Players");
-
-        match player_data
-            .find_one(doc! { "tag": format!("#{}",&proper_tag)}, None)
-            .await
+        let player_data: Collection<Document> = database.collection(collection);
+        if let Some(player) = player_data
+            .find_one(
+                doc! {"$or": [
+                    {"tag": &tag},
+                    {"members.tag": &tag},
+                ]},
+                None,
+            )
+            .await?
         {
-            Ok(Some(player)) => {
-                result = Some(player);
-                break;
-            }
-            Ok(None) => {
-                continue;
-            }
-            Err(_err) => {
-                result = None;
-                break;
-            }
+            return Ok(Some(TagHolder::Player(player)));
         }
     }
-    result
+    Ok(None)
 }
 
 pub fn is_mannequin(enemy: &Document) -> bool {
-    enemy.get("tag").is_none()
+    super::models::is_bye(enemy)
 }
 
 pub fn is_disqualified(enemy: &Document) -> bool {
     enemy.get("reason").is_some()
 }
 
+/// Finds every player of the current rounds who has not played yet, or whose result is disputed.
+///
+/// Each document is tagged with the `round` it comes from, since several rounds can be played at once.
 pub async fn find_all_false_battles(ctx: &Context<'_>, region: &Region) -> Cursor<Document> {
     let database = ctx.data().database.regional_databases.get(region).unwrap();
-    let round = find_round_from_config(&get_config(ctx, region).await);
-    let collection: Collection<Document> = database.collection(round.as_str());
-    collection.find(doc! {"battle": false}, None).await.unwrap()
+    let rounds = find_rounds_from_config(&get_config(ctx, region).await);
+    let unfinished = |round: &str| {
+        vec![
+            doc! {"$match": {"$or": [{"battle": false}, {"dispute": OPEN}]}},
+            doc! {"$addFields": {"round": round}},
+        ]
+    };
+    let mut pipeline = unfinished(&rounds[0]);
+    for round in rounds.iter().skip(1) {
+        pipeline.push(doc! {
+            "$unionWith": {
+                "coll": round,
+                "pipeline": unfinished(round),
+            }
+        });
+    }
+    let collection: Collection<Document> = database.collection(rounds[0].as_str());
+    collection.aggregate(pipeline, None).await.unwrap()
 }
@@ -0,0 +1,66 @@
+//! Documents and brackets shared by the tests of the database and tournament modules.
+use super::config::make_config;
+use super::mannequin::add_mannequin;
+use super::memory::MemoryRepository;
+use super::repository::Repository;
+use super::update::assign_match_id;
+use dbc_bot::Region;
+use mongodb::bson::{doc, Document};
+
+pub fn player(n: i32) -> Document {
+    doc! {
+        "name": format!("Player {n}"),
+        "tag": format!("#P{n}"),
+        "discord_id": n.to_string(),
+        "discord_name": format!("player{n}"),
+        "region": Region::EU.short(),
+        "match_id": null,
+        "battle": false,
+        "ready": false,
+    }
+}
+
+pub fn config(format: &str, round: i32, total: i32) -> Document {
+    let mut config = make_config();
+    config.insert("format", format);
+    config.insert("tournament", true);
+    config.insert("round", round);
+    config.insert("total", total);
+    config
+}
+
+/// Registers `players` players, padded with mannequins to a bracket of `size`, and seeds them
+/// in registration order.
+pub async fn start(repository: &MemoryRepository, players: i32, size: i32) -> Vec<Document> {
+    let seeded: Vec<Document> = (1..=players)
+        .map(|n| repository.insert("Round 1", player(n)))
+        .collect();
+    for _ in players..size {
+        repository.insert("Round 1", add_mannequin(&Region::EU, None));
+    }
+    assign_match_id(repository, &seeded).await.unwrap();
+    seeded
+}
+
+pub async fn in_round(repository: &MemoryRepository, round: &str, n: i32) -> Document {
+    repository
+        .find_in_round(round, doc! {"tag": format!("#P{n}")})
+        .await
+        .unwrap()
+        .remove(0)
+}
+
+pub fn match_ids(repository: &MemoryRepository, round: &str) -> Vec<(Option<String>, i32)> {
+    let mut ids: Vec<(Option<String>, i32)> = repository
+        .documents(round)
+        .iter()
+        .map(|player| {
+            (
+                player.get_str("tag").ok().map(str::to_string),
+                player.get_i32("match_id").unwrap(),
+            )
+        })
+        .collect();
+    ids.sort();
+    ids
+}
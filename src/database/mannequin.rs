@@ -1,20 +1,10 @@
+use super::models::Mannequin;
 use dbc_bot::Region;
-use mongodb::bson::{doc, Bson::Null, Document};
+use mongodb::bson::{doc, Document};
 pub fn add_mannequin(region: &Region, match_id: Option<i32>) -> Document {
-    let match_id: mongodb::bson::Bson = match match_id {
-        Some(id) => mongodb::bson::Bson::Int32(id),
-        None => Null,
-    };
-    let mannequin = doc! {
-      "name": "Mannequin",
-      "discord_name": "Mannequin",
-      "tag": Null,
-      "discord_id": Null,
-      "region": format!("{:?}", *region),
-      "match_id": match_id,
-      "battle": false
-    };
-    mannequin
+    Mannequin::new(region, match_id)
+        .to_document()
+        .expect("a mannequin always serializes")
 }
 
 pub fn update_mannequin(match_id: i32) -> Document {
@@ -0,0 +1,327 @@
+//! A [`Repository`] kept in memory, to run the bracket logic in tests without MongoDB.
+use super::repository::{Repository, Write, CONFIG, MANAGERS, PLAYERS, RESULTS};
+use crate::Error;
+use async_trait::async_trait;
+use mongodb::bson::{oid::ObjectId, Bson, Document};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+pub struct MemoryRepository {
+    collections: Mutex<BTreeMap<String, Vec<Document>>>,
+}
+
+impl MemoryRepository {
+    pub fn new(config: Document) -> Self {
+        let repository = Self::default();
+        repository.insert(CONFIG, config);
+        repository
+    }
+
+    /// Inserts a document, giving it an `_id` like MongoDB does.
+    pub fn insert(&self, collection: &str, mut document: Document) -> Document {
+        if !document.contains_key("_id") {
+            document.insert("_id", ObjectId::new());
+        }
+        self.collections
+            .lock()
+            .unwrap()
+            .entry(collection.to_string())
+            .or_default()
+            .push(document.clone());
+        document
+    }
+
+    /// Every document of a collection, in insertion order.
+    pub fn documents(&self, collection: &str) -> Vec<Document> {
+        self.collections
+            .lock()
+            .unwrap()
+            .get(collection)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn find(&self, collection: &str, filter: &Document) -> Vec<Document> {
+        self.documents(collection)
+            .into_iter()
+            .filter(|document| matches(document, filter))
+            .collect()
+    }
+
+    fn update(&self, collection: &str, filter: &Document, update: &Document, upsert: bool) {
+        let mut collections = self.collections.lock().unwrap();
+        let documents = collections.entry(collection.to_string()).or_default();
+        match documents
+            .iter_mut()
+            .find(|document| matches(document, filter))
+        {
+            Some(document) => apply(document, update),
+            None if upsert => {
+                let mut document = filter.clone();
+                document.insert("_id", ObjectId::new());
+                apply(&mut document, update);
+                documents.push(document);
+            }
+            None => {}
+        }
+    }
+
+    fn delete(&self, collection: &str, filter: &Document) -> bool {
+        let mut collections = self.collections.lock().unwrap();
+        let Some(documents) = collections.get_mut(collection) else {
+            return false;
+        };
+        match documents
+            .iter()
+            .position(|document| matches(document, filter))
+        {
+            Some(index) => {
+                documents.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Inserts a document unless the collection has one with the same `_id`, like a unique index.
+fn insert_new(documents: &mut Vec<Document>, mut document: Document) -> bool {
+    if !document.contains_key("_id") {
+        document.insert("_id", ObjectId::new());
+    }
+    if documents
+        .iter()
+        .any(|existing| existing.get("_id") == document.get("_id"))
+    {
+        return false;
+    }
+    documents.push(document);
+    true
+}
+
+/// Applies writes to a copy of the collections, which only replaces them if every write succeeds.
+fn stage(staged: &mut BTreeMap<String, Vec<Document>>, writes: Vec<Write>) -> Result<(), Error> {
+    for write in writes {
+        match write {
+            Write::Insert { round, document } => {
+                if !insert_new(staged.entry(round.clone()).or_default(), document) {
+                    return Err(format!("Duplicate key in {round}").into());
+                }
+            }
+            Write::Update {
+                round,
+                filter,
+                update,
+            } => {
+                if let Some(document) = staged
+                    .entry(round)
+                    .or_default()
+                    .iter_mut()
+                    .find(|document| matches(document, &filter))
+                {
+                    apply(document, &update);
+                }
+            }
+            Write::UpdateMany {
+                round,
+                filter,
+                update,
+            } => {
+                staged
+                    .entry(round)
+                    .or_default()
+                    .iter_mut()
+                    .filter(|document| matches(document, &filter))
+                    .for_each(|document| apply(document, &update));
+            }
+            Write::Delete { round, filter } => {
+                staged
+                    .entry(round)
+                    .or_default()
+                    .retain(|document| !matches(document, &filter));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn matches(document: &Document, filter: &Document) -> bool {
+    filter.iter().all(|(key, value)| match value {
+        Bson::Null => matches!(document.get(key), None | Some(Bson::Null)),
+        Bson::Document(condition) if condition.contains_key("$ne") => {
+            let mut equal = Document::new();
+            equal.insert(key, condition.get("$ne").cloned().unwrap_or(Bson::Null));
+            !matches(document, &equal)
+        }
+        Bson::Document(condition) if condition.contains_key("$regex") => {
+            match (condition.get_str("$regex"), document.get_str(key)) {
+                (Ok(pattern), Ok(value)) => Regex::new(pattern).is_ok_and(|re| re.is_match(value)),
+                _ => false,
+            }
+        }
+        value => document.get(key) == Some(value),
+    })
+}
+
+/// Orders two documents on the keys of a sort document, like MongoDB does for values of one type.
+fn compare(a: &Document, b: &Document, sort: &Document) -> Ordering {
+    sort.iter()
+        .map(|(key, direction)| {
+            let order = match (a.get(key), b.get(key)) {
+                (Some(Bson::DateTime(a)), Some(Bson::DateTime(b))) => a.cmp(b),
+                (Some(Bson::Int32(a)), Some(Bson::Int32(b))) => a.cmp(b),
+                (Some(Bson::Int64(a)), Some(Bson::Int64(b))) => a.cmp(b),
+                (Some(Bson::String(a)), Some(Bson::String(b))) => a.cmp(b),
+                (None | Some(Bson::Null), Some(_)) => Ordering::Less,
+                (Some(_), None | Some(Bson::Null)) => Ordering::Greater,
+                _ => Ordering::Equal,
+            };
+            match direction.as_i32() {
+                Some(-1) => order.reverse(),
+                _ => order,
+            }
+        })
+        .find(|order| order.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn apply(document: &mut Document, update: &Document) {
+    if let Ok(set) = update.get_document("$set") {
+        for (key, value) in set {
+            document.insert(key, value.clone());
+        }
+    }
+    if let Ok(unset) = update.get_document("$unset") {
+        for key in unset.keys() {
+            document.remove(key);
+        }
+    }
+}
+
+#[async_trait]
+impl Repository for MemoryRepository {
+    async fn config(&self) -> Result<Option<Document>, Error> {
+        Ok(self.documents(CONFIG).into_iter().next())
+    }
+
+    async fn update_config(&self, update: Document) -> Result<(), Error> {
+        self.update(CONFIG, &Document::new(), &update, false);
+        Ok(())
+    }
+
+    async fn upsert_player(&self, filter: Document, player: Document) -> Result<(), Error> {
+        let mut update = Document::new();
+        update.insert("$set", player);
+        self.update(PLAYERS, &filter, &update, true);
+        Ok(())
+    }
+
+    async fn delete_player(&self, filter: Document) -> Result<(), Error> {
+        self.delete(PLAYERS, &filter);
+        Ok(())
+    }
+
+    async fn round_names(&self) -> Result<Vec<String>, Error> {
+        Ok(self.collections.lock().unwrap().keys().cloned().collect())
+    }
+
+    async fn find_in_round(&self, round: &str, filter: Document) -> Result<Vec<Document>, Error> {
+        Ok(self.find(round, &filter))
+    }
+
+    async fn count_in_round(&self, round: &str, filter: Document) -> Result<u64, Error> {
+        Ok(self.find(round, &filter).len() as u64)
+    }
+
+    async fn insert_into_round(&self, round: &str, player: &Document) -> Result<(), Error> {
+        self.insert(round, player.clone());
+        Ok(())
+    }
+
+    async fn update_in_round(
+        &self,
+        round: &str,
+        filter: Document,
+        update: Document,
+    ) -> Result<(), Error> {
+        self.update(round, &filter, &update, false);
+        Ok(())
+    }
+
+    async fn delete_from_round(&self, round: &str, filter: Document) -> Result<bool, Error> {
+        Ok(self.delete(round, &filter))
+    }
+
+    async fn record(&self, result: Document, writes: Vec<Write>) -> Result<bool, Error> {
+        let mut collections = self.collections.lock().unwrap();
+        let mut staged = collections.clone();
+        if !insert_new(staged.entry(RESULTS.to_string()).or_default(), result) {
+            return Ok(false);
+        }
+        stage(&mut staged, writes)?;
+        *collections = staged;
+        Ok(true)
+    }
+
+    async fn apply(&self, writes: Vec<Write>) -> Result<(), Error> {
+        let mut collections = self.collections.lock().unwrap();
+        let mut staged = collections.clone();
+        stage(&mut staged, writes)?;
+        *collections = staged;
+        Ok(())
+    }
+
+    async fn find_result(&self, key: &str) -> Result<Option<Document>, Error> {
+        Ok(self
+            .documents(RESULTS)
+            .into_iter()
+            .find(|result| result.get_str("_id") == Ok(key)))
+    }
+
+    async fn managers(&self, server_id: &str) -> Result<Option<Document>, Error> {
+        Ok(self
+            .documents(MANAGERS)
+            .into_iter()
+            .find(|managers| managers.get_str("server_id") == Ok(server_id)))
+    }
+
+    async fn find_documents(
+        &self,
+        collection: &str,
+        filter: Document,
+        sort: Document,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<Document>, Error> {
+        let mut documents = self.find(collection, &filter);
+        // A stable sort keeps the insertion order between equal documents, as MongoDB mostly does
+        documents.sort_by(|a, b| compare(a, b, &sort));
+        let documents = documents.into_iter().skip(skip as usize);
+        Ok(match limit {
+            0 => documents.collect(),
+            limit => documents.take(limit.unsigned_abs() as usize).collect(),
+        })
+    }
+
+    async fn count_documents(&self, collection: &str, filter: Document) -> Result<u64, Error> {
+        Ok(self.find(collection, &filter).len() as u64)
+    }
+
+    async fn insert_document(&self, collection: &str, document: &Document) -> Result<(), Error> {
+        self.insert(collection, document.clone());
+        Ok(())
+    }
+
+    async fn delete_documents(&self, collection: &str, filter: Document) -> Result<u64, Error> {
+        let mut collections = self.collections.lock().unwrap();
+        let Some(documents) = collections.get_mut(collection) else {
+            return Ok(0);
+        };
+        let before = documents.len();
+        documents.retain(|document| !matches(document, &filter));
+        Ok((before - documents.len()) as u64)
+    }
+}
@@ -1,10 +1,27 @@
 pub mod add;
+pub mod audit;
+pub mod ban;
 pub mod battle;
+pub mod check_in;
 pub mod config;
+pub mod correction;
+pub mod deadline;
+pub mod dispute;
+pub mod eligibility;
 pub mod find;
+#[cfg(test)]
+pub mod fixtures;
 pub mod mannequin;
+#[cfg(test)]
+pub mod memory;
+pub mod models;
 pub mod open;
 pub mod remove;
+pub mod repair;
+pub mod repository;
+pub mod schedule;
 pub mod stat;
 pub mod statistics;
+pub mod team;
 pub mod update;
+pub mod waitlist;
@@ -0,0 +1,308 @@
+//! Typed shapes of the documents stored by the bot.
+//!
+//! Every regional database holds a `Config` document ([`RegionConfig`]), the registrations in
+//! `Players` ([`Player`]) and one collection per round, whose documents are [`MatchSlot`]s: either a
+//! player or a [`Mannequin`] standing in for a bye. Reading a document that does not have the
+//! expected shape gives a [`MalformedDocument`] error naming the document and the broken field,
+//! instead of a panic in the middle of a command.
+use dbc_bot::Region;
+use mongodb::bson::{self, doc, oid::ObjectId, Bson, Document};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A document that does not have the shape of the model it is read as.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MalformedDocument {
+    kind: &'static str,
+    id: Option<ObjectId>,
+    reason: String,
+}
+
+impl fmt::Display for MalformedDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.id {
+            Some(id) => write!(f, "Malformed {} document {id}: {}", self.kind, self.reason),
+            None => write!(f, "Malformed {} document: {}", self.kind, self.reason),
+        }
+    }
+}
+
+impl std::error::Error for MalformedDocument {}
+
+impl MalformedDocument {
+    fn new(kind: &'static str, document: &Document, reason: impl fmt::Display) -> Self {
+        Self {
+            kind,
+            id: document.get_object_id("_id").ok(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(
+    kind: &'static str,
+    document: &Document,
+) -> Result<T, MalformedDocument> {
+    bson::from_document(document.clone()).map_err(|e| MalformedDocument::new(kind, document, e))
+}
+
+/// A member invited by a team captain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Member {
+    pub tag: String,
+    #[serde(default)]
+    pub name: String,
+    pub discord_id: String,
+}
+
+/// A registered player, or the captain of a team in 2v2 and 3v3 tournaments.
+///
+/// The same document is copied from `Players` into every round the player reaches, together with
+/// the state of their match in that round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Player {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub name: String,
+    #[serde(default)]
+    pub name_color: Option<String>,
+    pub tag: String,
+    #[serde(default)]
+    pub icon: Option<i64>,
+    pub discord_id: String,
+    #[serde(default)]
+    pub discord_name: String,
+    pub region: String,
+    #[serde(default)]
+    pub match_id: Option<i32>,
+    #[serde(default)]
+    pub battle: bool,
+    #[serde(default)]
+    pub ready: bool,
+    /// Set once the match is decided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defeated: Option<bool>,
+    /// Why the player was disqualified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<Member>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub roster_confirmed: bool,
+    /// Any other field, e.g. the state of a dispute, so that nothing is lost when writing back.
+    #[serde(flatten)]
+    pub extra: Document,
+}
+
+impl Player {
+    pub fn from_document(document: &Document) -> Result<Self, MalformedDocument> {
+        parse("player", document)
+    }
+
+    pub fn user_id(&self) -> Result<u64, MalformedDocument> {
+        self.discord_id
+            .parse()
+            .map_err(|_| self.malformed(format!("`{}` is not a Discord ID", self.discord_id)))
+    }
+
+    pub fn region(&self) -> Result<Region, MalformedDocument> {
+        Region::find_key(&self.region)
+            .ok_or_else(|| self.malformed(format!("`{}` is not a region", self.region)))
+    }
+
+    pub fn match_id(&self) -> Result<i32, MalformedDocument> {
+        self.match_id
+            .ok_or_else(|| self.malformed("the player has not been placed in a match"))
+    }
+
+    fn malformed(&self, reason: impl fmt::Display) -> MalformedDocument {
+        MalformedDocument {
+            kind: "player",
+            id: self.id,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// Stands in for a missing opponent. Whoever faces a mannequin advances without playing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mannequin {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub name: String,
+    pub discord_name: String,
+    /// Always null, which is how a mannequin is told apart from a player.
+    pub tag: Option<String>,
+    pub discord_id: Option<String>,
+    pub region: String,
+    pub match_id: Option<i32>,
+    #[serde(default)]
+    pub battle: bool,
+    #[serde(flatten)]
+    pub extra: Document,
+}
+
+impl Mannequin {
+    pub fn new(region: &Region, match_id: Option<i32>) -> Self {
+        Self {
+            id: None,
+            name: "Mannequin".to_string(),
+            discord_name: "Mannequin".to_string(),
+            tag: None,
+            discord_id: None,
+            region: region.short(),
+            match_id,
+            battle: false,
+            extra: Document::new(),
+        }
+    }
+
+    pub fn to_document(&self) -> Result<Document, bson::ser::Error> {
+        bson::to_document(self)
+    }
+}
+
+/// One side of a match in a round collection.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "Document")]
+pub enum MatchSlot {
+    Player(Box<Player>),
+    Mannequin(Box<Mannequin>),
+}
+
+impl TryFrom<Document> for MatchSlot {
+    type Error = MalformedDocument;
+
+    fn try_from(document: Document) -> Result<Self, Self::Error> {
+        Self::from_document(&document)
+    }
+}
+
+impl MatchSlot {
+    pub fn from_document(document: &Document) -> Result<Self, MalformedDocument> {
+        if is_bye(document) {
+            parse("mannequin", document).map(|mannequin| MatchSlot::Mannequin(Box::new(mannequin)))
+        } else {
+            Player::from_document(document).map(|player| MatchSlot::Player(Box::new(player)))
+        }
+    }
+}
+
+/// Whether a document is a mannequin. Mannequins are stored with a null tag.
+pub fn is_bye(document: &Document) -> bool {
+    matches!(document.get("tag"), None | Some(Bson::Null))
+}
+
+/// The `Config` document of a region.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegionConfig {
+    pub registration: bool,
+    pub tournament: bool,
+    pub round: i32,
+    pub mode: Option<String>,
+    pub map: Option<String>,
+    pub total: i32,
+    pub role: Option<String>,
+    pub channel: Option<String>,
+    pub bracket_channel: Option<String>,
+    pub bracket_message_id: Option<String>,
+    pub log_channel: Option<String>,
+    pub format: Option<String>,
+    pub bracket_reset: bool,
+    pub third_place_match: bool,
+    pub placements_recorded: bool,
+    pub groups: i32,
+    pub group_size: i32,
+    pub group_advance: i32,
+    pub group_rounds: i32,
+    pub swiss_rounds: i32,
+    pub seeding: Option<String>,
+    pub seed_list: Vec<String>,
+    pub rng_seed: Option<i64>,
+    pub best_of: i32,
+    pub best_of_rounds: Document,
+    pub draw_rule: Option<String>,
+    pub team_size: i32,
+    pub watch_interval: i32,
+    pub dispute_window: i32,
+}
+
+impl Default for RegionConfig {
+    fn default() -> Self {
+        Self {
+            registration: false,
+            tournament: false,
+            round: 0,
+            mode: None,
+            map: None,
+            total: 0,
+            role: None,
+            channel: None,
+            bracket_channel: None,
+            bracket_message_id: None,
+            log_channel: None,
+            format: None,
+            bracket_reset: false,
+            third_place_match: false,
+            placements_recorded: false,
+            groups: 0,
+            group_size: 0,
+            group_advance: 0,
+            group_rounds: 0,
+            swiss_rounds: 0,
+            seeding: None,
+            seed_list: vec![],
+            rng_seed: None,
+            best_of: 3,
+            best_of_rounds: Document::new(),
+            draw_rule: None,
+            team_size: 1,
+            watch_interval: 120,
+            dispute_window: 30,
+        }
+    }
+}
+
+impl RegionConfig {
+    pub fn from_document(document: &Document) -> Result<Self, MalformedDocument> {
+        parse("config", document)
+    }
+
+    /// The role given to the players of the tournament.
+    pub fn role_id(&self) -> Result<u64, MalformedDocument> {
+        Self::id_of("role", &self.role)
+    }
+
+    /// The channel where results are announced.
+    pub fn channel_id(&self) -> Result<u64, MalformedDocument> {
+        Self::id_of("channel", &self.channel)
+    }
+
+    pub fn mode(&self) -> Result<&str, MalformedDocument> {
+        self.mode.as_deref().ok_or_else(|| MalformedDocument {
+            kind: "config",
+            id: None,
+            reason: "the mode of the tournament is not set".to_string(),
+        })
+    }
+
+    pub fn map(&self) -> &str {
+        self.map.as_deref().unwrap_or("Any")
+    }
+
+    fn id_of(field: &str, value: &Option<String>) -> Result<u64, MalformedDocument> {
+        let value = value.as_deref().unwrap_or("");
+        value.parse().map_err(|_| MalformedDocument {
+            kind: "config",
+            id: None,
+            reason: match value {
+                "" => format!("`{field}` is not set"),
+                value => format!("`{field}` is `{value}`, which is not a Discord ID"),
+            },
+        })
+    }
+}
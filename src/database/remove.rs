@@ -1,54 +1,104 @@
-use super::config::get_config;
+use super::config::find_config;
 use super::mannequin::add_mannequin;
+use super::models::Player;
+use super::repository::Repository;
 use crate::Region;
 use crate::{Context, Error};
 use mongodb::bson::{doc, Document};
 
-use super::find::find_round_from_config;
+use super::find::{find_round_from_config, find_rounds_from_config};
+use crate::bracket_tournament::double_elimination::parse_winners_round;
 pub async fn remove_player(
-    ctx: &Context<'_>,
+    database: &dyn Repository,
     player: &Document,
     region: &Region,
 ) -> Result<(), Error> {
-    let database = ctx.data().database.regional_databases.get(region).unwrap();
-    let config = get_config(ctx, region).await;
-    let current_round = find_round_from_config(&config);
-    let round_collection = database.collection::<Document>(&current_round);
-    let players_collection = database.collection::<Document>("Players");
-    match round_collection.name() {
+    let config = find_config(database).await;
+    let mut current_round = find_round_from_config(&config);
+    for round in find_rounds_from_config(&config) {
+        if database
+            .count_in_round(&round, doc! {"_id": player.get("_id")})
+            .await?
+            > 0
+        {
+            current_round = round;
+            break;
+        }
+    }
+    match current_round.as_str() {
         "Players" => {
-            players_collection
-                .delete_one(doc! {"_id": player.get("_id")}, None)
+            database
+                .delete_player(doc! {"_id": player.get("_id")})
                 .await?;
         }
         _ => {
-            let match_id = player.get_i32("match_id").unwrap();
+            let match_id = Player::from_document(player)?.match_id()?;
             let mannequin = add_mannequin(region, Some(match_id));
-            round_collection.insert_one(mannequin, None).await?;
-            round_collection
-                .delete_one(doc! {"_id": player.get("_id")}, None)
+            database
+                .insert_into_round(&current_round, &mannequin)
+                .await?;
+            database
+                .delete_from_round(&current_round, doc! {"_id": player.get("_id")})
                 .await?;
-            let (_, r_u32) = current_round.split_at(6);
-            let next_round_collection = database.collection::<Document>(&format!(
-                "Round {}",
-                r_u32.trim().parse::<u32>().unwrap_or(0) + 1_u32
-            ));
-            if next_round_collection
-                .delete_one(doc! {"_id": player.get("_id")}, None)
-                .await
-                .is_ok()
-            {};
+            if let Some(round) = parse_winners_round(&current_round) {
+                database
+                    .delete_from_round(
+                        &format!("Round {}", round + 1),
+                        doc! {"_id": player.get("_id")},
+                    )
+                    .await?;
+            }
         }
     }
     Ok(())
 }
 
 pub async fn remove_registration(ctx: &Context<'_>, player: &Document) -> Result<(), Error> {
-    let region = Region::find_key(player.get_str("region").unwrap()).unwrap();
+    let region = Player::from_document(player)?.region()?;
     let database = ctx.data().database.regional_databases.get(&region).unwrap();
-    let players_collection = database.collection::<Document>("Players");
-    players_collection
-        .delete_one(doc! {"_id": player.get("_id")}, None)
+    database
+        .delete_player(doc! {"_id": player.get("_id")})
         .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::remove_player;
+    use crate::database::fixtures::{config, in_round, player, start};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::Repository;
+    use dbc_bot::Region;
+    use mongodb::bson::doc;
+
+    #[tokio::test]
+    async fn removed_player_is_replaced_by_a_mannequin() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 2));
+        start(&repository, 4, 4).await;
+        let leaving = in_round(&repository, "Round 1", 2).await;
+        remove_player(&repository, &leaving, &Region::EU)
+            .await
+            .unwrap();
+        assert!(repository
+            .find_in_round("Round 1", doc! {"tag": "#P2"})
+            .await
+            .unwrap()
+            .is_empty());
+        let bye = repository
+            .find_in_round("Round 1", doc! {"tag": null})
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(bye.get_i32("match_id"), leaving.get_i32("match_id"));
+    }
+
+    #[tokio::test]
+    async fn removed_registration_is_deleted() {
+        let repository = MemoryRepository::new(config("Single Elimination", 0, 0));
+        let registered = repository.insert("Players", player(1));
+        remove_player(&repository, &registered, &Region::EU)
+            .await
+            .unwrap();
+        assert!(repository.documents("Players").is_empty());
+    }
+}
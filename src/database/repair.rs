@@ -0,0 +1,261 @@
+//! Repair of match results that were only partly written.
+//!
+//! Results are recorded in one transaction, but a result written before that, or by hand, can leave
+//! the winner in the next round while the match still looks unplayed, a decided match whose players
+//! were never moved on, or a player twice in the same round. Matches with an entry in `Results` are
+//! complete by construction and are not checked.
+use std::collections::BTreeMap;
+
+use super::config::find_config;
+use super::find::{find_rounds_from_config, is_mannequin, is_round_collection};
+use super::repository::{Repository, Write};
+use super::update::{plan_result, result_key};
+use crate::Error;
+use dbc_bot::Region;
+use mongodb::bson::{doc, DateTime, Document};
+
+/// Finishes recording every half-applied match of the current rounds and removes duplicated players.
+///
+/// Returns a description of each repair.
+pub async fn repair_results(
+    database: &dyn Repository,
+    region: &Region,
+) -> Result<Vec<String>, Error> {
+    let config = find_config(database).await;
+    let mut repairs = remove_duplicates(database).await?;
+    for round in find_rounds_from_config(&config) {
+        if !is_round_collection(&round) {
+            continue;
+        }
+        let mut matches: BTreeMap<i32, Vec<Document>> = BTreeMap::new();
+        for player in database.find_in_round(&round, doc! {}).await? {
+            if let Ok(match_id) = player.get_i32("match_id") {
+                matches.entry(match_id).or_default().push(player);
+            }
+        }
+        for (match_id, players) in matches {
+            let [first, second] = players.as_slice() else {
+                continue;
+            };
+            if database
+                .find_result(&result_key(region, &round, match_id))
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+            if let Some(repair) =
+                repair_match(database, region, &round, match_id, first, second).await?
+            {
+                repairs.push(repair);
+            }
+        }
+    }
+    Ok(repairs)
+}
+
+async fn repair_match(
+    database: &dyn Repository,
+    region: &Region,
+    round: &str,
+    match_id: i32,
+    first: &Document,
+    second: &Document,
+) -> Result<Option<String>, Error> {
+    let decided = match (first.get_bool("defeated"), second.get_bool("defeated")) {
+        (Ok(false), Ok(true)) => Some((first, second)),
+        (Ok(true), Ok(false)) => Some((second, first)),
+        _ => None,
+    };
+    let (winner, loser) = match decided {
+        Some(players) => players,
+        None => {
+            let first_advanced = has_advanced(database, region, round, first, second).await?;
+            let second_advanced = has_advanced(database, region, round, second, first).await?;
+            match (first_advanced, second_advanced) {
+                (true, false) => (first, second),
+                (false, true) => (second, first),
+                (true, true) => {
+                    return Ok(Some(format!(
+                        "{round} match {match_id}: both players were moved on without a result. Please override it!"
+                    )))
+                }
+                (false, false) => return Ok(None),
+            }
+        }
+    };
+    let reason = loser.get_str("reason").ok();
+    let mut missing = vec![];
+    for write in plan_result(database, region, round, winner, loser, reason).await? {
+        // A decided match keeps the time it was decided at
+        if decided.is_some() && matches!(&write, Write::Update { round: of, .. } if of == round) {
+            continue;
+        }
+        if !is_present(database, &write).await? {
+            missing.push(write);
+        }
+    }
+    let moved = missing
+        .iter()
+        .any(|write| matches!(write, Write::Insert { .. }));
+    if decided.is_some() && !moved {
+        return Ok(None);
+    }
+    let result = doc! {
+        "_id": result_key(region, round, match_id),
+        "round": round,
+        "match_id": match_id,
+        "winner": winner.get("_id"),
+        "loser": loser.get("_id"),
+        "reason": reason,
+        "recorded_at": DateTime::now(),
+        "repaired": true,
+    };
+    let name = winner.get_str("tag").unwrap_or("Mannequin");
+    if !database.record(result, missing).await? {
+        return Ok(None);
+    }
+    Ok(Some(match decided {
+        Some(_) => format!("{round} match {match_id}: moved the players on after {name}'s win"),
+        None => format!(
+            "{round} match {match_id}: {name} was already moved on, recorded them as the winner"
+        ),
+    }))
+}
+
+/// Whether `player` already is where winning the match would have put them.
+async fn has_advanced(
+    database: &dyn Repository,
+    region: &Region,
+    round: &str,
+    player: &Document,
+    opponent: &Document,
+) -> Result<bool, Error> {
+    if is_mannequin(player) {
+        return Ok(false);
+    }
+    for write in plan_result(database, region, round, player, opponent, None).await? {
+        if let Write::Insert { document, .. } = &write {
+            if document.get("_id") == player.get("_id") && is_present(database, &write).await? {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Whether an insert of a result has already been applied. Updates are always applied again.
+async fn is_present(database: &dyn Repository, write: &Write) -> Result<bool, Error> {
+    let Write::Insert { round, document } = write else {
+        return Ok(false);
+    };
+    let filter = if is_mannequin(document) {
+        doc! {"match_id": document.get("match_id"), "tag": null}
+    } else {
+        doc! {"_id": document.get("_id")}
+    };
+    Ok(database.count_in_round(round, filter).await? > 0)
+}
+
+/// Removes the copies of players who were placed twice in the same round.
+async fn remove_duplicates(database: &dyn Repository) -> Result<Vec<String>, Error> {
+    let mut repairs = vec![];
+    for round in database.round_names().await? {
+        if !is_round_collection(&round) {
+            continue;
+        }
+        let mut seen: Vec<String> = vec![];
+        for player in database.find_in_round(&round, doc! {}).await? {
+            let Ok(tag) = player.get_str("tag") else {
+                continue;
+            };
+            if !seen.iter().any(|seen| seen == tag) {
+                seen.push(tag.to_string());
+                continue;
+            }
+            database
+                .delete_from_round(&round, doc! {"_id": player.get("_id")})
+                .await?;
+            repairs.push(format!("{round}: removed a copy of {tag}"));
+        }
+    }
+    Ok(repairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::repair_results;
+    use crate::database::fixtures::{config, in_round, match_ids, player, start};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::{Repository, RESULTS};
+    use crate::database::update::update_match_id;
+    use dbc_bot::Region;
+    use mongodb::bson::doc;
+
+    #[tokio::test]
+    async fn repair_records_a_winner_who_already_advanced() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 2));
+        start(&repository, 4, 4).await;
+        // The winner was moved on, but the match was never marked as played
+        let winner = in_round(&repository, "Round 1", 1).await;
+        repository.insert("Round 2", update_match_id(winner).unwrap());
+
+        let repairs = repair_results(&repository, &Region::EU).await.unwrap();
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repository.documents("Round 2").len(), 1);
+        assert_eq!(
+            in_round(&repository, "Round 1", 1)
+                .await
+                .get_bool("defeated"),
+            Ok(false)
+        );
+        assert_eq!(
+            in_round(&repository, "Round 1", 4)
+                .await
+                .get_bool("defeated"),
+            Ok(true)
+        );
+        assert_eq!(repository.documents(RESULTS).len(), 1);
+        assert!(repair_results(&repository, &Region::EU)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn repair_moves_on_a_decided_winner() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 2));
+        start(&repository, 4, 4).await;
+        // The match was marked as played, but the winner never reached the next round
+        for (n, defeated) in [(2, false), (3, true)] {
+            repository
+                .update_in_round(
+                    "Round 1",
+                    doc! {"tag": format!("#P{n}")},
+                    doc! {"$set": {"battle": true, "defeated": defeated}},
+                )
+                .await
+                .unwrap();
+        }
+
+        let repairs = repair_results(&repository, &Region::EU).await.unwrap();
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(
+            match_ids(&repository, "Round 2"),
+            vec![(Some("#P2".into()), 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn repair_removes_duplicated_players() {
+        let repository = MemoryRepository::new(config("Single Elimination", 2, 2));
+        let mut copy = player(1);
+        copy.insert("match_id", 1);
+        repository.insert("Round 2", copy.clone());
+        repository.insert("Round 2", copy);
+
+        let repairs = repair_results(&repository, &Region::EU).await.unwrap();
+        assert_eq!(repairs, vec!["Round 2: removed a copy of #P1".to_string()]);
+        assert_eq!(repository.documents("Round 2").len(), 1);
+    }
+}
@@ -0,0 +1,425 @@
+//! Storage of the bot behind a trait, so that the bracket logic does not depend on MongoDB.
+//!
+//! Every regional [`Database`] is a [`Repository`] over its `Config`, `Players` and round
+//! collections, and the general database over its `Managers` and the collections that outlive a
+//! tournament, such as `Bans` and `AuditLog`. Filters and updates are the same documents MongoDB
+//! takes: equality on fields (`null` matching a missing field), `$ne`, `$regex`, `$set` and `$unset`. The
+//! in-memory backend used by the tests understands the same subset.
+//!
+//! Match results are written through [`Repository::record`], which applies all the writes of a
+//! result in one transaction together with the entry of the match in `Results`. The entry is keyed
+//! by region, round and match, so a result that is submitted twice is only recorded once. Host
+//! corrections of recorded results go through [`Repository::apply`] the same way.
+//!
+//! MongoDB only has transactions on a replica set or a sharded cluster. On a standalone server the
+//! writes are applied one after the other instead, and a warning is logged at startup.
+use crate::Error;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::error::{
+    ErrorKind, WriteFailure, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT,
+};
+use mongodb::options::{FindOptions, UpdateOptions};
+use mongodb::{ClientSession, Database};
+use tracing::warn;
+
+pub const CONFIG: &str = "Config";
+pub const PLAYERS: &str = "Players";
+pub const MANAGERS: &str = "Managers";
+pub const RESULTS: &str = "Results";
+
+/// Attempts at a transaction that keeps conflicting with another one, e.g. the same result
+/// submitted twice at once.
+const TRANSACTION_ATTEMPTS: usize = 5;
+const DUPLICATE_KEY: i32 = 11000;
+
+/// One write of a match result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Write {
+    Insert {
+        round: String,
+        document: Document,
+    },
+    Update {
+        round: String,
+        filter: Document,
+        update: Document,
+    },
+    /// Updates every document matching `filter`.
+    UpdateMany {
+        round: String,
+        filter: Document,
+        update: Document,
+    },
+    /// Deletes every document matching `filter`.
+    Delete {
+        round: String,
+        filter: Document,
+    },
+}
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+    /// The `Config` document of the region, if it has been created.
+    async fn config(&self) -> Result<Option<Document>, Error>;
+
+    async fn update_config(&self, update: Document) -> Result<(), Error>;
+
+    /// Registers a player, or updates the registration matching `filter`.
+    async fn upsert_player(&self, filter: Document, player: Document) -> Result<(), Error>;
+
+    async fn delete_player(&self, filter: Document) -> Result<(), Error>;
+
+    /// Names of every collection, rounds included.
+    async fn round_names(&self) -> Result<Vec<String>, Error>;
+
+    async fn find_in_round(&self, round: &str, filter: Document) -> Result<Vec<Document>, Error>;
+
+    async fn count_in_round(&self, round: &str, filter: Document) -> Result<u64, Error>;
+
+    async fn insert_into_round(&self, round: &str, player: &Document) -> Result<(), Error>;
+
+    async fn update_in_round(
+        &self,
+        round: &str,
+        filter: Document,
+        update: Document,
+    ) -> Result<(), Error>;
+
+    /// Deletes the first document matching `filter`, returning whether there was one.
+    async fn delete_from_round(&self, round: &str, filter: Document) -> Result<bool, Error>;
+
+    /// The `Managers` document of a server, with the roles allowed to host.
+    async fn managers(&self, server_id: &str) -> Result<Option<Document>, Error>;
+
+    /// Documents of a collection that is not a round, such as `Bans`, sorted on `sort` (`1` for
+    /// ascending, `-1` for descending), skipping the first `skip` and returning at most `limit`, or
+    /// every one for `0`.
+    async fn find_documents(
+        &self,
+        collection: &str,
+        filter: Document,
+        sort: Document,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<Document>, Error>;
+
+    async fn count_documents(&self, collection: &str, filter: Document) -> Result<u64, Error>;
+
+    async fn insert_document(&self, collection: &str, document: &Document) -> Result<(), Error>;
+
+    /// Deletes every document matching `filter`, returning how many there were.
+    async fn delete_documents(&self, collection: &str, filter: Document) -> Result<u64, Error>;
+
+    /// Applies `writes` and inserts `result` into `Results`, all or nothing.
+    ///
+    /// Returns `false` without writing anything if `Results` already has an entry with the `_id` of
+    /// `result`.
+    async fn record(&self, result: Document, writes: Vec<Write>) -> Result<bool, Error>;
+
+    /// Applies `writes`, all or nothing.
+    async fn apply(&self, writes: Vec<Write>) -> Result<(), Error>;
+
+    /// The entry of a recorded match in `Results`.
+    async fn find_result(&self, key: &str) -> Result<Option<Document>, Error>;
+}
+
+#[async_trait]
+impl Repository for Database {
+    async fn config(&self) -> Result<Option<Document>, Error> {
+        Ok(self
+            .collection::<Document>(CONFIG)
+            .find_one(None, None)
+            .await?)
+    }
+
+    async fn update_config(&self, update: Document) -> Result<(), Error> {
+        self.collection::<Document>(CONFIG)
+            .update_one(doc! {}, update, None)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_player(&self, filter: Document, player: Document) -> Result<(), Error> {
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.collection::<Document>(PLAYERS)
+            .update_one(filter, doc! {"$set": player}, options)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_player(&self, filter: Document) -> Result<(), Error> {
+        self.collection::<Document>(PLAYERS)
+            .delete_one(filter, None)
+            .await?;
+        Ok(())
+    }
+
+    async fn round_names(&self) -> Result<Vec<String>, Error> {
+        Ok(self.list_collection_names(None).await?)
+    }
+
+    async fn find_in_round(&self, round: &str, filter: Document) -> Result<Vec<Document>, Error> {
+        Ok(self
+            .collection::<Document>(round)
+            .find(filter, None)
+            .await?
+            .try_collect()
+            .await?)
+    }
+
+    async fn count_in_round(&self, round: &str, filter: Document) -> Result<u64, Error> {
+        Ok(self
+            .collection::<Document>(round)
+            .count_documents(filter, None)
+            .await?)
+    }
+
+    async fn insert_into_round(&self, round: &str, player: &Document) -> Result<(), Error> {
+        self.collection::<Document>(round)
+            .insert_one(player, None)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_in_round(
+        &self,
+        round: &str,
+        filter: Document,
+        update: Document,
+    ) -> Result<(), Error> {
+        self.collection::<Document>(round)
+            .update_one(filter, update, None)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_from_round(&self, round: &str, filter: Document) -> Result<bool, Error> {
+        let result = self
+            .collection::<Document>(round)
+            .delete_one(filter, None)
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    async fn managers(&self, server_id: &str) -> Result<Option<Document>, Error> {
+        Ok(self
+            .collection::<Document>(MANAGERS)
+            .find_one(doc! {"server_id": server_id}, None)
+            .await?)
+    }
+
+    async fn find_documents(
+        &self,
+        collection: &str,
+        filter: Document,
+        sort: Document,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<Document>, Error> {
+        let options = FindOptions::builder()
+            .sort(sort)
+            .skip(skip)
+            .limit(limit)
+            .build();
+        Ok(self
+            .collection::<Document>(collection)
+            .find(filter, options)
+            .await?
+            .try_collect()
+            .await?)
+    }
+
+    async fn count_documents(&self, collection: &str, filter: Document) -> Result<u64, Error> {
+        Ok(self
+            .collection::<Document>(collection)
+            .count_documents(filter, None)
+            .await?)
+    }
+
+    async fn insert_document(&self, collection: &str, document: &Document) -> Result<(), Error> {
+        self.collection::<Document>(collection)
+            .insert_one(document, None)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_documents(&self, collection: &str, filter: Document) -> Result<u64, Error> {
+        let result = self
+            .collection::<Document>(collection)
+            .delete_many(filter, None)
+            .await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn record(&self, result: Document, writes: Vec<Write>) -> Result<bool, Error> {
+        let key = result.get_str("_id")?;
+        match transaction(self, Some(&result), &writes).await {
+            Ok(()) => Ok(true),
+            // A player already in the next round also fails as a duplicate, and is still an error
+            Err(e) if is_duplicate_key(&e) && self.find_result(key).await?.is_some() => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn apply(&self, writes: Vec<Write>) -> Result<(), Error> {
+        Ok(transaction(self, None, &writes).await?)
+    }
+
+    async fn find_result(&self, key: &str) -> Result<Option<Document>, Error> {
+        Ok(self
+            .collection::<Document>(RESULTS)
+            .find_one(doc! {"_id": key}, None)
+            .await?)
+    }
+}
+
+/// Whether the server can run transactions, i.e. is a replica set or a sharded cluster.
+pub async fn supports_transactions(database: &Database) -> Result<bool, Error> {
+    let hello = database.run_command(doc! {"hello": 1}, None).await?;
+    Ok(hello.contains_key("setName") || hello.get_str("msg") == Ok("isdbgrid"))
+}
+
+/// Inserts `result` into `Results` and applies `writes` in one transaction, retrying when it keeps
+/// conflicting with another one. Without transactions, they are applied one after the other, the
+/// entry in `Results` first so that a result is still only recorded once.
+async fn transaction(
+    database: &Database,
+    result: Option<&Document>,
+    writes: &[Write],
+) -> mongodb::error::Result<()> {
+    let mut session = database
+        .collection::<Document>(RESULTS)
+        .client()
+        .start_session(None)
+        .await?;
+    let mut attempt = 1;
+    loop {
+        match session.start_transaction(None).await {
+            Err(e) if matches!(e.kind.as_ref(), ErrorKind::Transaction { .. }) => {
+                return apply_writes(database, None, result, writes).await;
+            }
+            started => started?,
+        }
+        let error = match apply_writes(database, Some(&mut session), result, writes).await {
+            Ok(()) => match commit(&mut session).await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            },
+            Err(e) => {
+                // The server may have aborted the transaction already
+                let _ = session.abort_transaction().await;
+                e
+            }
+        };
+        if is_duplicate_key(&error)
+            || !error.contains_label(TRANSIENT_TRANSACTION_ERROR)
+            || attempt == TRANSACTION_ATTEMPTS
+        {
+            return Err(error);
+        }
+        warn!("Retrying a transaction: {error}");
+        attempt += 1;
+    }
+}
+
+async fn apply_writes(
+    database: &Database,
+    mut session: Option<&mut ClientSession>,
+    result: Option<&Document>,
+    writes: &[Write],
+) -> mongodb::error::Result<()> {
+    if let Some(result) = result {
+        let results = database.collection::<Document>(RESULTS);
+        match session.as_deref_mut() {
+            Some(session) => {
+                results
+                    .insert_one_with_session(result, None, session)
+                    .await?
+            }
+            None => results.insert_one(result, None).await?,
+        };
+    }
+    for write in writes {
+        match write {
+            Write::Insert { round, document } => {
+                let collection = database.collection::<Document>(round);
+                match session.as_deref_mut() {
+                    Some(session) => {
+                        collection
+                            .insert_one_with_session(document, None, session)
+                            .await?
+                    }
+                    None => collection.insert_one(document, None).await?,
+                };
+            }
+            Write::Update {
+                round,
+                filter,
+                update,
+            } => {
+                let collection = database.collection::<Document>(round);
+                match session.as_deref_mut() {
+                    Some(session) => {
+                        collection
+                            .update_one_with_session(filter.clone(), update.clone(), None, session)
+                            .await?
+                    }
+                    None => {
+                        collection
+                            .update_one(filter.clone(), update.clone(), None)
+                            .await?
+                    }
+                };
+            }
+            Write::UpdateMany {
+                round,
+                filter,
+                update,
+            } => {
+                let collection = database.collection::<Document>(round);
+                match session.as_deref_mut() {
+                    Some(session) => {
+                        collection
+                            .update_many_with_session(filter.clone(), update.clone(), None, session)
+                            .await?
+                    }
+                    None => {
+                        collection
+                            .update_many(filter.clone(), update.clone(), None)
+                            .await?
+                    }
+                };
+            }
+            Write::Delete { round, filter } => {
+                let collection = database.collection::<Document>(round);
+                match session.as_deref_mut() {
+                    Some(session) => {
+                        collection
+                            .delete_many_with_session(filter.clone(), None, session)
+                            .await?
+                    }
+                    None => collection.delete_many(filter.clone(), None).await?,
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn commit(session: &mut ClientSession) -> mongodb::error::Result<()> {
+    loop {
+        match session.commit_transaction().await {
+            Err(e) if e.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT) => continue,
+            result => return result,
+        }
+    }
+}
+
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == DUPLICATE_KEY
+    )
+}
@@ -0,0 +1,124 @@
+//! Scheduled opening and closing of the registration, opening of the check-in and start of the
+//! tournament.
+//!
+//! The times are stored in the config of the region (`registration_opens`, `registration_closes`,
+//! `check_in_opens` and `tournament_starts`), so a schedule survives a restart of the bot. An event is cleared from
+//! the config once it has been carried out, whether it succeeded or not.
+use super::repository::Repository;
+use crate::Error;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use mongodb::bson::{doc, DateTime, Document};
+
+/// How hosts write the time of an event, always in UTC.
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    OpenRegistration,
+    CloseRegistration,
+    OpenCheckIn,
+    StartTournament,
+}
+
+impl Event {
+    pub const ALL: [Event; 4] = [
+        Event::OpenRegistration,
+        Event::CloseRegistration,
+        Event::OpenCheckIn,
+        Event::StartTournament,
+    ];
+
+    /// Key of the time of the event in the config.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Event::OpenRegistration => "registration_opens",
+            Event::CloseRegistration => "registration_closes",
+            Event::OpenCheckIn => "check_in_opens",
+            Event::StartTournament => "tournament_starts",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Event::OpenRegistration => "Registration opens",
+            Event::CloseRegistration => "Registration closes",
+            Event::OpenCheckIn => "Check-in opens",
+            Event::StartTournament => "Tournament starts",
+        }
+    }
+}
+
+/// When an event is scheduled, if it is.
+pub fn scheduled(config: &Document, event: Event) -> Option<DateTime> {
+    config.get_datetime(event.key()).ok().copied()
+}
+
+/// Reads a time written by a host, `Ok(None)` when left blank.
+pub fn parse_time(text: &str) -> Result<Option<DateTime>, chrono::ParseError> {
+    match text.trim() {
+        "" => Ok(None),
+        text => {
+            let time = NaiveDateTime::parse_from_str(text, TIME_FORMAT)?;
+            Ok(Some(DateTime::from_millis(time.timestamp_millis())))
+        }
+    }
+}
+
+/// A time in the format hosts write it in.
+pub fn format_time(time: DateTime) -> String {
+    Utc.timestamp_millis_opt(time.timestamp_millis())
+        .single()
+        .map(|time| time.format(TIME_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+/// The events whose time has come, in the order they have to be carried out.
+///
+/// Events that no longer make sense, like opening the registration of a running tournament, are
+/// due as well so that they get cleared.
+pub fn due_events(config: &Document, now: DateTime) -> Vec<Event> {
+    Event::ALL
+        .into_iter()
+        .filter(|event| scheduled(config, *event).is_some_and(|time| time <= now))
+        .collect()
+}
+
+pub async fn clear_event(database: &dyn Repository, event: Event) -> Result<(), Error> {
+    database
+        .update_config(doc! {"$set": {event.key(): null}})
+        .await
+}
+
+/// Whether everything the registration needs has been configured.
+pub fn registration_ready(config: &Document) -> bool {
+    ["mode", "role", "channel", "bracket_channel"]
+        .iter()
+        .all(|key| config.get_str(key).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{due_events, parse_time, Event};
+    use crate::database::config::make_config;
+
+    #[test]
+    fn scheduled_events_come_due_in_order() {
+        let mut settings = make_config();
+        let time = |text: &str| parse_time(text).unwrap().unwrap();
+        settings.insert("registration_opens", time("2024-03-01 18:00"));
+        settings.insert("registration_closes", time("2024-03-03 18:00"));
+        settings.insert("check_in_opens", time("2024-03-03 12:00"));
+        settings.insert("tournament_starts", time("2024-03-03 18:00"));
+        assert!(due_events(&settings, time("2024-03-01 17:59")).is_empty());
+        assert_eq!(
+            due_events(&settings, time("2024-03-01 18:00")),
+            vec![Event::OpenRegistration]
+        );
+        assert_eq!(
+            due_events(&settings, time("2024-03-04 00:00")),
+            Event::ALL.to_vec()
+        );
+        assert_eq!(parse_time(" ").unwrap(), None);
+        assert!(parse_time("tomorrow").is_err());
+    }
+}
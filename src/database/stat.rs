@@ -1,6 +1,7 @@
 use crate::{Context, Error};
 use dbc_bot::Region;
-use mongodb::bson::doc;
+use mongodb::bson::{doc, Document};
+use mongodb::Database;
 
 pub async fn count_registers(ctx: &Context<'_>, region: &Region) -> Result<i32, Error> {
     let database = ctx.data().database.regional_databases.get(region).unwrap();
@@ -22,3 +23,12 @@ pub async fn count_registers(ctx: &Context<'_>, region: &Region) -> Result<i32,
     };
     Ok(count)
 }
+
+/// Number of registered players, without the mannequins.
+pub async fn count_players(database: &Database) -> Result<i32, Error> {
+    let count = database
+        .collection::<Document>("Players")
+        .count_documents(doc! { "name": { "$ne": "Mannequin" } }, None)
+        .await?;
+    Ok(count as i32)
+}
@@ -1,38 +1,58 @@
+use crate::bracket_tournament::double_elimination;
 use crate::{Context, Error};
-use dbc_bot::Region;
+use dbc_bot::{Format, Region};
 use mongodb::{
     bson::{doc, Bson, Document},
     Collection, Database,
 };
 
-use super::{config::get_config, find::find_round_from_config};
+use super::{config::get_config, find::find_rounds_from_config};
 pub struct Count {
     database: Database,
-    round_name: String,
+    format: Format,
+    total: i32,
+    bracket_reset: bool,
     round_i32: i32,
-    round: Collection<Document>,
+    rounds: Vec<Collection<Document>>,
     player_counts: u64,
+    group_rounds: i32,
+    qualified: i32,
+    scheduled_matches: u64,
 }
 
 impl Count {
     pub async fn new(ctx: &Context<'_>, region: &Region) -> Result<Self, Error> {
-        let round = find_round_from_config(&get_config(ctx, region).await);
+        let config = get_config(ctx, region).await;
         let database = ctx.data().database.regional_databases.get(region).unwrap();
 
         let mut count = Count {
             database: database.to_owned(),
-            round_name: round.clone(),
+            format: Format::from_config(&config),
+            total: config.get_i32("total").unwrap_or(0),
+            bracket_reset: config.get_bool("bracket_reset").unwrap_or(false),
             round_i32: 0,
-            round: database.collection(&round),
+            rounds: find_rounds_from_config(&config)
+                .iter()
+                .map(|round| database.collection(round))
+                .collect(),
             player_counts: 0,
+            group_rounds: config.get_i32("group_rounds").unwrap_or(0),
+            qualified: config.get_i32("groups").unwrap_or(0)
+                * config.get_i32("group_advance").unwrap_or(0),
+            scheduled_matches: 0,
         };
         count.player_counts = count.get_player_counts().await?;
-        count.round_i32 = count
-            .round_name
-            .split(' ')
-            .nth(1)
-            .unwrap_or("0")
-            .parse::<i32>()?;
+        count.round_i32 = config.get_i32("round").unwrap_or(0);
+        count.scheduled_matches = count.count_in_rounds(doc! {}).await? / 2;
+        Ok(count)
+    }
+
+    /// Counts the documents matching the filter across every round that is currently played.
+    async fn count_in_rounds(&self, filter: Document) -> Result<u64, Error> {
+        let mut count = 0;
+        for round in &self.rounds {
+            count += round.count_documents(filter.clone(), None).await?;
+        }
         Ok(count)
     }
 
@@ -43,12 +63,26 @@ impl Count {
         Ok(count)
     }
 
+    pub fn get_format(&self) -> &Format {
+        &self.format
+    }
+
     pub fn get_counts_of_all_players(&self) -> u64 {
         self.player_counts
     }
 
     pub fn get_counts_of_rounds(&self) -> u64 {
-        (self.player_counts as f64).log2().ceil() as u64
+        let rounds = (self.player_counts as f64).log2().ceil() as u64;
+        match self.format {
+            Format::DoubleElimination => {
+                double_elimination::total_stages(rounds as i32, self.bracket_reset) as u64
+            }
+            Format::SingleElimination => rounds,
+            Format::Swiss => self.total as u64,
+            Format::GroupStage => {
+                self.group_rounds as u64 + (self.qualified.max(1) as f64).log2().ceil() as u64
+            }
+        }
     }
 
     pub fn get_current_round(&self) -> u64 {
@@ -56,12 +90,23 @@ impl Count {
     }
 
     pub fn get_counts_of_matches_in_current_round(&self) -> u64 {
-        (1 << (self.player_counts.checked_ilog2().unwrap_or(0) + 1)) / (1 << self.round_i32)
+        match self.format {
+            Format::DoubleElimination => {
+                double_elimination::stage_rounds(self.round_i32, self.total)
+                    .iter()
+                    .map(|round| double_elimination::matches_in_round(round, self.total) as u64)
+                    .sum()
+            }
+            Format::SingleElimination => {
+                (1 << (self.player_counts.checked_ilog2().unwrap_or(0) + 1)) / (1 << self.round_i32)
+            }
+            Format::GroupStage | Format::Swiss => self.scheduled_matches,
+        }
     }
 
     pub async fn get_counts_of_players_in_current_round(&self) -> Result<u64, Error> {
         let filter = doc! {"discord_id": {"$ne": Bson::Null}};
-        let count = self.round.count_documents(filter, None).await?;
+        let count = self.count_in_rounds(filter).await?;
         Ok(count)
     }
 
@@ -79,13 +124,13 @@ impl Count {
 
     pub async fn get_counts_of_matches_happened(&self) -> Result<u64, Error> {
         let filter = doc! {"battle": true};
-        let count = self.round.count_documents(filter, None).await?;
+        let count = self.count_in_rounds(filter).await?;
         Ok(count / 2)
     }
 
     pub async fn get_counts_of_matches_unhappened(&self) -> Result<u64, Error> {
         let filter = doc! {"battle": false};
-        let count = self.round.count_documents(filter, None).await?;
+        let count = self.count_in_rounds(filter).await?;
         Ok(count / 2)
     }
 
@@ -95,25 +140,25 @@ impl Count {
 
     pub async fn get_counts_of_byes_in_current_round(&self) -> Result<u64, Error> {
         let filter = doc! {"discord_id": Bson::Null};
-        let count = self.round.count_documents(filter, None).await?;
+        let count = self.count_in_rounds(filter).await?;
         Ok(count)
     }
 
     pub async fn get_counts_of_advanced(&self) -> Result<u64, Error> {
         let filter = doc! {"defeated": false};
-        let count = self.round.count_documents(filter, None).await?;
+        let count = self.count_in_rounds(filter).await?;
         Ok(count)
     }
 
     pub async fn get_counts_of_eliminated(&self) -> Result<u64, Error> {
         let filter = doc! {"defeated": true};
-        let count = self.round.count_documents(filter, None).await?;
+        let count = self.count_in_rounds(filter).await?;
         Ok(count)
     }
 
     pub async fn get_counts_of_inactive(&self) -> Result<u64, Error> {
         let filter = doc! {"ready": false, "battle": false};
-        let count = self.round.count_documents(filter, None).await?;
+        let count = self.count_in_rounds(filter).await?;
         Ok(count)
     }
 }
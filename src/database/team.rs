@@ -0,0 +1,157 @@
+//! Teams for 2v2 and 3v3 tournaments.
+//!
+//! The captain's `Players` document is the team's entry in the bracket. It also holds the team name,
+//! the invited `members` and whether the captain has confirmed the roster, so pairing and results
+//! work the same as for solo players.
+use crate::database::config::find_config;
+use crate::database::repository::{Repository, PLAYERS};
+use crate::discord::role::{remove_role, remove_role_by_id};
+use crate::{Context, Error};
+use dbc_bot::Region;
+use mongodb::bson::{doc, Document};
+use poise::serenity_prelude::{self as serenity, UserId};
+use tracing::error;
+
+pub const MAX_TEAM_SIZE: i32 = 3;
+
+/// Players per team, `1` for solo tournaments.
+pub fn team_size(config: &Document) -> i32 {
+    config
+        .get_i32("team_size")
+        .unwrap_or(1)
+        .clamp(1, MAX_TEAM_SIZE)
+}
+
+/// Label of a team size, e.g. `3` -> `3v3`.
+pub fn team_size_label(size: i32) -> String {
+    match size {
+        1 => "Solo".to_string(),
+        size => format!("{size}v{size}"),
+    }
+}
+
+/// The members invited by a captain, not counting the captain.
+pub fn members(player: &Document) -> Vec<Document> {
+    player
+        .get_array("members")
+        .map(|members| {
+            members
+                .iter()
+                .filter_map(|member| member.as_document().cloned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Tags of everyone who has to play for this entry, the captain first.
+pub fn team_tags(player: &Document) -> Vec<String> {
+    let mut tags = vec![player.get_str("tag").unwrap_or("").to_string()];
+    tags.extend(
+        members(player)
+            .iter()
+            .filter_map(|member| member.get_str("tag").ok())
+            .map(str::to_string),
+    );
+    tags
+}
+
+pub fn is_roster_confirmed(player: &Document) -> bool {
+    player.get_bool("roster_confirmed").unwrap_or(false)
+}
+
+/// Finds the team a Discord user plays for without being its captain.
+pub async fn find_team_of_member(
+    ctx: &Context<'_>,
+    region: &Region,
+    discord_id: u64,
+) -> Result<Option<Document>, Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    Ok(database
+        .collection::<Document>("Players")
+        .find_one(doc! {"members.discord_id": discord_id.to_string()}, None)
+        .await?)
+}
+
+/// Deregisters every team whose captain has not confirmed the roster. Returns how many were removed.
+pub async fn remove_unconfirmed_teams(
+    ctx: &serenity::Context,
+    database: &dyn Repository,
+) -> Result<i32, Error> {
+    let config = find_config(database).await;
+    if team_size(&config) == 1 {
+        return Ok(0);
+    }
+    let unconfirmed = database
+        .find_in_round(
+            PLAYERS,
+            doc! {"discord_id": {"$ne": null}, "roster_confirmed": {"$ne": true}},
+        )
+        .await?;
+    for captain in &unconfirmed {
+        database
+            .delete_from_round(PLAYERS, doc! {"_id": captain.get_object_id("_id")?})
+            .await?;
+        let Ok(id) = captain.get_str("discord_id").unwrap_or("").parse::<u64>() else {
+            continue;
+        };
+        if let Err(e) = remove_role_by_id(ctx, &config, id).await {
+            error!("{e}");
+        }
+    }
+    Ok(unconfirmed.len() as i32)
+}
+
+/// Removes the regional role from the members of a team, e.g. once the team is knocked out.
+pub async fn remove_member_roles(
+    ctx: &Context<'_>,
+    player: &Document,
+    region: &Region,
+) -> Result<(), Error> {
+    for member in members(player) {
+        let Ok(id) = member.get_str("discord_id").unwrap_or("").parse::<u64>() else {
+            continue;
+        };
+        let user = UserId(id).to_user(ctx.http()).await?;
+        if let Err(e) = remove_role(ctx, &user, region).await {
+            error!("{e}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_roster_confirmed, team_size, team_tags};
+    use crate::database::config::make_config;
+    use crate::database::fixtures::player;
+    use mongodb::bson::doc;
+
+    #[test]
+    fn team_size_is_kept_within_bounds() {
+        let mut config = make_config();
+        assert_eq!(team_size(&config), 1);
+        config.insert("team_size", 3);
+        assert_eq!(team_size(&config), 3);
+        config.insert("team_size", 5);
+        assert_eq!(team_size(&config), 3);
+        config.insert("team_size", 0);
+        assert_eq!(team_size(&config), 1);
+    }
+
+    #[test]
+    fn captain_plays_with_the_invited_members() {
+        let mut captain = player(1);
+        assert_eq!(team_tags(&captain), vec!["#P1"]);
+        assert!(!is_roster_confirmed(&captain));
+        captain.insert(
+            "members",
+            vec![
+                doc! {"tag": "#P2", "discord_id": "2"},
+                doc! {"tag": "#P3", "discord_id": "3"},
+            ],
+        );
+        captain.insert("roster_confirmed", true);
+        assert_eq!(team_tags(&captain), vec!["#P1", "#P2", "#P3"]);
+        assert!(is_roster_confirmed(&captain));
+    }
+}
@@ -1,71 +1,62 @@
-use dbc_bot::Region;
-use futures::TryStreamExt;
-use mongodb::{
-    bson::{self, doc, Document},
-    options::AggregateOptions,
-    Collection, Database,
-};
+use dbc_bot::{Format, Region};
+use mongodb::bson::{self, doc, oid::ObjectId, Document};
 
+use crate::bracket_tournament::double_elimination::{
+    self, losers_advance, losers_drop, losers_round_name, parse_losers_round, parse_winners_round,
+    GRAND_FINAL, GRAND_FINAL_RESET,
+};
 use crate::{database::mannequin::update_mannequin, Context, Error};
 
-use super::config::{get_config, open_tour_close_reg, reset_config, toggle_reg_config};
+use crate::bracket_tournament::group_stage::{forfeit_group_matches, parse_group_round};
+use crate::bracket_tournament::placements::THIRD_PLACE_MATCH;
+use crate::bracket_tournament::seeding::{bracket_positions, seed_players};
 
-pub async fn assign_match_id(database: &Database) -> Result<(), Error> {
-    let collection: Collection<Document> = database.collection("Round 1");
-    let mut byes_counter = collection
-        .count_documents(doc! {"name": "Mannequin"}, None)
-        .await?;
-    print!("{}", byes_counter);
-    let mut player_cursor = collection
-        .find(
-            doc! {
-                    "name": { "$ne": "Mannequin" },
-                    "match_id": null
-            },
-            None,
-        )
-        .await?;
-    let mut double_match_id: i32 = 2;
-    //So here is the math behind this:
-    //We want 2 consecutive players to be assigned with the same match_id
-    //So we assign double_match_id (declared as integer i32) and start with 2, for every iteration, this increments by 1
-    //And we take half of the value to get the actual match_id
-    //Therefore, 1st player: 2/2=1; 2nd player: 3/2=1; 3rd player 4/2=2; 4th player: 5/2=2; and so on
-    while let Ok(Some(mut player)) = player_cursor.try_next().await {
-        let match_id = double_match_id / 2;
-        let update = doc! {
-            "$set": {"match_id": match_id}
+use super::config::{find_config, open_tour_close_reg, reset_config, toggle_reg_config};
+use super::find::{is_disqualified, is_mannequin};
+use super::mannequin::add_mannequin;
+use super::models::{MalformedDocument, Player, RegionConfig};
+use super::repository::{Repository, Write, CONFIG, PLAYERS};
+
+/// Places the players of `Round 1` in their bracket positions, given in seeding order.
+///
+/// Seeds past the number of players are byes, so the mannequins face the top seeds.
+pub async fn assign_match_id(database: &dyn Repository, seeded: &[Document]) -> Result<(), Error> {
+    let size = database.count_in_round("Round 1", doc! {}).await? as usize;
+    for (slot, seed) in bracket_positions(size).into_iter().enumerate() {
+        let match_id = slot as i32 / 2 + 1;
+        match seeded.get(seed - 1) {
+            Some(player) => {
+                database
+                    .update_in_round(
+                        "Round 1",
+                        doc! { "_id": player.get_object_id("_id")? },
+                        doc! {"$set": {"match_id": match_id, "seed": seed as i32}},
+                    )
+                    .await?
+            }
+            None => {
+                database
+                    .update_in_round(
+                        "Round 1",
+                        doc! {"match_id": null, "name": "Mannequin"},
+                        update_mannequin(match_id),
+                    )
+                    .await?
+            }
         };
-        player.insert("match_id", match_id);
-        collection
-            .update_one(doc! { "_id": player.get_object_id("_id")? }, update, None)
-            .await?;
-
-        //not a while loop here because we need to assign match_id to mannequin after assign an id to player
-        if byes_counter > 0 {
-            collection
-                .update_one(
-                    doc! {"match_id": null, "name": "Mannequin"},
-                    update_mannequin(match_id),
-                    None,
-                )
-                .await?;
-            byes_counter -= 1;
-            double_match_id += 1;
-        }
-        double_match_id += 1;
     }
     Ok(())
 }
 
-pub fn update_match_id(mut player: Document) -> Document {
-    let old_match_id = player.get_i32("match_id").unwrap();
+pub fn update_match_id(mut player: Document) -> Result<Document, MalformedDocument> {
+    let old_match_id = Player::from_document(&player)?.match_id()?;
     let new_match_id = (old_match_id + 1) / 2;
     player.insert("match_id", new_match_id);
     player.insert("ready", false);
-    player
+    Ok(player)
 }
 
+/// Records the result of a match. Returns `false` if the match had already been recorded.
 pub async fn update_result(
     ctx: &Context<'_>,
     region: &Region,
@@ -73,46 +64,320 @@ pub async fn update_result(
     winner: &Document,
     loser: &Document,
     reason: impl Into<Option<&str>>,
-) -> Result<(), Error> {
+) -> Result<bool, Error> {
     let database = ctx.data().database.regional_databases.get(region).unwrap();
-    let round_coll: Collection<Document> = database.collection(round);
-    let filter = |player: &Document| {
-        doc! {
-            "_id": player.get_object_id("_id").unwrap()
-        }
+    record_result(database, region, round, winner, loser, reason).await
+}
+
+/// Key of a match in `Results`, which makes sure it is only recorded once.
+pub fn result_key(region: &Region, round: &str, match_id: i32) -> String {
+    format!("{}/{round}/{match_id}", region.short())
+}
+
+/// Records the result of a match and advances the winner, without needing a command context.
+///
+/// Every write is applied in one transaction, and a match that has already been recorded is left
+/// untouched. Returns whether the result was recorded.
+pub async fn record_result(
+    database: &dyn Repository,
+    region: &Region,
+    round: &str,
+    winner: &Document,
+    loser: &Document,
+    reason: impl Into<Option<&str>>,
+) -> Result<bool, Error> {
+    record_scored_result(database, region, round, winner, loser, reason.into(), None).await
+}
+
+/// Records the result of a match like [`record_result`], with the games won by the winner and the
+/// loser kept in the same entry of `Results`.
+pub async fn record_scored_result(
+    database: &dyn Repository,
+    region: &Region,
+    round: &str,
+    winner: &Document,
+    loser: &Document,
+    reason: Option<&str>,
+    score: Option<(i32, i32)>,
+) -> Result<bool, Error> {
+    let match_id = winner.get_i32("match_id")?;
+    let writes = plan_result(database, region, round, winner, loser, reason).await?;
+    let mut result = doc! {
+        "_id": result_key(region, round, match_id),
+        "round": round,
+        "match_id": match_id,
+        "winner": winner.get("_id"),
+        "loser": loser.get("_id"),
+        "reason": reason,
+        "recorded_at": bson::DateTime::now(),
     };
+    if let Some((won, lost)) = score {
+        result.insert("score", vec![won, lost]);
+    }
+    database.record(result, writes).await
+}
+
+/// Records a match that neither player turned up for.
+///
+/// Both players lose, and a mannequin wins in their place so that the bracket keeps a player in
+/// every match. Returns whether the result was recorded.
+pub async fn record_double_forfeit(
+    database: &dyn Repository,
+    region: &Region,
+    round: &str,
+    first: &Document,
+    second: &Document,
+    reason: &str,
+) -> Result<bool, Error> {
+    let match_id = first.get_i32("match_id")?;
+    let mut bye = add_mannequin(region, Some(match_id));
+    bye.insert("_id", ObjectId::new());
+    let mut writes = plan_result(database, region, round, &bye, first, Some(reason)).await?;
+    if parse_group_round(round).is_some() {
+        writes.extend(forfeit_group_matches(database, round, second, reason).await?);
+    }
+    writes.push(Write::Update {
+        round: round.to_string(),
+        filter: doc! {"_id": second.get_object_id("_id")?},
+        update: doc! {
+            "$set": {
+                "battle": true,
+                "defeated": true,
+                "decided_at": bson::DateTime::now(),
+                "reason": reason,
+            }
+        },
+    });
+    let result = doc! {
+        "_id": result_key(region, round, match_id),
+        "round": round,
+        "match_id": match_id,
+        "winner": null,
+        "loser": first.get("_id"),
+        "double_forfeit": [first.get("_id"), second.get("_id")],
+        "reason": reason,
+        "recorded_at": bson::DateTime::now(),
+    };
+    database.record(result, writes).await
+}
+
+/// Every write needed to record the result of a match and advance its players.
+pub async fn plan_result(
+    database: &dyn Repository,
+    region: &Region,
+    round: &str,
+    winner: &Document,
+    loser: &Document,
+    reason: Option<&str>,
+) -> Result<Vec<Write>, Error> {
+    let (winner_id, loser_id) = (winner.get_object_id("_id")?, loser.get_object_id("_id")?);
 
     let update = |defeated: bool| {
         doc! {
             "$set": {
                 "battle": true,
-                "defeated": defeated
+                "defeated": defeated,
+                "decided_at": bson::DateTime::now()
             }
         }
     };
 
-    let next_coll = format! {"Round {}",round.split(' ').nth(1).unwrap().parse::<i32>()?+1};
-    let next_round: Collection<Document> = database.collection(&next_coll);
-    if winner.get_str("discord_id").is_ok() {
-        next_round
-            .insert_one(update_match_id(winner.clone()), None)
-            .await?;
+    let config = find_config(database).await;
+    let mut writes = match Format::from_config(&config) {
+        Format::DoubleElimination => {
+            advance_double_elimination(
+                database,
+                region,
+                &config,
+                round,
+                winner,
+                loser,
+                reason.is_some(),
+            )
+            .await?
+        }
+        // Nobody advances in Swiss rounds, the next round is paired from the standings
+        Format::Swiss => vec![],
+        Format::GroupStage if parse_group_round(round).is_some() => match reason {
+            Some(r) => forfeit_group_matches(database, round, loser, r).await?,
+            None => vec![],
+        },
+        // The third-place match is the last match of both players
+        Format::SingleElimination | Format::GroupStage if round == THIRD_PLACE_MATCH => vec![],
+        Format::SingleElimination | Format::GroupStage => {
+            let next_coll = format! {"Round {}",round.split(' ').nth(1).unwrap().parse::<i32>()?+1};
+            match winner.get_str("discord_id") {
+                Ok(_) => vec![Write::Insert {
+                    round: next_coll,
+                    document: update_match_id(winner.clone())?,
+                }],
+                // A double forfeit leaves a bye to whoever waits in the next match
+                Err(_)
+                    if parse_winners_round(round)
+                        .is_some_and(|n| n < config.get_i32("total").unwrap_or(0)) =>
+                {
+                    let mut bye =
+                        add_mannequin(region, Some((winner.get_i32("match_id")? + 1) / 2));
+                    bye.insert("_id", ObjectId::new());
+                    vec![Write::Insert {
+                        round: next_coll,
+                        document: bye,
+                    }]
+                }
+                Err(_) => vec![],
+            }
+        }
+    };
+
+    writes.push(Write::Update {
+        round: round.to_string(),
+        filter: doc! {"_id": winner_id},
+        update: update(false),
+    });
+    writes.push(Write::Update {
+        round: round.to_string(),
+        filter: doc! {"_id": loser_id},
+        update: update(true),
+    });
+    if let Some(r) = reason {
+        writes.push(Write::Update {
+            round: round.to_string(),
+            filter: doc! {"_id": loser_id},
+            update: doc! {"$set": {"reason": r}},
+        });
     }
 
-    round_coll
-        .update_one(filter(winner), update(false), None)
-        .await?;
+    Ok(writes)
+}
 
-    round_coll
-        .update_one(filter(loser), update(true), None)
-        .await?;
-    if let Some(r) = reason.into() {
-        round_coll
-            .update_one(filter(loser), doc! {"$set": {"reason": r}}, None)
-            .await?;
+/// Moves a player into a match of another round, clearing the state of the match they came from.
+pub fn move_to_match(mut player: Document, match_id: i32) -> Document {
+    player.insert("match_id", match_id);
+    player.insert("battle", false);
+    player.insert("ready", false);
+    player.remove("defeated");
+    for field in [
+        "decided_at",
+        "override_note",
+        "dispute",
+        "dispute_id",
+        "dispute_note",
+        "replay_after",
+    ] {
+        player.remove(field);
     }
+    player
+}
 
-    Ok(())
+/// Writes that place both players of a finished double-elimination match into their next matches.
+///
+/// Disqualified losers are replaced by a mannequin in the losers bracket. When two mannequins end up
+/// in the same match, that match is resolved straight away so that the bye keeps moving forward.
+async fn advance_double_elimination(
+    database: &dyn Repository,
+    region: &Region,
+    config: &Document,
+    round: &str,
+    winner: &Document,
+    loser: &Document,
+    loser_disqualified: bool,
+) -> Result<Vec<Write>, Error> {
+    let total = config.get_i32("total")?;
+    let bracket_reset = config.get_bool("bracket_reset").unwrap_or(false);
+    let mut pending = vec![(
+        round.to_string(),
+        winner.clone(),
+        loser.clone(),
+        loser_disqualified,
+    )];
+    let mut writes = vec![];
+    while let Some((round, winner, loser, loser_disqualified)) = pending.pop() {
+        let match_id = winner.get_i32("match_id")?;
+        let mut placements: Vec<(String, Document)> = vec![];
+        if let Some(winners_round) = parse_winners_round(&round) {
+            if winners_round < total {
+                placements.push((
+                    format!("Round {}", winners_round + 1),
+                    move_to_match(winner.clone(), (match_id + 1) / 2),
+                ));
+            } else {
+                let mut champion = move_to_match(winner.clone(), 1);
+                champion.insert("from_winners", true);
+                placements.push((GRAND_FINAL.to_string(), champion));
+            }
+            let (losers_round, losers_match) = losers_drop(winners_round, match_id, total);
+            let dropped = if is_mannequin(&loser) || is_disqualified(&loser) || loser_disqualified {
+                add_mannequin(region, Some(losers_match))
+            } else {
+                move_to_match(loser.clone(), losers_match)
+            };
+            placements.push((losers_round_name(losers_round), dropped));
+        } else if let Some(losers_round) = parse_losers_round(&round) {
+            match losers_advance(losers_round, match_id, total) {
+                Some((next_round, next_match)) => placements.push((
+                    losers_round_name(next_round),
+                    move_to_match(winner.clone(), next_match),
+                )),
+                None => {
+                    let mut challenger = move_to_match(winner.clone(), 1);
+                    challenger.insert("from_winners", false);
+                    placements.push((GRAND_FINAL.to_string(), challenger));
+                }
+            }
+        } else if round == GRAND_FINAL
+            && bracket_reset
+            && !double_elimination::is_from_winners(&winner)
+        {
+            placements.push((GRAND_FINAL_RESET.to_string(), move_to_match(winner, 1)));
+            placements.push((GRAND_FINAL_RESET.to_string(), move_to_match(loser, 1)));
+        }
+
+        for (next_round, mut player) in placements {
+            // Mannequins need an id up front, to resolve them in the same transaction
+            if !player.contains_key("_id") {
+                player.insert("_id", ObjectId::new());
+            }
+            writes.push(Write::Insert {
+                round: next_round.clone(),
+                document: player.clone(),
+            });
+            if !is_mannequin(&player) {
+                continue;
+            }
+            let match_id = player.get_i32("match_id")?;
+            let filter = doc! {
+                "match_id": match_id,
+                "tag": null,
+                "battle": false,
+            };
+            let mut byes = database.find_in_round(&next_round, filter).await?;
+            byes.extend(writes.iter().filter_map(|write| match write {
+                Write::Insert { round, document }
+                    if *round == next_round
+                        && is_mannequin(document)
+                        && document.get_i32("match_id") == Ok(match_id) =>
+                {
+                    Some(document.clone())
+                }
+                _ => None,
+            }));
+            if byes.len() == 2 {
+                let (bye_loser, bye_winner) = (byes.remove(1), byes.remove(0));
+                writes.push(Write::Update {
+                    round: next_round.clone(),
+                    filter: doc! {"_id": bye_winner.get_object_id("_id")?},
+                    update: doc! {"$set": {"battle": true, "defeated": false}},
+                });
+                writes.push(Write::Update {
+                    round: next_round.clone(),
+                    filter: doc! {"_id": bye_loser.get_object_id("_id")?},
+                    update: doc! {"$set": {"battle": true, "defeated": true}},
+                });
+                pending.push((next_round, bye_winner, bye_loser, true));
+            }
+        }
+    }
+    Ok(writes)
 }
 
 pub async fn toggle_registration(
@@ -121,68 +386,65 @@ pub async fn toggle_registration(
     status: bool,
 ) -> Result<(), Error> {
     let database = ctx.data().database.regional_databases.get(region).unwrap();
-    let toggle = toggle_reg_config(status);
-    let collection: Collection<Document> = database.collection("Config");
-    match collection.update_one(doc! {}, toggle, None).await {
-        Ok(_) => Ok(()),
-        Err(err) => Err(Box::new(err)),
-    }
+    database.update_config(toggle_reg_config(status)).await
 }
 
-pub async fn update_round_config(ctx: &Context<'_>, region: &Region) -> Result<(), Error> {
-    let database = ctx.data().database.regional_databases.get(region).unwrap();
-    let config = database.collection::<Document>("Config");
-    let config_doc = get_config(ctx, region).await;
-    let round = config_doc.get_i32("round").unwrap();
-    config
-        .update_one(doc! {}, doc! { "$set": { "round" : round + 1 } }, None)
+pub async fn update_round_config(database: &dyn Repository) -> Result<(), Error> {
+    let config_doc = find_config(database).await;
+    let round = RegionConfig::from_document(&config_doc)?.round;
+    database
+        .update_config(doc! { "$set": { "round" : round + 1 } })
         .await?; // Set total rounds, tournament_started to true and registration to falseet total rounds, tournament_started to true and registration to false
     Ok(())
 }
 
-pub async fn setting_tournament_config(ctx: &Context<'_>, region: &Region) -> Result<(), Error> {
-    let database = ctx.data().database.regional_databases.get(region).unwrap();
-    let config = database.collection::<Document>("Config");
-    config
-        .update_one(doc! {}, open_tour_close_reg(), None)
-        .await?; // Set total rounds, tournament_started to true and registration to false
+pub async fn setting_tournament_config(database: &dyn Repository) -> Result<(), Error> {
+    database.update_config(open_tour_close_reg()).await?; // Set total rounds, tournament_started to true and registration to false
     Ok(())
 }
 
-pub async fn update_round_1(ctx: &Context<'_>, region: &Region, rounds: i32) -> Result<(), Error> {
-    let database = ctx.data().database.regional_databases.get(region).unwrap();
-    let config = database.collection::<Document>("Config");
+pub async fn update_round_1(database: &dyn Repository, rounds: i32) -> Result<(), Error> {
     let update = doc! {
         "$set": {
             "total": rounds
         }
     };
-    config.update_one(doc! {}, update, None).await?;
-    let players: Collection<Document> = database.collection("Players");
-    let pipeline = vec![
-        bson::doc! { "$match": bson::Document::new() },
-        bson::doc! { "$out": "Round 1" },
-    ];
-    let options = AggregateOptions::builder().allow_disk_use(true).build();
-    players.aggregate(pipeline, Some(options)).await?;
-    assign_match_id(database).await?;
+    database.update_config(update).await?;
+    for player in database.find_in_round(PLAYERS, doc! {}).await? {
+        database.insert_into_round("Round 1", &player).await?;
+    }
+    let seeded = seed_players(database).await?;
+    assign_match_id(database, &seeded).await?;
     Ok(())
 }
 
-pub async fn resetting_tournament_config(
-    ctx: &Context<'_>,
-    region: &Region,
-    backup: Option<Document>,
+pub async fn update_total_rounds(database: &dyn Repository, rounds: i32) -> Result<(), Error> {
+    database
+        .update_config(doc! {"$set": {"total": rounds}})
+        .await?;
+    Ok(())
+}
+
+pub async fn update_group_rounds(
+    database: &dyn Repository,
+    group_rounds: i32,
 ) -> Result<(), Error> {
-    let database = ctx.data().database.regional_databases.get(region).unwrap();
-    let config = database.collection::<Document>("Config");
-    config.delete_one(doc! {}, None).await?;
-    config
-        .insert_one(backup.unwrap_or(reset_config()), None)
+    database
+        .update_config(doc! {"$set": {"group_rounds": group_rounds}})
         .await?;
     Ok(())
 }
 
+pub async fn resetting_tournament_config(
+    database: &dyn Repository,
+    backup: Option<Document>,
+) -> Result<(), Error> {
+    database.delete_from_round(CONFIG, doc! {}).await?;
+    database
+        .insert_into_round(CONFIG, &backup.unwrap_or(reset_config()))
+        .await
+}
+
 pub async fn set_ready(
     ctx: &Context<'_>,
     region: &Region,
@@ -200,3 +462,185 @@ pub async fn set_ready(
         .await?; // Set total rounds, tournament_started to true and registration to false
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        record_result, record_scored_result, resetting_tournament_config, result_key,
+        setting_tournament_config, update_round_1,
+    };
+    use crate::database::add::insert_mannequins;
+    use crate::database::fixtures::{config, in_round, match_ids, player, start};
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::Repository;
+    use dbc_bot::Region;
+    use mongodb::bson::doc;
+
+    #[tokio::test]
+    async fn starting_a_bracket_seeds_players_against_byes() {
+        let mut settings = config("Single Elimination", 0, 0);
+        settings.insert("seeding", "Manual");
+        settings.insert("seed_list", vec!["#P3", "#P1", "#P2"]);
+        let repository = MemoryRepository::new(settings);
+        for n in 1..=3 {
+            repository.insert("Players", player(n));
+        }
+        insert_mannequins(&repository, &Region::EU, 1)
+            .await
+            .unwrap();
+        update_round_1(&repository, 2).await.unwrap();
+        assert_eq!(
+            match_ids(&repository, "Round 1"),
+            [
+                (None, 1),
+                (Some("#P1".into()), 2),
+                (Some("#P2".into()), 2),
+                (Some("#P3".into()), 1)
+            ]
+        );
+
+        let backup = repository.config().await.unwrap().unwrap();
+        setting_tournament_config(&repository).await.unwrap();
+        resetting_tournament_config(&repository, Some(backup.clone()))
+            .await
+            .unwrap();
+        assert_eq!(repository.config().await.unwrap(), Some(backup));
+    }
+
+    #[tokio::test]
+    async fn seeds_meet_in_standard_bracket_positions() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 3));
+        start(&repository, 8, 8).await;
+        let tags = |ids: &[i32]| -> Vec<(Option<String>, i32)> {
+            ids.iter()
+                .zip([1, 1, 2, 2, 3, 3, 4, 4])
+                .map(|(n, match_id)| (Some(format!("#P{n}")), match_id))
+                .collect()
+        };
+        let mut expected = tags(&[1, 8, 4, 5, 2, 7, 3, 6]);
+        expected.sort();
+        assert_eq!(match_ids(&repository, "Round 1"), expected);
+    }
+
+    #[tokio::test]
+    async fn byes_go_to_the_top_seeds() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 3));
+        start(&repository, 6, 8).await;
+        let byes: Vec<i32> = repository
+            .find_in_round("Round 1", doc! {"tag": null})
+            .await
+            .unwrap()
+            .iter()
+            .map(|bye| bye.get_i32("match_id").unwrap())
+            .collect();
+        assert_eq!(byes, vec![1, 3]);
+        assert_eq!(
+            in_round(&repository, "Round 1", 1)
+                .await
+                .get_i32("match_id"),
+            Ok(1)
+        );
+        assert_eq!(
+            in_round(&repository, "Round 1", 2)
+                .await
+                .get_i32("match_id"),
+            Ok(3)
+        );
+    }
+
+    #[tokio::test]
+    async fn single_elimination_winner_advances() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 3));
+        start(&repository, 8, 8).await;
+        let winner = in_round(&repository, "Round 1", 4).await;
+        let loser = in_round(&repository, "Round 1", 5).await;
+        record_result(&repository, &Region::EU, "Round 1", &winner, &loser, None)
+            .await
+            .unwrap();
+
+        let winner = in_round(&repository, "Round 1", 4).await;
+        let loser = in_round(&repository, "Round 1", 5).await;
+        assert_eq!(winner.get_bool("battle"), Ok(true));
+        assert_eq!(winner.get_bool("defeated"), Ok(false));
+        assert_eq!(loser.get_bool("defeated"), Ok(true));
+        assert!(loser.get("reason").is_none());
+        assert_eq!(
+            match_ids(&repository, "Round 2"),
+            vec![(Some("#P4".into()), 1)]
+        );
+        let advanced = in_round(&repository, "Round 2", 4).await;
+        assert_eq!(advanced.get_bool("ready"), Ok(false));
+        assert_eq!(advanced.get_object_id("_id"), winner.get_object_id("_id"));
+    }
+
+    #[tokio::test]
+    async fn forfeit_records_the_reason() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 1));
+        start(&repository, 2, 2).await;
+        let winner = in_round(&repository, "Round 1", 1).await;
+        let loser = in_round(&repository, "Round 1", 2).await;
+        record_result(
+            &repository,
+            &Region::EU,
+            "Round 1",
+            &winner,
+            &loser,
+            "Inactive",
+        )
+        .await
+        .unwrap();
+        let loser = in_round(&repository, "Round 1", 2).await;
+        assert_eq!(loser.get_str("reason"), Ok("Inactive"));
+    }
+
+    #[tokio::test]
+    async fn recording_a_result_twice_is_a_no_op() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 2));
+        start(&repository, 4, 4).await;
+        let winner = in_round(&repository, "Round 1", 1).await;
+        let loser = in_round(&repository, "Round 1", 4).await;
+        for recorded in [true, false] {
+            assert_eq!(
+                record_result(&repository, &Region::EU, "Round 1", &winner, &loser, None)
+                    .await
+                    .unwrap(),
+                recorded
+            );
+        }
+        assert_eq!(repository.documents("Round 2").len(), 1);
+        let result = repository
+            .find_result(&result_key(&Region::EU, "Round 1", 1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.get("winner"), winner.get("_id"));
+    }
+
+    #[tokio::test]
+    async fn score_is_recorded_with_the_result() {
+        let repository = MemoryRepository::new(config("Single Elimination", 1, 2));
+        start(&repository, 4, 4).await;
+        let winner = in_round(&repository, "Round 1", 1).await;
+        let loser = in_round(&repository, "Round 1", 4).await;
+        record_scored_result(
+            &repository,
+            &Region::EU,
+            "Round 1",
+            &winner,
+            &loser,
+            None,
+            Some((2, 1)),
+        )
+        .await
+        .unwrap();
+        let result = repository
+            .find_result(&result_key(&Region::EU, "Round 1", 1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            result.get_array("score").unwrap(),
+            &vec![2.into(), 1.into()]
+        );
+    }
+}
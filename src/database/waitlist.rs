@@ -0,0 +1,225 @@
+//! Registration cap and waitlist.
+//!
+//! A region may cap how many players register (`max_players`, `0` for no cap). Once the cap is
+//! reached, new registrants are put on the `Waitlist` in the order they registered, and the first of
+//! them take the places freed when registered players deregister or fail to check in.
+use super::ban::find_ban;
+use super::repository::{Repository, PLAYERS};
+use crate::Error;
+use dbc_bot::Region;
+use mongodb::bson::{doc, DateTime, Document};
+
+pub const WAITLIST: &str = "Waitlist";
+
+/// Most players allowed to register, `None` when there is no cap.
+pub fn max_players(config: &Document) -> Option<u64> {
+    match config.get_i32("max_players").unwrap_or(0) {
+        cap if cap <= 0 => None,
+        cap => Some(cap as u64),
+    }
+}
+
+/// Whether the cap has been reached, so that new registrants are waitlisted.
+pub async fn registration_full(
+    database: &dyn Repository,
+    config: &Document,
+) -> Result<bool, Error> {
+    Ok(match max_players(config) {
+        Some(cap) => database.count_in_round(PLAYERS, doc! {}).await? >= cap,
+        None => false,
+    })
+}
+
+/// The waitlisted players, the first to be promoted first.
+pub async fn waitlist(database: &dyn Repository) -> Result<Vec<Document>, Error> {
+    let mut waitlist = database.find_in_round(WAITLIST, doc! {}).await?;
+    waitlist.sort_by_key(|player| player.get_datetime("waitlisted_at").ok().copied());
+    Ok(waitlist)
+}
+
+/// Puts a registrant at the end of the waitlist, or keeps their place if they are on it already.
+/// Returns their position, starting from 1.
+pub async fn join_waitlist(database: &dyn Repository, player: Document) -> Result<usize, Error> {
+    let discord_id = player.get_str("discord_id")?.to_string();
+    let filter = doc! {"discord_id": &discord_id};
+    match database.count_in_round(WAITLIST, filter.clone()).await? {
+        0 => {
+            let mut player = player;
+            player.insert("waitlisted_at", DateTime::now());
+            database.insert_into_round(WAITLIST, &player).await?;
+        }
+        _ => {
+            database
+                .update_in_round(WAITLIST, filter, doc! {"$set": player})
+                .await?;
+        }
+    }
+    Ok(waitlist(database)
+        .await?
+        .iter()
+        .position(|player| player.get_str("discord_id") == Ok(&discord_id))
+        .map_or(0, |position| position + 1))
+}
+
+/// Keeps a newly registered player only if they are within the cap, and moves them to the waitlist
+/// otherwise. Returns their waitlist position if they were moved.
+///
+/// Registrations confirmed at the same time can all see the last free place, so the cap is checked
+/// again once the player is in `Players`, where the earliest registrations keep their places.
+pub async fn keep_within_cap(
+    database: &dyn Repository,
+    config: &Document,
+    discord_id: &str,
+) -> Result<Option<usize>, Error> {
+    let Some(cap) = max_players(config) else {
+        return Ok(None);
+    };
+    let mut players = database.find_in_round(PLAYERS, doc! {}).await?;
+    players.sort_by_key(|player| player.get_object_id("_id").ok());
+    let Some(index) = players
+        .iter()
+        .position(|player| player.get_str("discord_id") == Ok(discord_id))
+    else {
+        return Ok(None);
+    };
+    if (index as u64) < cap {
+        return Ok(None);
+    }
+    let mut player = players.remove(index);
+    database
+        .delete_player(doc! {"discord_id": discord_id})
+        .await?;
+    player.remove("_id");
+    Ok(Some(join_waitlist(database, player).await?))
+}
+
+/// Moves the first waitlisted players into `Players` until the cap is reached again. Returns the
+/// promoted players.
+///
+/// Players banned while they were waiting are taken off the waitlist instead.
+pub async fn promote_from_waitlist(
+    database: &dyn Repository,
+    general: &dyn Repository,
+    config: &Document,
+    region: &Region,
+) -> Result<Vec<Document>, Error> {
+    let mut promoted = vec![];
+    for mut player in waitlist(database).await? {
+        if registration_full(database, config).await? {
+            break;
+        }
+        database
+            .delete_from_round(WAITLIST, doc! {"_id": player.get("_id")})
+            .await?;
+        let banned = find_ban(
+            general,
+            player.get_str("discord_id").ok(),
+            player.get_str("tag").ok(),
+            region,
+        )
+        .await?;
+        if banned.is_some() {
+            continue;
+        }
+        player.remove("waitlisted_at");
+        database.insert_into_round(PLAYERS, &player).await?;
+        promoted.push(player);
+    }
+    Ok(promoted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        join_waitlist, keep_within_cap, promote_from_waitlist, registration_full, waitlist,
+    };
+    use crate::database::ban::{make_ban_doc, BANS};
+    use crate::database::config::make_config;
+    use crate::database::fixtures::player;
+    use crate::database::memory::MemoryRepository;
+    use crate::database::repository::Repository;
+    use dbc_bot::Region;
+    use mongodb::bson::doc;
+
+    fn capped(max_players: i32) -> MemoryRepository {
+        let mut settings = make_config();
+        settings.insert("max_players", max_players);
+        MemoryRepository::new(settings)
+    }
+
+    #[tokio::test]
+    async fn waitlisted_players_are_promoted_in_order() {
+        let repository = capped(2);
+        let general = MemoryRepository::default();
+        repository.insert("Players", player(1));
+        repository.insert("Players", player(2));
+        let config = repository.config().await.unwrap().unwrap();
+        assert!(registration_full(&repository, &config).await.unwrap());
+        assert_eq!(join_waitlist(&repository, player(3)).await.unwrap(), 1);
+        assert_eq!(join_waitlist(&repository, player(4)).await.unwrap(), 2);
+        assert_eq!(join_waitlist(&repository, player(3)).await.unwrap(), 1);
+        assert!(
+            promote_from_waitlist(&repository, &general, &config, &Region::EU)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        repository
+            .delete_player(doc! {"discord_id": "1"})
+            .await
+            .unwrap();
+        let promoted = promote_from_waitlist(&repository, &general, &config, &Region::EU)
+            .await
+            .unwrap();
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(promoted[0].get_str("discord_id"), Ok("3"));
+        assert!(!promoted[0].contains_key("waitlisted_at"));
+        assert_eq!(waitlist(&repository).await.unwrap().len(), 1);
+        assert_eq!(repository.documents("Players").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn banned_players_are_not_promoted() {
+        let repository = capped(1);
+        let general = MemoryRepository::default();
+        general.insert(
+            BANS,
+            make_ban_doc(Some("2"), None, "Cheating", "9", None, None),
+        );
+        join_waitlist(&repository, player(2)).await.unwrap();
+        join_waitlist(&repository, player(3)).await.unwrap();
+        let config = repository.config().await.unwrap().unwrap();
+        let promoted = promote_from_waitlist(&repository, &general, &config, &Region::EU)
+            .await
+            .unwrap();
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(promoted[0].get_str("discord_id"), Ok("3"));
+        assert!(waitlist(&repository).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn registrations_past_the_cap_are_waitlisted() {
+        let repository = capped(2);
+        // Both saw a free place before either was registered
+        for n in 1..=3 {
+            repository.insert("Players", player(n));
+        }
+        let config = repository.config().await.unwrap().unwrap();
+        assert_eq!(
+            keep_within_cap(&repository, &config, "2").await.unwrap(),
+            None
+        );
+        assert_eq!(
+            keep_within_cap(&repository, &config, "3").await.unwrap(),
+            Some(1)
+        );
+        let registered: Vec<String> = repository
+            .documents("Players")
+            .iter()
+            .map(|player| player.get_str("discord_id").unwrap().to_string())
+            .collect();
+        assert_eq!(registered, vec!["1", "2"]);
+        assert_eq!(waitlist(&repository).await.unwrap().len(), 1);
+    }
+}
@@ -1,17 +1,16 @@
+use crate::database::repository::Repository;
 use crate::{Context, Error};
-use mongodb::bson::{doc, Document};
 use poise::serenity_prelude::RoleId;
 use tracing::error;
 use tracing::info;
 
 pub async fn is_host(ctx: Context<'_>) -> Result<bool, Error> {
     let server_id = ctx.guild_id().unwrap().to_string();
-    let doc: Document = ctx
+    let doc = ctx
         .data()
         .database
         .general
-        .collection("Managers")
-        .find_one(doc! {"server_id": server_id}, None)
+        .managers(&server_id)
         .await?
         .unwrap();
     let hosts = doc.get_array("role_id").unwrap().to_vec();
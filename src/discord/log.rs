@@ -1,53 +1,88 @@
-use crate::{database::config::get_config, host::tournament::disqualify::Form, Context, Error};
+use crate::database::config::find_config;
+use crate::database::deadline::Outcome;
+use crate::database::repository::Repository;
+use crate::database::schedule::Event;
+use crate::{host::tournament::disqualify::Form, Context, Error};
 use dbc_bot::{chunk, Region};
+use mongodb::bson::Document;
+use mongodb::Database;
 use poise::serenity_prelude::{
-    ChannelId, CreateEmbed, Embed, GuildChannel, Message, MessageId, User,
+    self as serenity, ChannelId, CreateEmbed, Embed, GuildChannel, Http, Message, MessageId,
+    Timestamp, User,
 };
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum LogType {
     Info,
     Disqualify,
     DisqualifyInactives,
+    Reinstate,
+    Deadline,
+    Schedule,
+    Override,
+    Dispute,
     Test,
 }
 
 #[derive(Debug)]
-pub struct Log<'a> {
+pub struct Log {
     pub channel: GuildChannel,
     pub log_type: LogType,
-    pub ctx: Context<'a>,
+    pub http: Arc<Http>,
+    /// The general database, with the host roles of the servers.
+    pub general: Database,
     pub region: Region,
+    /// The user the log is sent on behalf of, the bot itself for scheduled logs.
     pub host: User,
+    pub created_at: Timestamp,
 }
 
-impl<'a> Log<'a> {
+impl Log {
     const DEFAULT_DISQUALIFY: &'static str = "Player has been disqualified from the tournament.";
 
-    pub async fn new(ctx: &Context<'a>, region: &Region, log_type: LogType) -> Result<Self, Error> {
-        let channel = Self::get_channel(ctx, region).await?;
+    pub async fn new(ctx: &Context<'_>, region: &Region, log_type: LogType) -> Result<Self, Error> {
+        let database = ctx.data().database.regional_databases.get(region).unwrap();
+        let http = ctx.serenity_context().http.clone();
+        let channel = Self::get_channel(&http, database).await?;
         Ok(Self {
             channel,
             log_type,
-            ctx: *ctx,
+            http,
+            general: ctx.data().database.general.clone(),
             region: region.clone(),
             host: ctx.author().to_owned(),
+            created_at: ctx.created_at(),
         })
     }
-    async fn get_channel(ctx: &Context<'_>, region: &Region) -> Result<GuildChannel, Error> {
-        let config = get_config(ctx, region).await;
+
+    /// A log sent by a background task of the bot rather than in reply to a command.
+    pub async fn scheduled(
+        ctx: &serenity::Context,
+        general: &Database,
+        database: &Database,
+        region: &Region,
+        log_type: LogType,
+    ) -> Result<Self, Error> {
+        let channel = Self::get_channel(&ctx.http, database).await?;
+        Ok(Self {
+            channel,
+            log_type,
+            http: ctx.http.clone(),
+            general: general.clone(),
+            region: region.clone(),
+            host: ctx.cache.current_user().into(),
+            created_at: Timestamp::now(),
+        })
+    }
+
+    async fn get_channel(http: &Http, database: &Database) -> Result<GuildChannel, Error> {
+        let config = find_config(database).await;
         let channel_id = config.get_str("log_channel")?.parse::<u64>()?;
-        let channel = match ctx
-            .guild()
-            .unwrap()
-            .channels(ctx.http())
-            .await?
-            .get(&ChannelId(channel_id))
-        {
-            Some(channel) => channel.to_owned(),
-            None => return Err("Failed to get channel".into()),
-        };
-        Ok(channel)
+        match ChannelId(channel_id).to_channel(http).await?.guild() {
+            Some(channel) => Ok(channel),
+            None => Err("Failed to get channel".into()),
+        }
     }
 
     pub async fn send_disqualify_log(
@@ -64,7 +99,7 @@ impl<'a> Log<'a> {
         };
         let msg = self
             .channel
-            .send_message(self.ctx.http(), |s| {
+            .send_message(&self.http, |s| {
                 s.embed(|e| {
                     e.title("DISQUALIFY")
                         .description(format!(
@@ -95,6 +130,186 @@ impl<'a> Log<'a> {
         Ok(msg)
     }
 
+    /// Marks the log of a disqualification as reverted, keeping its proof.
+    pub async fn revert_disqualify_log(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        note: &str,
+    ) -> Result<Message, Error> {
+        let current_embeds = channel_id.message(&self.http, message_id).await?.embeds;
+        let Some(original) = current_embeds.first() else {
+            return Err("The disqualification log has no embed!".into());
+        };
+        let mut embeds = vec![Self::recreate_default_log_embed(original.to_owned())
+            .title("DISQUALIFY (REVERTED)")
+            .description(format!(
+                r#"{description}
+**Reverted**: {note}
+**Reverted by**: <@{host_id}>(`{host_id}`) <t:{time}:f>."#,
+                description = original.description.clone().unwrap_or_default().trim_end(),
+                host_id = self.host.id.0,
+                time = self.created_at.unix_timestamp()
+            ))
+            .color(0x50C878)
+            .to_owned()];
+        embeds.extend(
+            current_embeds[1..]
+                .iter()
+                .filter_map(|embed| Self::recreate_img_log_embed(embed.to_owned())),
+        );
+        let msg = channel_id
+            .edit_message(&self.http, message_id, |m| m.add_embeds(embeds))
+            .await?;
+        Ok(msg)
+    }
+
+    /// Logs a reinstatement whose disqualification log cannot be found.
+    pub async fn send_reinstate_log(
+        &self,
+        user_id: &str,
+        round: &str,
+        note: &str,
+    ) -> Result<Message, Error> {
+        let msg = self
+            .channel
+            .send_message(&self.http, |s| {
+                s.embed(|e| {
+                    e.title("REINSTATE")
+                        .description(format!(
+                            r#"<@{user_id}>(`{user_id}`) has been reinstated in the tournament region {region} at {round}.
+**Note**: {note}
+**Reinstated by**: <@{host_id}>(`{host_id}`)."#,
+                            region = self.region,
+                            host_id = self.host.id.0
+                        ))
+                        .color(0x50C878)
+                        .timestamp(self.created_at)
+                })
+            })
+            .await?;
+        Ok(msg)
+    }
+
+    pub async fn send_override_log(
+        &self,
+        round: &str,
+        match_id: i32,
+        winner: &Document,
+        loser: &Document,
+        note: &str,
+    ) -> Result<Message, Error> {
+        let msg = self
+            .channel
+            .send_message(&self.http, |s| {
+                s.embed(|e| {
+                    e.title("RESULT OVERRIDE")
+                        .description(format!(
+                            r#"The result of {round} match {match_id} in region {region} has been set by a host.
+**Winner**: <@{winner_id}> {winner_name} ({winner_tag})
+**Loser**: <@{loser_id}> {loser_name} ({loser_tag})
+**Note**: {note}
+**Overridden by**: <@{host_id}>(`{host_id}`)."#,
+                            region = self.region,
+                            winner_id = winner.get_str("discord_id").unwrap_or(""),
+                            winner_name = winner.get_str("name").unwrap_or(""),
+                            winner_tag = winner.get_str("tag").unwrap_or(""),
+                            loser_id = loser.get_str("discord_id").unwrap_or(""),
+                            loser_name = loser.get_str("name").unwrap_or(""),
+                            loser_tag = loser.get_str("tag").unwrap_or(""),
+                            host_id = self.host.id.0
+                        ))
+                        .color(0xFFA500)
+                        .timestamp(self.created_at)
+                })
+            })
+            .await?;
+        Ok(msg)
+    }
+
+    /// Mentions of the host roles of the server, to ping them about a dispute.
+    async fn host_mentions(&self) -> Result<String, Error> {
+        let managers = self
+            .general
+            .managers(&self.channel.guild_id.to_string())
+            .await?;
+        Ok(managers
+            .and_then(|m| m.get_array("role_id").ok().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|role| role.as_str())
+            .map(|role| format!("<@&{role}>"))
+            .collect::<Vec<String>>()
+            .join(" "))
+    }
+
+    pub async fn send_dispute_log(
+        &self,
+        round: &str,
+        match_id: i32,
+        reason: &str,
+        evidence: &[String],
+    ) -> Result<Message, Error> {
+        let hosts = self.host_mentions().await?;
+        let msg = self
+            .channel
+            .send_message(&self.http, |s| {
+                s.content(hosts).embed(|e| {
+                    e.title("DISPUTE")
+                        .description(format!(
+                            r#"<@{user_id}>(`{user_id}`) disputes the result of {round} match {match_id} in region {region}.
+The match is paused until a host resolves the dispute from the tournament menu.
+**Reason**: {reason}
+**Evidence**: {evidence}"#,
+                            user_id = self.host.id.0,
+                            region = self.region,
+                            evidence = if evidence.is_empty() {
+                                "None".to_string()
+                            } else {
+                                evidence.join("\n")
+                            }
+                        ))
+                        .color(0xFFA500)
+                        .timestamp(self.created_at);
+                    if let Some(image) = evidence.first() {
+                        e.image(image);
+                    }
+                    e
+                })
+            })
+            .await?;
+        Ok(msg)
+    }
+
+    pub async fn send_dispute_resolution_log(
+        &self,
+        dispute: &Document,
+        resolution: &str,
+        note: &str,
+    ) -> Result<Message, Error> {
+        let msg = self
+            .channel
+            .send_message(&self.http, |s| {
+                s.embed(|e| {
+                    e.title("DISPUTE RESOLVED")
+                        .description(format!(
+                            r#"The dispute of <@{user_id}> about {round} match {match_id} in region {region} has been **{resolution}**.
+**Note**: {note}
+**Resolved by**: <@{host_id}>(`{host_id}`)."#,
+                            user_id = dispute.get_str("opened_by").unwrap_or(""),
+                            round = dispute.get_str("round").unwrap_or(""),
+                            match_id = dispute.get_i32("match_id").unwrap_or(0),
+                            region = self.region,
+                            host_id = self.host.id.0
+                        ))
+                        .color(0x50C878)
+                        .timestamp(self.created_at)
+                })
+            })
+            .await?;
+        Ok(msg)
+    }
+
     pub async fn update_proof(
         ctx: &Context<'_>,
         channel_id: ChannelId,
@@ -153,7 +368,7 @@ impl<'a> Log<'a> {
     pub async fn test_log(&self) -> Result<Message, Error> {
         let msg = self
             .channel
-            .send_message(self.ctx.http(), |s| {
+            .send_message(&self.http, |s| {
                 s.embed(|e| {
                     e.title("TEST")
                         .description(r#"Successfully sent a log here"#.to_string())
@@ -170,7 +385,7 @@ impl<'a> Log<'a> {
             .title("DISQUALIFY INACTIVE")
             .description(format!(r#"Due to inactivity, the following players have been disqualified from the tournament region {region}. Disqualifed by <@{host}>(`{host}`)"#, region = self.region, host = self.host.id.0))
             .color(0xFF0000)
-            .timestamp(self.ctx.created_at())
+            .timestamp(self.created_at)
             .to_owned();
         embeds.push(default_embed);
         let chunk_player = chunk::<String>(&players, 50);
@@ -183,7 +398,68 @@ impl<'a> Log<'a> {
         });
         let msg = self
             .channel
-            .send_message(self.ctx.http(), |s| s.add_embeds(embeds))
+            .send_message(&self.http, |s| s.add_embeds(embeds))
+            .await?;
+        Ok(msg)
+    }
+
+    pub async fn send_deadline_log(
+        &self,
+        rounds: &str,
+        outcomes: &[Outcome],
+    ) -> Result<Message, Error> {
+        let mut embeds: Vec<CreateEmbed> = vec![CreateEmbed::default()
+            .title("ROUND DEADLINE")
+            .description(format!(
+                r#"The deadline of {rounds} in region {region} has passed. The matches that were not played have been resolved as follows."#,
+                region = self.region
+            ))
+            .color(0xFF0000)
+            .timestamp(self.created_at)
+            .to_owned()];
+        let lines: Vec<String> = match outcomes.is_empty() {
+            true => vec!["Every match had been played.".to_string()],
+            false => outcomes.iter().map(Outcome::describe).collect(),
+        };
+        chunk::<String>(&lines, 20).iter().for_each(|chunk| {
+            embeds.push(
+                CreateEmbed::default()
+                    .description(chunk.join("\n"))
+                    .to_owned(),
+            );
+        });
+        let msg = self
+            .channel
+            .send_message(&self.http, |s| s.add_embeds(embeds))
+            .await?;
+        Ok(msg)
+    }
+
+    pub async fn send_schedule_log(
+        &self,
+        event: Event,
+        outcome: &Result<String, String>,
+    ) -> Result<Message, Error> {
+        let (description, color) = match outcome {
+            Ok(done) => (done.to_string(), 0x50C878),
+            Err(reason) => (
+                format!("The scheduled event was not carried out: {reason}"),
+                0xFF0000,
+            ),
+        };
+        let msg = self
+            .channel
+            .send_message(&self.http, |s| {
+                s.embed(|e| {
+                    e.title(format!("SCHEDULED: {}", event.label().to_uppercase()))
+                        .description(format!(
+                            "**Region**: {region}\n{description}",
+                            region = self.region
+                        ))
+                        .color(color)
+                        .timestamp(self.created_at)
+                })
+            })
             .await?;
         Ok(msg)
     }
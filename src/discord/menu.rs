@@ -1,16 +1,23 @@
 use super::prompt::prompt;
+use crate::bracket_tournament::best_of::{best_of, wins_needed};
+use crate::database::config::get_config;
+use crate::database::dispute::{dispute_deadline, dispute_window};
+use crate::database::models::Player;
+use crate::database::team::team_size;
 use crate::host::registration::index::registration_mod_panel;
 use crate::host::tournament::index::tournament_mod_panel;
 use crate::host::utilities::index::utilities_mod_panel;
 use crate::players::registration::deregister::deregister_menu;
 use crate::players::registration::register::register_menu;
+use crate::players::registration::team::team_menu;
+use crate::players::tournament::dispute::dispute_menu;
 use crate::players::tournament::ready;
 use crate::players::tournament::submit::submit_result;
 use crate::players::tournament::view2::{view_managers, view_opponent_wrapper};
 use crate::players::view::view_info;
 use crate::Context;
 use crate::Error;
-use dbc_bot::Region;
+use dbc_bot::{DrawRule, Region};
 use futures::StreamExt;
 use mongodb::bson::Document;
 use poise::serenity_prelude::{ButtonStyle, ReactionType};
@@ -34,6 +41,10 @@ pub async fn registration_menu(
     help: bool,
     player: Option<Document>,
 ) -> Result<(), Error> {
+    let team = match player.as_ref().and_then(|p| p.get_str("region").ok()) {
+        Some(region) => team_size(&get_config(ctx, &Region::find_key(region).unwrap()).await) > 1,
+        None => false,
+    };
     msg.edit(*ctx, |e| {
         e.components(|c| {
             c.create_action_row(|r| {
@@ -49,6 +60,12 @@ pub async fn registration_menu(
                         .style(ButtonStyle::Primary)
                         .emoji(ReactionType::Unicode("🤓".to_string()))
                 })
+                .create_button(|b| {
+                    b.custom_id("team")
+                        .disabled(!team)
+                        .style(ButtonStyle::Primary)
+                        .emoji(ReactionType::Unicode("👥".to_string()))
+                })
                 .create_button(|b| {
                     b.custom_id("deregister")
                         .disabled(!deregister)
@@ -69,6 +86,7 @@ pub async fn registration_menu(
                     r#"Below are options:
 📝: Register.
 🤓: View personal information.
+👥: Manage your team.
 🚪: Deregister.
 ❓: Help."#,
                 )
@@ -91,6 +109,10 @@ pub async fn registration_menu(
                 mci.defer(&ctx.http()).await?;
                 return deregister_menu(ctx, msg, player.unwrap()).await;
             }
+            "team" => {
+                mci.defer(&ctx.http()).await?;
+                return team_menu(ctx, msg, player.unwrap()).await;
+            }
             "personal" => {
                 mci.defer(&ctx.http()).await?;
                 return view_info(ctx, msg, player.unwrap()).await;
@@ -121,8 +143,12 @@ pub async fn tournament_menu(
     submit: bool,
     help: bool,
     player: Document,
+    round: &str,
 ) -> Result<(), Error> {
-    let region = Region::find_key(player.get_str("region").unwrap()).unwrap();
+    let region = Player::from_document(&player)?.region()?;
+    let config = get_config(ctx, &region).await;
+    let best_of = best_of(&config, round);
+    let dispute = dispute_deadline(&config, &player).is_some();
     msg.edit(*ctx, |e| {
         e.components(|c| {
             c.create_action_row(|r| {
@@ -157,6 +183,14 @@ pub async fn tournament_menu(
                         .emoji(ReactionType::Unicode("❓".to_string()))
                 })
             })
+            .create_action_row(|r| {
+                r.create_button(|b| {
+                    b.custom_id("dispute")
+                        .disabled(!dispute)
+                        .style(ButtonStyle::Danger)
+                        .emoji(ReactionType::Unicode("⚖️".to_string()))
+                })
+            })
             // .create_action_row(|r| {
             //     r.create_button(|b| {
             //         b.custom_id("managers")
@@ -168,15 +202,23 @@ pub async fn tournament_menu(
         })
         .embed(|e| {
             e.title("Tournament Menu")
-                .description(
+                .description(format!(
                     r#"Below are the available options!
 ⚔️: Find out who your opponent is for the current round!
 💪: Mark your activity!
 📥: Submit your result!
 👤: View Personal Information
 ❓: Help.
+⚖️: Dispute the result of your match{}.
+🎯 {round} is best of {best_of}: first to {} wins. {}
 "#,
-                )
+                    match dispute_window(&config) {
+                        Some(minutes) => format!(" within {minutes} minutes"),
+                        None => " (turned off)".to_string(),
+                    },
+                    wins_needed(best_of),
+                    DrawRule::from_config(&config).describe()
+                ))
                 .color(0xFFFF00)
         })
     })
@@ -208,6 +250,10 @@ pub async fn tournament_menu(
                 mci.defer(&ctx.http()).await?;
                 return view_info(ctx, msg, player).await;
             }
+            "dispute" => {
+                mci.defer(&ctx.http()).await?;
+                return dispute_menu(ctx, msg, &region, round, player).await;
+            }
             "help" => {
                 mci.defer(&ctx.http()).await?;
                 return prompt(
@@ -1,16 +1,25 @@
 use crate::database::config::get_config;
+use crate::database::models::RegionConfig;
+use crate::database::team::members;
 use crate::Context;
 use crate::Error;
 use dbc_bot::Region;
+use mongodb::bson::Document;
 use poise::serenity_prelude::RoleId;
 use poise::serenity_prelude::User;
+use poise::serenity_prelude::UserId;
 use strum::IntoEnumIterator;
 use tracing::error;
 pub async fn get_region_from_role(ctx: &Context<'_>, roles: Vec<RoleId>) -> Option<Region> {
     for role in roles.iter() {
         for region in Region::iter() {
             let config = get_config(ctx, &region).await;
-            let role_id_from_db = config.get_str("role").unwrap().parse::<u64>().unwrap();
+            // A region without a role cannot be the one the user plays in
+            let Ok(role_id_from_db) =
+                RegionConfig::from_document(&config).and_then(|config| config.role_id())
+            else {
+                continue;
+            };
             match role.to_role_cached(ctx.cache()) {
                 Some(role) => {
                     if role.id == role_id_from_db {
@@ -49,9 +58,7 @@ pub async fn get_roles_from_user(
 #[allow(dead_code)]
 pub async fn get_region_role_id(ctx: &Context<'_>, region: &Region) -> Option<u64> {
     let config = get_config(ctx, region).await;
-    config
-        .get_str("role")
-        .map_or_else(|_| None, |s| Some(s.parse::<u64>().unwrap()))
+    RegionConfig::from_document(&config).ok()?.role_id().ok()
 }
 /// Remove a role from a user
 /// `user: poise::serenity_prelude::User` - The user to remove the role from
@@ -62,7 +69,7 @@ pub async fn remove_role(
     region: &Region,
 ) -> Result<(), Error> {
     let config = get_config(ctx, region).await;
-    let role_id = config.get_str("role").unwrap().parse::<u64>().unwrap();
+    let role_id = RegionConfig::from_document(&config)?.role_id()?;
     let mut member = match ctx.guild().unwrap().member(ctx.http(), user.id.0).await {
         Ok(m) => m,
         Err(e) => {
@@ -87,6 +94,54 @@ pub async fn remove_role(
     }
 }
 
+/// Remove the regional role from a user outside of a command, e.g. from a background task
+/// `config: &Document` - The config to get the role from. The server is found from the cached role.
+pub async fn remove_role_by_id(
+    ctx: &poise::serenity_prelude::Context,
+    config: &Document,
+    user_id: u64,
+) -> Result<(), Error> {
+    let role_id = RegionConfig::from_document(config)?.role_id()?;
+    let Some(role) = RoleId(role_id).to_role_cached(&ctx.cache) else {
+        return Err(format!("Failed to find the role {role_id} in any server!").into());
+    };
+    match ctx
+        .http
+        .remove_member_role(role.guild_id.0, user_id, role_id, None)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error!("{e}");
+            Err(format!("Failed to remove the role from {user_id}!").into())
+        }
+    }
+}
+
+/// Assign the regional role to a user outside of a command, e.g. from a background task
+/// `config: &Document` - The config to get the role from. The server is found from the cached role.
+pub async fn assign_role_by_id(
+    ctx: &poise::serenity_prelude::Context,
+    config: &Document,
+    user_id: u64,
+) -> Result<(), Error> {
+    let role_id = RegionConfig::from_document(config)?.role_id()?;
+    let Some(role) = RoleId(role_id).to_role_cached(&ctx.cache) else {
+        return Err(format!("Failed to find the role {role_id} in any server!").into());
+    };
+    match ctx
+        .http
+        .add_member_role(role.guild_id.0, user_id, role_id, None)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error!("{e}");
+            Err(format!("Failed to assign the role to {user_id}!").into())
+        }
+    }
+}
+
 /// Assign a role to a user
 /// `user: poise::serenity_prelude::User` - The user to assign the role to
 /// `region: &Option<Region>` - The region to get the role from
@@ -96,8 +151,8 @@ pub async fn assign_role(
     region: &Option<Region>,
 ) -> Result<(), Error> {
     let config = get_config(ctx, &region.clone().unwrap()).await;
-    let role = match config.get_str("role") {
-        Ok(role) => Some(role),
+    let role_id = match RegionConfig::from_document(&config).and_then(|config| config.role_id()) {
+        Ok(role_id) => role_id,
         Err(e) => {
             error!("{e}");
             return Err("Failed to get the role from the database!".into());
@@ -110,10 +165,7 @@ pub async fn assign_role(
             return Err("Failed to find the user! User is not found in the server!".into());
         }
     };
-    match member
-        .add_role((*ctx).http(), role.unwrap().parse::<u64>().unwrap())
-        .await
-    {
+    match member.add_role((*ctx).http(), role_id).await {
         Ok(_) => Ok(()),
         Err(e) => {
             error!("{e}");
@@ -125,3 +177,29 @@ pub async fn assign_role(
         }
     }
 }
+
+/// Gives or removes the regional role of a player and the members of their team.
+pub async fn set_roles(ctx: &Context<'_>, region: &Region, player: &Document, give: bool) {
+    let mut ids = vec![player.get_str("discord_id").unwrap_or("").to_string()];
+    ids.extend(
+        members(player)
+            .iter()
+            .filter_map(|member| member.get_str("discord_id").ok().map(str::to_string)),
+    );
+    for id in ids {
+        let Ok(id) = id.parse::<u64>() else {
+            continue;
+        };
+        let Ok(user) = UserId(id).to_user(ctx.http()).await else {
+            continue;
+        };
+        let result = if give {
+            assign_role(ctx, &user, &Some(region.clone())).await
+        } else {
+            remove_role(ctx, &user, region).await
+        };
+        if let Err(e) = result {
+            error!("{e}");
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::brawlstars::getters::get_player_icon;
 use crate::database::config::get_config;
 use crate::database::find::find_round_from_config;
+use crate::database::models::Player;
 use crate::Region;
 use crate::{Context, Error};
 use futures::StreamExt;
@@ -104,18 +105,21 @@ async fn get_player_data(
     index: &i32,
     total: &i32,
 ) -> Result<(), Error> {
-    let name = player.get_str("name").unwrap_or("Unknown player");
-    let tag = player.get_str("tag").unwrap();
-    let discord_id = player.get_str("discord_id").unwrap();
-    let match_id = player
-        .get_i32("match_id")
-        .map_or_else(|_| "Not yet assigned".to_string(), |id| id.to_string());
-    let battle = match player.get_bool("battle").unwrap() {
+    let Player {
+        name,
+        tag,
+        discord_id,
+        match_id,
+        battle,
+        icon,
+        ..
+    } = Player::from_document(&player)?;
+    let match_id = match_id.map_or_else(|| "Not yet assigned".to_string(), |id| id.to_string());
+    let battle = match battle {
         true => "Already played",
         false => "Not yet played",
     };
-    let icon = player.get_i64("icon").unwrap();
-    let icon_url = get_player_icon(icon);
+    let icon_url = get_player_icon(icon.unwrap_or(28000000));
     msg.edit(*ctx, |s| {
         s.embed(|e| {
             e.title(format!(
@@ -1,20 +1,32 @@
+use crate::database::audit::audit;
+use crate::database::check_in::{check_in_open, open_check_in};
 use crate::database::config::get_config;
 use crate::database::open::{registration_open, tournament};
+use crate::database::repository::{Repository, PLAYERS};
+use crate::database::schedule::registration_ready;
 use crate::database::stat::count_registers;
 use crate::database::update::toggle_registration;
+use crate::database::waitlist::{max_players, waitlist};
+use crate::players::registration::check_in::announce_check_in;
 use crate::{Context, Error};
 use dbc_bot::Region;
 use futures::StreamExt;
+use mongodb::bson::{doc, Document};
 use poise::serenity_prelude::ReactionType;
 use poise::ReplyHandle;
 
 use super::detail::{detail, term};
 
 const TIMEOUT: u64 = 300;
+const WAITLIST_SHOWN: usize = 10;
 struct Reg {
     registration: bool,
     tournament: bool,
+    check_in: bool,
     count: i32,
+    checked_in: i32,
+    cap: Option<u64>,
+    waitlist: Vec<Document>,
     region: Region,
 }
 
@@ -35,11 +47,26 @@ pub async fn registration_mod_panel(
             "registration" => {
                 mci.defer(&ctx.http()).await?;
                 toggle_registration(ctx, region, !reg.registration).await?;
+                audit(
+                    ctx,
+                    Some(region),
+                    "registration.toggle",
+                    reg.registration,
+                    !reg.registration,
+                )
+                .await?;
             }
             "detail" => {
                 mci.defer(&ctx.http()).await?;
                 detail(ctx, msg, region).await?;
             }
+            "check_in" => {
+                mci.defer(&ctx.http()).await?;
+                let database = ctx.data().database.regional_databases.get(region).unwrap();
+                open_check_in(database).await?;
+                announce_check_in(ctx.serenity_context(), database, region).await?;
+                audit(ctx, Some(region), "check_in.open", false, true).await?;
+            }
             _ => {
                 reg = getter(ctx, region).await?;
                 display_info(ctx, msg, region, &reg).await?;
@@ -64,18 +91,49 @@ async fn display_info(
         ""
     };
     let check_flag = prerequisite(ctx, region).await;
+    let cap = match reg.cap {
+        Some(cap) => format!(" (cap: {cap})"),
+        None => String::new(),
+    };
+    let waitlist = match reg.waitlist.is_empty() {
+        true => "Nobody is waitlisted.".to_string(),
+        false => format!(
+            "Waitlist ({}):\n{}",
+            reg.waitlist.len(),
+            reg.waitlist
+                .iter()
+                .take(WAITLIST_SHOWN)
+                .enumerate()
+                .map(|(i, player)| format!(
+                    "{}. <@{}> ({})",
+                    i + 1,
+                    player.get_str("discord_id").unwrap_or("0"),
+                    player.get_str("tag").unwrap_or("")
+                ))
+                .collect::<Vec<String>>()
+                .join("\n")
+        ),
+    };
+    let check_in = match reg.check_in {
+        true => format!("open, {}/{} checked in", reg.checked_in, reg.count),
+        false => "closed".to_string(),
+    };
     msg.edit(*ctx, |m| {
         m.embed(|e| {
             e.title("**Registration Panel**")
                 .description(format!(
                     r#"Registration is currently: {}
-There are {} registered players for the tournament of {}.{flag}
+Check-in is currently: {check_in}
+There are {} registered players{cap} for the tournament of {}.{flag}
+{waitlist}
 Below are options:
 🔒: Toggle registration
 - Open/Close registration phase for players.
 - This will be disabled during the tournament phase.
 🔍: View
 - Lets you see all players who has already registered in the tournament
+✅: Open check-in
+- Asks every registered player to check in. Players who have not checked in are removed when the tournament starts.
 "#,
                     term(reg.registration),
                     reg.count,
@@ -95,6 +153,12 @@ Below are options:
                     b.custom_id("detail")
                         .style(poise::serenity_prelude::ButtonStyle::Primary)
                         .emoji(ReactionType::Unicode("🔍".to_string()))
+                });
+                row.create_button(|b| {
+                    b.custom_id("check_in")
+                        .style(poise::serenity_prelude::ButtonStyle::Primary)
+                        .disabled(reg.tournament || reg.check_in || !check_flag)
+                        .emoji(ReactionType::Unicode("✅".to_string()))
                 })
             })
         })
@@ -107,18 +171,25 @@ async fn getter(ctx: &Context<'_>, region: &Region) -> Result<Reg, Error> {
     let status = registration_open(ctx).await;
     let tournament_status = tournament(ctx, region).await;
     let count = count_registers(ctx, region).await?;
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let check_in = check_in_open(&get_config(ctx, region).await);
+    let checked_in = database
+        .count_in_round(PLAYERS, doc! {"checked_in": true})
+        .await? as i32;
+    let cap = max_players(&get_config(ctx, region).await);
+    let waitlist = waitlist(database).await?;
     Ok(Reg {
         registration: status,
         tournament: tournament_status,
+        check_in,
         count,
+        checked_in,
+        cap,
+        waitlist,
         region: region.clone(),
     })
 }
 
 async fn prerequisite(ctx: &Context<'_>, region: &Region) -> bool {
-    let config = get_config(ctx, region).await;
-    !(config.get("mode").is_none()
-        || config.get("role").is_none()
-        || config.get("channel").is_none()
-        || config.get("bracket_channel").is_none())
+    registration_ready(&get_config(ctx, region).await)
 }
@@ -0,0 +1,468 @@
+//! Host override of a match result.
+//!
+//! A host can set the winner of any match, played or not. When the match was already recorded the
+//! other way, the two players are swapped in the matches the result led to (e.g. `Round N+1`), and a
+//! match the wrong player has already played there is reopened. Every override is logged with the
+//! host's note.
+use crate::bracket_tournament::placements::{post_placements, record_placements};
+use crate::bracket_tournament::seeding::normalise_tag;
+use crate::database::audit::audit;
+use crate::database::config::get_config;
+use crate::database::correction::{plan_reinstatement, plan_replay, plan_reversal};
+use crate::database::find::{is_mannequin, is_round_collection};
+use crate::database::repository::Repository;
+use crate::discord::log::{Log, LogType};
+use crate::discord::prompt::prompt;
+use crate::discord::role::set_roles;
+use crate::players::tournament::submit::{conclude_match, match_outcome};
+use crate::{Context, Error};
+use dbc_bot::Region;
+use futures::{StreamExt, TryStreamExt};
+use mongodb::bson::{doc, Document};
+use mongodb::Database;
+use poise::serenity_prelude::ButtonStyle;
+use poise::ReplyHandle;
+
+const TIMEOUT: u64 = 120;
+
+#[derive(Debug, poise::Modal)]
+#[name = "Override Result"]
+struct OverrideModal {
+    #[name = "Round"]
+    #[placeholder = "For example: Round 2, Losers Round 1, Grand Final"]
+    round: String,
+
+    #[name = "Match ID"]
+    #[placeholder = "The number of the match in that round"]
+    match_id: String,
+
+    #[name = "Winner"]
+    #[placeholder = "Player tag or Discord ID of the winner"]
+    winner: String,
+
+    #[name = "Note"]
+    #[placeholder = "Why the result is overridden. This is kept in the log"]
+    #[paragraph]
+    note: String,
+}
+
+#[derive(Debug, Clone)]
+struct Correction {
+    round: String,
+    match_id: i32,
+    winner: Document,
+    loser: Document,
+    note: String,
+}
+
+pub async fn override_result(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+) -> Result<(), Error> {
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title("✏️ Override Result")
+                .description(
+                    r#"Set the winner of any match, for instance when the battle log missed a game.
+If the match was already recorded the other way, the players are swapped in the later rounds.
+A note is required and every override is logged."#,
+                )
+                .color(0xFFFF00)
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| b.custom_id("open_modal").label("Set winner"))
+            })
+        })
+    })
+    .await?;
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let mut correction = None;
+    let resp = msg.clone().into_message().await?;
+    let mut cic = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(TIMEOUT))
+        .build();
+    while let Some(mci) = &cic.next().await {
+        match mci.data.custom_id.as_str() {
+            "open_modal" => {
+                let Some(form) = poise::execute_modal_on_component_interaction::<OverrideModal>(
+                    ctx,
+                    mci.clone(),
+                    None,
+                    None,
+                )
+                .await?
+                else {
+                    continue;
+                };
+                match find_match(database, form).await? {
+                    Ok(found) => {
+                        display_confirmation(ctx, msg, &found).await?;
+                        correction = Some(found);
+                    }
+                    Err(reason) => {
+                        return prompt(
+                            ctx,
+                            msg,
+                            "Unable to override the result!",
+                            reason,
+                            None,
+                            Some(0xFF0000),
+                        )
+                        .await;
+                    }
+                }
+            }
+            "confirm" => {
+                mci.defer(&ctx.http()).await?;
+                if let Some(correction) = &correction {
+                    return apply_override(ctx, msg, region, correction).await;
+                }
+            }
+            "cancel" => {
+                mci.defer(&ctx.http()).await?;
+                return prompt(
+                    ctx,
+                    msg,
+                    "Result override cancelled",
+                    "No result has been changed.",
+                    None,
+                    None,
+                )
+                .await;
+            }
+            _ => continue,
+        }
+    }
+    Ok(())
+}
+
+/// Finds the match of the form and which of its players the host picked as the winner.
+async fn find_match(
+    database: &Database,
+    form: OverrideModal,
+) -> Result<Result<Correction, String>, Error> {
+    let round = form.round.trim().to_string();
+    let collections = database.list_collection_names(None).await?;
+    if !is_round_collection(&round) || !collections.contains(&round) {
+        return Ok(Err(format!("`{round}` is not a round of this tournament!")));
+    }
+    let Ok(match_id) = form.match_id.trim().parse::<i32>() else {
+        return Ok(Err(format!("`{}` is not a match ID!", form.match_id)));
+    };
+    let note = form.note.trim().to_string();
+    if note.is_empty() {
+        return Ok(Err(
+            "Please explain why the result is overridden!".to_string()
+        ));
+    }
+    let players: Vec<Document> = database
+        .collection::<Document>(&round)
+        .find(doc! {"match_id": match_id}, None)
+        .await?
+        .try_collect()
+        .await?;
+    if players.len() != 2 {
+        return Ok(Err(format!(
+            "Match {match_id} of {round} does not have two players yet!"
+        )));
+    }
+    let picked = form.winner.trim();
+    let is_picked = |player: &Document| {
+        !is_mannequin(player)
+            && (player.get_str("discord_id") == Ok(picked)
+                || normalise_tag(player.get_str("tag").unwrap_or("")) == normalise_tag(picked))
+    };
+    let (winner, loser) = match (is_picked(&players[0]), is_picked(&players[1])) {
+        (true, false) => (players[0].clone(), players[1].clone()),
+        (false, true) => (players[1].clone(), players[0].clone()),
+        _ => {
+            return Ok(Err(format!(
+                "{picked} does not play in match {match_id} of {round}!"
+            )))
+        }
+    };
+    Ok(Ok(Correction {
+        round,
+        match_id,
+        winner,
+        loser,
+        note,
+    }))
+}
+
+async fn display_confirmation(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    correction: &Correction,
+) -> Result<(), Error> {
+    let describe = |player: &Document| match player.get_str("discord_id") {
+        Ok(id) => format!(
+            "<@{id}> {} ({})",
+            player.get_str("name").unwrap_or(""),
+            player.get_str("tag").unwrap_or("")
+        ),
+        Err(_) => "Mannequin".to_string(),
+    };
+    let recorded = match correction.winner.get_bool("defeated") {
+        Ok(false) => "Already won by this player",
+        Ok(true) => "Won by the other player, later rounds will be corrected",
+        Err(_) => "Not played yet",
+    };
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title("✏️ Override Result - Confirmation")
+                .description("**Please confirm the result below.**")
+                .fields(vec![
+                    ("Round", correction.round.clone(), true),
+                    ("Match", correction.match_id.to_string(), true),
+                    ("Recorded result", recorded.to_string(), false),
+                    ("Winner", describe(&correction.winner), true),
+                    ("Loser", describe(&correction.loser), true),
+                    ("Note", correction.note.clone(), false),
+                ])
+                .color(0xFFFF00)
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| {
+                    b.label("Confirm")
+                        .style(ButtonStyle::Danger)
+                        .custom_id("confirm")
+                })
+                .create_button(|b| {
+                    b.label("Cancel")
+                        .style(ButtonStyle::Primary)
+                        .custom_id("cancel")
+                })
+            })
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+async fn apply_override(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+    correction: &Correction,
+) -> Result<(), Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let Correction {
+        round,
+        match_id,
+        winner,
+        loser,
+        note,
+    } = correction;
+    let mut reopened = vec![];
+    match winner.get_bool("defeated") {
+        Ok(false) => {
+            return prompt(
+                ctx,
+                msg,
+                "Nothing to override!",
+                "This player has already been recorded as the winner of the match.",
+                None,
+                Some(0xFFFF00),
+            )
+            .await;
+        }
+        Err(_) => {
+            conclude_match(
+                ctx.serenity_context(),
+                database,
+                region,
+                round,
+                winner,
+                loser,
+                None,
+            )
+            .await?;
+        }
+        Ok(true) => match reverse_result(ctx, region, round, *match_id, winner, loser).await? {
+            Ok(matches) => reopened = matches,
+            Err(reason) => {
+                return prompt(
+                    ctx,
+                    msg,
+                    "Unable to override the result!",
+                    reason,
+                    None,
+                    Some(0xFF0000),
+                )
+                .await;
+            }
+        },
+    }
+    database
+        .collection::<Document>(round)
+        .update_many(
+            doc! {"match_id": match_id},
+            doc! {"$set": {"override_note": note}},
+            None,
+        )
+        .await?;
+    let previous = match winner.get_bool("defeated") {
+        Ok(true) => loser.get("discord_id").cloned(),
+        _ => None,
+    };
+    audit(
+        ctx,
+        Some(region),
+        "match.override",
+        doc! {"round": round, "match_id": *match_id, "winner": previous},
+        doc! {"round": round, "match_id": *match_id, "winner": winner.get("discord_id"), "note": note},
+    )
+    .await?;
+    let log = Log::new(ctx, region, LogType::Override).await?;
+    let log_msg = log
+        .send_override_log(round, *match_id, winner, loser, note)
+        .await?;
+    let reopened = if reopened.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\nThe wrong player had already played these matches, which have to be played again:\n- {}",
+            reopened.join("\n- ")
+        )
+    };
+    prompt(
+        ctx,
+        msg,
+        "Result overridden!",
+        format!(
+            "<@{}> is now the winner of {round} match {match_id}. The log has been recorded [here]({}).{reopened}",
+            winner.get_str("discord_id").unwrap_or(""),
+            log_msg.link()
+        ),
+        None,
+        Some(0x50C878),
+    )
+    .await
+}
+
+/// Records the win of a match for the player who was recorded as its loser, and swaps the two
+/// players in the later rounds.
+///
+/// Returns the matches that have to be played again, or why the result cannot be reversed.
+pub async fn reverse_result(
+    ctx: &Context<'_>,
+    region: &Region,
+    round: &str,
+    match_id: i32,
+    winner: &Document,
+    loser: &Document,
+) -> Result<Result<Vec<String>, String>, Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let config = get_config(ctx, region).await;
+    let (writes, reopened) =
+        match plan_reversal(database, &config, region, round, match_id, winner, loser).await? {
+            Ok(planned) => planned,
+            Err(reason) => return Ok(Err(reason)),
+        };
+    database.apply(writes).await?;
+    update_roles(ctx, region, &config, round, winner, loser).await;
+    rerecord_placements(ctx, database, region, &config).await?;
+    Ok(Ok(reopened))
+}
+
+/// Reopens a played match so that both players play it again, removing them from the matches the
+/// result led to. Games played before the replay no longer count towards the result.
+///
+/// Returns why the match cannot be reopened, if a later match has already been played.
+pub async fn replay_match(
+    ctx: &Context<'_>,
+    region: &Region,
+    round: &str,
+    match_id: i32,
+) -> Result<Result<(), String>, Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let config = get_config(ctx, region).await;
+    let players = database
+        .find_in_round(round, doc! {"match_id": match_id})
+        .await?;
+    let writes = match plan_replay(database, &config, region, round, match_id).await? {
+        Ok(writes) => writes,
+        Err(reason) => return Ok(Err(reason)),
+    };
+    database.apply(writes).await?;
+    // The result that knocked the loser out no longer stands
+    if let Some(loser) = players
+        .iter()
+        .find(|player| player.get_bool("defeated") == Ok(true))
+    {
+        if let Some(winner) = players.iter().find(|player| *player != loser) {
+            if match_outcome(&config, round, winner).1 {
+                set_roles(ctx, region, loser, true).await;
+            }
+        }
+    }
+    rerecord_placements(ctx, database, region, &config).await?;
+    Ok(Ok(()))
+}
+
+/// Undoes the disqualification of a player: their match is reopened, the opponent is taken back out
+/// of the later rounds, and the roles of the player and their team are restored.
+///
+/// Returns why the disqualification cannot be undone, if a later match has already been played.
+pub async fn reinstate_player(
+    ctx: &Context<'_>,
+    region: &Region,
+    round: &str,
+    player: &Document,
+) -> Result<Result<(), String>, Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let config = get_config(ctx, region).await;
+    let writes = match plan_reinstatement(database, &config, region, round, player).await? {
+        Ok(writes) => writes,
+        Err(reason) => return Ok(Err(reason)),
+    };
+    database.apply(writes).await?;
+    set_roles(ctx, region, player, true).await;
+    rerecord_placements(ctx, database, region, &config).await?;
+    Ok(Ok(()))
+}
+
+/// Records the placements again if they were recorded before a result changed.
+async fn rerecord_placements(
+    ctx: &Context<'_>,
+    database: &Database,
+    region: &Region,
+    config: &Document,
+) -> Result<(), Error> {
+    if !config.get_bool("placements_recorded").unwrap_or(false) {
+        return Ok(());
+    }
+    database
+        .collection::<Document>("Config")
+        .update_one(doc! {}, doc! {"$set": {"placements_recorded": false}}, None)
+        .await?;
+    if let Some(placements) = record_placements(database, false).await? {
+        post_placements(ctx, database, region, &placements).await?;
+    }
+    Ok(())
+}
+
+/// Gives the role back to a winner who was knocked out by the wrong result, and removes it from the
+/// loser if the real result knocks them out.
+async fn update_roles(
+    ctx: &Context<'_>,
+    region: &Region,
+    config: &Document,
+    round: &str,
+    winner: &Document,
+    loser: &Document,
+) {
+    let (_, was_eliminated) = match_outcome(config, round, loser);
+    let (_, eliminated) = match_outcome(config, round, winner);
+    if was_eliminated {
+        set_roles(ctx, region, winner, true).await;
+    }
+    if eliminated {
+        set_roles(ctx, region, loser, false).await;
+    }
+}
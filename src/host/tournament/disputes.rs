@@ -0,0 +1,288 @@
+//! Host review of the results players have disputed.
+use crate::database::audit::audit;
+use crate::database::dispute::{close_dispute, find_open_disputes, Resolution};
+use crate::discord::log::{Log, LogType};
+use crate::discord::prompt::prompt;
+use crate::{Context, Error};
+use dbc_bot::Region;
+use futures::{StreamExt, TryStreamExt};
+use mongodb::bson::{doc, Document};
+use poise::serenity_prelude::{ButtonStyle, CreateSelectMenuOption};
+use poise::ReplyHandle;
+
+use super::correction::{replay_match, reverse_result};
+
+const TIMEOUT: u64 = 300;
+
+#[derive(Debug, poise::Modal)]
+#[name = "Resolve Dispute"]
+struct ResolutionNote {
+    #[name = "Note"]
+    #[placeholder = "Why the dispute is resolved this way. This is kept in the log and the match"]
+    #[paragraph]
+    note: Option<String>,
+}
+
+pub async fn resolve_disputes(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+) -> Result<(), Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let disputes = find_open_disputes(database).await?;
+    if disputes.is_empty() {
+        return prompt(
+            ctx,
+            msg,
+            "No open disputes",
+            "Players have not disputed any result that is still waiting for a decision.",
+            None,
+            Some(0x50C878),
+        )
+        .await;
+    }
+    let mut options: Vec<CreateSelectMenuOption> = vec![];
+    for dispute in disputes.iter().take(25) {
+        let id = dispute.get_object_id("_id")?;
+        let mut option = CreateSelectMenuOption::default();
+        option
+            .label(format!(
+                "{} - Match {}",
+                dispute.get_str("round").unwrap_or(""),
+                dispute.get_i32("match_id").unwrap_or(0)
+            ))
+            .value(id.to_hex())
+            .description(
+                dispute
+                    .get_str("reason")
+                    .unwrap_or("")
+                    .chars()
+                    .take(100)
+                    .collect::<String>(),
+            );
+        options.push(option);
+    }
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title("⚖️ Disputes")
+                .description(format!(
+                    "There are {} open disputes. The tournament cannot move on until they are resolved.\nSelect a dispute to review it.",
+                    disputes.len()
+                ))
+                .color(0xFFFF00)
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_select_menu(|m| {
+                    m.custom_id("dispute")
+                        .placeholder("Select a dispute")
+                        .options(|o| o.set_options(options))
+                })
+            })
+        })
+    })
+    .await?;
+    let mut selected: Option<Document> = None;
+    let resp = msg.clone().into_message().await?;
+    let mut cic = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(TIMEOUT))
+        .build();
+    while let Some(mci) = &cic.next().await {
+        let resolution = match mci.data.custom_id.as_str() {
+            "dispute" => {
+                mci.defer(&ctx.http()).await?;
+                selected = disputes
+                    .iter()
+                    .find(|dispute| {
+                        dispute
+                            .get_object_id("_id")
+                            .is_ok_and(|id| id.to_hex() == mci.data.values[0])
+                    })
+                    .cloned();
+                if let Some(dispute) = &selected {
+                    display_dispute(ctx, msg, dispute).await?;
+                }
+                continue;
+            }
+            "uphold" => Resolution::Uphold,
+            "overturn" => Resolution::Overturn,
+            "replay" => Resolution::Replay,
+            _ => continue,
+        };
+        let Some(dispute) = &selected else {
+            continue;
+        };
+        let Some(ResolutionNote { note }) = poise::execute_modal_on_component_interaction::<
+            ResolutionNote,
+        >(ctx, mci.clone(), None, None)
+        .await?
+        else {
+            continue;
+        };
+        let note = note.unwrap_or_default().trim().to_string();
+        return resolve(ctx, msg, region, dispute, resolution, &note).await;
+    }
+    Ok(())
+}
+
+async fn display_dispute(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    dispute: &Document,
+) -> Result<(), Error> {
+    let evidence: Vec<String> = dispute
+        .get_array("evidence")
+        .map(|links| {
+            links
+                .iter()
+                .filter_map(|link| link.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title("⚖️ Dispute")
+                .description(format!(
+                    r#"<@{}> ({}) disputes the result of {} match {}.
+**Reason**: {}
+**Evidence**: {}
+
+✅ Uphold: the result stands.
+🔄 Overturn: <@{}> wins the match instead.
+🔁 Replay: the match is played again."#,
+                    dispute.get_str("opened_by").unwrap_or(""),
+                    dispute.get_str("tag").unwrap_or(""),
+                    dispute.get_str("round").unwrap_or(""),
+                    dispute.get_i32("match_id").unwrap_or(0),
+                    dispute.get_str("reason").unwrap_or(""),
+                    if evidence.is_empty() {
+                        "None".to_string()
+                    } else {
+                        evidence.join("\n")
+                    },
+                    dispute.get_str("opened_by").unwrap_or("")
+                ))
+                .color(0xFFFF00);
+            if let Some(image) = evidence.first() {
+                e.image(image);
+            }
+            e
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| {
+                    b.custom_id("uphold")
+                        .label("Uphold")
+                        .style(ButtonStyle::Success)
+                })
+                .create_button(|b| {
+                    b.custom_id("overturn")
+                        .label("Overturn")
+                        .style(ButtonStyle::Danger)
+                })
+                .create_button(|b| {
+                    b.custom_id("replay")
+                        .label("Replay")
+                        .style(ButtonStyle::Primary)
+                })
+            })
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+async fn resolve(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+    dispute: &Document,
+    resolution: Resolution,
+    note: &str,
+) -> Result<(), Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let round = dispute.get_str("round")?;
+    let match_id = dispute.get_i32("match_id")?;
+    let outcome = match resolution {
+        Resolution::Uphold => Ok(String::new()),
+        Resolution::Overturn => {
+            let players: Vec<Document> = database
+                .collection::<Document>(round)
+                .find(doc! {"match_id": match_id}, None)
+                .await?
+                .try_collect()
+                .await?;
+            let opened_by = dispute.get_str("opened_by")?;
+            let disputer = players
+                .iter()
+                .find(|player| player.get_str("discord_id") == Ok(opened_by));
+            let opponent = players
+                .iter()
+                .find(|player| player.get_str("discord_id") != Ok(opened_by));
+            match (disputer, opponent) {
+                (Some(disputer), Some(opponent)) => {
+                    reverse_result(ctx, region, round, match_id, disputer, opponent)
+                        .await?
+                        .map(|reopened| {
+                            if reopened.is_empty() {
+                                String::new()
+                            } else {
+                                format!(
+                                    "\nThese matches have to be played again:\n- {}",
+                                    reopened.join("\n- ")
+                                )
+                            }
+                        })
+                }
+                _ => Err(format!(
+                    "Match {match_id} of {round} no longer has both players!"
+                )),
+            }
+        }
+        Resolution::Replay => replay_match(ctx, region, round, match_id)
+            .await?
+            .map(|_| "\nBoth players have to play the match again.".to_string()),
+    };
+    let details = match outcome {
+        Ok(details) => details,
+        Err(reason) => {
+            return prompt(
+                ctx,
+                msg,
+                "Unable to resolve the dispute!",
+                reason,
+                None,
+                Some(0xFF0000),
+            )
+            .await;
+        }
+    };
+    close_dispute(database, dispute, resolution, note, ctx.author().id.0).await?;
+    audit(
+        ctx,
+        Some(region),
+        "dispute.resolve",
+        doc! {"round": round, "match_id": match_id, "opened_by": dispute.get("opened_by")},
+        doc! {"resolution": resolution.status(), "note": note},
+    )
+    .await?;
+    let log = Log::new(ctx, region, LogType::Dispute).await?;
+    let log_msg = log
+        .send_dispute_resolution_log(dispute, resolution.status(), note)
+        .await?;
+    prompt(
+        ctx,
+        msg,
+        "Dispute resolved!",
+        format!(
+            "The dispute of <@{}> has been {}. The log has been recorded [here]({}).{details}",
+            dispute.get_str("opened_by").unwrap_or(""),
+            resolution.status(),
+            log_msg.link()
+        ),
+        None,
+        Some(0x50C878),
+    )
+    .await
+}
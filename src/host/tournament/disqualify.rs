@@ -1,9 +1,11 @@
+use crate::database::audit::audit;
 use crate::database::battle::force_lose;
-use crate::database::config::get_config;
 use crate::database::find::{
-    find_enemy_by_match_id_and_self_tag, find_enemy_of_mannequin, find_player_by_discord_id,
-    find_round_from_config,
+    find_enemy_by_match_id_and_self_tag, find_enemy_of_mannequin, find_player_in_current_rounds,
 };
+use crate::database::models::Player;
+use crate::database::repository::Repository;
+use crate::database::team::remove_member_roles;
 use crate::database::update::{set_ready, update_result};
 use crate::discord::log::{Log, LogType};
 use crate::discord::prompt::prompt;
@@ -11,7 +13,7 @@ use crate::discord::role::remove_role;
 use crate::{Context, Error};
 use dbc_bot::Region;
 use futures::StreamExt;
-use mongodb::bson::{doc, Document};
+use mongodb::bson::{doc, Bson, Document};
 use mongodb::{Collection, Cursor};
 use poise::serenity_prelude::UserId;
 use poise::ReplyHandle;
@@ -62,7 +64,7 @@ pub async fn disqualify_players(
     .await?;
     let mut form = Form::default();
     let mut player = Document::new();
-    let round = find_round_from_config(&get_config(ctx, region).await);
+    let mut round = String::new();
 
     disqualify_id(ctx, msg).await?;
     let resp = msg.clone().into_message().await?;
@@ -74,15 +76,15 @@ pub async fn disqualify_players(
         match mci.data.custom_id.as_str() {
             "open_modal" => {
                 form = create_disqualify_modal(ctx, mci.clone()).await?;
-                match find_player_by_discord_id(
+                match find_player_in_current_rounds(
                     ctx,
                     region,
                     form.user_id.parse::<u64>().unwrap_or(0),
-                    &round,
                 )
                 .await
                 {
-                    Ok(Some(p)) => {
+                    Ok(Some((r, p))) => {
+                        round = r;
                         player = p;
                         display_confirmation(ctx, msg, &player).await?
                     }
@@ -158,6 +160,7 @@ async fn display_confirmation(
     msg: &ReplyHandle<'_>,
     player: &Document,
 ) -> Result<(), Error> {
+    let player = Player::from_document(player)?;
     msg.edit(*ctx, |s| {
         s.components(|c| {
             c.create_action_row(|a| {
@@ -180,18 +183,10 @@ async fn display_confirmation(
                     "**Please confirm this is the player that you would like to disqualify.**",
                 )
                 .fields(vec![
-                    (
-                        "Mention",
-                        format!("<@{}>", player.get_str("discord_id").unwrap()),
-                        true,
-                    ),
-                    (
-                        "Region",
-                        player.get_str("region").unwrap().to_string(),
-                        true,
-                    ),
-                    ("Name", player.get_str("name").unwrap().to_string(), true),
-                    ("Tag", player.get_str("tag").unwrap().to_string(), true),
+                    ("Mention", format!("<@{}>", player.discord_id), true),
+                    ("Region", player.region.clone(), true),
+                    ("Name", player.name.clone(), true),
+                    ("Tag", player.tag.clone(), true),
                 ])
                 .timestamp(ctx.created_at())
         })
@@ -238,6 +233,35 @@ async fn post_confirm(
         Ok(_) => {
             let log = Log::new(ctx, region, LogType::Disqualify).await?;
             let log_msg = log.send_disqualify_log(form, round).await?;
+            // Kept so that the log can be marked as reverted if the player is reinstated
+            ctx.data()
+                .database
+                .regional_databases
+                .get(region)
+                .unwrap()
+                .update_in_round(
+                    round,
+                    doc! {"_id": player.get_object_id("_id")?},
+                    doc! {"$set": {"disqualify_log": {
+                        "channel": log_msg.channel_id.to_string(),
+                        "message": log_msg.id.to_string(),
+                    }}},
+                )
+                .await?;
+            audit(
+                ctx,
+                Some(region),
+                "player.disqualify",
+                Bson::Null,
+                doc! {
+                    "discord_id": &form.user_id,
+                    "tag": player.get("tag"),
+                    "round": round,
+                    "match_id": player.get("match_id"),
+                    "reason": &form.reason,
+                },
+            )
+            .await?;
             let enemy = find_enemy_by_match_id_and_self_tag(
                 ctx,
                 region,
@@ -277,6 +301,7 @@ Opponent:
             let user = UserId(form.user_id.parse::<u64>().unwrap())
                 .to_user(ctx.http())
                 .await?;
+            remove_member_roles(ctx, player, region).await?;
             let more_info = if remove_role(ctx, &user, region).await.is_err() {
                 "⚠️ The user is removed from the tournament, but it is unable to remove the role from this player! The player may not be present in the server."
             } else {
@@ -353,7 +378,7 @@ pub async fn mass_disqualify_wrapper(
     let log = Log::new(ctx, region, LogType::DisqualifyInactives).await?;
     let mut players = HashSet::new();
 
-    let perc = counts / 10;
+    let perc = (counts / 10).max(1);
     let mut index = 0;
     while let Some(player) = battles_handle.next().await {
         match player {
@@ -396,7 +421,7 @@ pub async fn mass_disqualify_wrapper(
             }
         }
         if index % perc == 0 {
-            let progress = index / perc;
+            let progress = (index / perc).min(10);
             prompt(
                 ctx,
                 msg,
@@ -420,9 +445,16 @@ Progress bar: {}
         }
         index += 1;
     }
-    let m = log
-        .disqualify_inactive_logs(players.into_iter().collect())
-        .await?;
+    let players: Vec<String> = players.into_iter().collect();
+    audit(
+        ctx,
+        Some(region),
+        "player.disqualify_inactive",
+        Bson::Null,
+        players.clone(),
+    )
+    .await?;
+    let m = log.disqualify_inactive_logs(players).await?;
     prompt(
         ctx,
         msg,
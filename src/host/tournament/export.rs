@@ -0,0 +1,61 @@
+//! Host command to download the bracket as an SVG image and an HTML page.
+use crate::bracket_tournament::bracket_update::collect_bracket;
+use crate::database::config::get_config;
+use crate::discord::prompt::prompt;
+use crate::visual::export::{bracket_html, bracket_svg};
+use crate::{Context, Error};
+use dbc_bot::Region;
+use poise::serenity_prelude::AttachmentType;
+use poise::ReplyHandle;
+
+pub async fn export_bracket(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+) -> Result<(), Error> {
+    prompt(
+        ctx,
+        msg,
+        "Exporting the bracket...",
+        "<a:loading:1187839622680690689> Please wait while the bracket is being drawn...",
+        None,
+        Some(0xFFFF00),
+    )
+    .await?;
+    let config = get_config(ctx, region).await;
+    let bracket = collect_bracket(ctx, region, &config).await?;
+    let mut files = vec![(
+        format!("Tournament_bracket_{}.svg", region.short()),
+        bracket_svg(&bracket, false),
+    )];
+    if bracket.finals > 0 {
+        files.push((
+            format!("Tournament_losers_bracket_{}.svg", region.short()),
+            bracket_svg(&bracket, true),
+        ));
+    }
+    files.push((
+        format!("Tournament_bracket_{}.html", region.short()),
+        bracket_html(region, &bracket),
+    ));
+    msg.edit(*ctx, |m| {
+        m.embed(|e| {
+            e.title("Bracket exported!")
+                .description(
+                    "<:tick:1187839626338111600> The bracket is ready to download. \
+The SVG scales to any size, and the HTML page shows the details of a match when hovering it.",
+                )
+                .color(0x50C878)
+        })
+        .components(|c| c);
+        for (filename, content) in files {
+            m.attachment(AttachmentType::Bytes {
+                data: content.into_bytes().into(),
+                filename,
+            });
+        }
+        m
+    })
+    .await?;
+    Ok(())
+}
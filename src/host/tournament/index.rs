@@ -1,5 +1,5 @@
 use crate::database::config::get_config;
-use crate::database::find::find_round_from_config;
+use crate::database::find::find_rounds_from_config;
 use crate::database::stat::count_registers;
 use crate::discord::checks::is_mod;
 use crate::Context;
@@ -9,10 +9,16 @@ use futures::StreamExt;
 use poise::serenity_prelude::ReactionType;
 use poise::ReplyHandle;
 
+use super::correction::override_result;
+use super::disputes::resolve_disputes;
 use super::disqualify::disqualify_players;
+use super::export::export_bracket;
 use super::next::display_next_round;
+use super::reinstate::reinstate_players;
+use super::repair::repair_matches;
 use super::reset::reset_wrapper as reset;
 use super::setup::starter_wrapper;
+use super::standings::{display_placements, display_standings};
 use super::statistics::statistics_information;
 const TIMEOUT: u64 = 300;
 
@@ -49,6 +55,34 @@ pub async fn tournament_mod_panel(
                 mci.defer(&ctx.http()).await?;
                 return reset(ctx, msg, region).await;
             }
+            "standings" => {
+                mci.defer(&ctx.http()).await?;
+                return display_standings(ctx, msg, region).await;
+            }
+            "override" => {
+                mci.defer(&ctx.http()).await?;
+                return override_result(ctx, msg, region).await;
+            }
+            "placements" => {
+                mci.defer(&ctx.http()).await?;
+                return display_placements(ctx, msg, region).await;
+            }
+            "disputes" => {
+                mci.defer(&ctx.http()).await?;
+                return resolve_disputes(ctx, msg, region).await;
+            }
+            "repair" => {
+                mci.defer(&ctx.http()).await?;
+                return repair_matches(ctx, msg, region).await;
+            }
+            "export" => {
+                mci.defer(&ctx.http()).await?;
+                return export_bracket(ctx, msg, region).await;
+            }
+            "reinstate" => {
+                mci.defer(&ctx.http()).await?;
+                return reinstate_players(ctx, msg, region).await;
+            }
             _ => {}
         }
     }
@@ -61,7 +95,7 @@ async fn display_start_menu(
     msg: &ReplyHandle<'_>,
     region: &Region,
 ) -> Result<(), Error> {
-    let round = find_round_from_config(&get_config(ctx, region).await);
+    let round = find_rounds_from_config(&get_config(ctx, region).await).join(" & ");
     let valid = tournament_available(ctx, region).await || prerequisite(ctx, region).await;
     let menu = r#"Below are available options:
 ▶️: Start
@@ -75,10 +109,25 @@ async fn display_start_menu(
 🔨: Disqualify
 - Lets you disqualify players from the tournament.
 - This will replace a disqualified player with a mannequin.
+♻️: Reinstate
+- Lets you undo a disqualification made by mistake in the current round.
 🚩: Reset
 - Lets you reset the tournament.
 - This will reset the tournament to the initial state.
 - This is a dangerous action, use with caution.
+📋: Standings
+- Lets you see the group or Swiss standings.
+✏️: Override
+- Lets you set the winner of a match, with a note that is logged.
+- Later rounds are corrected if the wrong player already advanced.
+🏅: Placements
+- Lets you see the final placements of the last finished tournament.
+⚖️: Disputes
+- Lets you review the results players have disputed, and uphold, overturn or replay them.
+🩹: Repair
+- Finds matches whose result was only partly saved, and finishes recording them.
+📤: Export
+- Lets you download the bracket as an SVG image and an HTML page with the details of every match.
     "#;
     match round.as_str() {
         "Players" => {
@@ -159,6 +208,52 @@ async fn display_start_buttons(
                         .disabled(true)
                 })
             })
+            .create_action_row(|row| {
+                row.create_button(|b| {
+                    b.custom_id("standings")
+                        .style(poise::serenity_prelude::ButtonStyle::Secondary)
+                        .emoji(ReactionType::Unicode("📋".to_string()))
+                        .disabled(!next)
+                })
+                .create_button(|b| {
+                    b.custom_id("override")
+                        .style(poise::serenity_prelude::ButtonStyle::Danger)
+                        .emoji(ReactionType::Unicode("✏️".to_string()))
+                        .disabled(!next)
+                })
+                .create_button(|b| {
+                    b.custom_id("placements")
+                        .style(poise::serenity_prelude::ButtonStyle::Secondary)
+                        .emoji(ReactionType::Unicode("🏅".to_string()))
+                        .disabled(false)
+                })
+                .create_button(|b| {
+                    b.custom_id("disputes")
+                        .style(poise::serenity_prelude::ButtonStyle::Danger)
+                        .emoji(ReactionType::Unicode("⚖️".to_string()))
+                        .disabled(!next)
+                })
+                .create_button(|b| {
+                    b.custom_id("repair")
+                        .style(poise::serenity_prelude::ButtonStyle::Secondary)
+                        .emoji(ReactionType::Unicode("🩹".to_string()))
+                        .disabled(!next)
+                })
+            })
+            .create_action_row(|row| {
+                row.create_button(|b| {
+                    b.custom_id("export")
+                        .style(poise::serenity_prelude::ButtonStyle::Secondary)
+                        .emoji(ReactionType::Unicode("📤".to_string()))
+                        .disabled(!next)
+                })
+                .create_button(|b| {
+                    b.custom_id("reinstate")
+                        .style(poise::serenity_prelude::ButtonStyle::Danger)
+                        .emoji(ReactionType::Unicode("♻️".to_string()))
+                        .disabled(!next)
+                })
+            })
         })
     })
     .await?;
@@ -1,7 +1,13 @@
+pub mod correction;
+pub mod disputes;
 pub mod disqualify;
 pub mod download;
+pub mod export;
 pub mod index;
 pub mod next;
+pub mod reinstate;
+pub mod repair;
 pub mod reset;
 pub mod setup;
+pub mod standings;
 pub mod statistics;
@@ -1,9 +1,19 @@
+use crate::bracket_tournament::group_stage::seed_knockout;
+use crate::bracket_tournament::placements::{
+    has_third_place_match, is_final_stage, post_placements, record_placements,
+    setup_third_place_match,
+};
+use crate::bracket_tournament::swiss::generate_round;
+use crate::database::audit::audit;
 use crate::database::config::get_config;
-use crate::database::find::{find_all_false_battles, find_round_from_config};
+use crate::database::deadline::deadline;
+use crate::database::dispute::OPEN;
+use crate::database::find::{find_all_false_battles, find_rounds_from_config};
 use crate::database::update::update_round_config;
 use crate::discord::checks::is_mod;
+use crate::discord::prompt::prompt;
 use crate::{Context, Error};
-use dbc_bot::Region;
+use dbc_bot::{Format, Region};
 use futures::stream::StreamExt;
 use mongodb::bson::{self, Document};
 use mongodb::Cursor;
@@ -47,13 +57,53 @@ pub async fn display_next_round(
     while let Some(mci) = &cic.next().await {
         if mci.data.custom_id.as_str() == "continue" {
             mci.defer(&ctx.http()).await?;
-            update_round_config(ctx, region).await?;
-            let config = crate::database::config::get_config(ctx, region).await;
-            let round = config.get_i32("round").unwrap();
+            let database = ctx.data().database.regional_databases.get(region).unwrap();
+            let config = get_config(ctx, region).await;
+            let round = config.get_i32("round")?;
+            if Format::from_config(&config) == Format::Swiss {
+                if round >= config.get_i32("total")? {
+                    if let Some(placements) = record_placements(database, true).await? {
+                        post_placements(ctx, database, region, &placements).await?;
+                    }
+                    return prompt(
+                        ctx,
+                        msg,
+                        "All Swiss rounds are finished!",
+                        "The final standings can be viewed from the 📋 Standings button of the tournament menu.",
+                        None,
+                        Some(0xFFFF00),
+                    )
+                    .await;
+                }
+                info!("Pairing Swiss round {}.", round + 1);
+                generate_round(database, region, round + 1).await?;
+            }
+            if Format::from_config(&config) == Format::GroupStage
+                && round == config.get_i32("group_rounds")?
+            {
+                info!("Group stage is finished, seeding the bracket.");
+                seed_knockout(ctx, region).await?;
+            }
+            update_round_config(database).await?;
+            let previous = find_rounds_from_config(&config).join(" & ");
+            let config = get_config(ctx, region).await;
+            if has_third_place_match(&config) && is_final_stage(&config) {
+                info!("Semifinals are finished, setting up the third-place match.");
+                setup_third_place_match(ctx, region).await?;
+            }
+            let round = find_rounds_from_config(&config).join(" & ");
+            audit(
+                ctx,
+                Some(region),
+                "tournament.next_round",
+                previous,
+                round.clone(),
+            )
+            .await?;
             msg.edit(*ctx, |m| {
                 m.embed(|e| {
                     e.title("Next Round is set!")
-                        .description(format!("Now the tournament is at round {round}!"))
+                        .description(format!("Now the tournament is at {round}!"))
                 })
             })
             .await?;
@@ -74,10 +124,14 @@ pub async fn display_false_battles(result: &mut Cursor<Document>) -> Vec<String>
             }
         }
     }
-    let mut match_groups: HashMap<i32, Vec<&Document>> = HashMap::new();
+    let mut match_groups: HashMap<(&str, i32), Vec<&Document>> = HashMap::new();
     for player in &players {
         if let Some(match_id) = player.get("match_id").and_then(bson::Bson::as_i32) {
-            match_groups.entry(match_id).or_default().push(player);
+            let round = player.get_str("round").unwrap_or("");
+            match_groups
+                .entry((round, match_id))
+                .or_default()
+                .push(player);
         }
     }
     let mut battles: Vec<String> = match_groups
@@ -94,10 +148,16 @@ pub async fn display_false_battles(result: &mut Cursor<Document>) -> Vec<String>
                 let tag2 = player2.get_str("tag").unwrap_or("").to_string();
                 format!(
                     r#"**Some battles are not finished!**
-# Match {} 
+# {} - Match {}{} 
 <@{}> - <@{}>
 {}({}) - {}({})"#,
+                    player1.get_str("round").unwrap_or("Round"),
                     player1.get_i32("match_id").unwrap(),
+                    if player1.get_str("dispute") == Ok(OPEN) {
+                        " (disputed)"
+                    } else {
+                        ""
+                    },
                     dis1,
                     dis2,
                     name1,
@@ -123,7 +183,15 @@ pub async fn paginate(
     false_battles: &mut Cursor<Document>,
 ) -> Result<(), Error> {
     let moderator = is_mod(*ctx).await?;
-    let pages = display_false_battles(false_battles).await;
+    let mut pages = display_false_battles(false_battles).await;
+    if let Some(deadline) = deadline(&get_config(ctx, region).await) {
+        let timestamp = deadline.timestamp_millis() / 1000;
+        for page in pages.iter_mut() {
+            page.push_str(&format!(
+                "\n\nUnfinished matches are resolved at the deadline, <t:{timestamp}:F> (<t:{timestamp}:R>)."
+            ));
+        }
+    }
     // Define some unique identifiers for the navigation buttons
     let ctx_id = ctx.id();
     let prev_button_id = format!("{}prev", ctx_id);
@@ -186,9 +254,11 @@ pub async fn paginate(
             return compact(ctx, msg, region).await;
         } else if press.data.custom_id == disqualify_all_id {
             press.defer(ctx.http()).await?;
-            let round = find_round_from_config(&get_config(ctx, region).await);
-            return disqualify::mass_disqualify_wrapper(ctx, msg, region, &round, false_battles)
-                .await;
+            for round in find_rounds_from_config(&get_config(ctx, region).await) {
+                disqualify::mass_disqualify_wrapper(ctx, msg, region, &round, false_battles)
+                    .await?;
+            }
+            return Ok(());
         } else {
             // This is an unrelated button interaction
             continue;
@@ -0,0 +1,274 @@
+//! Host undo of a disqualification.
+//!
+//! A player disqualified by mistake gets their match back: the opponent is taken out of the matches
+//! the forfeit sent them to, the player's regional role is restored and the original log is marked
+//! as reverted.
+use super::correction::reinstate_player;
+use crate::database::audit::audit;
+use crate::database::config::get_config;
+use crate::database::find::find_rounds_from_config;
+use crate::discord::log::{Log, LogType};
+use crate::discord::prompt::prompt;
+use crate::{Context, Error};
+use dbc_bot::Region;
+use futures::StreamExt;
+use mongodb::bson::{doc, Document};
+use poise::serenity_prelude::{ButtonStyle, ChannelId, MessageId};
+use poise::ReplyHandle;
+use tracing::error;
+
+const TIMEOUT: u64 = 120;
+
+#[derive(Debug, poise::Modal)]
+#[name = "Reinstate Player"]
+struct ReinstateModal {
+    #[name = "User Id to be reinstated:"]
+    #[placeholder = "The user ID of the disqualified player"]
+    user_id: String,
+
+    #[name = "Note"]
+    #[placeholder = "Why the disqualification is undone. This is kept in the log"]
+    #[paragraph]
+    note: String,
+}
+
+pub async fn reinstate_players(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+) -> Result<(), Error> {
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title("♻️ Reinstate Player")
+                .description(
+                    r#"Undo the disqualification of a player in the current round.
+Their match is reopened, and their opponent is taken back out of the next round if they advanced.
+A note is required and the original disqualification log is marked as reverted."#,
+                )
+                .color(0xFFFF00)
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| b.custom_id("open_modal").label("Reinstate Player"))
+            })
+        })
+    })
+    .await?;
+    let mut found = None;
+    let resp = msg.clone().into_message().await?;
+    let mut cic = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(TIMEOUT))
+        .build();
+    while let Some(mci) = &cic.next().await {
+        match mci.data.custom_id.as_str() {
+            "open_modal" => {
+                let Some(form) = poise::execute_modal_on_component_interaction::<ReinstateModal>(
+                    ctx,
+                    mci.clone(),
+                    None,
+                    None,
+                )
+                .await?
+                else {
+                    continue;
+                };
+                let note = form.note.trim().to_string();
+                if note.is_empty() {
+                    return prompt(
+                        ctx,
+                        msg,
+                        "Unable to reinstate the player!",
+                        "Please explain why the disqualification is undone!",
+                        None,
+                        Some(0xFF0000),
+                    )
+                    .await;
+                }
+                match find_disqualified_player(ctx, region, form.user_id.trim()).await? {
+                    Some((round, player)) => {
+                        display_confirmation(ctx, msg, &round, &player, &note).await?;
+                        found = Some((round, player, note));
+                    }
+                    None => {
+                        return prompt(
+                            ctx,
+                            msg,
+                            "Not found",
+                            "No disqualified player found with the given user ID in the current round.",
+                            None,
+                            Some(0xFF0000),
+                        )
+                        .await;
+                    }
+                }
+            }
+            "confirm" => {
+                mci.defer(&ctx.http()).await?;
+                if let Some((round, player, note)) = &found {
+                    return post_confirm(ctx, msg, region, round, player, note).await;
+                }
+            }
+            "cancel" => {
+                mci.defer(&ctx.http()).await?;
+                return prompt(
+                    ctx,
+                    msg,
+                    "Reinstatement cancelled",
+                    "The player is still disqualified.",
+                    None,
+                    None,
+                )
+                .await;
+            }
+            _ => continue,
+        }
+    }
+    Ok(())
+}
+
+/// Finds the disqualified player of a user in the current rounds.
+async fn find_disqualified_player(
+    ctx: &Context<'_>,
+    region: &Region,
+    user_id: &str,
+) -> Result<Option<(String, Document)>, Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    for round in find_rounds_from_config(&get_config(ctx, region).await) {
+        if let Some(player) = database
+            .collection::<Document>(&round)
+            .find_one(
+                doc! {"discord_id": user_id, "reason": {"$exists": true}},
+                None,
+            )
+            .await?
+        {
+            return Ok(Some((round, player)));
+        }
+    }
+    Ok(None)
+}
+
+async fn display_confirmation(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    round: &str,
+    player: &Document,
+    note: &str,
+) -> Result<(), Error> {
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title("♻️ Reinstate Player - Confirmation")
+                .description(
+                    "**Please confirm this is the player that you would like to reinstate.**",
+                )
+                .fields(vec![
+                    (
+                        "Mention",
+                        format!("<@{}>", player.get_str("discord_id").unwrap_or("")),
+                        true,
+                    ),
+                    (
+                        "Name",
+                        player.get_str("name").unwrap_or("").to_string(),
+                        true,
+                    ),
+                    ("Tag", player.get_str("tag").unwrap_or("").to_string(), true),
+                    ("Round", round.to_string(), true),
+                    (
+                        "Match",
+                        player.get_i32("match_id").unwrap_or(0).to_string(),
+                        true,
+                    ),
+                    (
+                        "Disqualified for",
+                        player.get_str("reason").unwrap_or("").to_string(),
+                        false,
+                    ),
+                    ("Note", note.to_string(), false),
+                ])
+                .color(0xFFFF00)
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| {
+                    b.label("Confirm")
+                        .style(ButtonStyle::Danger)
+                        .custom_id("confirm")
+                })
+                .create_button(|b| {
+                    b.label("Cancel")
+                        .style(ButtonStyle::Primary)
+                        .custom_id("cancel")
+                })
+            })
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+async fn post_confirm(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+    round: &str,
+    player: &Document,
+    note: &str,
+) -> Result<(), Error> {
+    if let Err(reason) = reinstate_player(ctx, region, round, player).await? {
+        return prompt(
+            ctx,
+            msg,
+            "Unable to reinstate the player!",
+            reason,
+            None,
+            Some(0xFF0000),
+        )
+        .await;
+    }
+    let user_id = player.get_str("discord_id").unwrap_or("");
+    audit(
+        ctx,
+        Some(region),
+        "player.reinstate",
+        doc! {"round": round, "match_id": player.get("match_id"), "reason": player.get("reason")},
+        doc! {"discord_id": user_id, "note": note},
+    )
+    .await?;
+    let log = Log::new(ctx, region, LogType::Reinstate).await?;
+    let original = player.get_document("disqualify_log").ok().and_then(|log| {
+        Some((
+            ChannelId(log.get_str("channel").ok()?.parse().ok()?),
+            MessageId(log.get_str("message").ok()?.parse().ok()?),
+        ))
+    });
+    let log_msg = match original {
+        Some((channel_id, message_id)) => {
+            match log
+                .revert_disqualify_log(channel_id, message_id, note)
+                .await
+            {
+                Ok(log_msg) => log_msg,
+                Err(e) => {
+                    error!("{e}");
+                    log.send_reinstate_log(user_id, round, note).await?
+                }
+            }
+        }
+        None => log.send_reinstate_log(user_id, round, note).await?,
+    };
+    prompt(
+        ctx,
+        msg,
+        "Player reinstated!",
+        format!(
+            "<@{user_id}> is back in {round} match {}, and their role has been restored. The log has been updated [here]({}).\nPlease let both players know that the match has to be played.",
+            player.get_i32("match_id").unwrap_or(0),
+            log_msg.link()
+        ),
+        None,
+        Some(0x50C878),
+    )
+    .await
+}
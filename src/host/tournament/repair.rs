@@ -0,0 +1,54 @@
+//! Host command to finish recording matches whose result was only partly saved.
+use crate::database::audit::audit;
+use crate::database::repair::repair_results;
+use crate::discord::prompt::prompt;
+use crate::{Context, Error};
+use dbc_bot::Region;
+use mongodb::bson::Bson;
+use poise::ReplyHandle;
+
+pub async fn repair_matches(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+) -> Result<(), Error> {
+    prompt(
+        ctx,
+        msg,
+        "Checking matches...",
+        "Please wait while every match of the current rounds is checked...",
+        None,
+        Some(0xFFFF00),
+    )
+    .await?;
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let repairs = repair_results(database, region).await?;
+    if repairs.is_empty() {
+        return prompt(
+            ctx,
+            msg,
+            "Nothing to repair",
+            "Every match of the current rounds has been recorded completely.",
+            None,
+            Some(0x50C878),
+        )
+        .await;
+    }
+    audit(
+        ctx,
+        Some(region),
+        "match.repair",
+        Bson::Null,
+        repairs.clone(),
+    )
+    .await?;
+    prompt(
+        ctx,
+        msg,
+        "Matches repaired!",
+        format!("- {}", repairs.join("\n- ")),
+        None,
+        Some(0x50C878),
+    )
+    .await
+}
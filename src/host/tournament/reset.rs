@@ -1,12 +1,17 @@
 use crate::{
+    database::audit::audit,
     database::config::{get_config, reset_config},
+    database::find::is_round_collection,
+    database::models::MatchSlot,
+    database::repository::RESULTS,
+    database::waitlist::WAITLIST,
     discord::prompt::prompt,
     Context, Error,
 };
 use dbc_bot::Region;
 use futures::stream::StreamExt;
 use mongodb::{
-    bson::{doc, Document},
+    bson::{doc, Bson, Document},
     Collection, Database,
 };
 use poise::ReplyHandle;
@@ -128,6 +133,7 @@ async fn reset(ctx: &Context<'_>, msg: &ReplyHandle<'_>, region: &Region) -> Res
     )
     .await?;
     clear_all_players(&collection).await;
+    audit(ctx, Some(region), "tournament.reset", config, Bson::Null).await?;
     prompt(
         ctx,
         msg,
@@ -154,7 +160,11 @@ async fn reset(ctx: &Context<'_>, msg: &ReplyHandle<'_>, region: &Region) -> Res
 async fn clear_rounds_and_reset_config(database: &Database) -> Result<(), Error> {
     let collections = database.list_collection_names(None).await?;
     for collection in collections {
-        if collection.starts_with("Round") {
+        if is_round_collection(&collection)
+            || collection == "Disputes"
+            || collection == RESULTS
+            || collection == WAITLIST
+        {
             database
                 .collection::<Document>(&collection)
                 .drop(None)
@@ -182,19 +192,27 @@ async fn remove_regional_roles(
 ) -> Result<(), Error> {
     let mut cursor = collection.find(doc! {}, None).await?;
     while let Some(player) = cursor.next().await {
-        if player.clone()?.get_str("name").unwrap() == "Mannequin" {
-            continue;
-        }
-        let id = player?
-            .get_str("discord_id")
-            .unwrap()
-            .parse::<u64>()
-            .unwrap();
-        let mut member = match ctx.guild().unwrap().member(ctx.http(), id).await {
-            Ok(m) => m,
-            Err(_) => continue,
+        let ids = match MatchSlot::from_document(&player?)? {
+            MatchSlot::Player(player) => {
+                let mut ids = vec![player.user_id()?];
+                // The members of a team hold the regional role as well
+                ids.extend(
+                    player
+                        .members
+                        .iter()
+                        .filter_map(|member| member.discord_id.parse::<u64>().ok()),
+                );
+                ids
+            }
+            MatchSlot::Mannequin(_) => continue,
         };
-        member.remove_role(ctx.http(), role_id).await?;
+        for id in ids {
+            let mut member = match ctx.guild().unwrap().member(ctx.http(), id).await {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            member.remove_role(ctx.http(), role_id).await?;
+        }
     }
     Ok(())
 }
@@ -1,196 +1,206 @@
+use crate::bracket_tournament::group_stage::setup_group_stage;
+use crate::bracket_tournament::swiss::generate_round;
 use crate::database::add::insert_mannequins;
-use crate::database::config::get_config;
-use crate::database::stat::count_registers;
+use crate::database::audit::audit;
+use crate::database::check_in::remove_absent_players;
+use crate::database::config::find_config;
+use crate::database::find::{find_rounds_from_config, is_round_collection};
+use crate::database::stat::count_players;
+use crate::database::team::remove_unconfirmed_teams;
 use crate::database::update::{
-    resetting_tournament_config, setting_tournament_config, update_round_1, update_round_config,
+    resetting_tournament_config, setting_tournament_config, update_group_rounds, update_round_1,
+    update_round_config, update_total_rounds,
 };
 use crate::discord::prompt::prompt;
+use crate::players::registration::waitlist::promote_waitlisted;
 use crate::{Context, Error};
-use dbc_bot::Region;
+use dbc_bot::{Format, Region};
 use mongodb::bson::{doc, Bson::Null, Document};
-use mongodb::Collection;
+use mongodb::{Collection, Database};
+use poise::serenity_prelude as serenity;
 use poise::ReplyHandle;
 use tracing::error;
 const MINIMUM_PLAYERS: i32 = 3; // The minimum amount of players required to start a tournament
 
+const PROMPTS: [&str; 14] = [
+    "<:tick:1187839626338111600> Closed! Registration is now closed!", //0
+    "<:tick:1187839626338111600> Opened! Tournament is opened!",       //1
+    "<a:loading:1187839622680690689> Counting players...",             //2
+    "<:tick:1187839626338111600> Counted! Players:  ",                 //3
+    "<:info:1187845402163167363> Byes: ",                              //4
+    "<:tick:1187839626338111600> Calculated! Rounds: ",                //5
+    "<:tick:1187839626338111600> Done! First round is set!",           //6
+    "<:tick:1187839626338111600> Groups are drawn! Matchdays: ",       //7
+    "<:tick:1187839626338111600> First Swiss round is paired! Rounds: ", //8
+    "<:info:1187845402163167363> Teams removed without a confirmed roster: ", //9
+    "<:sad:1187843167760949348> Not enough players to start the tournament! Aborting start!", //10
+    "<a:loading:1187839622680690689> Setting up first round",          //11
+    "<:info:1187845402163167363> Players removed for not checking in: ", //12
+    "<:info:1187845402163167363> Players promoted from the waitlist: ", //13
+];
+
+/// How the first round of a tournament was set up.
+pub enum Stage {
+    Bracket { rounds: u32, byes: i32 },
+    Groups { matchdays: i32 },
+    Swiss { rounds: i32 },
+}
+
+/// A tournament that has just started.
+pub struct Launch {
+    pub count: i32,
+    /// Teams removed because their captain never confirmed the roster.
+    pub unconfirmed: i32,
+    /// Players removed because they never checked in.
+    pub absent: i32,
+    /// Waitlisted players registered in the places freed by the removals.
+    pub promoted: i32,
+    pub stage: Stage,
+}
+
+impl Launch {
+    /// The steps of the start, one per line.
+    pub fn summary(&self) -> String {
+        let mut lines = vec![PROMPTS[0].to_string(), PROMPTS[1].to_string()];
+        if self.unconfirmed > 0 {
+            lines.push(format!("{}{}", PROMPTS[9], self.unconfirmed));
+        }
+        if self.absent > 0 {
+            lines.push(format!("{}{}", PROMPTS[12], self.absent));
+        }
+        if self.promoted > 0 {
+            lines.push(format!("{}{}", PROMPTS[13], self.promoted));
+        }
+        lines.push(format!("{}{}", PROMPTS[3], self.count));
+        match self.stage {
+            Stage::Swiss { rounds } => lines.push(format!("{}{rounds}", PROMPTS[8])),
+            Stage::Groups { matchdays } => lines.push(format!("{}{matchdays}", PROMPTS[7])),
+            Stage::Bracket { rounds, byes } => {
+                lines.push(format!("{}{rounds}", PROMPTS[5]));
+                lines.push(format!("{}{byes}", PROMPTS[4]));
+                lines.push(PROMPTS[6].to_string());
+            }
+        }
+        lines.join("\n")
+    }
+}
+
 pub async fn start_tournament(
     ctx: &Context<'_>,
     msg: &ReplyHandle<'_>,
     region: &Region,
-) -> Result<(), Error> {
-    let prompts = [
-        "<:tick:1187839626338111600> Closed! Registration is now closed!", //0
-        "<:tick:1187839626338111600> Opened! Tournament is opened!",       //1
-        "<a:loading:1187839622680690689> Counting players...",             //2
-        "<:tick:1187839626338111600> Counted! Players:  ",                 //3
-        "<:sad:1187843167760949348> Not enough players to start the tournament! Aborting start!", //4
-        "<:info:1187845402163167363> Byes: ", //5
-        "<a:loading:1187839622680690689> Calculating rounds...", //6
-        "<:tick:1187839626338111600> Calculated! Rounds: ", //7
-        "<a:loading:1187839622680690689> Setting up first round", //8
-        "<:tick:1187839626338111600> Done! First round is set!", //9
-        "<a:loading:1187839622680690689> Generating tournament bracket image...", //10
-        "<:tick:1187839626338111600> Done! Tournament bracket image generated!", //11
-        "<:sad:1187843167760949348> Tournament bracket image failed to generate!", //12
-    ];
-    msg.edit(*ctx, |s| {
-        s.embed(|e| {
-            e.title("Setting up tournament")
-                .description(format!("{}\n{}", prompts[0], prompts[1]))
-        })
-        .components(|c| c)
-    })
-    .await?;
-    msg.edit(*ctx, |s| {
-        s.embed(|e| {
-            e.title("Setting up tournament")
-                .description(format!("{}\n{}\n{}", prompts[0], prompts[1], prompts[2]))
-        })
-    })
-    .await?;
-    setting_tournament_config(ctx, region).await?;
-    let count = count_registers(ctx, region).await?;
-    if count < MINIMUM_PLAYERS {
-        msg.edit(*ctx, |s| {
-            s.embed(|e| {
-                e.title("Setting up tournament").description(format!(
-                    "{}\n{}\n{}\n{}{}\n{}",
-                    prompts[0], prompts[1], prompts[2], prompts[3], count, prompts[4]
-                ))
-            })
-        })
-        .await?;
-        Err("Not enough players to start the tournament!")?;
-    }
+) -> Result<Launch, Error> {
     msg.edit(*ctx, |s| {
         s.embed(|e| {
             e.title("Setting up tournament").description(format!(
-                "{}\n{}\n{}{}\n{}",
-                prompts[0], prompts[1], prompts[3], &count, prompts[6]
+                "{}\n{}\n{}\n{}",
+                PROMPTS[0], PROMPTS[1], PROMPTS[2], PROMPTS[11]
             ))
         })
+        .components(|c| c)
     })
     .await?;
-    let rounds = (count as f64).log2().ceil() as u32;
-    msg.edit(*ctx, |s| {
-        s.embed(|e| {
-            e.title("Setting up tournament").description(format!(
-                "{}\n{}\n{}{}\n{}{}",
-                prompts[0], prompts[1], prompts[3], &count, prompts[7], &rounds
-            ))
-        })
-    })
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let launch = start_or_revert(
+        ctx.serenity_context(),
+        database,
+        &ctx.data().database.general,
+        region,
+    )
     .await?;
-
-    let byes = 2_i32.pow(rounds) - count;
-    msg.edit(*ctx, |s| {
-        s.embed(|e| {
-            e.title("Setting up tournament").description(format!(
-                "{}\n{}\n{}{}\n{}{}\n{}{}",
-                prompts[0], prompts[1], prompts[3], &count, prompts[7], &rounds, prompts[5], &byes
-            ))
-        })
-    })
+    audit(
+        ctx,
+        Some(region),
+        "tournament.start",
+        "Players",
+        find_rounds_from_config(&find_config(database).await).join(" & "),
+    )
     .await?;
-    update_round_config(ctx, region).await?;
-    insert_mannequins(ctx, region, byes).await?;
     msg.edit(*ctx, |s| {
         s.embed(|e| {
-            e.title("Setting up tournament").description(format!(
-                "{}\n{}\n{}{}\n{}{}\n{}{}\n{}",
-                prompts[0],
-                prompts[1],
-                prompts[3],
-                &count,
-                prompts[7],
-                &rounds,
-                prompts[5],
-                &byes,
-                prompts[8]
-            ))
+            e.title("Setting up tournament")
+                .description(launch.summary())
         })
     })
     .await?;
-    update_round_1(ctx, region, rounds as i32).await?;
-    msg.edit(*ctx, |s| {
-        s.embed(|e| {
-            e.title("Setting up tournament").description(format!(
-                "{}\n{}\n{}{}\n{}{}\n{}{}\n{}",
-                prompts[0],
-                prompts[1],
-                prompts[3],
-                &count,
-                prompts[7],
-                &rounds,
-                prompts[5],
-                &byes,
-                prompts[9]
-            ))
-        })
+    Ok(launch)
+}
+
+/// Starts the tournament of a region, putting the players and the config back as they were if it
+/// cannot start.
+pub async fn start_or_revert(
+    ctx: &serenity::Context,
+    database: &Database,
+    general: &Database,
+    region: &Region,
+) -> Result<Launch, Error> {
+    let config = find_config(database).await;
+    match launch_tournament(ctx, database, general, region).await {
+        Ok(launch) => Ok(launch),
+        Err(e) => {
+            error!("{e}");
+            revert(database).await?;
+            resetting_tournament_config(database, Some(config)).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Closes the registration and sets up the first round of the tournament.
+async fn launch_tournament(
+    ctx: &serenity::Context,
+    database: &Database,
+    general: &Database,
+    region: &Region,
+) -> Result<Launch, Error> {
+    setting_tournament_config(database).await?;
+    let unconfirmed = remove_unconfirmed_teams(ctx, database).await?;
+    let absent = remove_absent_players(ctx, database).await?;
+    let promoted = promote_waitlisted(ctx, database, general, region)
+        .await?
+        .len() as i32;
+    let count = count_players(database).await?;
+    if count < MINIMUM_PLAYERS {
+        return Err(format!(
+            "{}\nThere are {count} players, at least {MINIMUM_PLAYERS} are needed.",
+            PROMPTS[10]
+        )
+        .into());
+    }
+    let config = find_config(database).await;
+    let stage = match Format::from_config(&config) {
+        Format::Swiss => {
+            let rounds = config.get_i32("swiss_rounds").unwrap_or(0);
+            if !(1..count).contains(&rounds) {
+                Err("Swiss rounds must be set, and fewer than the number of players!")?;
+            }
+            update_total_rounds(database, rounds).await?;
+            generate_round(database, region, 1).await?;
+            update_round_config(database).await?;
+            Stage::Swiss { rounds }
+        }
+        Format::GroupStage => {
+            let matchdays = setup_group_stage(database).await?;
+            update_group_rounds(database, matchdays).await?;
+            update_round_config(database).await?;
+            Stage::Groups { matchdays }
+        }
+        Format::SingleElimination | Format::DoubleElimination => {
+            let rounds = (count as f64).log2().ceil() as u32;
+            let byes = 2_i32.pow(rounds) - count;
+            update_round_config(database).await?;
+            insert_mannequins(database, region, byes).await?;
+            update_round_1(database, rounds as i32).await?;
+            Stage::Bracket { rounds, byes }
+        }
+    };
+    Ok(Launch {
+        count,
+        unconfirmed,
+        absent,
+        promoted,
+        stage,
     })
-    .await?;
-    // msg.edit(*ctx, |s| {
-    //     s.embed(|e| {
-    //         e.title("Setting up tournament").description(format!(
-    //             "{}\n{}\n{}{}\n{}{}\n{}{}\n{}\n{}",
-    //             prompts[0],
-    //             prompts[1],
-    //             prompts[3],
-    //             &count,
-    //             prompts[7],
-    //             &rounds,
-    //             prompts[5],
-    //             &byes,
-    //             prompts[9],
-    //             prompts[10]
-    //         ))
-    //     })
-    // })
-    // .await?;
-    // match update_bracket(ctx, Some(region)).await {
-    //     Ok(_) => {
-    //         msg.edit(*ctx, |s| {
-    //             s.embed(|e| {
-    //                 e.title("Setting up tournament").description(format!(
-    //                     "{}\n{}\n{}{}\n{}{}\n{}{}\n{}\n{}",
-    //                     prompts[0],
-    //                     prompts[1],
-    //                     prompts[3],
-    //                     &count,
-    //                     prompts[7],
-    //                     &rounds,
-    //                     prompts[5],
-    //                     &byes,
-    //                     prompts[9],
-    //                     prompts[11]
-    //                 ))
-    //             })
-    //         })
-    //         .await?;
-    //     }
-    //     Err(e) => {
-    //         msg.edit(*ctx, |s| {
-    //             s.embed(|e| {
-    //                 e.title("Setting up tournament").description(format!(
-    //                     "{}\n{}\n{}{}\n{}{}\n{}{}\n{}\n{}",
-    //                     prompts[0],
-    //                     prompts[1],
-    //                     prompts[3],
-    //                     &count,
-    //                     prompts[7],
-    //                     &rounds,
-    //                     prompts[5],
-    //                     &byes,
-    //                     prompts[9],
-    //                     prompts[12]
-    //                 ))
-    //             })
-    //         })
-    //         .await?;
-    //         return Err(e);
-    //     }
-    // }
-    Ok(())
 }
 
 pub async fn starter_wrapper(
@@ -198,7 +208,6 @@ pub async fn starter_wrapper(
     msg: &ReplyHandle<'_>,
     region: &Region,
 ) -> Result<(), Error> {
-    let config = get_config(ctx, region).await;
     match start_tournament(ctx, msg, region).await {
         Ok(_) => {
             prompt(
@@ -213,14 +222,11 @@ pub async fn starter_wrapper(
             Ok(())
         }
         Err(e) => {
-            error!("{e}");
-            revert(ctx, region).await?;
-            resetting_tournament_config(ctx, region, Some(config)).await?;
             prompt(
                 ctx,
                 msg,
                 "Failed to start tournament!",
-                "<:sad:1187843167760949348> Failed to start tournament!",
+                format!("<:sad:1187843167760949348> Failed to start tournament!\n{e}"),
                 None,
                 Some(0xFF0000),
             )
@@ -230,8 +236,7 @@ pub async fn starter_wrapper(
     }
 }
 
-async fn revert(ctx: &Context<'_>, region: &Region) -> Result<(), Error> {
-    let database = ctx.data().database.regional_databases.get(region).unwrap();
+async fn revert(database: &Database) -> Result<(), Error> {
     let collection: Collection<Document> = database.collection("Players");
     collection
         .delete_many(doc! {"discord_id": Null}, None)
@@ -239,12 +244,13 @@ async fn revert(ctx: &Context<'_>, region: &Region) -> Result<(), Error> {
     let update = doc! {
         "$set": {
             "match_id": Null,
+            "group": Null,
         }
     };
     collection.update_many(doc! {}, update, None).await?;
     let collections = database.list_collection_names(None).await?;
     for collection in collections {
-        if collection.starts_with("Round") {
+        if is_round_collection(&collection) {
             database
                 .collection::<Document>(&collection)
                 .drop(None)
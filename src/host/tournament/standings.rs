@@ -0,0 +1,173 @@
+use crate::bracket_tournament::group_stage::{group_standings, Standing};
+use crate::bracket_tournament::placements::{
+    format_placements, latest_placements, record_placements,
+};
+use crate::bracket_tournament::swiss::{swiss_standings, SwissRecord};
+use crate::database::config::get_config;
+use crate::discord::prompt::prompt;
+use crate::{Context, Error};
+use dbc_bot::{Format, Region};
+use mongodb::bson::Document;
+use poise::ReplyHandle;
+
+/// Rows shown in the Swiss standings, to stay within the embed description limit.
+const SWISS_ROWS: usize = 40;
+
+pub async fn display_standings(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+) -> Result<(), Error> {
+    let config = get_config(ctx, region).await;
+    match Format::from_config(&config) {
+        Format::GroupStage => display_group_standings(ctx, msg, region, &config).await,
+        Format::Swiss => display_swiss_standings(ctx, msg, region).await,
+        _ => {
+            prompt(
+                ctx,
+                msg,
+                "No standings available!",
+                "Standings are only kept for tournaments with a group stage or Swiss rounds.",
+                None,
+                Some(0xFF0000),
+            )
+            .await
+        }
+    }
+}
+
+/// Shows the placements of the last finished tournament of the region.
+pub async fn display_placements(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+) -> Result<(), Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    record_placements(database, false).await?;
+    let Some(placements) = latest_placements(database).await? else {
+        return prompt(
+            ctx,
+            msg,
+            "No placements yet!",
+            "Placements are recorded once the final of the tournament has been played.",
+            None,
+            Some(0xFFFF00),
+        )
+        .await;
+    };
+    msg.edit(*ctx, |s| {
+        s.components(|c| c).embed(|e| {
+            e.title(format!("Final placements - {}", region.full()))
+                .description(format_placements(&placements))
+                .color(0xFFFF00)
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+async fn display_group_standings(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+    config: &Document,
+) -> Result<(), Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let standings = group_standings(database).await?;
+    if standings.is_empty() {
+        return prompt(
+            ctx,
+            msg,
+            "No standings available!",
+            "The group stage has not started yet.",
+            None,
+            Some(0xFFFF00),
+        )
+        .await;
+    }
+    let advance = config.get_i32("group_advance").unwrap_or(0).max(0) as usize;
+    msg.edit(*ctx, |s| {
+        s.components(|c| c).embed(|e| {
+            e.title(format!("Group standings - {}", region.full()))
+                .description(format!(
+                    "Ranked by wins, then results between tied players, then wins of beaten opponents.\nThe top {advance} of each group advance to the bracket."
+                ))
+                .color(0xFFFF00);
+            for (group, table) in &standings {
+                e.field(format!("Group {group}"), format_table(table, advance), true);
+            }
+            e
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+async fn display_swiss_standings(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+) -> Result<(), Error> {
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    let standings = swiss_standings(database).await?;
+    let mut table = standings
+        .iter()
+        .take(SWISS_ROWS)
+        .enumerate()
+        .map(|(index, record)| format_swiss_row(index, record))
+        .collect::<Vec<String>>()
+        .join("\n");
+    if standings.len() > SWISS_ROWS {
+        table.push_str(&format!(
+            "\n...and {} more players",
+            standings.len() - SWISS_ROWS
+        ));
+    }
+    msg.edit(*ctx, |s| {
+        s.components(|c| c).embed(|e| {
+            e.title(format!("Swiss standings - {}", region.full()))
+                .description(format!(
+                    "Ranked by wins, then Buchholz (wins of all opponents), then wins of beaten opponents.\n{table}"
+                ))
+                .color(0xFFFF00)
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+fn format_swiss_row(index: usize, record: &SwissRecord) -> String {
+    format!(
+        "{}{}. {} ({}-{}) Buchholz: {}",
+        if record.disqualified { "❌ " } else { "" },
+        index + 1,
+        record.player.get_str("name").unwrap_or("Unknown"),
+        record.wins,
+        record.losses,
+        record.buchholz
+    )
+}
+
+fn format_table(table: &[Standing], advance: usize) -> String {
+    table
+        .iter()
+        .enumerate()
+        .map(|(index, standing)| {
+            let marker = if standing.disqualified {
+                "❌"
+            } else if index < advance {
+                "✅"
+            } else {
+                "▫️"
+            };
+            format!(
+                "{marker} {}. {} ({}-{})",
+                index + 1,
+                standing.player.get_str("name").unwrap_or("Unknown"),
+                standing.wins,
+                standing.losses
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -17,6 +17,7 @@ pub async fn statistics_information(
     )
     .await?;
     let count = Count::new(ctx, region).await?;
+    let format = count.get_format();
     let player_counts = count.get_counts_of_all_players();
     let round_counts = count.get_counts_of_rounds();
     let current = count.get_current_round();
@@ -41,6 +42,7 @@ pub async fn statistics_information(
         &format!(
             r#"**Region: {r} insight.**
 # Overall statistics:
+**🧩 Format**: {format}
 **👥 Players**: {player_counts}
 **⚽ Rounds**: {round_counts}
 # Round {current} statistics:
@@ -1,5 +1,6 @@
 use crate::{
-    bracket_tournament::bracket_update::update_bracket, discord::prompt::prompt, Context, Error,
+    bracket_tournament::bracket_update::update_bracket, database::config::get_config,
+    discord::prompt::prompt, Context, Error,
 };
 use dbc_bot::Region;
 use poise::ReplyHandle;
@@ -9,6 +10,21 @@ pub async fn bracket_display(
     msg: &ReplyHandle<'_>,
     region: &Region,
 ) -> Result<(), Error> {
+    if !get_config(ctx, region)
+        .await
+        .get_bool("tournament")
+        .unwrap_or(false)
+    {
+        return prompt(
+            ctx,
+            msg,
+            "Unable to display the bracket!",
+            "The tournament has not started yet, so there is no bracket to show.",
+            None,
+            Some(0xFF0000),
+        )
+        .await;
+    }
     prompt(
         ctx,
         msg,
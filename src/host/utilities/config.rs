@@ -1,7 +1,18 @@
-use crate::database::config::{make_config, set_config};
+use crate::bracket_tournament::best_of::{is_valid_best_of, parse_overrides};
+use crate::bracket_tournament::seeding::{is_valid_tag, normalise_tag, seed_list};
+use crate::database::audit::audit_config;
+use crate::database::config::{find_config, make_config, set_config};
+use crate::database::deadline::{reminders, round_length};
+use crate::database::dispute::dispute_window;
+use crate::database::eligibility::{excluded_clubs, min_account_age, rules, Rule, MIN_ACCOUNT_AGE};
+use crate::database::schedule::{format_time, parse_time, scheduled, Event};
+use crate::database::team::{team_size, team_size_label, MAX_TEAM_SIZE};
+use crate::database::waitlist::max_players;
 use crate::discord::prompt::prompt;
+use crate::players::registration::waitlist::promote_waitlisted;
+use crate::players::tournament::watcher::{watch_interval, WATCH_TICK};
 use crate::{Context, Error};
-use dbc_bot::{Mode, Region};
+use dbc_bot::{DrawRule, Format, Mode, Region, Seeding};
 use futures::StreamExt;
 use mongodb::{bson::doc, bson::Document, Collection};
 use std::sync::Arc;
@@ -28,6 +39,149 @@ struct RoleSelection {
     role_id: String,
 }
 
+#[derive(Debug, poise::Modal)]
+#[name = "Group Stage"]
+struct GroupStageSettings {
+    #[name = "Number of groups"]
+    #[placeholder = "For example: 8"]
+    groups: String,
+
+    #[name = "Maximum players per group"]
+    #[placeholder = "For example: 4"]
+    group_size: String,
+
+    #[name = "Players advancing from each group"]
+    #[placeholder = "For example: 2"]
+    advance: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Swiss Rounds"]
+struct SwissRounds {
+    #[name = "Number of Swiss rounds"]
+    #[placeholder = "For example: 5"]
+    rounds: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Seed List"]
+struct SeedList {
+    #[name = "Player tags, from the first seed to the last"]
+    #[placeholder = "Separate tags with spaces, commas or new lines"]
+    #[paragraph]
+    tags: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Best Of"]
+struct BestOf {
+    #[name = "Games per match in every round"]
+    #[placeholder = "1, 3, 5 or 7"]
+    best_of: String,
+
+    #[name = "Rounds with a different number of games"]
+    #[placeholder = "One round per line, for example:\nSemifinal: 5\nFinal: 7"]
+    #[paragraph]
+    rounds: Option<String>,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Result Watcher"]
+struct WatchInterval {
+    #[name = "Seconds between two checks of the battle logs"]
+    #[placeholder = "At least 30, or 0 to turn the watcher off"]
+    seconds: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Dispute Window"]
+struct DisputeWindow {
+    #[name = "Minutes a player has to dispute a result"]
+    #[placeholder = "For example: 30, or 0 to turn disputes off"]
+    minutes: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Registration Cap"]
+struct MaxPlayers {
+    #[name = "Most players allowed to register"]
+    #[placeholder = "For example: 64, or 0 for no cap"]
+    players: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Eligibility"]
+struct Eligibility {
+    #[name = "Minimum trophies"]
+    #[placeholder = "For example: 10000, or leave blank"]
+    min_trophies: Option<String>,
+
+    #[name = "Maximum trophies"]
+    #[placeholder = "For example: 40000, or leave blank"]
+    max_trophies: Option<String>,
+
+    #[name = "Minimum experience level"]
+    #[placeholder = "For example: 100, or leave blank"]
+    min_level: Option<String>,
+
+    #[name = "Minimum number of brawlers"]
+    #[placeholder = "For example: 30, or leave blank"]
+    min_brawlers: Option<String>,
+
+    #[name = "Minimum 3v3 victories"]
+    #[placeholder = "For example: 1000, or leave blank"]
+    min_3v3_victories: Option<String>,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Account Age"]
+struct AccountAge {
+    #[name = "Days a Discord account must have existed"]
+    #[placeholder = "For example: 30, or 0 for any account"]
+    days: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Club Restrictions"]
+struct ExcludedClubs {
+    #[name = "Tags of the clubs whose members cannot play"]
+    #[placeholder = "Separated by commas, for example: #2PP, #8QQ. Leave blank for none"]
+    #[paragraph]
+    clubs: Option<String>,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Round Deadlines"]
+struct RoundDeadlines {
+    #[name = "Hours each round lasts"]
+    #[placeholder = "For example: 48, or 0 to turn deadlines off"]
+    hours: String,
+
+    #[name = "Reminders, in minutes before the deadline"]
+    #[placeholder = "For example: 1440, 60. Leave blank for no reminders"]
+    reminders: Option<String>,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Schedule"]
+struct Schedule {
+    #[name = "Registration opens (UTC)"]
+    #[placeholder = "YYYY-MM-DD HH:MM, or leave blank"]
+    registration_opens: Option<String>,
+
+    #[name = "Registration closes (UTC)"]
+    #[placeholder = "YYYY-MM-DD HH:MM, or leave blank"]
+    registration_closes: Option<String>,
+
+    #[name = "Check-in opens (UTC)"]
+    #[placeholder = "YYYY-MM-DD HH:MM, or leave blank"]
+    check_in_opens: Option<String>,
+
+    #[name = "Tournament starts (UTC)"]
+    #[placeholder = "YYYY-MM-DD HH:MM, or leave blank"]
+    tournament_starts: Option<String>,
+}
+
 #[derive(Debug, poise::Modal)]
 #[name = "Channel"]
 struct Channel {
@@ -56,11 +210,72 @@ pub async fn configurate(
         .timeout(std::time::Duration::from_secs(120));
     let mut cic = cib.build();
     while let Some(mci) = &cic.next().await {
+        let before = find_config(database).await;
         match mci.data.values[0].as_str() {
             "mode" => {
                 mci.defer(&ctx.http()).await?;
                 mode_option(ctx, msg, &collection).await?;
             }
+            "format" => {
+                mci.defer(&ctx.http()).await?;
+                format_option(ctx, msg, &collection).await?;
+            }
+            "bracket_reset" => {
+                mci.defer(&ctx.http()).await?;
+                bracket_reset_option(ctx, msg, &collection).await?;
+            }
+            "third_place_match" => {
+                mci.defer(&ctx.http()).await?;
+                third_place_match_option(ctx, msg, &collection).await?;
+            }
+            "group_stage" => {
+                group_stage_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "swiss_rounds" => {
+                swiss_rounds_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "seeding" => {
+                mci.defer(&ctx.http()).await?;
+                seeding_option(ctx, msg, &collection).await?;
+            }
+            "seed_list" => {
+                seed_list_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "best_of" => {
+                best_of_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "team_size" => {
+                mci.defer(&ctx.http()).await?;
+                team_size_option(ctx, msg, &collection).await?;
+            }
+            "draw_rule" => {
+                mci.defer(&ctx.http()).await?;
+                draw_rule_option(ctx, msg, &collection).await?;
+            }
+            "watch_interval" => {
+                watch_interval_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "dispute_window" => {
+                dispute_window_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "round_length" => {
+                round_length_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "schedule" => {
+                schedule_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "eligibility" => {
+                eligibility_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "excluded_clubs" => {
+                excluded_clubs_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "account_age" => {
+                account_age_option(ctx, msg, mci.clone(), &collection).await?;
+            }
+            "max_players" => {
+                max_players_option(ctx, msg, mci.clone(), &collection, region).await?;
+            }
             "role" => {
                 role_option(ctx, msg, mci.clone(), &collection).await?;
             }
@@ -80,7 +295,7 @@ pub async fn configurate(
                 continue;
             }
         };
-
+        audit_config(ctx, region, &before, &find_config(database).await).await?;
         display_config(ctx, msg, region).await?;
     }
     Ok(())
@@ -115,6 +330,113 @@ async fn display_config(
         Ok(mode) => format!("{}", Mode::find_key(mode).unwrap()),
         Err(_) => "Not yet set".to_string(),
     };
+    let format = Format::from_config(&config);
+    let bracket_reset = match format {
+        Format::DoubleElimination if config.get_bool("bracket_reset").unwrap_or(false) => "Enabled",
+        Format::DoubleElimination => "Disabled",
+        Format::SingleElimination | Format::GroupStage | Format::Swiss => "Not applicable",
+    };
+    let third_place_match = match format {
+        Format::SingleElimination | Format::GroupStage
+            if config.get_bool("third_place_match").unwrap_or(false) =>
+        {
+            "Enabled"
+        }
+        Format::SingleElimination | Format::GroupStage => "Disabled",
+        Format::DoubleElimination | Format::Swiss => "Not applicable",
+    };
+    let group_stage = match format {
+        Format::GroupStage => format!(
+            "{} groups of up to {} players, top {} of each group advance",
+            config.get_i32("groups").unwrap_or(0),
+            config.get_i32("group_size").unwrap_or(0),
+            config.get_i32("group_advance").unwrap_or(0)
+        ),
+        _ => "Not applicable".to_string(),
+    };
+    let swiss_rounds = match format {
+        Format::Swiss => match config.get_i32("swiss_rounds").unwrap_or(0) {
+            0 => "Not yet set".to_string(),
+            rounds => format!("{rounds} rounds"),
+        },
+        _ => "Not applicable".to_string(),
+    };
+    let seeding = match Seeding::from_config(&config) {
+        Seeding::Manual => format!("Manual ({} players listed)", seed_list(&config).len()),
+        Seeding::Random => match config.get_i64("rng_seed") {
+            Ok(seed) => format!("Random (seed {seed})"),
+            Err(_) => "Random (seed drawn at the start)".to_string(),
+        },
+        seeding => seeding.to_string(),
+    };
+    let best_of = match config.get_document("best_of_rounds") {
+        Ok(rounds) if !rounds.is_empty() => format!(
+            "Bo{} ({})",
+            config.get_i32("best_of").unwrap_or(3),
+            rounds
+                .iter()
+                .map(|(round, games)| format!("{round}: Bo{games}"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        _ => format!("Bo{}", config.get_i32("best_of").unwrap_or(3)),
+    };
+    let draw_rule = DrawRule::from_config(&config);
+    let teams = team_size_label(team_size(&config));
+    let watcher = match watch_interval(&config) {
+        Some(seconds) => format!("Checks battle logs every {seconds} seconds"),
+        None => "Off".to_string(),
+    };
+    let disputes = match dispute_window(&config) {
+        Some(minutes) => format!("Open for {minutes} minutes after a result"),
+        None => "Off".to_string(),
+    };
+    let deadlines = match round_length(&config) {
+        Some(hours) => match reminders(&config).as_slice() {
+            [] => format!("Rounds last {hours} hours"),
+            minutes => format!(
+                "Rounds last {hours} hours, reminders {} minutes before the deadline",
+                minutes
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        },
+        None => "Off".to_string(),
+    };
+    let schedule = Event::ALL
+        .into_iter()
+        .filter_map(|event| {
+            scheduled(&config, event).map(|time| {
+                let timestamp = time.timestamp_millis() / 1000;
+                format!("{} <t:{timestamp}:F>", event.label())
+            })
+        })
+        .collect::<Vec<String>>();
+    let schedule = match schedule.is_empty() {
+        true => "Nothing scheduled".to_string(),
+        false => schedule.join(", "),
+    };
+    let cap = match max_players(&config) {
+        Some(players) => format!("{players} players, then a waitlist"),
+        None => "No cap".to_string(),
+    };
+    let mut requirements = rules(&config)
+        .iter()
+        .map(|(rule, limit)| rule.describe(*limit))
+        .collect::<Vec<String>>();
+    let clubs = excluded_clubs(&config);
+    if !clubs.is_empty() {
+        requirements.push(format!("not in the clubs {}", clubs.join(", ")));
+    }
+    if let Some(days) = min_account_age(&config) {
+        requirements.push(format!("a Discord account at least {days} days old"));
+    }
+    let eligibility = match requirements.is_empty() {
+        true => "Anyone can register".to_string(),
+        false => requirements.join(", "),
+    };
     let role = match config.get_str("role") {
         Ok(role) => format!("<@&{}>", role),
         Err(_) => "Not yet set".to_string(),
@@ -134,7 +456,22 @@ async fn display_config(
     let description = format!(
         r#"
         **Registration status:** {}
+        **Registration cap:** {}
+        **Eligibility:** {}
         **Tournament status:** {}
+        **Format:** {}
+        **Teams:** {}
+        **Grand final bracket reset:** {}
+        **Third-place match:** {}
+        **Group stage:** {}
+        **Swiss rounds:** {}
+        **Seeding:** {}
+        **Best of:** {}
+        **Draws:** {}
+        **Result watcher:** {}
+        **Disputes:** {}
+        **Round deadlines:** {}
+        **Schedule:** {}
         **Mode:** {}
         **Map:** {}
         **Role assigned to players:** {}
@@ -143,7 +480,22 @@ async fn display_config(
         **Channel to store logs:** {}
         "#,
         registration_status,
+        cap,
+        eligibility,
         tournament_status,
+        format,
+        teams,
+        bracket_reset,
+        third_place_match,
+        group_stage,
+        swiss_rounds,
+        seeding,
+        best_of,
+        draw_rule,
+        watcher,
+        disputes,
+        deadlines,
+        schedule,
         mode,
         map,
         role,
@@ -161,6 +513,96 @@ async fn display_config(
                             .placeholder("Select a field to configure")
                             .options(|o| {
                                 o.create_option(|o| {
+                                    o.label("Format")
+                                        .value("format")
+                                        .description("Select the bracket format of the tournament")
+                                })
+                                .create_option(|o| {
+                                    o.label("Team Size").value("team_size").description(
+                                        "Select solo, 2v2 or 3v3 for the tournament",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Bracket Reset").value("bracket_reset").description(
+                                        "Toggle a rematch when the losers bracket wins the grand final",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Third Place Match").value("third_place_match").description(
+                                        "Toggle a match between the semifinal losers for third place",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Group Stage").value("group_stage").description(
+                                        "Set the groups and how many players advance from each",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Swiss Rounds").value("swiss_rounds").description(
+                                        "Set how many rounds a Swiss tournament lasts",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Seeding").value("seeding").description(
+                                        "Select how players are seeded into the bracket",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Seed List").value("seed_list").description(
+                                        "Set the order of the players for manual seeding",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Best Of").value("best_of").description(
+                                        "Set how many games a match lasts, for every round or only some",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Draw Rule").value("draw_rule").description(
+                                        "Toggle between replaying and ignoring drawn games",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Result Watcher").value("watch_interval").description(
+                                        "Set how often results are checked without a submission",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Dispute Window").value("dispute_window").description(
+                                        "Set how long players have to dispute a result",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Round Deadlines").value("round_length").description(
+                                        "Set how long each round lasts and when players are reminded",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Registration Cap").value("max_players").description(
+                                        "Set how many players can register before the waitlist",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Eligibility").value("eligibility").description(
+                                        "Set the trophies, level, brawlers and victories players need",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Club Restrictions").value("excluded_clubs").description(
+                                        "Set the clubs whose members cannot play",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Account Age").value("account_age").description(
+                                        "Set how old a player's Discord account must be",
+                                    )
+                                })
+                                .create_option(|o| {
+                                    o.label("Schedule").value("schedule").description(
+                                        "Set in UTC when registration opens and closes, check-in opens and the tournament starts",
+                                    )
+                                })
+                                .create_option(|o| {
                                     o.label("Mode")
                                         .value("mode")
                                         .description("Select game mode for the tournament")
@@ -257,6 +699,1091 @@ async fn mode_option(
     Ok(())
 }
 
+async fn format_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let config = collection
+        .find_one(doc! {}, None)
+        .await?
+        .unwrap_or_default();
+    if config.get_bool("tournament").unwrap_or(false) {
+        prompt(
+            ctx,
+            msg,
+            "Unable to change the format!",
+            "The format cannot be changed while the tournament is ongoing!
+            Directing back to configuration menu...",
+            None,
+            Some(0xFF0000),
+        )
+        .await?;
+        std::thread::sleep(std::time::Duration::from_secs(3));
+        return Ok(());
+    }
+    msg.edit(*ctx, |s| {
+        s.content("Setting the format for the tournament!")
+            .ephemeral(true)
+            .components(|c| {
+                c.create_action_row(|c| {
+                    c.create_select_menu(|m| {
+                        m.custom_id("menu")
+                            .placeholder("Select a format")
+                            .options(|o| {
+                                for format in Format::iter() {
+                                    let mut option = CreateSelectMenuOption::default();
+                                    option.label(format.to_string()).value(format.to_string());
+                                    o.add_option(option);
+                                }
+                                o
+                            })
+                    })
+                })
+            })
+    })
+    .await?;
+    let resp = msg.clone().into_message().await?;
+    let cib = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(120));
+    let mut cic = cib.build();
+    if let Some(mci2) = &cic.next().await {
+        mci2.defer(ctx.http()).await?;
+        let format = Format::find_key(mci2.data.values[0].as_str()).unwrap();
+        collection
+            .update_one(
+                doc! {},
+                set_config("format", Some(format!("{:?}", format).as_str())),
+                None,
+            )
+            .await?;
+        msg.edit(*ctx, |s| {
+            s.components(|c| c).embed(|e| {
+                e.title("Format has been set!").description(format!(
+                    "Format has been set to {}
+                    Directing back to configuration menu...",
+                    mci2.data.values[0].as_str()
+                ))
+            })
+        })
+        .await?;
+    }
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn team_size_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let config = collection
+        .find_one(doc! {}, None)
+        .await?
+        .unwrap_or_default();
+    if config.get_bool("registration").unwrap_or(false)
+        || config.get_bool("tournament").unwrap_or(false)
+    {
+        prompt(
+            ctx,
+            msg,
+            "Unable to change the team size!",
+            "The team size cannot be changed while registration is open or the tournament is ongoing!
+            Directing back to configuration menu...",
+            None,
+            Some(0xFF0000),
+        )
+        .await?;
+        std::thread::sleep(std::time::Duration::from_secs(3));
+        return Ok(());
+    }
+    msg.edit(*ctx, |s| {
+        s.content("Setting the team size for the tournament!")
+            .ephemeral(true)
+            .components(|c| {
+                c.create_action_row(|c| {
+                    c.create_select_menu(|m| {
+                        m.custom_id("menu")
+                            .placeholder("Select a team size")
+                            .options(|o| {
+                                for size in 1..=MAX_TEAM_SIZE {
+                                    let mut option = CreateSelectMenuOption::default();
+                                    option.label(team_size_label(size)).value(size);
+                                    o.add_option(option);
+                                }
+                                o
+                            })
+                    })
+                })
+            })
+    })
+    .await?;
+    let resp = msg.clone().into_message().await?;
+    let cib = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(120));
+    let mut cic = cib.build();
+    if let Some(mci2) = &cic.next().await {
+        mci2.defer(ctx.http()).await?;
+        let size = mci2.data.values[0].parse::<i32>()?;
+        collection
+            .update_one(doc! {}, doc! {"$set": {"team_size": size}}, None)
+            .await?;
+        msg.edit(*ctx, |s| {
+            s.components(|c| c).embed(|e| {
+                e.title("Team size has been set!").description(format!(
+                    "The tournament is now played {}
+                    Directing back to configuration menu...",
+                    team_size_label(size)
+                ))
+            })
+        })
+        .await?;
+    }
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn bracket_reset_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let config = collection
+        .find_one(doc! {}, None)
+        .await?
+        .unwrap_or_default();
+    let bracket_reset = !config.get_bool("bracket_reset").unwrap_or(false);
+    collection
+        .update_one(
+            doc! {},
+            doc! {"$set": {"bracket_reset": bracket_reset}},
+            None,
+        )
+        .await?;
+    prompt(
+        ctx,
+        msg,
+        "Bracket reset has been toggled!",
+        format!(
+            "Bracket reset is now {}. This only applies to double elimination tournaments.
+            Directing back to configuration menu...",
+            if bracket_reset { "enabled" } else { "disabled" }
+        ),
+        None,
+        None,
+    )
+    .await?;
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn third_place_match_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let config = collection
+        .find_one(doc! {}, None)
+        .await?
+        .unwrap_or_default();
+    if config.get_bool("tournament").unwrap_or(false) {
+        prompt(
+            ctx,
+            msg,
+            "Failed to toggle the third-place match!",
+            "The tournament has already started.
+            Directing back to configuration menu...",
+            None,
+            Some(0xFF0000),
+        )
+        .await?;
+        std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+        return Ok(());
+    }
+    let third_place_match = !config.get_bool("third_place_match").unwrap_or(false);
+    collection
+        .update_one(
+            doc! {},
+            doc! {"$set": {"third_place_match": third_place_match}},
+            None,
+        )
+        .await?;
+    prompt(
+        ctx,
+        msg,
+        "Third-place match has been toggled!",
+        format!(
+            "The third-place match is now {}. This only applies to single elimination brackets.
+            Directing back to configuration menu...",
+            if third_place_match {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        ),
+        None,
+        None,
+    )
+    .await?;
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn group_stage_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let settings =
+        poise::execute_modal_on_component_interaction::<GroupStageSettings>(ctx, mci, None, None)
+            .await;
+    let parsed = match settings {
+        Ok(Some(settings)) => (
+            settings.groups.trim().parse::<i32>(),
+            settings.group_size.trim().parse::<i32>(),
+            settings.advance.trim().parse::<i32>(),
+        ),
+        Ok(None) | Err(_) => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the group stage!",
+                "No settings have been entered! Please try again!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+            std::thread::sleep(std::time::Duration::from_secs(3));
+            return Ok(());
+        }
+    };
+    match parsed {
+        (Ok(groups), Ok(group_size), Ok(advance))
+            if groups > 0 && group_size > 1 && (1..=group_size).contains(&advance) =>
+        {
+            collection
+                .update_one(
+                    doc! {},
+                    doc! {"$set": {
+                        "groups": groups,
+                        "group_size": group_size,
+                        "group_advance": advance,
+                    }},
+                    None,
+                )
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Group stage has been set!",
+                format!(
+                    "{groups} groups of up to {group_size} players, the top {advance} of each group advance to the bracket.
+                    Directing back to configuration menu..."
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        _ => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the group stage!",
+                "Please enter whole numbers. A group needs at least 2 players, and no more players can advance than a group holds!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    }
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn swiss_rounds_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    match poise::execute_modal_on_component_interaction::<SwissRounds>(ctx, mci, None, None).await {
+        Ok(Some(SwissRounds { rounds })) if rounds.trim().parse::<i32>().is_ok_and(|r| r > 0) => {
+            let rounds = rounds.trim().parse::<i32>()?;
+            collection
+                .update_one(doc! {}, doc! {"$set": {"swiss_rounds": rounds}}, None)
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Swiss rounds have been set!",
+                format!(
+                    "The Swiss tournament will last **{rounds}** rounds!
+                    Directing back to configuration menu..."
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        _ => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set Swiss rounds!",
+                "Please enter a whole number of rounds greater than 0!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn watch_interval_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    match poise::execute_modal_on_component_interaction::<WatchInterval>(ctx, mci, None, None).await
+    {
+        Ok(Some(WatchInterval { seconds }))
+            if seconds
+                .trim()
+                .parse::<i32>()
+                .is_ok_and(|s| s == 0 || s >= WATCH_TICK as i32) =>
+        {
+            let seconds = seconds.trim().parse::<i32>()?;
+            collection
+                .update_one(doc! {}, doc! {"$set": {"watch_interval": seconds}}, None)
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Result watcher has been set!",
+                format!(
+                    "{}
+                    Directing back to configuration menu...",
+                    match seconds {
+                        0 => "Results are now only recorded when players submit them.".to_string(),
+                        seconds =>
+                            format!("Battle logs are now checked every **{seconds}** seconds!"),
+                    }
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        _ => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the result watcher!",
+                format!("Please enter 0 or a whole number of seconds of at least {WATCH_TICK}!"),
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn dispute_window_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    match poise::execute_modal_on_component_interaction::<DisputeWindow>(ctx, mci, None, None).await
+    {
+        Ok(Some(DisputeWindow { minutes }))
+            if minutes.trim().parse::<i32>().is_ok_and(|m| m >= 0) =>
+        {
+            let minutes = minutes.trim().parse::<i32>()?;
+            collection
+                .update_one(doc! {}, doc! {"$set": {"dispute_window": minutes}}, None)
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Dispute window has been set!",
+                format!(
+                    "{}
+                    Directing back to configuration menu...",
+                    match minutes {
+                        0 => "Players can no longer dispute results.".to_string(),
+                        minutes =>
+                            format!("Players can now dispute a result for **{minutes}** minutes!"),
+                    }
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        _ => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the dispute window!",
+                "Please enter a whole number of minutes, or 0 to turn disputes off!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn round_length_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let parsed =
+        match poise::execute_modal_on_component_interaction::<RoundDeadlines>(ctx, mci, None, None)
+            .await
+        {
+            Ok(Some(RoundDeadlines { hours, reminders })) => {
+                let minutes: Result<Vec<i32>, _> = reminders
+                    .unwrap_or_default()
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|m| !m.is_empty())
+                    .map(str::parse::<i32>)
+                    .collect();
+                match (hours.trim().parse::<i32>(), minutes) {
+                    (Ok(hours), Ok(minutes)) if hours >= 0 && minutes.iter().all(|&m| m > 0) => {
+                        Some((hours, minutes))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+    match parsed {
+        Some((hours, minutes)) => {
+            collection
+                .update_one(
+                    doc! {},
+                    doc! {"$set": {"round_length": hours, "reminders": &minutes}},
+                    None,
+                )
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Round deadlines have been set!",
+                format!(
+                    "{}
+                    Directing back to configuration menu...",
+                    match hours {
+                        0 => "Rounds no longer have a deadline.".to_string(),
+                        hours => format!(
+                            "Each round now lasts **{hours}** hours! Unfinished matches are resolved at the deadline."
+                        ),
+                    }
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        None => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the round deadlines!",
+                "Please enter a whole number of hours, or 0 to turn deadlines off, and the reminders as whole numbers of minutes separated by commas!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn eligibility_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let config = collection
+        .find_one(doc! {}, None)
+        .await?
+        .unwrap_or_default();
+    let current = rules(&config);
+    let limit = |rule| {
+        current
+            .iter()
+            .find(|(r, _)| *r == rule)
+            .map(|(_, limit)| limit.to_string())
+    };
+    let defaults = Eligibility {
+        min_trophies: limit(Rule::MinTrophies),
+        max_trophies: limit(Rule::MaxTrophies),
+        min_level: limit(Rule::MinLevel),
+        min_brawlers: limit(Rule::MinBrawlers),
+        min_3v3_victories: limit(Rule::MinTrioVictories),
+    };
+    let limits = match poise::execute_modal_on_component_interaction::<Eligibility>(
+        ctx,
+        mci,
+        Some(defaults),
+        None,
+    )
+    .await
+    {
+        Ok(Some(eligibility)) => [
+            eligibility.min_trophies,
+            eligibility.max_trophies,
+            eligibility.min_level,
+            eligibility.min_brawlers,
+            eligibility.min_3v3_victories,
+        ]
+        .iter()
+        .map(|limit| match limit.as_deref().unwrap_or("").trim() {
+            "" => Ok(None),
+            limit => limit.parse::<u32>().map(|limit| Some(limit as i64)),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .ok(),
+        _ => None,
+    };
+    match limits {
+        Some(limits) => {
+            let mut eligibility = Document::new();
+            for (rule, limit) in Rule::ALL.into_iter().zip(limits) {
+                if let Some(limit) = limit {
+                    eligibility.insert(rule.key(), limit);
+                }
+            }
+            eligibility.insert("excluded_clubs", excluded_clubs(&config));
+            if let Some(days) = min_account_age(&config) {
+                eligibility.insert(MIN_ACCOUNT_AGE, days);
+            }
+            collection
+                .update_one(doc! {}, doc! {"$set": {"eligibility": eligibility}}, None)
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Eligibility has been set!",
+                "Registrants who do not meet the requirements are told which ones they fail.
+                Directing back to configuration menu...",
+                None,
+                None,
+            )
+            .await?;
+        }
+        None => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the eligibility!",
+                "Please enter every requirement as a whole number, or leave it blank!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn excluded_clubs_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let config = collection
+        .find_one(doc! {}, None)
+        .await?
+        .unwrap_or_default();
+    let defaults = ExcludedClubs {
+        clubs: Some(excluded_clubs(&config).join(", ")),
+    };
+    let clubs = match poise::execute_modal_on_component_interaction::<ExcludedClubs>(
+        ctx,
+        mci,
+        Some(defaults),
+        None,
+    )
+    .await
+    {
+        Ok(Some(ExcludedClubs { clubs })) => {
+            let clubs = clubs
+                .unwrap_or_default()
+                .split(',')
+                .filter(|club| !club.trim().is_empty())
+                .map(normalise_tag)
+                .collect::<Vec<String>>();
+            clubs.iter().all(|club| is_valid_tag(club)).then_some(clubs)
+        }
+        _ => None,
+    };
+    match clubs {
+        Some(clubs) => {
+            collection
+                .update_one(
+                    doc! {},
+                    doc! {"$set": {"eligibility.excluded_clubs": &clubs}},
+                    None,
+                )
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Club restrictions have been set!",
+                format!(
+                    "{}
+                    Directing back to configuration menu...",
+                    match clubs.is_empty() {
+                        true => "Members of any club can register.".to_string(),
+                        false => format!("Members of {} cannot register.", clubs.join(", ")),
+                    }
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        None => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the club restrictions!",
+                "Please enter club tags separated by commas, or leave it blank!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn account_age_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    match poise::execute_modal_on_component_interaction::<AccountAge>(ctx, mci, None, None).await {
+        Ok(Some(AccountAge { days })) if days.trim().parse::<u32>().is_ok() => {
+            let days = days.trim().parse::<u32>()? as i64;
+            collection
+                .update_one(
+                    doc! {},
+                    doc! {"$set": {format!("eligibility.{MIN_ACCOUNT_AGE}"): days}},
+                    None,
+                )
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Account age has been set!",
+                format!(
+                    "{}
+                    Directing back to configuration menu...",
+                    match days {
+                        0 => "Discord accounts of any age can register.".to_string(),
+                        days => format!(
+                            "Discord accounts must be at least **{days}** days old to register."
+                        ),
+                    }
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        _ => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the account age!",
+                "Please enter a whole number of days, or 0 for any account!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn max_players_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+    region: &Region,
+) -> Result<(), Error> {
+    match poise::execute_modal_on_component_interaction::<MaxPlayers>(ctx, mci, None, None).await {
+        Ok(Some(MaxPlayers { players })) if players.trim().parse::<i32>().is_ok_and(|p| p >= 0) => {
+            let players = players.trim().parse::<i32>()?;
+            collection
+                .update_one(doc! {}, doc! {"$set": {"max_players": players}}, None)
+                .await?;
+            // A higher cap frees places for the waitlist right away
+            let database = ctx.data().database.regional_databases.get(region).unwrap();
+            let promoted = match find_config(database).await.get_bool("tournament") {
+                Ok(true) => 0,
+                _ => promote_waitlisted(
+                    ctx.serenity_context(),
+                    database,
+                    &ctx.data().database.general,
+                    region,
+                )
+                .await?
+                .len(),
+            };
+            prompt(
+                ctx,
+                msg,
+                "Registration cap has been set!",
+                format!(
+                    "{}{}
+                    Directing back to configuration menu...",
+                    match players {
+                        0 => "Any number of players can now register.".to_string(),
+                        players => format!(
+                            "Up to **{players}** players can now register, the rest are waitlisted!"
+                        ),
+                    },
+                    match promoted {
+                        0 => String::new(),
+                        promoted =>
+                            format!("\n{promoted} players have been promoted from the waitlist."),
+                    }
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        _ => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the registration cap!",
+                "Please enter a whole number of players, or 0 for no cap!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn schedule_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let config = collection
+        .find_one(doc! {}, None)
+        .await?
+        .unwrap_or_default();
+    let current = |event| scheduled(&config, event).map(format_time);
+    let defaults = Schedule {
+        registration_opens: current(Event::OpenRegistration),
+        registration_closes: current(Event::CloseRegistration),
+        check_in_opens: current(Event::OpenCheckIn),
+        tournament_starts: current(Event::StartTournament),
+    };
+    let times = match poise::execute_modal_on_component_interaction::<Schedule>(
+        ctx,
+        mci,
+        Some(defaults),
+        None,
+    )
+    .await
+    {
+        Ok(Some(schedule)) => [
+            schedule.registration_opens,
+            schedule.registration_closes,
+            schedule.check_in_opens,
+            schedule.tournament_starts,
+        ]
+        .iter()
+        .map(|time| parse_time(time.as_deref().unwrap_or("")))
+        .collect::<Result<Vec<_>, _>>()
+        .ok(),
+        _ => None,
+    };
+    match times {
+        Some(times) => {
+            let mut update = Document::new();
+            for (event, time) in Event::ALL.into_iter().zip(&times) {
+                update.insert(event.key(), time);
+            }
+            collection
+                .update_one(doc! {}, doc! {"$set": update}, None)
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Schedule has been set!",
+                "The times are read as UTC. Scheduled events are carried out automatically, and logged in the log channel.
+                Directing back to configuration menu...",
+                None,
+                None,
+            )
+            .await?;
+        }
+        None => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the schedule!",
+                "Please write every time in UTC as YYYY-MM-DD HH:MM, for example 2024-03-01 18:00, or leave it blank!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn seeding_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    msg.edit(*ctx, |s| {
+        s.content("Setting the seeding for the tournament!")
+            .ephemeral(true)
+            .components(|c| {
+                c.create_action_row(|c| {
+                    c.create_select_menu(|m| {
+                        m.custom_id("menu")
+                            .placeholder("Select a seeding")
+                            .options(|o| {
+                                for seeding in Seeding::iter() {
+                                    let mut option = CreateSelectMenuOption::default();
+                                    option.label(seeding.to_string()).value(seeding.to_string());
+                                    o.add_option(option);
+                                }
+                                o
+                            })
+                    })
+                })
+            })
+    })
+    .await?;
+    let resp = msg.clone().into_message().await?;
+    let cib = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(120));
+    let mut cic = cib.build();
+    if let Some(mci2) = &cic.next().await {
+        mci2.defer(ctx.http()).await?;
+        let seeding = Seeding::find_key(mci2.data.values[0].as_str()).unwrap();
+        collection
+            .update_one(
+                doc! {},
+                set_config("seeding", Some(format!("{:?}", seeding).as_str())),
+                None,
+            )
+            .await?;
+        msg.edit(*ctx, |s| {
+            s.components(|c| c).embed(|e| {
+                e.title("Seeding has been set!").description(format!(
+                    "Seeding has been set to {}
+                    Directing back to configuration menu...",
+                    mci2.data.values[0].as_str()
+                ))
+            })
+        })
+        .await?;
+    }
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn seed_list_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    match poise::execute_modal_on_component_interaction::<SeedList>(ctx, mci, None, None).await {
+        Ok(Some(SeedList { tags })) => {
+            let tags: Vec<String> = tags
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|tag| !tag.is_empty())
+                .map(normalise_tag)
+                .collect();
+            collection
+                .update_one(doc! {}, doc! {"$set": {"seed_list": &tags}}, None)
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Seed list has been set!",
+                format!(
+                    "**{}** players have been listed. Unlisted players are seeded after them. This only applies to manual seeding.
+                    Directing back to configuration menu...",
+                    tags.len()
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        Ok(None) | Err(_) => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the seed list!",
+                "No tags have been entered! Please try again!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    };
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn best_of_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    mci: Arc<MessageComponentInteraction>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let settings =
+        match poise::execute_modal_on_component_interaction::<BestOf>(ctx, mci, None, None).await {
+            Ok(Some(settings)) => settings,
+            Ok(None) | Err(_) => {
+                prompt(
+                    ctx,
+                    msg,
+                    "Fail to set the best of!",
+                    "No settings have been entered! Please try again!",
+                    None,
+                    Some(0xFF0000),
+                )
+                .await?;
+                std::thread::sleep(std::time::Duration::from_secs(3));
+                return Ok(());
+            }
+        };
+    let best_of = settings.best_of.trim().parse::<i32>();
+    let rounds = parse_overrides(settings.rounds.as_deref().unwrap_or(""));
+    match (best_of, rounds) {
+        (Ok(best_of), Ok(rounds)) if is_valid_best_of(best_of) => {
+            collection
+                .update_one(
+                    doc! {},
+                    doc! {"$set": {"best_of": best_of, "best_of_rounds": &rounds}},
+                    None,
+                )
+                .await?;
+            prompt(
+                ctx,
+                msg,
+                "Best of has been set!",
+                format!(
+                    "Matches are best of {best_of}, with {} rounds set differently.
+                    Directing back to configuration menu...",
+                    rounds.len()
+                ),
+                None,
+                None,
+            )
+            .await?;
+        }
+        (_, Err(e)) => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the best of!",
+                format!("{e}! Please write one round per line, for example `Final: 7`."),
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+        _ => {
+            prompt(
+                ctx,
+                msg,
+                "Fail to set the best of!",
+                "Matches can only be best of 1, 3, 5 or 7!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+        }
+    }
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
+async fn draw_rule_option(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    collection: &Collection<Document>,
+) -> Result<(), Error> {
+    let config = collection
+        .find_one(doc! {}, None)
+        .await?
+        .unwrap_or_default();
+    let draw_rule = match DrawRule::from_config(&config) {
+        DrawRule::Replay => DrawRule::Ignore,
+        DrawRule::Ignore => DrawRule::Replay,
+    };
+    collection
+        .update_one(
+            doc! {},
+            set_config("draw_rule", Some(format!("{:?}", draw_rule).as_str())),
+            None,
+        )
+        .await?;
+    prompt(
+        ctx,
+        msg,
+        "Draw rule has been toggled!",
+        format!(
+            "{}
+            Directing back to configuration menu...",
+            draw_rule.describe()
+        ),
+        None,
+        None,
+    )
+    .await?;
+    std::thread::sleep(std::time::Duration::from_secs(3)); //Delay to prevent discord from rate limiting
+    Ok(())
+}
+
 async fn role_option(
     ctx: &Context<'_>,
     msg: &ReplyHandle<'_>,
@@ -1,5 +1,5 @@
 use chrono::{Local, Timelike};
-use mongodb::bson::Bson;
+use mongodb::bson::{Bson, Document};
 use poise::serenity_prelude::{Colour, Timestamp};
 use std::error::Error;
 use std::fmt;
@@ -108,6 +108,112 @@ impl Mode {
     }
 }
 
+/// The structure of the bracket a region is played with.
+#[derive(Debug, poise::ChoiceParameter, EnumIter, Eq, Hash, PartialEq, Clone)]
+pub enum Format {
+    #[name = "Single Elimination"]
+    SingleElimination,
+    #[name = "Double Elimination"]
+    DoubleElimination,
+    #[name = "Group Stage + Single Elimination"]
+    GroupStage,
+    #[name = "Swiss"]
+    Swiss,
+}
+
+impl Format {
+    pub fn find_key(name: &str) -> Option<Format> {
+        match name {
+            "Single Elimination" | "SingleElimination" => Some(Format::SingleElimination),
+            "Double Elimination" | "DoubleElimination" => Some(Format::DoubleElimination),
+            "Group Stage + Single Elimination" | "GroupStage" => Some(Format::GroupStage),
+            "Swiss" => Some(Format::Swiss),
+            _ => None,
+        }
+    }
+
+    /// Reads the format from a region's config, falling back to single elimination when it is not set.
+    pub fn from_config(config: &Document) -> Format {
+        config
+            .get_str("format")
+            .ok()
+            .and_then(Format::find_key)
+            .unwrap_or(Format::SingleElimination)
+    }
+}
+
+/// How players are ordered before they are placed in the bracket.
+#[derive(Debug, poise::ChoiceParameter, EnumIter, Eq, Hash, PartialEq, Clone)]
+pub enum Seeding {
+    #[name = "Trophies"]
+    Trophies,
+    #[name = "Highest Trophies"]
+    HighestTrophies,
+    #[name = "Manual"]
+    Manual,
+    #[name = "Random"]
+    Random,
+}
+
+impl Seeding {
+    pub fn find_key(name: &str) -> Option<Seeding> {
+        match name {
+            "Trophies" => Some(Seeding::Trophies),
+            "Highest Trophies" | "HighestTrophies" => Some(Seeding::HighestTrophies),
+            "Manual" => Some(Seeding::Manual),
+            "Random" => Some(Seeding::Random),
+            _ => None,
+        }
+    }
+
+    /// Reads the seeding from a region's config, falling back to a random draw when it is not set.
+    pub fn from_config(config: &Document) -> Seeding {
+        config
+            .get_str("seeding")
+            .ok()
+            .and_then(Seeding::find_key)
+            .unwrap_or(Seeding::Random)
+    }
+}
+
+/// What happens to a game that ends in a draw.
+#[derive(Debug, poise::ChoiceParameter, EnumIter, Eq, Hash, PartialEq, Clone)]
+pub enum DrawRule {
+    #[name = "Replay"]
+    Replay,
+    #[name = "Ignore"]
+    Ignore,
+}
+
+impl DrawRule {
+    pub fn find_key(name: &str) -> Option<DrawRule> {
+        match name {
+            "Replay" => Some(DrawRule::Replay),
+            "Ignore" => Some(DrawRule::Ignore),
+            _ => None,
+        }
+    }
+
+    /// Reads the draw rule from a region's config, falling back to replaying draws when it is not set.
+    pub fn from_config(config: &Document) -> DrawRule {
+        config
+            .get_str("draw_rule")
+            .ok()
+            .and_then(DrawRule::find_key)
+            .unwrap_or(DrawRule::Replay)
+    }
+
+    /// Explains the rule to players.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            DrawRule::Replay => "Draws do not count and are replayed.",
+            DrawRule::Ignore => {
+                "Draws use up a game without a winner. If nobody has the most wins once every game is played, play on until someone leads."
+            }
+        }
+    }
+}
+
 pub fn get_color(result: String) -> Colour {
     match result.as_str() {
         "victory" => Colour::new(0x90EE90), // Green
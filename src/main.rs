@@ -5,12 +5,12 @@ use mongodb::{
     Client, Collection, Database,
 };
 use poise::{
-    serenity_prelude::{self as serenity, GatewayIntents},
+    serenity_prelude::{self as serenity, GatewayIntents, Interaction},
     Event, FrameworkError,
 };
 use std::{collections::HashMap, fs::File, sync::Arc};
 use strum::IntoEnumIterator;
-use tracing::{error, info, instrument, trace};
+use tracing::{error, info, instrument, trace, warn};
 use tracing_subscriber::{filter, prelude::*};
 mod bracket_tournament;
 mod brawlstars;
@@ -63,6 +63,10 @@ async fn run() -> Result<(), Error> {
         commands::update_proof::update_proof(),
         commands::setup::setup(),
         commands::player_lookup::lookup_player(),
+        commands::challonge::challonge_export(),
+        commands::challonge::challonge_import(),
+        commands::ban::ban(),
+        commands::audit::audit_log(),
     ];
     info!("{} commands registered", commands.len());
     // match dotenv::dotenv() {
@@ -79,12 +83,30 @@ async fn run() -> Result<(), Error> {
     info!("Generating options");
     let options = poise::FrameworkOptions {
         commands,
-        event_handler: |_ctx, event, _framework, _data| {
+        event_handler: |ctx, event, _framework, data| {
             Box::pin(async move {
-                if let Event::Ready { data_about_bot } = event {
-                    let bot_name = data_about_bot.user.name.to_owned();
-                    info!("{username} is online", username = bot_name);
-                    println!("{} is online!", bot_name);
+                match event {
+                    Event::Ready { data_about_bot } => {
+                        let bot_name = data_about_bot.user.name.to_owned();
+                        info!("{username} is online", username = bot_name);
+                        println!("{} is online!", bot_name);
+                    }
+                    // Check-in buttons sent by direct message outlive any menu, so they are handled here
+                    Event::InteractionCreate {
+                        interaction: Interaction::MessageComponent(mci),
+                    } if mci
+                        .data
+                        .custom_id
+                        .starts_with(players::registration::check_in::CHECK_IN_BUTTON) =>
+                    {
+                        players::registration::check_in::check_in_button(
+                            ctx,
+                            mci,
+                            &data.database.regional_databases,
+                        )
+                        .await?;
+                    }
+                    _ => {}
                 }
 
                 Ok(())
@@ -113,6 +135,8 @@ async fn run() -> Result<(), Error> {
     info!("Options generated successfully!");
 
     let database = prepare_databases().await?;
+    let watched_databases = database.regional_databases.clone();
+    let scheduled_databases = (database.general.clone(), watched_databases.clone());
 
     info!("Generating framework...");
     let framework = poise::Framework::builder()
@@ -124,6 +148,18 @@ async fn run() -> Result<(), Error> {
         .setup(|ctx, _ready, framework| {
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                tokio::spawn(players::tournament::watcher::watch_battle_logs(
+                    ctx.clone(),
+                    watched_databases,
+                ));
+                info!("Battle log watcher started");
+                let (general, regional) = scheduled_databases;
+                tokio::spawn(players::tournament::scheduler::run_scheduler(
+                    ctx.clone(),
+                    general,
+                    regional,
+                ));
+                info!("Scheduler started");
                 Ok(Data {
                     database,
                     // managers
@@ -169,6 +205,9 @@ async fn prepare_databases() -> Result<Databases, Error> {
 
     let client = Client::with_options(options)?;
     let general = client.database("General");
+    if !database::repository::supports_transactions(&general).await? {
+        warn!("MongoDB is not a replica set: match results are written without transactions, so a crash can leave one partly recorded. See the README.");
+    }
 
     let mut regional_database: HashMap<Region, Database> = HashMap::new();
     regional_database.insert(Region::APAC, client.database("APAC"));
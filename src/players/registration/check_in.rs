@@ -0,0 +1,164 @@
+use crate::database::check_in::{check_in, check_in_open};
+use crate::database::config::find_config;
+use crate::database::repository::{Repository, PLAYERS};
+use crate::discord::prompt::prompt;
+use crate::{Context, Error};
+use dbc_bot::Region;
+use futures::StreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::Database;
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, InteractionResponseType, MessageComponentInteraction, UserId,
+};
+use poise::ReplyHandle;
+use std::collections::HashMap;
+use tracing::{error, info};
+
+/// Prefix of the check-in buttons sent by direct message, followed by the region.
+pub const CHECK_IN_BUTTON: &str = "check_in:";
+const TIMEOUT: u64 = 120;
+
+/// Asks a registered player who has not checked in yet to do so.
+pub async fn check_in_menu(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+) -> Result<(), Error> {
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title("Check-in is open!")
+                .description(format!(
+                    "Please confirm that you will play in the {} tournament. Players who have not checked in by the time the tournament starts are removed from it!",
+                    region.full()
+                ))
+                .color(0xFFFF00)
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| {
+                    b.custom_id("check_in")
+                        .label("Check in")
+                        .emoji('✅')
+                        .style(ButtonStyle::Success)
+                })
+            })
+        })
+    })
+    .await?;
+    let resp = msg.clone().into_message().await?;
+    let mut cic = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(TIMEOUT))
+        .build();
+    if let Some(mci) = &cic.next().await {
+        mci.defer(&ctx.http()).await?;
+        let database = ctx.data().database.regional_databases.get(region).unwrap();
+        let discord_id = ctx.author().id.to_string();
+        let (title, description, color) = match check_in_open(&find_config(database).await)
+            && check_in(database, &discord_id).await?
+        {
+            true => (
+                "You have checked in!",
+                "You are all set. Please stay tuned for the start of the tournament!",
+                None,
+            ),
+            false => (
+                "Failed to check in!",
+                "Check-in has already closed.",
+                Some(0xFF0000),
+            ),
+        };
+        return prompt(ctx, msg, title, description, None, color).await;
+    }
+    Ok(())
+}
+
+/// Sends every registered player a direct message with a button to check in.
+pub async fn announce_check_in(
+    ctx: &serenity::Context,
+    database: &Database,
+    region: &Region,
+) -> Result<(), Error> {
+    let players: Vec<Document> = database
+        .find_in_round(PLAYERS, doc! {"discord_id": {"$ne": null}})
+        .await?;
+    info!(
+        "Sending the check-in of {region} to {} players",
+        players.len()
+    );
+    for player in players {
+        let Ok(id) = player.get_str("discord_id").unwrap_or("").parse::<u64>() else {
+            continue;
+        };
+        let sent = match UserId(id).create_dm_channel(ctx).await {
+            Ok(channel) => channel
+                .send_message(ctx, |m| {
+                    m.embed(|e| {
+                        e.title("Check-in is open!")
+                            .description(format!(
+                                "Please confirm that you will play in the {} tournament, with this button or from </menu:1211629882564677664>. Players who have not checked in by the time the tournament starts are removed from it!",
+                                region.full()
+                            ))
+                            .color(0xFFFF00)
+                    })
+                    .components(|c| {
+                        c.create_action_row(|a| {
+                            a.create_button(|b| {
+                                b.custom_id(format!("{CHECK_IN_BUTTON}{}", region.short()))
+                                    .label("Check in")
+                                    .emoji('✅')
+                                    .style(ButtonStyle::Success)
+                            })
+                        })
+                    })
+                })
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            error!("Failed to send the check-in to {id}: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Checks in the player who pressed the button of a check-in direct message.
+pub async fn check_in_button(
+    ctx: &serenity::Context,
+    mci: &MessageComponentInteraction,
+    databases: &HashMap<Region, Database>,
+) -> Result<(), Error> {
+    let Some(database) = mci
+        .data
+        .custom_id
+        .strip_prefix(CHECK_IN_BUTTON)
+        .and_then(Region::find_key)
+        .and_then(|region| databases.get(&region))
+    else {
+        return Ok(());
+    };
+    let checked_in = check_in_open(&find_config(database).await)
+        && check_in(database, &mci.user.id.to_string()).await?;
+    let (title, description, color) = match checked_in {
+        true => (
+            "You have checked in!",
+            "You are all set. Please stay tuned for the start of the tournament!",
+            0x50C878,
+        ),
+        false => (
+            "Failed to check in!",
+            "Check-in has already closed, or you are no longer registered.",
+            0xFF0000,
+        ),
+    };
+    mci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|d| {
+                d.embed(|e| e.title(title).description(description).color(color))
+                    .components(|c| c)
+            })
+    })
+    .await?;
+    Ok(())
+}
@@ -1,9 +1,12 @@
 use crate::database::config::get_config;
+use crate::database::models::{Player, RegionConfig};
 use crate::database::remove::remove_registration;
+use crate::database::team::{is_roster_confirmed, remove_member_roles};
 use crate::discord::prompt;
 use crate::players::registration::deregister::prompt::prompt;
+use crate::players::registration::waitlist::promote_waitlisted;
 use crate::{Context, Error};
-use dbc_bot::{CustomError, Region};
+use dbc_bot::CustomError;
 use futures::StreamExt;
 use mongodb::bson::Document;
 use poise::serenity_prelude::ButtonStyle;
@@ -45,9 +48,23 @@ pub async fn deregister_menu(
     if let Some(mci) = &cic.next().await {
         match mci.data.custom_id.as_str() {
             "deregister" => {
-                let region = Region::find_key(player.get_str("region").unwrap()).unwrap();
+                let region = Player::from_document(&player)?.region()?;
                 remove_registration(ctx, &player).await?;
-                if let Err(e) = remove_role(ctx, msg, &get_config(ctx, &region).await).await {
+                if is_roster_confirmed(&player) {
+                    remove_member_roles(ctx, &player, &region).await?;
+                }
+                let config = get_config(ctx, &region).await;
+                if !config.get_bool("tournament").unwrap_or(false) {
+                    let database = ctx.data().database.regional_databases.get(&region).unwrap();
+                    promote_waitlisted(
+                        ctx.serenity_context(),
+                        database,
+                        &ctx.data().database.general,
+                        &region,
+                    )
+                    .await?;
+                }
+                if let Err(e) = remove_role(ctx, msg, &config).await {
                     return prompt(ctx, msg, "ERROR", format!("{e}"), None, Some(0xFF0000)).await;
                 }
 
@@ -75,7 +92,7 @@ async fn remove_role(
     msg: &ReplyHandle<'_>,
     config: &Document,
 ) -> Result<(), Error> {
-    let role_id = config.get_str("role").unwrap().parse::<u64>().unwrap();
+    let role_id = RegionConfig::from_document(config)?.role_id()?;
     let mut member = match ctx.author_member().await {
         Some(m) => m.deref().to_owned(),
         None => {
@@ -1,2 +1,5 @@
+pub mod check_in;
 pub mod deregister;
 pub mod register;
+pub mod team;
+pub mod waitlist;
@@ -1,15 +1,22 @@
 use crate::brawlstars::api::{request, APIResult};
+use crate::brawlstars::getters::get_player_icon;
 use crate::brawlstars::player::stat;
+use crate::brawlstars::verify::IconChallenge;
 use crate::database::add::add_player;
-use crate::database::config::make_player_doc;
-use crate::database::find::find_tag;
+use crate::database::ban::{describe_ban, find_ban};
+use crate::database::config::{get_config, make_player_doc};
+use crate::database::eligibility::{account_age_problem, failed_rules};
+use crate::database::find::{find_tag, TagHolder};
+use crate::database::models::Player;
 use crate::database::open::registration_region_open;
+use crate::database::team::team_size;
+use crate::database::waitlist::{join_waitlist, keep_within_cap, registration_full};
 use crate::discord::prompt::prompt;
 use crate::discord::role::assign_role;
 use crate::{Context, Error};
 use dbc_bot::Region;
 use futures::StreamExt;
-use mongodb::bson::Document;
+use mongodb::bson::{DateTime, Document};
 use poise::serenity_prelude::{self as serenity};
 use poise::ReplyHandle;
 use std::sync::Arc;
@@ -17,10 +24,13 @@ use strum::IntoEnumIterator;
 use tracing::info;
 
 const TIMEOUT: u64 = 120;
+const VERIFY_TIME: u64 = 300;
+const VERIFY_ATTEMPTS: usize = 5;
 struct PlayerRegistration {
     tag: Option<String>,
     region: Option<Region>,
     player: Option<Document>,
+    challenge: Option<IconChallenge>,
 }
 
 #[derive(Debug, poise::Modal)]
@@ -37,6 +47,7 @@ pub async fn register_menu(ctx: &Context<'_>, msg: &ReplyHandle<'_>) -> Result<(
         tag: None,
         region: None,
         player: None,
+        challenge: None,
     };
     display_register_region(ctx, msg).await?;
     let resp = msg.clone().into_message().await?;
@@ -49,6 +60,16 @@ pub async fn register_menu(ctx: &Context<'_>, msg: &ReplyHandle<'_>) -> Result<(
             "APAC" | "EU" | "NASA" => {
                 register.region = Some(Region::find_key(mci.data.custom_id.as_str()).unwrap());
                 mci.defer(&ctx.http()).await?;
+                if let Some(ban) = find_ban(
+                    &ctx.data().database.general,
+                    Some(&ctx.author().id.to_string()),
+                    None,
+                    &register.region.clone().unwrap(),
+                )
+                .await?
+                {
+                    return banned(ctx, msg, &ban).await;
+                }
                 if registration_region_open(ctx, &register.region.clone().unwrap()).await {
                     register_tag(ctx, msg).await?;
                     continue;
@@ -67,17 +88,37 @@ pub async fn register_menu(ctx: &Context<'_>, msg: &ReplyHandle<'_>) -> Result<(
             }
             "open_modal" => {
                 register.tag = Some(create_modal_tag(ctx, mci.clone()).await?.to_uppercase());
-                match find_tag(ctx, &register.tag.clone().unwrap()).await {
-                    Some(player) => {
+                match find_tag(
+                    ctx,
+                    &register.tag.clone().unwrap(),
+                    register.region.as_ref(),
+                )
+                .await?
+                {
+                    Some(TagHolder::Ban(ban)) => {
+                        return banned(ctx, msg, &ban).await;
+                    }
+                    // A waitlisted player registering again keeps their place
+                    Some(TagHolder::Player(player))
+                        if !(player.contains_key("waitlisted_at")
+                            && player.get_str("discord_id")
+                                == Ok(&ctx.author().id.to_string())) =>
+                    {
                         return already_used(ctx, msg, player).await;
                     }
-                    None => {
-                        register.player = display_confirmation(ctx, msg, &register).await?;
+                    _ => {
+                        register.player = display_confirmation(ctx, msg, &mut register).await?;
                         continue;
                     }
                 }
             }
-            "confirm" => return confirm(ctx, msg, &register).await,
+            "confirm" => {
+                mci.defer(&ctx.http()).await?;
+                if verify_ownership(ctx, msg, &register).await? {
+                    return confirm(ctx, msg, &register).await;
+                }
+                return Ok(());
+            }
             "cancel" => {
                 mci.defer(&ctx.http()).await?;
                 return cancel(ctx, msg).await;
@@ -154,10 +195,38 @@ async fn create_modal_tag(
 async fn display_confirmation(
     ctx: &Context<'_>,
     msg: &ReplyHandle<'_>,
-    register: &PlayerRegistration,
+    register: &mut PlayerRegistration,
 ) -> Result<Option<Document>, Error> {
     match request("player", register.tag.clone().unwrap().as_str()).await {
         Ok(APIResult::Successful(player)) => {
+            let config = get_config(ctx, &register.region.clone().unwrap()).await;
+            let mut failed = failed_rules(&config, &player);
+            failed.extend(account_age_problem(
+                &config,
+                &ctx.author().id.to_string(),
+                DateTime::now(),
+            ));
+            if !failed.is_empty() {
+                prompt(
+                    ctx,
+                    msg,
+                    "You are not eligible for this tournament!",
+                    format!(
+                        "The account tagged {} does not meet the entry requirements of this region:\n{}",
+                        register.tag.clone().unwrap(),
+                        failed
+                            .iter()
+                            .map(|reason| format!("- {reason}"))
+                            .collect::<Vec<String>>()
+                            .join("\n")
+                    ),
+                    None,
+                    Some(0xFF0000),
+                )
+                .await?;
+                return Ok(None);
+            }
+            register.challenge = Some(IconChallenge::new(&player));
             msg.edit(*ctx, |s| {
                 s.components(|c| {
                     c.create_action_row(|a| {
@@ -224,29 +293,204 @@ async fn display_confirmation(
     }
 }
 
-async fn confirm(
+//Step 4
+/// Asks the registrant to change their profile icon and checks it through the API, so that nobody
+/// registers with an account that is not theirs. Returns whether the account was verified.
+async fn verify_ownership(
     ctx: &Context<'_>,
     msg: &ReplyHandle<'_>,
     register: &PlayerRegistration,
+) -> Result<bool, Error> {
+    let (Some(tag), Some(challenge)) = (register.tag.clone(), register.challenge) else {
+        return Ok(false);
+    };
+    match verify_account(ctx, msg, &tag, &challenge, None).await? {
+        Verification::Verified => Ok(true),
+        Verification::Cancelled => {
+            cancel(ctx, msg).await?;
+            Ok(false)
+        }
+        Verification::TimedOut => {
+            prompt(
+                ctx,
+                msg,
+                "Verification timed out!",
+                "You can run </menu:1211629882564677664> again to register once you are ready to change your profile icon.",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+            Ok(false)
+        }
+        Verification::Failed => {
+            prompt(
+                ctx,
+                msg,
+                "Failed to verify your account!",
+                "We could not confirm that this account is yours. Please run </menu:1211629882564677664> again to retry, or ask the Host or Moderator team for help!",
+                None,
+                Some(0xFF0000),
+            )
+            .await?;
+            Ok(false)
+        }
+    }
+}
+
+/// How an icon challenge ended.
+pub enum Verification {
+    Verified,
+    Cancelled,
+    TimedOut,
+    /// The icon did not change in any of the attempts.
+    Failed,
+}
+
+/// Shows an icon challenge and checks it through the API each time **Verify** is pressed. The icon
+/// is changed by the user running the menu, or by `member` for an account invited into a team.
+pub async fn verify_account(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    tag: &str,
+    challenge: &IconChallenge,
+    member: Option<&str>,
+) -> Result<Verification, Error> {
+    let mut note = String::new();
+    for attempt in 1..=VERIFY_ATTEMPTS {
+        display_challenge(ctx, msg, tag, challenge, member, &note).await?;
+        let resp = msg.clone().into_message().await?;
+        let mci = resp
+            .await_component_interaction(&ctx.serenity_context().shard)
+            .timeout(std::time::Duration::from_secs(VERIFY_TIME))
+            .await;
+        let Some(mci) = mci else {
+            return Ok(Verification::TimedOut);
+        };
+        mci.defer(&ctx.http()).await?;
+        if mci.data.custom_id == "cancel" {
+            return Ok(Verification::Cancelled);
+        }
+        note = match request("player", tag).await {
+            Ok(APIResult::Successful(player)) if challenge.is_met(&player) => {
+                info!("{} verified the ownership of {tag}", ctx.author().id);
+                return Ok(Verification::Verified);
+            }
+            Ok(APIResult::Successful(_)) => format!(
+                "The profile icon has not changed yet. It can take a minute for the change to show up. Attempts left: {}",
+                VERIFY_ATTEMPTS - attempt
+            ),
+            Ok(_) | Err(_) => format!(
+                "We could not reach the game to check the profile. Attempts left: {}",
+                VERIFY_ATTEMPTS - attempt
+            ),
+        };
+    }
+    Ok(Verification::Failed)
+}
+
+async fn display_challenge(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    tag: &str,
+    challenge: &IconChallenge,
+    member: Option<&str>,
+    note: &str,
 ) -> Result<(), Error> {
-    add_player(
-        ctx,
-        register.player.clone().unwrap(),
-        &register.region.clone().unwrap(),
-    )
+    let (title, owner) = match member {
+        Some(member) => (
+            "Verify your member's account!",
+            format!("that the account tagged {tag} belongs to <@{member}>, ask them to"),
+        ),
+        None => (
+            "Step 4: Verify your account!",
+            format!("that the account tagged {tag} is yours"),
+        ),
+    };
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title(title)
+                .description(format!(
+                    "To prove {owner}:\n1. {}.\n2. Press **Verify**.\nThe icon can be changed back once the registration is done.\n{note}",
+                    challenge.instruction()
+                ))
+                .color(0xFFFF00);
+            if let Some(icon) = challenge.required {
+                e.image(get_player_icon(icon));
+            }
+            e
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| {
+                    b.label("Verify")
+                        .style(poise::serenity_prelude::ButtonStyle::Success)
+                        .custom_id("verify")
+                })
+                .create_button(|b| {
+                    b.label("Cancel")
+                        .style(poise::serenity_prelude::ButtonStyle::Danger)
+                        .custom_id("cancel")
+                })
+            })
+        })
+    })
     .await?;
+    Ok(())
+}
+
+async fn confirm(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    register: &PlayerRegistration,
+) -> Result<(), Error> {
+    let region = register.region.clone().unwrap();
+    let database = ctx.data().database.regional_databases.get(&region).unwrap();
+    let config = get_config(ctx, &region).await;
+    if registration_full(database, &config).await? {
+        let position = join_waitlist(database, register.player.clone().unwrap()).await?;
+        return waitlisted(ctx, msg, position).await;
+    }
+    add_player(ctx, register.player.clone().unwrap(), &region).await?;
+    if let Some(position) = keep_within_cap(database, &config, &ctx.author().id.to_string()).await?
+    {
+        return waitlisted(ctx, msg, position).await;
+    }
     if let Err(e) = assign_role(ctx, ctx.author(), &register.region).await {
         return prompt(ctx, msg, "ERROR", format!("{e}"), None, Some(0xFF0000)).await;
     }
+    let tag = match &register.player {
+        Some(player) => Player::from_document(player)?.tag,
+        None => String::new(),
+    };
+    let team_note = match team_size(&config) {
+        1 => "",
+        _ => "\nThis is a team tournament: you are the captain! Open 👥 in the menu to invite your members and confirm your roster.",
+    };
     prompt(
         ctx,
         msg,
         "Congratulations! You are one of our participants!",
-        format!("<@{}>, we have collected your registration with the account tagged {}\nYou can run </menu:1211629882564677664> again to view your registration!", ctx.author().id, register.player.clone().unwrap().get_str("tag").unwrap()),
+        format!("<@{}>, we have collected your registration with the account tagged {}\nYou can run </menu:1211629882564677664> again to view your registration!{}", ctx.author().id, tag, team_note),
         None,
         Some(0xFFFF00)).await
 }
 
+async fn waitlisted(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    position: usize,
+) -> Result<(), Error> {
+    prompt(
+        ctx,
+        msg,
+        "The tournament is full!",
+        format!("<@{}>, all places of the tournament have been taken, so you are **#{position}** on the waitlist.\nIf a place frees up before the tournament starts, you will be registered automatically and notified by direct message!", ctx.author().id),
+        None,
+        Some(0xFFA500),
+    )
+    .await
+}
+
 async fn cancel(ctx: &Context<'_>, msg: &ReplyHandle<'_>) -> Result<(), Error> {
     prompt(
         ctx,
@@ -259,12 +503,27 @@ async fn cancel(ctx: &Context<'_>, msg: &ReplyHandle<'_>) -> Result<(), Error> {
     .await
 }
 
+async fn banned(ctx: &Context<'_>, msg: &ReplyHandle<'_>, ban: &Document) -> Result<(), Error> {
+    prompt(
+        ctx,
+        msg,
+        "You are banned from this tournament!",
+        format!(
+            "{}\nIf you think this is a mistake, please issue to the Host or Moderator team!",
+            describe_ban(ban)
+        ),
+        None,
+        Some(0xFF0000),
+    )
+    .await
+}
+
 async fn already_used(
     ctx: &Context<'_>,
     msg: &ReplyHandle<'_>,
     player: Document,
 ) -> Result<(), Error> {
-    let discord_id = player.get_str("discord_id").unwrap();
+    let discord_id = Player::from_document(&player)?.discord_id;
     prompt(
         ctx,
         msg,
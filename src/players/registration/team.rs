@@ -0,0 +1,377 @@
+use crate::brawlstars::api::{request, APIResult};
+use crate::brawlstars::verify::IconChallenge;
+use crate::database::ban::find_ban;
+use crate::database::config::get_config;
+use crate::database::eligibility::{account_age_problem, failed_rules};
+use crate::database::find::{find_tag, TagHolder};
+use crate::database::models::Player;
+use crate::database::team::{is_roster_confirmed, members, team_size, team_size_label};
+use crate::discord::prompt::prompt;
+use crate::discord::role::assign_role;
+use crate::players::registration::register::{verify_account, Verification};
+use crate::{Context, Error};
+use dbc_bot::Region;
+use futures::StreamExt;
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::Collection;
+use poise::serenity_prelude::{ButtonStyle, CreateSelectMenuOption, UserId};
+use poise::ReplyHandle;
+
+const TIMEOUT: u64 = 300;
+
+#[derive(Debug, poise::Modal)]
+#[name = "Team Name"]
+struct TeamName {
+    #[name = "Name of your team"]
+    #[max_length = 30]
+    name: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Invite a Member"]
+struct TeamInvite {
+    #[name = "Player tag of the member"]
+    #[placeholder = "The tag should start with # For instance, #ABC123"]
+    #[min_length = 5]
+    #[max_length = 10]
+    tag: String,
+
+    #[name = "Discord ID of the member"]
+    #[placeholder = "Right click the member and copy their ID"]
+    discord_id: String,
+}
+
+/// Lets a captain name their team, invite members and confirm the roster.
+pub async fn team_menu(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    player: Document,
+) -> Result<(), Error> {
+    let region = Player::from_document(&player)?.region()?;
+    let database = ctx.data().database.regional_databases.get(&region).unwrap();
+    let collection: Collection<Document> = database.collection("Players");
+    let size = team_size(&get_config(ctx, &region).await);
+    let mut player = player;
+    display_team(ctx, msg, &player, size).await?;
+    let resp = msg.clone().into_message().await?;
+    let mut cic = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(TIMEOUT))
+        .build();
+    while let Some(mci) = &cic.next().await {
+        match mci.data.custom_id.as_str() {
+            "name" => {
+                if let Some(TeamName { name }) = poise::execute_modal_on_component_interaction::<
+                    TeamName,
+                >(ctx, mci.clone(), None, None)
+                .await?
+                {
+                    collection
+                        .update_one(
+                            doc! {"_id": player.get_object_id("_id")?},
+                            doc! {"$set": {"team_name": name.trim()}},
+                            None,
+                        )
+                        .await?;
+                }
+            }
+            "invite" => {
+                let Some(invite) = poise::execute_modal_on_component_interaction::<TeamInvite>(
+                    ctx,
+                    mci.clone(),
+                    None,
+                    None,
+                )
+                .await?
+                else {
+                    continue;
+                };
+                match make_member(ctx, msg, &region, &player, size, &invite).await? {
+                    Ok(member) => {
+                        collection
+                            .update_one(
+                                doc! {"_id": player.get_object_id("_id")?},
+                                doc! {"$push": {"members": member}},
+                                None,
+                            )
+                            .await?;
+                    }
+                    Err(reason) => {
+                        prompt(
+                            ctx,
+                            msg,
+                            "Failed to invite the member!",
+                            reason,
+                            None,
+                            Some(0xFF0000),
+                        )
+                        .await?;
+                        std::thread::sleep(std::time::Duration::from_secs(3));
+                    }
+                }
+            }
+            "kick" => {
+                mci.defer(&ctx.http()).await?;
+                collection
+                    .update_one(
+                        doc! {"_id": player.get_object_id("_id")?},
+                        doc! {"$pull": {"members": {"tag": mci.data.values[0].as_str()}}},
+                        None,
+                    )
+                    .await?;
+            }
+            "confirm" => {
+                mci.defer(&ctx.http()).await?;
+                return confirm_roster(ctx, msg, &collection, &player, &region).await;
+            }
+            _ => continue,
+        }
+        player = collection
+            .find_one(doc! {"_id": player.get_object_id("_id")?}, None)
+            .await?
+            .unwrap_or(player);
+        display_team(ctx, msg, &player, size).await?;
+    }
+    Ok(())
+}
+
+async fn display_team(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    player: &Document,
+    size: i32,
+) -> Result<(), Error> {
+    let members = members(player);
+    let confirmed = is_roster_confirmed(player);
+    let full = members.len() as i32 + 1 >= size;
+    let roster = members
+        .iter()
+        .map(|member| {
+            format!(
+                "- <@{}> {} ({})",
+                member.get_str("discord_id").unwrap_or("0"),
+                member.get_str("name").unwrap_or("Unknown"),
+                member.get_str("tag").unwrap_or("")
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title(format!(
+                "Team: {}",
+                player.get_str("team_name").unwrap_or("Not yet named")
+            ))
+            .description(format!(
+                r#"This is a **{}** tournament. Invite your members with their player tag and Discord ID, then confirm the roster.
+Only teams with a confirmed roster take part once the tournament starts!
+**Captain:** <@{}> {} ({})
+**Members ({}/{}):**
+{}
+**Roster:** {}"#,
+                team_size_label(size),
+                player.get_str("discord_id").unwrap_or("0"),
+                player.get_str("name").unwrap_or("Unknown"),
+                player.get_str("tag").unwrap_or(""),
+                members.len() + 1,
+                size,
+                if roster.is_empty() { "None yet" } else { &roster },
+                if confirmed { "Confirmed" } else { "Not confirmed" }
+            ))
+            .color(0xFFFF00)
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| {
+                    b.custom_id("name")
+                        .label("Name team")
+                        .disabled(confirmed)
+                        .style(ButtonStyle::Primary)
+                })
+                .create_button(|b| {
+                    b.custom_id("invite")
+                        .label("Invite member")
+                        .disabled(confirmed || full)
+                        .style(ButtonStyle::Primary)
+                })
+                .create_button(|b| {
+                    b.custom_id("confirm")
+                        .label("Confirm roster")
+                        .disabled(confirmed || !full)
+                        .style(ButtonStyle::Success)
+                })
+            });
+            if !confirmed && !members.is_empty() {
+                c.create_action_row(|a| {
+                    a.create_select_menu(|m| {
+                        m.custom_id("kick")
+                            .placeholder("Remove a member")
+                            .options(|o| {
+                                for member in &members {
+                                    let tag = member.get_str("tag").unwrap_or("");
+                                    let mut option = CreateSelectMenuOption::default();
+                                    option
+                                        .label(member.get_str("name").unwrap_or(tag))
+                                        .value(tag)
+                                        .description(tag);
+                                    o.add_option(option);
+                                }
+                                o
+                            })
+                    })
+                });
+            }
+            c
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Checks an invitation and returns the member to add, or why they cannot join the team. The
+/// member proves they own the account with an icon challenge, like players who register.
+async fn make_member(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+    player: &Document,
+    size: i32,
+    invite: &TeamInvite,
+) -> Result<Result<Document, String>, Error> {
+    if members(player).len() as i32 + 1 >= size {
+        return Ok(Err("Your team is already full!".to_string()));
+    }
+    let Ok(discord_id) = invite.discord_id.trim().parse::<u64>() else {
+        return Ok(Err(format!("{} is not a Discord ID!", invite.discord_id)));
+    };
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    if database
+        .collection::<Document>("Players")
+        .find_one(
+            doc! {"$or": [
+                {"discord_id": discord_id.to_string()},
+                {"members.discord_id": discord_id.to_string()},
+            ]},
+            None,
+        )
+        .await?
+        .is_some()
+    {
+        return Ok(Err(format!("<@{discord_id}> is already in a team!")));
+    }
+    let Ok(user) = UserId(discord_id).to_user(ctx.http()).await else {
+        return Ok(Err(format!("Failed to find the user <@{discord_id}>!")));
+    };
+    let tag = format!(
+        "#{}",
+        invite.tag.trim().trim_start_matches('#').to_uppercase()
+    );
+    let general = &ctx.data().database.general;
+    if find_ban(general, Some(&discord_id.to_string()), None, region)
+        .await?
+        .is_some()
+    {
+        return Ok(Err(format!(
+            "<@{discord_id}> is banned from this tournament!"
+        )));
+    }
+    match find_tag(ctx, &tag, Some(region)).await? {
+        Some(TagHolder::Ban(_)) => {
+            return Ok(Err(format!("{tag} is banned from this tournament!")));
+        }
+        Some(TagHolder::Player(other)) => {
+            return Ok(Err(format!(
+                "{tag} has already been registered by <@{}>!",
+                other.get_str("discord_id").unwrap_or("0")
+            )));
+        }
+        None => {}
+    }
+    let profile = match request("player", &tag).await {
+        Ok(APIResult::Successful(profile)) => profile,
+        Ok(APIResult::NotFound(_)) => return Ok(Err(format!("Failed to find the account {tag}!"))),
+        Ok(APIResult::APIError(_)) | Err(_) => {
+            return Ok(Err(
+                "The API is so uncanny! Please try again later".to_string()
+            ))
+        }
+    };
+    let config = get_config(ctx, region).await;
+    let mut failed = failed_rules(&config, &profile);
+    failed.extend(account_age_problem(
+        &config,
+        &discord_id.to_string(),
+        DateTime::now(),
+    ));
+    if !failed.is_empty() {
+        return Ok(Err(format!(
+            "The account {tag} does not meet the entry requirements of this region:\n{}",
+            failed
+                .iter()
+                .map(|reason| format!("- {reason}"))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )));
+    }
+    let member = discord_id.to_string();
+    let challenge = IconChallenge::new(&profile);
+    match verify_account(ctx, msg, &tag, &challenge, Some(&member)).await? {
+        Verification::Verified => Ok(Ok(doc! {
+            "tag": &tag,
+            "name": profile["name"].as_str().unwrap_or("Unknown"),
+            "discord_id": member,
+            "discord_name": &user.name,
+        })),
+        Verification::Cancelled => Ok(Err(format!("The invitation of <@{member}> was cancelled."))),
+        Verification::TimedOut | Verification::Failed => Ok(Err(format!(
+            "We could not confirm that {tag} belongs to <@{member}>. Invite them again once they are ready to change their profile icon."
+        ))),
+    }
+}
+
+async fn confirm_roster(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    collection: &Collection<Document>,
+    player: &Document,
+    region: &Region,
+) -> Result<(), Error> {
+    collection
+        .update_one(
+            doc! {"_id": player.get_object_id("_id")?},
+            doc! {"$set": {"roster_confirmed": true}},
+            None,
+        )
+        .await?;
+    let mut failed = vec![];
+    for member in members(player) {
+        let id = member.get_str("discord_id").unwrap_or("0").parse::<u64>()?;
+        let user = UserId(id).to_user(ctx.http()).await?;
+        if assign_role(ctx, &user, &Some(region.clone()))
+            .await
+            .is_err()
+        {
+            failed.push(format!("<@{id}>"));
+        }
+    }
+    let note = if failed.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\n⚠️ Failed to assign the role to {}. Please ask the Host for help!",
+            failed.join(", ")
+        )
+    };
+    prompt(
+        ctx,
+        msg,
+        "Your roster is confirmed!",
+        format!(
+            "Team {} is ready for the tournament! As the captain, you submit the results of your team.{note}",
+            player.get_str("team_name").unwrap_or("")
+        ),
+        None,
+        Some(0xFFFF00),
+    )
+    .await
+}
@@ -0,0 +1,56 @@
+use crate::database::check_in::check_in_open;
+use crate::database::config::find_config;
+use crate::database::waitlist::promote_from_waitlist;
+use crate::discord::role::assign_role_by_id;
+use crate::Error;
+use dbc_bot::Region;
+use mongodb::bson::Document;
+use mongodb::Database;
+use poise::serenity_prelude::{self as serenity, UserId};
+use tracing::{error, info};
+
+/// Registers the first waitlisted players in the places that have freed up, giving them the
+/// regional role and letting them know by direct message. Banned players are skipped.
+pub async fn promote_waitlisted(
+    ctx: &serenity::Context,
+    database: &Database,
+    general: &Database,
+    region: &Region,
+) -> Result<Vec<Document>, Error> {
+    let config = find_config(database).await;
+    let promoted = promote_from_waitlist(database, general, &config, region).await?;
+    let check_in = match check_in_open(&config) {
+        true => "\nCheck-in is open, so please check in from </menu:1211629882564677664> before the tournament starts!",
+        false => "",
+    };
+    for player in &promoted {
+        let Ok(id) = player.get_str("discord_id").unwrap_or("").parse::<u64>() else {
+            continue;
+        };
+        info!("Promoted {id} from the waitlist of {region}");
+        if let Err(e) = assign_role_by_id(ctx, &config, id).await {
+            error!("{e}");
+        }
+        let sent = match UserId(id).create_dm_channel(ctx).await {
+            Ok(channel) => channel
+                .send_message(ctx, |m| {
+                    m.embed(|e| {
+                        e.title("A place has freed up!")
+                            .description(format!(
+                                "You have been moved from the waitlist into the {} tournament with the account tagged {}.{check_in}",
+                                region.full(),
+                                player.get_str("tag").unwrap_or("")
+                            ))
+                            .color(0xFFFF00)
+                    })
+                })
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            error!("Failed to tell {id} they were promoted from the waitlist: {e}");
+        }
+    }
+    Ok(promoted)
+}
@@ -0,0 +1,178 @@
+//! Disputes of match results by the players who lost them.
+use crate::database::config::get_config;
+use crate::database::dispute::{dispute_deadline, open_dispute};
+use crate::database::find::find_player_by_discord_id;
+use crate::discord::log::{Log, LogType};
+use crate::discord::prompt::prompt;
+use crate::{Context, Error};
+use dbc_bot::Region;
+use futures::StreamExt;
+use mongodb::bson::Document;
+use poise::serenity_prelude::ButtonStyle;
+use poise::ReplyHandle;
+
+const TIMEOUT: u64 = 300;
+
+#[derive(Debug, poise::Modal)]
+#[name = "Dispute Result"]
+struct DisputeModal {
+    #[name = "What went wrong?"]
+    #[placeholder = "For example: my opponent played with a substitute in the second game"]
+    #[paragraph]
+    #[max_length = 1000]
+    reason: String,
+
+    #[name = "Screenshots or other evidence"]
+    #[placeholder = "Links to images, one per line. Copy the link of an image sent on Discord"]
+    #[paragraph]
+    evidence: Option<String>,
+}
+
+/// Lets the loser of a match dispute the result while the dispute window is open.
+pub async fn dispute_menu(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+    round: &str,
+    player: Document,
+) -> Result<(), Error> {
+    let config = get_config(ctx, region).await;
+    let Some(deadline) = dispute_deadline(&config, &player) else {
+        return prompt(
+            ctx,
+            msg,
+            "You can no longer dispute this result!",
+            "Results can only be disputed by the loser of a match, shortly after the match.",
+            None,
+            Some(0xFF0000),
+        )
+        .await;
+    };
+    let match_id = player.get_i32("match_id")?;
+    msg.edit(*ctx, |s| {
+        s.embed(|e| {
+            e.title("⚖️ Dispute Result")
+                .description(format!(
+                    r#"You lost {round} match {match_id}.
+If you believe the result is wrong, explain what happened and attach links to screenshots as evidence.
+The tournament will not move on until a host has reviewed your dispute.
+You can dispute this result until <t:{}:t> (<t:{}:R>)."#,
+                    deadline.timestamp_millis() / 1000,
+                    deadline.timestamp_millis() / 1000
+                ))
+                .color(0xFFFF00)
+        })
+        .components(|c| {
+            c.create_action_row(|a| {
+                a.create_button(|b| {
+                    b.custom_id("open_modal")
+                        .label("Open dispute")
+                        .style(ButtonStyle::Danger)
+                })
+                .create_button(|b| {
+                    b.custom_id("cancel")
+                        .label("Cancel")
+                        .style(ButtonStyle::Secondary)
+                })
+            })
+        })
+    })
+    .await?;
+    let resp = msg.clone().into_message().await?;
+    let mut cic = resp
+        .await_component_interactions(&ctx.serenity_context().shard)
+        .timeout(std::time::Duration::from_secs(TIMEOUT))
+        .build();
+    while let Some(mci) = &cic.next().await {
+        match mci.data.custom_id.as_str() {
+            "open_modal" => {
+                let Some(DisputeModal { reason, evidence }) =
+                    poise::execute_modal_on_component_interaction::<DisputeModal>(
+                        ctx,
+                        mci.clone(),
+                        None,
+                        None,
+                    )
+                    .await?
+                else {
+                    continue;
+                };
+                return submit_dispute(ctx, msg, region, round, reason, evidence).await;
+            }
+            "cancel" => {
+                mci.defer(&ctx.http()).await?;
+                return prompt(
+                    ctx,
+                    msg,
+                    "Dispute cancelled",
+                    "The result of your match stands.",
+                    None,
+                    None,
+                )
+                .await;
+            }
+            _ => continue,
+        }
+    }
+    Ok(())
+}
+
+async fn submit_dispute(
+    ctx: &Context<'_>,
+    msg: &ReplyHandle<'_>,
+    region: &Region,
+    round: &str,
+    reason: String,
+    evidence: Option<String>,
+) -> Result<(), Error> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return prompt(
+            ctx,
+            msg,
+            "Unable to open the dispute!",
+            "Please explain why the result is wrong!",
+            None,
+            Some(0xFF0000),
+        )
+        .await;
+    }
+    let evidence: Vec<String> = evidence
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter(|link| link.starts_with("https://") || link.starts_with("http://"))
+        .map(str::to_string)
+        .collect();
+    // The result may have changed, or the window closed, while the form was open
+    let config = get_config(ctx, region).await;
+    let player = find_player_by_discord_id(ctx, region, ctx.author().id.0, round).await?;
+    let Some(player) = player.filter(|player| dispute_deadline(&config, player).is_some()) else {
+        return prompt(
+            ctx,
+            msg,
+            "You can no longer dispute this result!",
+            "The dispute window has closed or the result has already been disputed.",
+            None,
+            Some(0xFF0000),
+        )
+        .await;
+    };
+    let database = ctx.data().database.regional_databases.get(region).unwrap();
+    open_dispute(database, round, &player, reason, evidence.clone()).await?;
+    let log = Log::new(ctx, region, LogType::Dispute).await?;
+    let log_msg = log
+        .send_dispute_log(round, player.get_i32("match_id")?, reason, &evidence)
+        .await?;
+    prompt(
+        ctx,
+        msg,
+        "Dispute opened!",
+        format!(
+            "The hosts have been notified and will review your dispute [here]({}). You will hear from them soon!",
+            log_msg.link()
+        ),
+        None,
+        Some(0x50C878),
+    )
+    .await
+}
@@ -1,3 +1,6 @@
+pub mod dispute;
 pub mod ready;
+pub mod scheduler;
 pub mod submit;
 pub mod view2;
+pub mod watcher;
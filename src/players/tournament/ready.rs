@@ -1,5 +1,10 @@
 use crate::{
-    database::{config::get_config, find::find_round_from_config, update::set_ready},
+    database::{
+        config::get_config,
+        find::{find_player_in_current_rounds, find_round_from_config},
+        models::Player,
+        update::set_ready,
+    },
     discord::prompt::prompt,
     Context, Error,
 };
@@ -14,8 +19,12 @@ pub async fn ready(
     region: &Region,
     player: Document,
 ) -> Result<(), Error> {
-    let round = find_round_from_config(&get_config(ctx, region).await);
-    let discord_id = player.get_str("discord_id").unwrap();
+    let player = Player::from_document(&player)?;
+    let discord_id = player.discord_id.as_str();
+    let round = match find_player_in_current_rounds(ctx, region, player.user_id()?).await? {
+        Some((round, _)) => round,
+        None => find_round_from_config(&get_config(ctx, region).await),
+    };
     set_ready(ctx, region, &round, discord_id).await?;
     prompt(
         ctx,
@@ -0,0 +1,200 @@
+//! Background scheduler of timed tournament events.
+//!
+//! Every region is checked on each tick. Registration is opened and closed, check-in is opened and
+//! the tournament is started at the times scheduled by the hosts, and the result of each is logged in the log
+//! channel. While a tournament with a `round_length` is running, the current round is given its
+//! deadline the first time it is seen, the players of unfinished matches are sent a direct message
+//! when a reminder comes due, and once the deadline passes the unfinished matches are resolved and
+//! logged.
+use crate::database::check_in::{check_in_open, open_check_in};
+use crate::database::config::{find_config, toggle_reg_config};
+use crate::database::deadline::{
+    due_reminders, mark_reminded, resolve_deadline, round_length, schedule_deadline,
+    unfinished_matches,
+};
+use crate::database::find::{find_rounds_from_config, is_disqualified, is_mannequin};
+use crate::database::repository::Repository;
+use crate::database::schedule::{clear_event, due_events, registration_ready, Event};
+use crate::database::team::members;
+use crate::discord::log::{Log, LogType};
+use crate::host::tournament::setup::start_or_revert;
+use crate::players::registration::check_in::announce_check_in;
+use crate::players::tournament::watcher::WATCH_TICK;
+use crate::Error;
+use dbc_bot::Region;
+use mongodb::bson::{DateTime, Document};
+use mongodb::Database;
+use poise::serenity_prelude::{self as serenity, UserId};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Carries out the schedule of every region for the rest of the bot's life.
+pub async fn run_scheduler(
+    ctx: serenity::Context,
+    general: Database,
+    databases: HashMap<Region, Database>,
+) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(WATCH_TICK));
+    loop {
+        ticker.tick().await;
+        for (region, database) in &databases {
+            if let Err(e) = carry_out_events(&ctx, &general, database, region).await {
+                error!("Failed to carry out the schedule of {region}: {e}");
+            }
+            if let Err(e) = check_deadline(&ctx, &general, database, region).await {
+                error!("Failed to check the round deadline of {region}: {e}");
+            }
+        }
+    }
+}
+
+/// Opens or closes the registration, opens the check-in or starts the tournament, when the time has
+/// come.
+async fn carry_out_events(
+    ctx: &serenity::Context,
+    general: &Database,
+    database: &Database,
+    region: &Region,
+) -> Result<(), Error> {
+    let config = find_config(database).await;
+    for event in due_events(&config, DateTime::now()) {
+        // Cleared first, so that an event that fails is not tried again on every tick
+        clear_event(database, event).await?;
+        let config = find_config(database).await;
+        let registration = config.get_bool("registration").unwrap_or(false);
+        let tournament = config.get_bool("tournament").unwrap_or(false);
+        let outcome = match event {
+            Event::OpenRegistration | Event::StartTournament if tournament => {
+                Err("The tournament is already running.".to_string())
+            }
+            Event::OpenRegistration if registration => {
+                Err("Registration is already open.".to_string())
+            }
+            Event::OpenRegistration | Event::StartTournament if !registration_ready(&config) => {
+                Err("The mode, role, channel and bracket channel have to be set first.".to_string())
+            }
+            Event::OpenRegistration => {
+                database.update_config(toggle_reg_config(true)).await?;
+                Ok("Registration is now open!".to_string())
+            }
+            Event::CloseRegistration if !registration => {
+                Err("Registration is already closed.".to_string())
+            }
+            Event::CloseRegistration => {
+                database.update_config(toggle_reg_config(false)).await?;
+                Ok("Registration is now closed!".to_string())
+            }
+            Event::OpenCheckIn if tournament => {
+                Err("The tournament is already running.".to_string())
+            }
+            Event::OpenCheckIn if check_in_open(&config) => {
+                Err("Check-in is already open.".to_string())
+            }
+            Event::OpenCheckIn => {
+                open_check_in(database).await?;
+                announce_check_in(ctx, database, region).await?;
+                Ok(
+                    "Check-in is now open! Registered players have been sent a direct message."
+                        .to_string(),
+                )
+            }
+            Event::StartTournament => match start_or_revert(ctx, database, general, region).await {
+                Ok(launch) => Ok(launch.summary()),
+                Err(e) => Err(e.to_string()),
+            },
+        };
+        info!("Scheduled {event:?} in {region}: {outcome:?}");
+        Log::scheduled(ctx, general, database, region, LogType::Schedule)
+            .await?
+            .send_schedule_log(event, &outcome)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn check_deadline(
+    ctx: &serenity::Context,
+    general: &Database,
+    database: &Database,
+    region: &Region,
+) -> Result<(), Error> {
+    let config = find_config(database).await;
+    if !config.get_bool("tournament").unwrap_or(false)
+        || config.get_i32("round").unwrap_or(0) == 0
+        || round_length(&config).is_none()
+    {
+        return Ok(());
+    }
+    let now = DateTime::now();
+    let Some(deadline) = schedule_deadline(database, &config, now).await? else {
+        return Ok(());
+    };
+    if now < deadline {
+        let due = due_reminders(&config, deadline, now);
+        if !due.is_empty() {
+            remind_players(ctx, database, &config, deadline).await?;
+            mark_reminded(database, &config, &due).await?;
+        }
+        return Ok(());
+    }
+    let outcomes = resolve_deadline(database, region).await?;
+    info!(
+        "Deadline of {region} has passed, {} unfinished matches resolved",
+        outcomes.len()
+    );
+    let rounds = find_rounds_from_config(&config).join(" & ");
+    Log::scheduled(ctx, general, database, region, LogType::Deadline)
+        .await?
+        .send_deadline_log(&rounds, &outcomes)
+        .await?;
+    Ok(())
+}
+
+/// Sends every player of an unfinished match a reminder of the deadline.
+async fn remind_players(
+    ctx: &serenity::Context,
+    database: &Database,
+    config: &Document,
+    deadline: DateTime,
+) -> Result<(), Error> {
+    let timestamp = deadline.timestamp_millis() / 1000;
+    for (round, match_id, players) in unfinished_matches(database, config).await? {
+        for player in players
+            .iter()
+            .filter(|player| !is_mannequin(player) && !is_disqualified(player))
+        {
+            let mut ids = vec![player.get_str("discord_id").unwrap_or("0").to_string()];
+            ids.extend(
+                members(player)
+                    .iter()
+                    .filter_map(|member| member.get_str("discord_id").ok().map(str::to_string)),
+            );
+            for id in ids {
+                let Ok(id) = id.parse::<u64>() else {
+                    continue;
+                };
+                let sent = match UserId(id).create_dm_channel(ctx).await {
+                    Ok(channel) => channel
+                        .send_message(ctx, |m| {
+                            m.embed(|e| {
+                                e.title("Your match is due soon!")
+                                    .description(format!(
+                                        r#"Your {round} match {match_id} has to be played before <t:{timestamp}:F> (<t:{timestamp}:R>).
+If only one player of the match is ready by then, they advance. If neither is, both players are out of the tournament."#
+                                    ))
+                                    .color(0xFFA500)
+                            })
+                        })
+                        .await
+                        .map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = sent {
+                    error!("Failed to remind {id} of the deadline: {e}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,19 +1,28 @@
+use crate::bracket_tournament::best_of::{best_of, decide, wins_needed};
+use crate::bracket_tournament::double_elimination::{
+    decides_champion, loser_is_eliminated, parse_winners_round,
+};
+use crate::bracket_tournament::group_stage::parse_group_round;
+use crate::bracket_tournament::placements::{
+    has_third_place_match, post_placements, record_placements, THIRD_PLACE_MATCH,
+};
 use crate::brawlstars::api::{self, APIResult};
 use crate::database::battle::battle_happened;
-use crate::database::config::get_config;
+use crate::database::config::{find_config, get_config};
 use crate::database::find::{
-    find_enemy_by_match_id_and_self_tag, find_round_from_config, find_self_by_discord_id,
-    is_disqualified, is_mannequin,
+    find_enemy_by_match_id_and_self_tag, find_player_in_current_rounds, is_disqualified,
+    is_mannequin,
 };
-use crate::database::update::update_match_id;
-use crate::database::update::update_result;
+use crate::database::models::{MatchSlot, Player, RegionConfig};
+use crate::database::team::{members, team_size, team_tags};
+use crate::database::update::{record_scored_result, update_result};
 use crate::discord::prompt::prompt;
-use crate::discord::role::remove_role;
+use crate::discord::role::remove_role_by_id;
 use crate::{Context, Error};
-use dbc_bot::{QuoteStripper, Region};
+use dbc_bot::{DrawRule, Format, Region};
 use mongodb::bson::Document;
-use mongodb::Collection;
-use poise::serenity_prelude::{ChannelId, UserId};
+use mongodb::{Collection, Database};
+use poise::serenity_prelude::{self as serenity, ChannelId, Message, Timestamp};
 use poise::ReplyHandle;
 use tracing::error;
 
@@ -34,78 +43,75 @@ pub async fn submit_result(
         Some(0xFFFF00),
     )
     .await?;
-    let round = find_round_from_config(&get_config(ctx, region).await);
     //Check if the user is in the tournament
-    let caller = match find_self_by_discord_id(ctx, round).await.unwrap() {
-        Some(caller) => caller,
-        None => {
-            return prompt(
-                ctx,
-                msg,
-                "Sorry, you are not in the tournament!",
-                "You have to be in a tournament to use this command!",
-                Some(HAMSTER_VIOLIN_MEME),
-                Some(0xFF0000),
-            )
-            .await;
-        }
-    };
-    let region = Region::find_key(
-        caller
-            .get("region")
-            .unwrap()
-            .to_string()
-            .strip_quote()
-            .as_str(),
-    )
-    .unwrap();
+    let (round_name, caller) =
+        match find_player_in_current_rounds(ctx, region, ctx.author().id.0).await? {
+            Some(caller) => caller,
+            None => {
+                return prompt(
+                    ctx,
+                    msg,
+                    "Sorry, you are not in the tournament!",
+                    "You have to be in a tournament to use this command!",
+                    Some(HAMSTER_VIOLIN_MEME),
+                    Some(0xFF0000),
+                )
+                .await;
+            }
+        };
+    let player = Player::from_document(&caller)?;
+    let region = player.region()?;
 
     let database = ctx.data().database.regional_databases.get(&region).unwrap();
     let config = get_config(ctx, &region).await;
-    let channel = config
-        .get("channel")
-        .unwrap()
-        .as_str()
-        .unwrap()
-        .parse::<u64>()
-        .unwrap();
-    let channel_to_announce = ChannelId(channel);
+    let settings = RegionConfig::from_document(&config)?;
+    let channel_to_announce = ChannelId(settings.channel_id()?);
 
     //Get player document via their discord_id
-    let match_id: i32 = caller.get_i32("match_id").unwrap();
-    let caller_tag = caller.get_str("tag").unwrap();
+    let match_id: i32 = player.match_id()?;
+    let caller_tag = player.tag.as_str();
 
-    let mode = config.get_str("mode").unwrap();
-    let map = config.get_str("map").unwrap_or("Any");
-    let round_name = find_round_from_config(&config);
+    let mode = settings.mode()?;
+    let map = settings.map();
     let current_round: Collection<Document> = database.collection(&round_name);
-    let round = config.get("round").unwrap().as_i32().unwrap();
+    let (won, next) = round_labels(&config, &round_name);
     let caller = match battle_happened(ctx, caller_tag, &current_round, msg).await? {
         Some(caller) => caller, // Battle did not happen yet
         None => return Ok(()),  // Battle already happened
     };
     let enemy =
-        find_enemy_by_match_id_and_self_tag(ctx, &region, &round_name, &match_id, caller_tag)
+        match find_enemy_by_match_id_and_self_tag(ctx, &region, &round_name, &match_id, caller_tag)
             .await
-            .unwrap();
+        {
+            Some(enemy) => enemy,
+            None => {
+                return prompt(
+                    ctx,
+                    msg,
+                    "Your opponent is not decided yet!",
+                    "Your opponent is still playing their previous match. Please try again later!",
+                    None,
+                    Some(0xFFFF00),
+                )
+                .await;
+            }
+        };
     if is_mannequin(&enemy) || is_disqualified(&enemy) {
-        update_result(ctx, &region, &round_name, &caller, &enemy, None).await?;
+        if !update_result(ctx, &region, &round_name, &caller, &enemy, None).await? {
+            return already_recorded(ctx, msg).await;
+        }
         let m = channel_to_announce
             .send_message(ctx, |m| {
                 m.embed(|e| {
                     e.title("Result is here!")
                         .thumbnail(format!(
                             "https://cdn-old.brawlify.com/profile/{}.png",
-                            caller.get_i64("icon").unwrap_or(28000000)
+                            player.icon.unwrap_or(28000000)
                         ))
                         .description(format!(
-                        "Congratulations! <@{}> ({}-{}) has won round {} and proceeds to round {}!",
-                        caller.get_str("discord_id").unwrap(),
-                        caller.get_str("name").unwrap(),
-                        caller.get_str("tag").unwrap(),
-                        round,
-                        round + 1
-                    ))
+                            "Congratulations! <@{}> ({}-{}) has won {} and proceeds to {}!",
+                            player.discord_id, player.name, player.tag, won, next
+                        ))
                         .color(0xFFFF00)
                         .timestamp(ctx.created_at())
                 })
@@ -121,6 +127,9 @@ pub async fn submit_result(
         }).await?;
 
         // update_bracket(ctx, None).await?;
+        if let Some(placements) = record_placements(database, false).await? {
+            post_placements(ctx, database, &region, &placements).await?;
+        }
         return Ok(());
     }
 
@@ -128,46 +137,26 @@ pub async fn submit_result(
     // let bracket_chn_id = config.get_str("bracket_channel").unwrap();
     // let server_id = ctx.guild_id().unwrap().0;
 
-    match get_result(mode, map, caller, enemy).await {
-        Some(players) => {
-            let (winner, defeated) = players;
-            if round < config.get("total").unwrap().as_i32().unwrap() {
-                update_result(ctx, &region, &round_name, &winner, &defeated, None).await?;
-                let defeated_user = UserId(
-                    defeated
-                        .get_str("discord_id")
-                        .unwrap_or("0")
-                        .parse::<u64>()?,
-                )
-                .to_user(ctx.http())
-                .await?;
-                if let Err(e) = remove_role(ctx, &defeated_user, &region).await {
-                    error!("{e}");
-                }
-                // update_bracket(ctx, None).await?;
-                let m = channel_to_announce
-                    .send_message(ctx, |m| {
-                        m.embed(|e| {
-                            e.title("Result is here!")
-                            .thumbnail(format!(
-                                "https://cdn-old.brawlify.com/profile/{}.png",
-                                winner.get_i64("icon").unwrap_or(28000000)
-                            ))
-                                .description(format!(
-                                    r#"Congratulations! <@{}> ({}-{}) has won round {} and proceeds to round {}!"#,
-                                    winner.get_str("discord_id").unwrap(),
-                                    winner.get_str("name").unwrap(),
-                                    winner.get_str("tag").unwrap(),
-                                    round,
-                                    round + 1 // guild = server_id,
-                                              // chn = bracket_chn_id,
-                                              // msg_id = bracket_msg_id
-                                ))
-                                .color(0xFFFF00)
-                                .timestamp(ctx.created_at())
-                        })
-                    })
-                    .await?;
+    let best_of = best_of(&config, &round_name);
+    let draw_rule = DrawRule::from_config(&config);
+    let team_size = team_size(&config) as usize;
+    match get_result(mode, map, caller, enemy, best_of, &draw_rule, team_size).await {
+        Some((winner, defeated, score)) => {
+            let Some((m, champion)) = conclude_match(
+                ctx.serenity_context(),
+                database,
+                &region,
+                &round_name,
+                &winner,
+                &defeated,
+                Some(score),
+            )
+            .await?
+            else {
+                return already_recorded(ctx, msg).await;
+            };
+            let winner = Player::from_document(&winner)?;
+            if !champion {
                 msg.edit(*ctx, |s| {
                     s.embed(|e| {
                         e.title("Result is here!")
@@ -183,32 +172,19 @@ pub async fn submit_result(
                 })
                 .await?;
             } else {
-                update_result(ctx, &region, &round_name, &winner, &defeated, None).await?;
-                // update_bracket(ctx, None).await?;
-                let m = channel_to_announce
-                    .send_message(ctx, |m| {
-                        m.embed(|e| {
-                            e.title("Result is here!").description(format!(
-                                "CONGRATULATIONS! {}({}) IS THE TOURNAMENT CHAMPION!",
-                                winner.get_str("name").unwrap(),
-                                winner.get_str("tag").unwrap()
-                            ))
-                        })
-                    })
-                    .await?;
                 msg.edit(*ctx, |s| {
                     s.embed(|e| {
                         e.title("Result is here!")
                             .thumbnail(format!(
                                 "https://cdn-old.brawlify.com/profile/{}.png",
-                                winner.get_i64("icon").unwrap_or(28000000)
+                                winner.icon.unwrap_or(28000000)
                             ))
                             .description(format!(
                                 "CONGRATULATIONS! <@{}>({}-{}) IS THE TOURNAMENT CHAMPION!\n
 Your result is shown here [here]({})!",
-                                winner.get_str("discord_id").unwrap(),
-                                winner.get_str("name").unwrap(),
-                                winner.get_str("tag").unwrap(),
+                                winner.discord_id,
+                                winner.name,
+                                winner.tag,
                                 m.link()
                             ))
                             .color(0xFFFF00)
@@ -229,10 +205,13 @@ It may take up to 30 seconds for a new battle to appear in the battle log!
 In the meantime, please make sure that all of the recent battles satisfy these conditions: 
 - ⚔️ Mode: {mode}
 - 🗺️ Map: {map}
+- 🎯 Best of {best_of}: first to {needed} wins. {draws}
 - 🧑‍🤝‍🧑 Friendly room
 - 🤖 Turn OFF all bots"#,
                     mode = config.get_str("mode").unwrap_or("Any"),
-                    map = config.get_str("map").unwrap_or("Any")
+                    map = config.get_str("map").unwrap_or("Any"),
+                    needed = wins_needed(best_of),
+                    draws = draw_rule.describe()
                 ),
                 None,
                 Some(0xFFFF00),
@@ -243,63 +222,215 @@ In the meantime, please make sure that all of the recent battles satisfy these c
     Ok(())
 }
 
-async fn get_result(
+/// Tells the player that their match was recorded in the meantime, e.g. by a second click.
+async fn already_recorded(ctx: &Context<'_>, msg: &ReplyHandle<'_>) -> Result<(), Error> {
+    prompt(
+        ctx,
+        msg,
+        "You've already played this round!",
+        "The result of your match has already been recorded. Please wait until next round starts!",
+        None,
+        Some(0x00FF00),
+    )
+    .await
+}
+
+/// Whether the winner of a match becomes the tournament champion, and whether the loser is out.
+pub fn match_outcome(config: &Document, round_name: &str, winner: &Document) -> (bool, bool) {
+    let bracket_reset = config.get_bool("bracket_reset").unwrap_or(false);
+    match Format::from_config(config) {
+        Format::DoubleElimination => (
+            decides_champion(round_name, winner, bracket_reset),
+            loser_is_eliminated(round_name, winner, bracket_reset),
+        ),
+        Format::Swiss => (false, false),
+        Format::GroupStage if parse_group_round(round_name).is_some() => (false, false),
+        Format::SingleElimination | Format::GroupStage if round_name == THIRD_PLACE_MATCH => {
+            (false, true)
+        }
+        Format::SingleElimination | Format::GroupStage => {
+            let round = parse_winners_round(round_name).unwrap_or(0);
+            let total = config.get_i32("total").unwrap_or(0);
+            // Semifinal losers still play the third-place match
+            (
+                round >= total,
+                !(has_third_place_match(config) && round == total - 1),
+            )
+        }
+    }
+}
+
+/// Records a decided match, removes the role of an eliminated player and announces the result.
+///
+/// This is shared by results submitted by players and results found by the battle log watcher,
+/// which also know the games won by the winner and the loser. Returns the announcement and whether the winner is the tournament champion, or `None` if the
+/// match had already been recorded.
+pub async fn conclude_match(
+    ctx: &serenity::Context,
+    database: &Database,
+    region: &Region,
+    round_name: &str,
+    winner: &Document,
+    defeated: &Document,
+    score: Option<(i32, i32)>,
+) -> Result<Option<(Message, bool)>, Error> {
+    let config = find_config(database).await;
+    let channel_to_announce = ChannelId(RegionConfig::from_document(&config)?.channel_id()?);
+    let announced = Player::from_document(winner)?;
+    let (won, next) = round_labels(&config, round_name);
+    let (champion, eliminated) = match_outcome(&config, round_name, winner);
+    if !record_scored_result(database, region, round_name, winner, defeated, None, score).await? {
+        return Ok(None);
+    }
+    // update_bracket(ctx, None).await?;
+    let m = if !champion {
+        if eliminated {
+            let mut ids = vec![defeated.get_str("discord_id").unwrap_or("0").to_string()];
+            ids.extend(
+                members(defeated)
+                    .iter()
+                    .filter_map(|member| member.get_str("discord_id").ok().map(str::to_string)),
+            );
+            for id in ids {
+                if let Err(e) = remove_role_by_id(ctx, &config, id.parse::<u64>()?).await {
+                    error!("{e}");
+                }
+            }
+        }
+        channel_to_announce
+            .send_message(ctx, |m| {
+                m.embed(|e| {
+                    e.title("Result is here!")
+                        .thumbnail(format!(
+                            "https://cdn-old.brawlify.com/profile/{}.png",
+                            announced.icon.unwrap_or(28000000)
+                        ))
+                        .description(format!(
+                            r#"Congratulations! <@{}> ({}-{}) has won {} and proceeds to {}!"#,
+                            announced.discord_id, announced.name, announced.tag, won, next
+                        ))
+                        .color(0xFFFF00)
+                        .timestamp(Timestamp::now())
+                })
+            })
+            .await?
+    } else {
+        channel_to_announce
+            .send_message(ctx, |m| {
+                m.embed(|e| {
+                    e.title("Result is here!").description(format!(
+                        "CONGRATULATIONS! {}({}) IS THE TOURNAMENT CHAMPION!",
+                        announced.name, announced.tag
+                    ))
+                })
+            })
+            .await?
+    };
+    if let Some(placements) = record_placements(database, false).await? {
+        post_placements(ctx, database, region, &placements).await?;
+    }
+    Ok(Some((m, champion)))
+}
+
+/// Describes the round that was just won and where the winner goes next, for the announcements.
+fn round_labels(config: &Document, round_name: &str) -> (String, String) {
+    match Format::from_config(config) {
+        Format::DoubleElimination => (round_name.to_string(), "the next round".to_string()),
+        Format::Swiss => {
+            let round = parse_winners_round(round_name).unwrap_or(0);
+            let next = if round < config.get_i32("total").unwrap_or(0) {
+                format!("round {}", round + 1)
+            } else {
+                "the final standings".to_string()
+            };
+            (format!("round {}", round), next)
+        }
+        Format::GroupStage if parse_group_round(round_name).is_some() => (
+            format!("their {round_name} match"),
+            "the next matchday".to_string(),
+        ),
+        Format::SingleElimination | Format::GroupStage if round_name == THIRD_PLACE_MATCH => (
+            "the third-place match".to_string(),
+            "the podium".to_string(),
+        ),
+        Format::SingleElimination | Format::GroupStage => {
+            let round = parse_winners_round(round_name).unwrap_or(0);
+            (format!("round {}", round), format!("round {}", round + 1))
+        }
+    }
+}
+
+pub async fn get_result(
     mode: &str,
     map: &str,
     caller: Document,
     enemy: Document,
-) -> Option<(Document, Document)> {
-    let caller_tag = caller.get("tag").unwrap().as_str().unwrap();
-    let enemy_tag = enemy.get("tag").unwrap().as_str().unwrap();
+    best_of: i32,
+    draw_rule: &DrawRule,
+    team_size: usize,
+) -> Option<(Document, Document, (i32, i32))> {
+    let Some(MatchSlot::Player(player)) = MatchSlot::from_document(&caller).ok() else {
+        return None;
+    };
+    let caller_tag = player.tag.as_str();
+    let (caller_tags, enemy_tags) = (team_tags(&caller), team_tags(&enemy));
     let logs = match api::request("battle_log", caller_tag).await {
         Ok(APIResult::Successful(battle_log)) => battle_log["items"].as_array().unwrap().clone(),
         Ok(APIResult::APIError(_)) => return None,
         Ok(APIResult::NotFound(_)) | Err(_) => return None,
     };
+    // Games played before a replay was ordered do not count
+    let replay_after = caller
+        .get_datetime("replay_after")
+        .or_else(|_| enemy.get_datetime("replay_after"))
+        .ok()
+        .map(|time| time.timestamp_millis());
     let mut results: Vec<String> = vec![];
 
     for log in logs.iter() {
-        if !log_check(log, mode, map) {
+        if !log_check(log, mode, map, team_size) {
             continue;
         }
+        if let Some(after) = replay_after {
+            if battle_time(log).unwrap_or(after) <= after {
+                continue;
+            }
+        }
 
-        let player1 = log["battle"]["teams"][0][0]["tag"].as_str().unwrap();
-        let player2 = log["battle"]["teams"][1][0]["tag"].as_str().unwrap();
-        if (compare_tag(caller_tag, player1) || compare_tag(caller_tag, player2))
-            && (compare_tag(enemy_tag, player1) || compare_tag(enemy_tag, player2))
+        let (side1, side2) = (&log["battle"]["teams"][0], &log["battle"]["teams"][1]);
+        if (is_team_side(side1, &caller_tags) && is_team_side(side2, &enemy_tags))
+            || (is_team_side(side1, &enemy_tags) && is_team_side(side2, &caller_tags))
         {
             results.push(log["battle"]["result"].as_str().unwrap().to_string());
         }
     }
-    //If there are more than 1 result (best of 2), then we need to check the time
-    if results.len() > 1 {
-        let mut is_victory: Option<bool> = None;
-        let mut count_victory = 0;
-        let mut count_defeat = 0;
+    // The battle log is newest first, so the match is replayed from the oldest battle
+    results.reverse();
+    match decide(&results, best_of, draw_rule) {
+        Some((wins, defeats)) if wins > defeats => Some((caller, enemy, (wins, defeats))),
+        Some((wins, defeats)) => Some((enemy, caller, (defeats, wins))),
+        None => None,
+    }
+}
 
-        for result in results.iter().rev() {
-            match result.as_str() {
-                "defeat" => count_defeat += 1,
-                "victory" => count_victory += 1,
-                _ => {} // Handle other cases if needed
-            }
+/// When a battle was played, in milliseconds since the epoch.
+fn battle_time(log: &serde_json::Value) -> Option<i64> {
+    let time = log["battleTime"].as_str()?;
+    chrono::NaiveDateTime::parse_from_str(time, "%Y%m%dT%H%M%S%.fZ")
+        .ok()
+        .map(|time| time.and_utc().timestamp_millis())
+}
 
-            if count_defeat == 2 && count_victory < 2 {
-                is_victory = Some(false);
-                break;
-            } else if count_victory >= 2 {
-                is_victory = Some(true);
-                break;
-            }
-        }
-        match is_victory {
-            Some(true) => Some((caller, enemy)),
-            Some(false) => Some((enemy, caller)),
-            None => None,
-        }
-    } else {
-        None
-    }
+/// Whether every registered member of a team, and nobody else, played on this side of a battle.
+fn is_team_side(side: &serde_json::Value, tags: &[String]) -> bool {
+    let Some(side) = side.as_array() else {
+        return false;
+    };
+    side.len() == tags.len()
+        && tags.iter().all(|tag| {
+            side.iter()
+                .any(|player| player["tag"].as_str().is_some_and(|t| compare_tag(tag, t)))
+        })
 }
 
 fn compare_tag(s1: &str, s2: &str) -> bool {
@@ -325,7 +456,7 @@ fn compare_strings(str1: &str, str2: &str) -> bool {
     str1_normalized == str2_normalized
 }
 
-fn log_check(log: &serde_json::Value, mode: &str, map: &str) -> bool {
+fn log_check(log: &serde_json::Value, mode: &str, map: &str, team_size: usize) -> bool {
     // info!("{:?}", log); // Debugging purposes
     match log["event"]["mode"].as_str() {
         Some(m) => {
@@ -353,7 +484,7 @@ fn log_check(log: &serde_json::Value, mode: &str, map: &str) -> bool {
     };
     match log["battle"]["teams"][0].as_array() {
         Some(t) => {
-            if t.len() > 1 {
+            if t.len() != team_size {
                 return false;
             }
         }
@@ -362,7 +493,7 @@ fn log_check(log: &serde_json::Value, mode: &str, map: &str) -> bool {
 
     match log["battle"]["teams"][1].as_array() {
         Some(t) => {
-            if t.len() > 1 {
+            if t.len() != team_size {
                 return false;
             }
         }
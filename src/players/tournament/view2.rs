@@ -1,14 +1,16 @@
 use std::f64::consts::E;
 
+use crate::bracket_tournament::best_of::{best_of, wins_needed};
 use crate::database::config::{self, get_config};
 use crate::database::find::{
-    find_enemy_by_match_id_and_self_tag, find_round_from_config, find_self_by_discord_id,
-    is_disqualified, is_mannequin,
+    find_enemy_by_match_id_and_self_tag, find_player_in_current_rounds, is_disqualified,
+    is_mannequin,
 };
+use crate::database::models::{Player, RegionConfig};
 use crate::discord::prompt::{self, prompt};
 use crate::visual::pre_battle::get_image;
 use crate::{Context, Error};
-use dbc_bot::{QuoteStripper, Region};
+use dbc_bot::{DrawRule, QuoteStripper, Region};
 use futures::{StreamExt, TryStreamExt};
 use mongodb::bson::{doc, Document};
 use poise::serenity_prelude::ButtonStyle;
@@ -21,8 +23,9 @@ pub async fn view_opponent_wrapper(
     region: &Region,
 ) -> Result<(), Error> {
     let config = get_config(ctx, region).await;
-    let round = find_round_from_config(&config);
-    let player = match find_self_by_discord_id(ctx, round).await.unwrap() {
+    let (round_name, player) = match find_player_in_current_rounds(ctx, region, ctx.author().id.0)
+        .await?
+    {
         Some(caller) => caller,
         None => {
             msg.edit(*ctx, |s| {
@@ -35,7 +38,8 @@ pub async fn view_opponent_wrapper(
             return Ok(());
         }
     };
-    if !(player.get_bool("battle").unwrap()) {
+    let caller = Player::from_document(&player)?;
+    if !caller.battle {
         return prompt(
             ctx,
             msg,
@@ -48,15 +52,13 @@ Please stay tuned for the announcement to know when next round starts!",
         )
         .await;
     }
-    let match_id: i32 = player.get_i32("match_id").unwrap();
-    let caller_tag = player.get_str("tag").unwrap();
-    let round_name = find_round_from_config(&config);
+    let match_id: i32 = caller.match_id()?;
     let enemy = match find_enemy_by_match_id_and_self_tag(
         ctx,
         region,
         &round_name,
         &match_id,
-        caller_tag,
+        &caller.tag,
     )
     .await
     {
@@ -86,7 +88,7 @@ Please stay tuned for the announcement to know when next round starts!",
             return Ok(());
         }
     };
-    view_opponent(ctx, msg, region, player, enemy, config).await
+    view_opponent(ctx, msg, region, &round_name, player, enemy, config).await
 }
 
 /// View your opponent
@@ -94,6 +96,7 @@ pub async fn view_opponent(
     ctx: &Context<'_>,
     msg: &ReplyHandle<'_>,
     region: &Region,
+    round: &str,
     player: Document,
     enemy: Document,
     config: Document,
@@ -107,9 +110,13 @@ pub async fn view_opponent(
         Some(0xFFFF00),
     )
     .await?;
-    let round = config.get_i32("round")?;
-    let match_id = player.get_i32("match_id")?;
-    let prebattle = match get_image(&player, &enemy, &config).await {
+    let caller = Player::from_document(&player)?;
+    let opponent = Player::from_document(&enemy)?;
+    let settings = RegionConfig::from_document(&config)?;
+    let mode = settings.mode()?;
+    let match_id = caller.match_id()?;
+    let best_of = best_of(&config, round);
+    let prebattle = match get_image(&player, &enemy, &config, round).await {
         Ok(prebattle) => prebattle,
         Err(e) => {
             info!("{e}");
@@ -134,10 +141,10 @@ pub async fn view_opponent(
             .embed(|e| {
                 e.title("**DISCORD BRAWL CUP TOURNAMENT**")
                     .description(format!(
-r#"# Round {round} - Match {match_id}
+r#"# {round} - Match {match_id}
 **<@{}> vs. <@{}>**
 **🗣️ Before you start:**
-Plan with your opponent to schedule at least 2 CONSECUTIVE battles.
+Plan with your opponent to schedule at least {needed} CONSECUTIVE battles.
 **⚙️ During the battle:**
 - 🧑‍🤝‍🧑 Set up a friendly room.
 ## ⚔️ Mode: {}.
@@ -149,12 +156,14 @@ Plan with your opponent to schedule at least 2 CONSECUTIVE battles.
 **⚠️ Note:**
 - Only the MOST RECENT determinable number of matches with the opponent is considered once you submit your results.
 - Due to limitations, only up to 25 battles are viewable, so please submit the result as soon as possible!
-# Remember this is FIRST TO 2 WINS tournament!"#, 
-                        player.get_str("discord_id").unwrap(),
-                        enemy.get_str("discord_id").unwrap(),
-                        config.get_str("mode").unwrap(),
-                        config.get_str("map").unwrap_or("Any")
-
+# Remember this is a BEST OF {best_of} match: FIRST TO {needed} WINS!
+{draws}"#, 
+                        caller.discord_id,
+                        opponent.discord_id,
+                        mode,
+                        settings.map(),
+                        needed = wins_needed(best_of),
+                        draws = DrawRule::from_config(&config).describe()
                     )
                     )
             })
@@ -188,8 +197,8 @@ Plan with your opponent to schedule at least 2 CONSECUTIVE battles.
                 msg,
                 "Sample message to copy",
                 format!("Hi <@{enemy_id}>({enemy_name}), I am your opponent in {round}. Let me know when you are available to play. Thanks!", 
-                enemy_id = enemy.get_str("discord_id").unwrap_or("0"),
-                enemy_name = enemy.get_str("name").unwrap_or("Unknown"),
+                enemy_id = opponent.discord_id,
+                enemy_name = opponent.name,
                 round = round
             ),
                 None,
@@ -215,8 +224,9 @@ pub async fn view_managers(ctx: &Context<'_>) -> Result<(), Error> {
         .find(doc! {"guild_id": &guild_id}, None)
         .await?;
     while let Some(manager) = managers.try_next().await? {
-        let role_id = manager.get("role_id").unwrap().to_string().strip_quote();
-        list.push(role_id);
+        if let Some(role_id) = manager.get("role_id") {
+            list.push(role_id.to_string().strip_quote());
+        }
     }
     let role_msg = list
         .iter()
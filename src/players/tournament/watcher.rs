@@ -0,0 +1,140 @@
+//! Background watcher of battle logs.
+//!
+//! Every region with a running tournament is polled on the interval set in its config
+//! (`watch_interval`, in seconds, `0` turns the watcher off). Each unfinished match is checked with
+//! the same rules as a submitted result, and a decided match is recorded and announced as if a
+//! player had pressed submit.
+use crate::bracket_tournament::best_of::best_of;
+use crate::database::config::find_config;
+use crate::database::find::{find_rounds_from_config, is_disqualified, is_mannequin};
+use crate::database::team::team_size;
+use crate::players::tournament::submit::{conclude_match, get_result};
+use crate::Error;
+use dbc_bot::{DrawRule, Region};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::{Collection, Database};
+use poise::serenity_prelude as serenity;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+/// How often the watcher wakes up. Regions are polled at most this often.
+pub const WATCH_TICK: u64 = 30;
+pub const DEFAULT_WATCH_INTERVAL: i32 = 120;
+
+/// Seconds between two polls of a region, `None` when the watcher is turned off.
+pub fn watch_interval(config: &Document) -> Option<u64> {
+    match config
+        .get_i32("watch_interval")
+        .unwrap_or(DEFAULT_WATCH_INTERVAL)
+    {
+        interval if interval <= 0 => None,
+        interval => Some((interval as u64).max(WATCH_TICK)),
+    }
+}
+
+/// Polls the battle logs of every region for the rest of the bot's life.
+pub async fn watch_battle_logs(ctx: serenity::Context, databases: HashMap<Region, Database>) {
+    let mut last_polled: HashMap<Region, Instant> = HashMap::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs(WATCH_TICK));
+    loop {
+        ticker.tick().await;
+        for (region, database) in &databases {
+            let config = find_config(database).await;
+            let Some(interval) = watch_interval(&config) else {
+                continue;
+            };
+            if last_polled
+                .get(region)
+                .is_some_and(|last| last.elapsed() < Duration::from_secs(interval))
+            {
+                continue;
+            }
+            last_polled.insert(region.clone(), Instant::now());
+            if let Err(e) = watch_region(&ctx, database, region, &config).await {
+                error!("Failed to watch the battle logs of {region}: {e}");
+            }
+        }
+    }
+}
+
+/// Checks every unfinished match of the rounds currently played in a region.
+async fn watch_region(
+    ctx: &serenity::Context,
+    database: &Database,
+    region: &Region,
+    config: &Document,
+) -> Result<(), Error> {
+    if !config.get_bool("tournament").unwrap_or(false) || config.get_i32("round").unwrap_or(0) == 0
+    {
+        return Ok(());
+    }
+    let Ok(mode) = config.get_str("mode") else {
+        return Ok(());
+    };
+    let map = config.get_str("map").unwrap_or("Any");
+    let draw_rule = DrawRule::from_config(config);
+    let team_size = team_size(config) as usize;
+    for round in find_rounds_from_config(config) {
+        let best_of = best_of(config, &round);
+        let collection: Collection<Document> = database.collection(&round);
+        let unfinished: Vec<Document> = collection
+            .find(doc! {"battle": false, "tag": {"$ne": null}}, None)
+            .await?
+            .try_collect()
+            .await?;
+        let mut checked = HashSet::new();
+        for player in unfinished {
+            let Ok(match_id) = player.get_i32("match_id") else {
+                continue;
+            };
+            if !checked.insert(match_id) {
+                continue;
+            }
+            let enemy = collection
+                .find_one(
+                    doc! {"match_id": match_id, "_id": {"$ne": player.get_object_id("_id")?}},
+                    None,
+                )
+                .await?;
+            // Byes and disqualifications are claimed by the player who is left
+            let Some(enemy) = enemy.filter(|enemy| {
+                !is_mannequin(enemy) && !is_disqualified(enemy) && !is_disqualified(&player)
+            }) else {
+                continue;
+            };
+            let Some((winner, defeated, score)) =
+                get_result(mode, map, player, enemy, best_of, &draw_rule, team_size).await
+            else {
+                continue;
+            };
+            // The match may have been submitted while the battle log was fetched
+            if collection
+                .find_one(
+                    doc! {"_id": winner.get_object_id("_id")?, "battle": true},
+                    None,
+                )
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+            info!(
+                "Watcher recorded {round} match {match_id} in {region}: {} won",
+                winner.get_str("tag").unwrap_or("")
+            );
+            conclude_match(
+                ctx,
+                database,
+                region,
+                &round,
+                &winner,
+                &defeated,
+                Some(score),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
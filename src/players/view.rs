@@ -1,9 +1,9 @@
 use crate::brawlstars::api::{self, APIResult};
 use crate::brawlstars::player::stat;
+use crate::database::models::Player;
 use crate::discord::prompt;
 use crate::players::view::prompt::prompt;
 use crate::{Context, Error};
-use dbc_bot::Region;
 use mongodb::bson::Document;
 use poise::ReplyHandle;
 
@@ -12,9 +12,9 @@ pub async fn view_info(
     msg: &ReplyHandle<'_>,
     player: Document,
 ) -> Result<(), Error> {
-    let tag = player.get_str("tag").unwrap();
-    let region = Region::find_key(player.get_str("region").unwrap()).unwrap();
-    match api::request("player", tag).await {
+    let registered = Player::from_document(&player)?;
+    let region = registered.region()?;
+    match api::request("player", &registered.tag).await {
         Ok(APIResult::Successful(p)) => {
             msg.edit(*ctx, |s| {
                 s.components(|c| c).embed(|e| e.description("Hold on..."))
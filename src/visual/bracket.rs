@@ -0,0 +1,304 @@
+//! Drawing of the bracket images posted in the bracket channel.
+//!
+//! The winners bracket has a column per round plus one for the champion, the number of boxes
+//! halving from one column to the next. The losers bracket of a double elimination has a column
+//! per losers round followed by the grand final (and its reset), its rounds coming in pairs of equal
+//! size so that the number of boxes only halves every second column.
+use super::model::{BSImage, Component, Image, Rectangle, Text};
+use crate::Error;
+use chrono::{DateTime, Utc};
+use dbc_bot::{CustomError, Region};
+use image::{imageops::FilterType::Triangle, DynamicImage};
+use std::env;
+use std::io::Cursor;
+use tracing::error;
+
+const WIDTH: i64 = 10000;
+const HEIGHT: i64 = 5000;
+const HORIZONTAL_PADDING: f64 = 80.0;
+/// The proportions of the bracket are tuned for a tournament of this many rounds.
+const REFERENCE_ROUNDS: f64 = 6.0;
+/// Width of a box relative to its height, at the reference number of rounds.
+const REFERENCE_RATIO: f64 = 13.0;
+/// Font size at the reference number of rounds.
+const REFERENCE_FONT_SIZE: f64 = 25.0;
+const BOX_COLOR: u32 = 0xC0C0C0FF;
+const LINE_COLOR: u32 = 0x000000FF;
+const TEXT_COLOR: u32 = 0x000000FF;
+
+/// The matches of a tournament, as collected from its rounds.
+pub struct Bracket {
+    pub total: i32,
+    pub winners: Vec<BracketMatch>,
+    /// Losers rounds and grand finals of a double elimination, empty otherwise.
+    pub losers: Vec<BracketMatch>,
+    /// Grand final columns after the losers rounds, 0 without a losers bracket.
+    pub finals: i32,
+}
+
+/// One match of the bracket, drawn in the box of its match id in the given column.
+pub struct BracketMatch {
+    pub column: i32,
+    pub match_id: i32,
+    pub player1: String,
+    pub player2: String,
+    pub winner1: bool,
+    pub winner2: bool,
+    /// Whether the first player won, once the match is decided.
+    pub won: Option<bool>,
+    pub tag1: Option<String>,
+    pub tag2: Option<String>,
+    /// Games won by each player, when the match was decided from the battle log.
+    pub score: Option<(i32, i32)>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Draws the winners bracket of a tournament of `total` rounds as a PNG.
+pub async fn winners_bracket(
+    region: &Region,
+    total: i32,
+    matches: &[BracketMatch],
+) -> Result<Vec<u8>, Error> {
+    check_rounds(total)?;
+    let mut img = background(region)?;
+    let columns = total + 1;
+    let column_width = WIDTH as f64 / columns as f64;
+    let box_width = column_width - HORIZONTAL_PADDING;
+    let box_height = box_width / (total as f64 / REFERENCE_ROUNDS * REFERENCE_RATIO);
+    let font_size = font_size(total);
+    let champion = matches
+        .iter()
+        .find_map(|game| match (game.winner1, game.winner2) {
+            (true, _) => Some((game.match_id, &game.player1)),
+            (_, true) => Some((game.match_id, &game.player2)),
+            _ => None,
+        });
+
+    for i in 0..columns {
+        let games = 2_i32.pow((i - total).unsigned_abs());
+        let x_center = column_width * (i as f64 + 0.5);
+        let y_size = HEIGHT as f64 / games as f64;
+        draw_column(&mut img, i, columns, games, x_center, box_width, box_height).await?;
+        if i == columns - 1 {
+            if let Some((match_id, name)) = champion {
+                let y = y_size * ((match_id * 2 - 2) as f64 + 0.5);
+                draw_name(&mut img, name, x_center, y, font_size).await?;
+            }
+            continue;
+        }
+        for game in matches.iter().filter(|game| game.column - 1 == i) {
+            draw_match(&mut img, game, x_center, y_size, font_size).await?;
+        }
+    }
+    encode(img.build())
+}
+
+/// Draws the losers bracket of a tournament of `total` rounds as a PNG, followed by `finals` grand
+/// final columns and one for the champion.
+pub async fn losers_bracket(
+    region: &Region,
+    total: i32,
+    matches: &[BracketMatch],
+    finals: i32,
+) -> Result<Vec<u8>, Error> {
+    check_rounds(total)?;
+    let mut img = background(region)?;
+    let losers_rounds = (2 * (total - 1)).max(0);
+    let columns = losers_rounds + finals + 1;
+    let column_width = WIDTH as f64 / columns as f64;
+    let box_width = column_width - HORIZONTAL_PADDING;
+    let box_height = box_width / REFERENCE_RATIO;
+    let font_size = font_size((columns - 1).max(1));
+
+    for i in 0..columns {
+        let column = i + 1;
+        let slots = match column == columns {
+            true => 1,
+            false => 2 * losers_games(total, column, losers_rounds),
+        };
+        let x_center = column_width * (i as f64 + 0.5);
+        let y_size = HEIGHT as f64 / slots as f64;
+        draw_column(&mut img, i, columns, slots, x_center, box_width, box_height).await?;
+        if column == columns {
+            continue;
+        }
+        for game in matches.iter().filter(|game| game.column == column) {
+            draw_match(&mut img, game, x_center, y_size, font_size).await?;
+        }
+    }
+    encode(img.build())
+}
+
+/// A bracket needs at least one round to be drawn, which it only has once the tournament started.
+fn check_rounds(total: i32) -> Result<(), Error> {
+    if total < 1 {
+        return Err(Box::new(CustomError(
+            "The bracket has no rounds yet, the tournament has not started".to_string(),
+        )));
+    }
+    Ok(())
+}
+
+/// Matches in a column of the losers bracket.
+fn losers_games(total: i32, column: i32, losers_rounds: i32) -> i32 {
+    if column > losers_rounds {
+        return 1;
+    }
+    2_i32.pow((total - 1 - (column + 1) / 2).max(0) as u32)
+}
+
+fn font_size(columns: i32) -> u8 {
+    (REFERENCE_ROUNDS / columns as f64 * REFERENCE_FONT_SIZE).round() as u8
+}
+
+/// The preset of the region, stretched over the whole bracket.
+fn background(region: &Region) -> Result<BSImage, Error> {
+    let preset = match region {
+        Region::EU => "bracket_preset_eu.png",
+        Region::NASA => "bracket_preset_nasa.png",
+        Region::APAC => "bracket_preset_apac.png",
+    };
+    let current_dir = match env::current_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            error!("Failed to get current directory: {e}");
+            return Err(Box::new(CustomError(format!("{e}"))));
+        }
+    };
+    let bg_path = match current_dir
+        .join("assets/brackets")
+        .join(preset)
+        .into_os_string()
+        .into_string()
+    {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Failed to get bracket preset path: {:?}", e);
+            return Err(Box::new(CustomError(format!("{:?}", e))));
+        }
+    };
+    let mut img = BSImage::new(Some(WIDTH), Some(HEIGHT), bg_path, Some("Bracket"));
+    img.bg = img.bg.resize_exact(WIDTH as u32, HEIGHT as u32, Triangle);
+    Ok(img)
+}
+
+/// Draws the boxes of a column and the lines joining them to the neighbouring columns.
+async fn draw_column(
+    img: &mut BSImage,
+    i: i32,
+    columns: i32,
+    boxes: i32,
+    x_center: f64,
+    box_width: f64,
+    box_height: f64,
+) -> Result<(), Error> {
+    let y_size = HEIGHT as f64 / boxes as f64;
+    let left = x_center - box_width / 2.0;
+    let right = x_center + box_width / 2.0;
+    for j in 0..boxes {
+        let y_center = y_size * (j as f64 + 0.5);
+        let top = y_center - box_height / 2.0;
+        draw_rectangle(
+            img,
+            (left, top),
+            (right, y_center + box_height / 2.0),
+            BOX_COLOR,
+        )
+        .await?;
+    }
+    for j in 0..boxes {
+        let y_center = y_size * (j as f64 + 0.5);
+        let joint = right + HORIZONTAL_PADDING / 2.0;
+        if i != columns - 1 {
+            draw_rectangle(img, (right, y_center), (joint, y_center), LINE_COLOR).await?;
+        }
+        if i != 0 {
+            let stub = left - HORIZONTAL_PADDING / 2.0;
+            draw_rectangle(img, (stub, y_center), (left, y_center), LINE_COLOR).await?;
+        }
+        if j % 2 == 1 {
+            draw_rectangle(
+                img,
+                (joint, y_center - y_size),
+                (joint, y_center),
+                LINE_COLOR,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Fills the rectangle between two corners, both included, so that a line is 1 pixel thick.
+async fn draw_rectangle(
+    img: &mut BSImage,
+    (x1, y1): (f64, f64),
+    (x2, y2): (f64, f64),
+    color: u32,
+) -> Result<(), Error> {
+    let (x1, y1, x2, y2) = (x1 as i64, y1 as i64, x2 as i64, y2 as i64);
+    let rectangle = Rectangle {
+        width: x2 - x1 + 1,
+        height: y2 - y1 + 1,
+        color,
+        border: None,
+    }
+    .build()
+    .await?;
+    img.add_overlay(Component::new(rectangle, Some(x1), Some(y1), Some("box")));
+    Ok(())
+}
+
+async fn draw_match(
+    img: &mut BSImage,
+    game: &BracketMatch,
+    x_center: f64,
+    y_size: f64,
+    font_size: u8,
+) -> Result<(), Error> {
+    let y1 = y_size * ((game.match_id * 2 - 2) as f64 + 0.5);
+    let y2 = y_size * ((game.match_id * 2 - 1) as f64 + 0.5);
+    draw_name(img, &game.player1, x_center, y1, font_size).await?;
+    draw_name(img, &game.player2, x_center, y2, font_size).await
+}
+
+/// Writes a name centred on `x_center`, its top at `y`.
+async fn draw_name(
+    img: &mut BSImage,
+    name: &str,
+    x_center: f64,
+    y: f64,
+    font_size: u8,
+) -> Result<(), Error> {
+    let text = Text::new(name, font_size, TEXT_COLOR, None).build().await?;
+    let mut component = Component::new(text, None, Some(y as i64), Some(name));
+    component.set_x(x_center as i64 - component.width() / 2);
+    img.add_overlay(component);
+    Ok(())
+}
+
+fn encode(img: DynamicImage) -> Result<Vec<u8>, Error> {
+    let mut bytes = Cursor::new(vec![]);
+    img.write_to(&mut bytes, image::ImageOutputFormat::Png)?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_rounds, losers_bracket, losers_games, winners_bracket};
+    use dbc_bot::Region;
+
+    #[tokio::test]
+    async fn brackets_without_rounds_are_not_drawn() {
+        assert!(check_rounds(1).is_ok());
+        assert!(winners_bracket(&Region::EU, 0, &[]).await.is_err());
+        assert!(losers_bracket(&Region::EU, -1, &[], 2).await.is_err());
+    }
+
+    #[test]
+    fn losers_rounds_halve_every_second_column() {
+        let games: Vec<i32> = (1..=6).map(|column| losers_games(3, column, 4)).collect();
+        assert_eq!(games, vec![2, 2, 1, 1, 1, 1]);
+        assert_eq!(losers_games(1, 1, 0), 1);
+    }
+}
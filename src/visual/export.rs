@@ -0,0 +1,324 @@
+//! Export of the bracket as a scalable SVG and as a self-contained HTML page.
+//!
+//! Both are built from the same [`Bracket`] as the PNG posted in the bracket channel. Every match
+//! is a box with its two players, their score and the winner in bold; hovering a match shows the
+//! tags of the players, the score and when the match was decided.
+use super::bracket::{Bracket, BracketMatch};
+use crate::bracket_tournament::double_elimination::{
+    losers_round_name, losers_rounds, matches_in_round, GRAND_FINAL, GRAND_FINAL_RESET,
+};
+use dbc_bot::Region;
+
+const BOX_WIDTH: i32 = 220;
+const ROW_HEIGHT: i32 = 24;
+const BOX_HEIGHT: i32 = 2 * ROW_HEIGHT;
+const COLUMN_GAP: i32 = 48;
+const MATCH_GAP: i32 = 16;
+const HEADER_HEIGHT: i32 = 40;
+const MARGIN: i32 = 16;
+
+/// A round of the bracket, drawn as a column of matches.
+struct Column {
+    name: String,
+    /// Column of the matches of this round in [`BracketMatch::column`].
+    column: i32,
+    matches: i32,
+}
+
+/// The winners bracket, or the losers bracket and grand finals of a double elimination, as SVG.
+pub fn bracket_svg(bracket: &Bracket, losers: bool) -> String {
+    let (columns, matches) = match losers {
+        true => (losers_columns(bracket), &bracket.losers),
+        false => (winners_columns(bracket), &bracket.winners),
+    };
+    let rows = columns
+        .iter()
+        .map(|column| column.matches)
+        .max()
+        .unwrap_or(1);
+    let width = 2 * MARGIN + columns.len() as i32 * (BOX_WIDTH + COLUMN_GAP) - COLUMN_GAP;
+    let height = 2 * MARGIN + HEADER_HEIGHT + rows * (BOX_HEIGHT + MATCH_GAP);
+    let center = |index: usize, match_id: i32| -> (i32, i32) {
+        let column = &columns[index];
+        let slot = (height - 2 * MARGIN - HEADER_HEIGHT) / column.matches;
+        (
+            MARGIN + index as i32 * (BOX_WIDTH + COLUMN_GAP),
+            MARGIN + HEADER_HEIGHT + slot * (match_id - 1) + slot / 2,
+        )
+    };
+
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="{width}" height="{height}" font-family="'Lilita One', 'Trebuchet MS', sans-serif" font-size="14">
+<rect width="100%" height="100%" fill="#1e2230"/>
+"##
+    );
+    for (index, column) in columns.iter().enumerate() {
+        let (x, _) = center(index, 1);
+        svg.push_str(&format!(
+            r##"<text x="{}" y="{}" fill="#ffffff" font-size="18" text-anchor="middle">{}</text>
+"##,
+            x + BOX_WIDTH / 2,
+            MARGIN + HEADER_HEIGHT / 2,
+            escape(&column.name)
+        ));
+        // Lines from the matches of the previous round whose players come to this one
+        if index == 0 {
+            continue;
+        }
+        let previous = &columns[index - 1];
+        for match_id in 1..=column.matches {
+            let (x, y) = center(index, match_id);
+            let feeders = match previous.matches > column.matches {
+                true => vec![2 * match_id - 1, 2 * match_id],
+                false => vec![match_id],
+            };
+            for feeder in feeders {
+                let (from_x, from_y) = center(index - 1, feeder);
+                let (from_x, joint) = (from_x + BOX_WIDTH, x - COLUMN_GAP / 2);
+                svg.push_str(&format!(
+                    r##"<path d="M{from_x} {from_y}H{joint}V{y}H{x}" fill="none" stroke="#8a8fa3" stroke-width="2"/>
+"##
+                ));
+            }
+        }
+    }
+    for (index, column) in columns.iter().enumerate() {
+        for match_id in 1..=column.matches {
+            let game = matches
+                .iter()
+                .find(|game| game.column == column.column && game.match_id == match_id);
+            let (x, y) = center(index, match_id);
+            svg.push_str(&match_box(
+                x,
+                y - BOX_HEIGHT / 2,
+                &column.name,
+                match_id,
+                game,
+            ));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A page with both brackets and a panel showing the details of the match under the cursor.
+pub fn bracket_html(region: &Region, bracket: &Bracket) -> String {
+    let title = format!("{} tournament bracket", region.full());
+    let mut sections = format!(
+        "<h2>Bracket</h2>\n<div class=\"bracket\">\n{}</div>\n",
+        bracket_svg(bracket, false)
+    );
+    if bracket.finals > 0 {
+        sections.push_str(&format!(
+            "<h2>Losers bracket</h2>\n<div class=\"bracket\">\n{}</div>\n",
+            bracket_svg(bracket, true)
+        ));
+    }
+    format!(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+body {{ margin: 0; padding: 16px; background: #151821; color: #ffffff; font-family: 'Lilita One', 'Trebuchet MS', sans-serif; }}
+.bracket {{ overflow: auto; margin-bottom: 24px; }}
+.bracket svg {{ display: block; }}
+.match {{ cursor: default; }}
+.match:hover rect.box {{ stroke: #ffd54f; stroke-width: 3; }}
+#details {{ position: fixed; right: 16px; bottom: 16px; min-width: 240px; padding: 12px; border-radius: 8px; background: rgba(0, 0, 0, 0.85); white-space: pre-line; display: none; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{sections}<div id="details"></div>
+<script>
+const details = document.getElementById("details");
+for (const game of document.querySelectorAll(".match")) {{
+  game.addEventListener("mouseenter", () => {{
+    details.textContent = game.querySelector("title").textContent;
+    details.style.display = "block";
+  }});
+  game.addEventListener("mouseleave", () => {{
+    details.style.display = "none";
+  }});
+}}
+</script>
+</body>
+</html>
+"##,
+        title = escape(&title)
+    )
+}
+
+fn winners_columns(bracket: &Bracket) -> Vec<Column> {
+    (1..=bracket.total.max(1))
+        .map(|round| {
+            let name = format!("Round {round}");
+            Column {
+                matches: matches_in_round(&name, bracket.total),
+                name,
+                column: round,
+            }
+        })
+        .collect()
+}
+
+fn losers_columns(bracket: &Bracket) -> Vec<Column> {
+    let losers_rounds = losers_rounds(bracket.total);
+    let finals = [GRAND_FINAL, GRAND_FINAL_RESET]
+        .into_iter()
+        .take(bracket.finals as usize)
+        .map(str::to_string);
+    (1..=losers_rounds)
+        .map(losers_round_name)
+        .chain(finals)
+        .enumerate()
+        .map(|(index, name)| Column {
+            matches: matches_in_round(&name, bracket.total),
+            name,
+            column: index as i32 + 1,
+        })
+        .collect()
+}
+
+/// The box of a match with its top left corner at `(x, y)`, empty if nobody has reached it yet.
+fn match_box(x: i32, y: i32, round: &str, match_id: i32, game: Option<&BracketMatch>) -> String {
+    let mut details = format!("{round} - Match {match_id}");
+    let mut rows = String::new();
+    let players = game.map(|game| {
+        [
+            (&game.player1, &game.tag1, game.won == Some(true), 0),
+            (&game.player2, &game.tag2, game.won == Some(false), 1),
+        ]
+    });
+    for (row, (name, tag, won, side)) in players.into_iter().flatten().enumerate() {
+        let score = game
+            .and_then(|game| game.score)
+            .map(|(first, second)| if side == 0 { first } else { second });
+        let text_y = y + row as i32 * ROW_HEIGHT + ROW_HEIGHT / 2 + 5;
+        let weight = if won { "bold" } else { "normal" };
+        rows.push_str(&format!(
+            r##"<text x="{}" y="{text_y}" fill="#000000" font-weight="{weight}">{}</text>
+"##,
+            x + 8,
+            escape(truncate(name.trim(), 24).as_str())
+        ));
+        if let Some(score) = score {
+            rows.push_str(&format!(
+                r##"<text x="{}" y="{text_y}" fill="#000000" font-weight="{weight}" text-anchor="end">{score}</text>
+"##,
+                x + BOX_WIDTH - 8
+            ));
+        }
+        let name = match name.trim() {
+            "" => "Bye",
+            name => name,
+        };
+        details.push_str(&format!(
+            "\n{name}{}{}{}",
+            tag.as_ref()
+                .map_or(String::new(), |tag| format!(" ({tag})")),
+            score.map_or(String::new(), |score| format!(": {score}")),
+            if won { " - winner" } else { "" }
+        ));
+    }
+    match game.and_then(|game| game.decided_at) {
+        Some(time) => details.push_str(&format!(
+            "\nDecided at {}",
+            time.format("%Y-%m-%d %H:%M UTC")
+        )),
+        None => match game {
+            Some(game) if game.won.is_some() => {}
+            Some(_) => details.push_str("\nNot played yet"),
+            None => details.push_str("\nNobody has reached this match yet"),
+        },
+    }
+    format!(
+        r##"<g class="match">
+<title>{}</title>
+<rect class="box" x="{x}" y="{y}" width="{BOX_WIDTH}" height="{BOX_HEIGHT}" rx="4" fill="#c0c0c0"/>
+<line x1="{x}" y1="{}" x2="{}" y2="{}" stroke="#9a9a9a"/>
+{rows}</g>
+"##,
+        escape(&details),
+        y + ROW_HEIGHT,
+        x + BOX_WIDTH,
+        y + ROW_HEIGHT
+    )
+}
+
+/// Shortens a name that would overflow its box.
+fn truncate(name: &str, length: usize) -> String {
+    match name.chars().count() > length {
+        true => format!("{}…", name.chars().take(length - 1).collect::<String>()),
+        false => name.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bracket_html, bracket_svg};
+    use crate::visual::bracket::{Bracket, BracketMatch};
+    use chrono::{TimeZone, Utc};
+    use dbc_bot::Region;
+
+    fn decided(player1: &str, player2: &str) -> BracketMatch {
+        BracketMatch {
+            column: 1,
+            match_id: 1,
+            player1: player1.to_string(),
+            player2: player2.to_string(),
+            winner1: false,
+            winner2: false,
+            won: Some(true),
+            tag1: Some("#P1".to_string()),
+            tag2: Some("#P2".to_string()),
+            score: Some((2, 1)),
+            decided_at: Utc.with_ymd_and_hms(2026, 5, 1, 18, 30, 0).single(),
+        }
+    }
+
+    #[test]
+    fn svg_has_a_box_for_every_match() {
+        let bracket = Bracket {
+            total: 2,
+            winners: vec![decided("<Tom & Jerry>", "Bob")],
+            losers: vec![],
+            finals: 0,
+        };
+        let svg = bracket_svg(&bracket, false);
+        assert_eq!(svg.matches(r#"<g class="match">"#).count(), 3);
+        assert!(svg.contains(r#"font-weight="bold">&lt;Tom &amp; Jerry&gt;</text>"#));
+        assert!(svg.contains("&lt;Tom &amp; Jerry&gt; (#P1): 2 - winner\nBob (#P2): 1"));
+        assert!(svg.contains("Decided at 2026-05-01 18:30 UTC"));
+        assert!(svg.contains("Nobody has reached this match yet"));
+
+        let html = bracket_html(&Region::EU, &bracket);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("Losers bracket"));
+    }
+
+    #[test]
+    fn losers_bracket_ends_with_the_grand_finals() {
+        let bracket = Bracket {
+            total: 2,
+            winners: vec![],
+            losers: vec![],
+            finals: 2,
+        };
+        let svg = bracket_svg(&bracket, true);
+        assert_eq!(svg.matches(r#"<g class="match">"#).count(), 4);
+        assert!(svg.contains("Grand Final"));
+        assert!(bracket_html(&Region::EU, &bracket).contains("Losers bracket"));
+    }
+}
@@ -1,2 +1,4 @@
+pub mod bracket;
+pub mod export;
 pub mod model;
 pub mod pre_battle;
@@ -1,8 +1,7 @@
 use crate::Error;
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
 use async_trait::async_trait;
-use base64::{engine::general_purpose, Engine as _};
 use bytes::Bytes;
-use image::io::Reader as ImageReader;
 use image::{
     imageops::{self, FilterType::Lanczos3},
     DynamicImage, ImageBuffer, Rgba,
@@ -10,12 +9,13 @@ use image::{
 use image::{GenericImage, GenericImageView, Pixel};
 
 use std::env;
-use std::io::{Cursor, Read};
-use std::process::{Command, Stdio};
-use tracing::{error, info};
+use tracing::error;
 const DEFAULT_ICON: &str = "https://cdn.brawlify.com/profile/28000000.png?v=1";
 const DEFAULT_MODE_ICON: &str =
     "https://pbs.twimg.com/media/F2_Uy9rXgAAXXnP?format=png&name=360x360";
+const FONT_PATH: &str = "assets/fonts/LilitaOne-Regular.ttf";
+/// Transparent margin around a rendered text.
+const TEXT_PADDING: i64 = 5;
 
 pub struct BSImage {
     pub width: i64,
@@ -194,18 +194,6 @@ impl Text {
             .outline
             .as_ref()
             .map_or_else(|| (0, 0x00000000_u32), |b| (b.thickness, b.color));
-        let data = format!(
-            r#"{{
-                "text": "{text}",
-                "font_size": {font_size},
-                "font_color": "{font_color}",
-                "stroke_width": "{stroke}",
-                "stroke_color": "{stroke_color}"
-            }}"#,
-            text = self.text,
-            font_size = self.font_size,
-            font_color = self.font_color
-        );
         let current_dir = match env::current_dir() {
             Ok(dir) => dir,
             Err(e) => {
@@ -213,30 +201,92 @@ impl Text {
                 return Err(e.into());
             }
         };
-        let output = Command::new("python3")
-            .arg("scripts/text.py")
-            .arg(data)
-            .stdout(Stdio::piped())
-            .current_dir(current_dir)
-            .spawn()?;
-
-        let stdout = output.wait_with_output()?.stdout;
-        let buffer = std::str::from_utf8(&stdout)?;
-        if buffer.len() < 100 {
-            return Err("Failed to capture Python script output".into());
-        }
-        let image_bytes = match general_purpose::STANDARD.decode(buffer.trim_end()) {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                error!("{e}");
-                info!("Debug: {buffer}");
-                return Err(e.into());
+        let font = FontVec::try_from_vec(std::fs::read(current_dir.join(FONT_PATH))?)?;
+        // Font sizes are in pixels per em, while ab_glyph scales by the height of the font
+        let scale = PxScale::from(
+            f32::from(self.font_size) * font.height_unscaled()
+                / font.units_per_em().unwrap_or(1000.0),
+        );
+        let font = font.as_scaled(scale);
+
+        // Lay the glyphs out on one line, leaving room for the outline
+        let (left, top) = ((TEXT_PADDING + stroke) as f32, stroke as f32);
+        let mut caret = left;
+        let mut previous: Option<GlyphId> = None;
+        let mut glyphs = vec![];
+        for c in self.text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += font.kern(previous, id);
             }
+            glyphs.push(id.with_scale_and_position(scale, point(caret, top + font.ascent())));
+            caret += font.h_advance(id);
+            previous = Some(id);
+        }
+        let outlines: Vec<_> = glyphs
+            .into_iter()
+            .filter_map(|glyph| font.outline_glyph(glyph))
+            .collect();
+        let right = outlines
+            .iter()
+            .map(|glyph| glyph.px_bounds().max.x)
+            .fold(left, f32::max);
+        let bottom = outlines
+            .iter()
+            .map(|glyph| glyph.px_bounds().max.y)
+            .fold(top, f32::max);
+        let width = right.ceil() as i64 + TEXT_PADDING + stroke;
+        let height = (bottom - font.descent()).ceil() as i64 + TEXT_PADDING + stroke;
+
+        let mut fill = vec![0.0_f32; (width * height) as usize];
+        for glyph in &outlines {
+            let bounds = glyph.px_bounds();
+            glyph.draw(|x, y, coverage| {
+                let x = bounds.min.x as i64 + x as i64;
+                let y = bounds.min.y as i64 + y as i64;
+                if (0..width).contains(&x) && (0..height).contains(&y) {
+                    let pixel = &mut fill[(y * width + x) as usize];
+                    *pixel = pixel.max(coverage);
+                }
+            });
+        }
+        let coverage_at = |x: i64, y: i64| match (0..width).contains(&x) && (0..height).contains(&y)
+        {
+            true => fill[(y * width + x) as usize],
+            false => 0.0,
         };
-        let img = ImageReader::new(Cursor::new(image_bytes))
-            .with_guessed_format()?
-            .decode()?;
-        Ok(img)
+        let (r, g, b, _) = get_color(self.font_color);
+        let (sr, sg, sb, _) = get_color(stroke_color);
+        let mut img: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width as u32, height as u32);
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            let (x, y) = (x as i64, y as i64);
+            let fill_alpha = coverage_at(x, y);
+            // The outline is the text grown by its thickness in every direction
+            let mut stroke_alpha: f32 = 0.0;
+            for dy in -stroke..=stroke {
+                for dx in -stroke..=stroke {
+                    if dx * dx + dy * dy <= stroke * stroke {
+                        stroke_alpha = stroke_alpha.max(coverage_at(x + dx, y + dy));
+                    }
+                }
+            }
+            let stroke_alpha = stroke_alpha * (1.0 - fill_alpha);
+            let alpha = fill_alpha + stroke_alpha;
+            if alpha <= 0.0 {
+                continue;
+            }
+            let mix = |fill: u8, stroke: u8| {
+                ((f32::from(fill) * fill_alpha + f32::from(stroke) * stroke_alpha) / alpha).round()
+                    as u8
+            };
+            *pixel = Rgba([
+                mix(r, sr),
+                mix(g, sg),
+                mix(b, sb),
+                (alpha * 255.0).round() as u8,
+            ]);
+        }
+        Ok(DynamicImage::ImageRgba8(img))
     }
 }
 
@@ -1,6 +1,8 @@
 use super::model::{self, *};
+use crate::bracket_tournament::best_of::best_of;
 use crate::brawlstars::getters::get_mode_icon;
 use crate::brawlstars::getters::get_player_icon;
+use crate::database::models::{Player, RegionConfig};
 use crate::Error;
 use dbc_bot::CustomError;
 use image::{imageops, DynamicImage};
@@ -10,12 +12,14 @@ use std::io::Cursor;
 use tracing::error;
 const FONT_SIZE: u8 = 30;
 const ICON_SIZE: i64 = 200;
+const DEFAULT_ICON: i64 = 28000000;
 async fn create_battle_image(
-    player1: &Document,
-    player2: &Document,
-    round: i32,
+    player1: &Player,
+    player2: &Player,
+    round: &str,
     match_id: i32,
     mode: &str,
+    best_of: i32,
 ) -> Result<DynamicImage, Error> {
     let current_dir = match env::current_dir() {
         Ok(dir) => dir,
@@ -59,7 +63,7 @@ async fn create_battle_image(
 
     let mut icon1 = model::Component::new(
         model::CustomImage::new(
-            get_player_icon(player1.get_i64("icon").unwrap()),
+            get_player_icon(player1.icon.unwrap_or(DEFAULT_ICON)),
             Some(ICON_SIZE),
             Some(ICON_SIZE),
         )
@@ -73,7 +77,7 @@ async fn create_battle_image(
 
     let mut icon2 = model::Component::new(
         model::CustomImage::new(
-            get_player_icon(player2.get_i64("icon").unwrap()),
+            get_player_icon(player2.icon.unwrap_or(DEFAULT_ICON)),
             Some(ICON_SIZE),
             Some(ICON_SIZE),
         )
@@ -87,14 +91,9 @@ async fn create_battle_image(
     icon2.set_center_y(img.height);
 
     let mut name1 = model::Component::new(
-        model::Text::new(
-            player1.get_str("discord_name").unwrap(),
-            FONT_SIZE,
-            0xFFFFFFFF,
-            None,
-        )
-        .build()
-        .await?,
+        model::Text::new(&player1.discord_name, FONT_SIZE, 0xFFFFFFFF, None)
+            .build()
+            .await?,
         None,
         None,
         Some("name1"),
@@ -103,14 +102,9 @@ async fn create_battle_image(
     name1.set_y(icon1.y + icon1.height() + 10);
 
     let mut name2 = model::Component::new(
-        model::Text::new(
-            player2.get_str("discord_name").unwrap(),
-            FONT_SIZE,
-            0xFFFFFFFF,
-            None,
-        )
-        .build()
-        .await?,
+        model::Text::new(&player2.discord_name, FONT_SIZE, 0xFFFFFFFF, None)
+            .build()
+            .await?,
         None,
         None,
         Some("name2"),
@@ -119,7 +113,7 @@ async fn create_battle_image(
     name2.set_y(icon2.y + icon2.height() + 10);
 
     let mut tag1 = model::Component::new(
-        model::Text::new(player1.get_str("tag").unwrap(), FONT_SIZE, 0xFFFFFFFF, None)
+        model::Text::new(&player1.tag, FONT_SIZE, 0xFFFFFFFF, None)
             .build()
             .await?,
         None,
@@ -130,7 +124,7 @@ async fn create_battle_image(
     tag1.set_y(name1.y + name1.height() + 10);
 
     let mut tag2 = model::Component::new(
-        model::Text::new(player2.get_str("tag").unwrap(), FONT_SIZE, 0xFFFFFFFF, None)
+        model::Text::new(&player2.tag, FONT_SIZE, 0xFFFFFFFF, None)
             .build()
             .await?,
         None,
@@ -195,7 +189,7 @@ async fn create_battle_image(
 
     let mut upper_title = model::Component::new(
         model::Text::new(
-            format!("Round {round}"),
+            round.to_string(),
             35,
             0xFFFFFFFF,
             Some(model::Border {
@@ -307,9 +301,29 @@ async fn create_battle_image(
     mode_text.set_center_y(mode_bg.height());
     mode_bg.overlay(mode_text);
 
+    let mut best_of_text = model::Component::new(
+        model::Text::new(
+            format!("BEST OF {best_of}"),
+            FONT_SIZE,
+            0xFFFFFFFF,
+            Some(model::Border {
+                thickness: 3,
+                color: 0x000000FF,
+            }),
+        )
+        .build()
+        .await?,
+        None,
+        None,
+        Some("best_of_text"),
+    );
+    best_of_text.set_center_x(img.width);
+    best_of_text.set_y(mode_bg.y + mode_bg.height() + 10);
+
     // Component elements onto the base img
     img.add_overlay(title_box);
     img.add_overlay(mode_bg);
+    img.add_overlay(best_of_text);
     img.add_overlay(icon1);
     img.add_overlay(icon2);
     img.add_overlay(name1);
@@ -325,19 +339,33 @@ pub async fn generate_pre_battle_img(
     player1: &Document,
     player2: &Document,
     config: &Document,
+    round: &str,
 ) -> Result<DynamicImage, Error> {
-    let mode = config.get_str("mode").unwrap();
-    let round = config.get_i32("round").unwrap();
-    let match_id = player1.get_i32("match_id").unwrap();
-    create_battle_image(player1, player2, round, match_id, mode).await
+    let settings = RegionConfig::from_document(config)?;
+    let (player1, player2) = (
+        Player::from_document(player1)?,
+        Player::from_document(player2)?,
+    );
+    let match_id = player1.match_id()?;
+    let best_of = best_of(config, round);
+    create_battle_image(
+        &player1,
+        &player2,
+        round,
+        match_id,
+        settings.mode()?,
+        best_of,
+    )
+    .await
 }
 
 pub async fn get_image(
     player1: &Document,
     player2: &Document,
     config: &Document,
+    round: &str,
 ) -> Result<Vec<u8>, Error> {
-    let img = generate_pre_battle_img(player1, player2, config).await?;
+    let img = generate_pre_battle_img(player1, player2, config, round).await?;
     let mut bytes: Vec<u8> = Vec::new();
     img.write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)?;
     Ok(bytes)